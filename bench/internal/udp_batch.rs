@@ -0,0 +1,101 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use rust_miniss::{net::AsyncUdpSocket, Runtime};
+use std::net::SocketAddr;
+use std::time::Instant;
+
+const BATCH: usize = 32;
+const PAYLOAD: usize = 64;
+
+fn init_tracing() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .try_init();
+}
+
+/// Binds a sender/receiver pair on loopback.
+fn socket_pair() -> (AsyncUdpSocket, AsyncUdpSocket, SocketAddr) {
+    let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let sender = AsyncUdpSocket::bind(addr).expect("bind sender");
+    let receiver = AsyncUdpSocket::bind(addr).expect("bind receiver");
+    let target = receiver.local_addr().unwrap();
+    (sender, receiver, target)
+}
+
+fn udp_batch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("udp_datagrams");
+    group.throughput(Throughput::Elements(BATCH as u64));
+
+    group.bench_function(format!("send_to_recv_from_{}", BATCH), |b| {
+        b.iter_custom(|iters| {
+            init_tracing();
+            Runtime::new().block_on(async move {
+                let (sender, receiver, target) = socket_pair();
+                let payload = [7u8; PAYLOAD];
+                let mut buf = [0u8; 1500];
+
+                let start = Instant::now();
+                for _ in 0..iters {
+                    for _ in 0..BATCH {
+                        sender.send_to(&payload, target).await.unwrap();
+                    }
+                    for _ in 0..BATCH {
+                        receiver.recv_from(&mut buf).await.unwrap();
+                    }
+                }
+                start.elapsed()
+            })
+        })
+    });
+
+    group.bench_function(format!("send_batch_recv_batch_{}", BATCH), |b| {
+        b.iter_custom(|iters| {
+            init_tracing();
+            Runtime::new().block_on(async move {
+                let (sender, receiver, target) = socket_pair();
+                let payload = [7u8; PAYLOAD];
+                let batch: Vec<(&[u8], SocketAddr)> =
+                    (0..BATCH).map(|_| (&payload[..], target)).collect();
+
+                let start = Instant::now();
+                for _ in 0..iters {
+                    sender.send_batch(&batch).await.unwrap();
+                    let mut received = 0;
+                    while received < BATCH {
+                        received += receiver.recv_batch(BATCH, 1500).await.unwrap().len();
+                    }
+                }
+                start.elapsed()
+            })
+        })
+    });
+
+    #[cfg(target_os = "linux")]
+    group.bench_function(format!("gso_send_batch_gro_recv_batch_{}", BATCH), |b| {
+        b.iter_custom(|iters| {
+            init_tracing();
+            Runtime::new().block_on(async move {
+                let (sender, receiver, target) = socket_pair();
+                sender.set_gso(true);
+                receiver.set_gro(true).expect("UDP_GRO");
+                let payload = [7u8; PAYLOAD];
+                let batch: Vec<(&[u8], SocketAddr)> =
+                    (0..BATCH).map(|_| (&payload[..], target)).collect();
+
+                let start = Instant::now();
+                for _ in 0..iters {
+                    sender.send_batch(&batch).await.unwrap();
+                    let mut received = 0;
+                    while received < BATCH {
+                        received += receiver.recv_batch(BATCH, 65535).await.unwrap().len();
+                    }
+                }
+                start.elapsed()
+            })
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(udp_benches, udp_batch);
+criterion_main!(udp_benches);
@@ -18,7 +18,9 @@
 //! ## Configuration Flags
 //!
 //! This build script sets the `io_backend` configuration flag which is used by
-//! conditional compilation attributes throughout the codebase.
+//! conditional compilation attributes throughout the codebase. A backend
+//! chosen with `RUSTFLAGS='--cfg io_backend="epoll"'` takes precedence, so
+//! the fallback backends can be built and tested on any Linux kernel.
 
 use std::process::Command;
 
@@ -27,6 +29,13 @@ fn main() {
     println!("cargo:rustc-check-cfg=cfg(io_backend, values(\"io_uring\", \"epoll\", \"kqueue\"))");
     println!("cargo:rustc-check-cfg=cfg(has_io_uring)");
 
+    // A backend passed in RUSTFLAGS must be the only one enabled
+    let rustflags = std::env::var("CARGO_ENCODED_RUSTFLAGS").unwrap_or_default();
+    if rustflags.contains("io_backend=") {
+        eprintln!("Using the IO backend set in RUSTFLAGS");
+        return;
+    }
+
     if cfg!(target_os = "linux") {
         match get_kernel_version() {
             Ok(version) => {
@@ -0,0 +1,344 @@
+//! A runtime-wide pool of threads for blocking work.
+//!
+//! Work that would stall a core, such as the file operations the epoll
+//! backend cannot make asynchronous, or user code calling blocking APIs
+//! through [`spawn_blocking`], runs on this pool instead. One pool serves all
+//! cores of a runtime: threads are started on demand up to a maximum and exit
+//! after sitting idle, and the queue of jobs waiting for a thread is bounded.
+//!
+//! Results travel back to the submitting core through its wakeup mechanism:
+//! the backend's notifier for I/O completions, the task's waker for
+//! [`spawn_blocking`].
+
+use crate::task::{TaskError, TaskResult};
+use futures::channel::oneshot;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::Duration;
+
+/// A unit of work for the pool.
+pub(crate) type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// Sizing of a [`BlockingPool`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockingPoolOptions {
+    /// Most threads the pool runs at once. Defaults to 64.
+    pub max_threads: usize,
+    /// Most jobs waiting for a free thread. Defaults to 4096.
+    pub queue_capacity: usize,
+    /// How long a thread waits for work before exiting. Defaults to 10 seconds.
+    pub keep_alive: Duration,
+}
+
+impl Default for BlockingPoolOptions {
+    fn default() -> Self {
+        Self {
+            max_threads: 64,
+            queue_capacity: 4096,
+            keep_alive: Duration::from_secs(10),
+        }
+    }
+}
+
+/// A pool of threads for blocking work, shared by all cores of a runtime.
+#[derive(Debug)]
+pub struct BlockingPool {
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    options: BlockingPoolOptions,
+    state: Mutex<State>,
+    /// Signalled when a job is queued or the pool shuts down.
+    work_available: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    queue: VecDeque<Job>,
+    threads: usize,
+    idle: usize,
+    /// Tasks in [`spawn_blocking`], and backends, waiting for room in the
+    /// queue.
+    space_waiters: Vec<Waker>,
+    shutdown: bool,
+}
+
+impl std::fmt::Debug for State {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("State")
+            .field("queued", &self.queue.len())
+            .field("threads", &self.threads)
+            .field("idle", &self.idle)
+            .field("shutdown", &self.shutdown)
+            .finish()
+    }
+}
+
+impl BlockingPool {
+    /// Creates a pool sized by `options`. No threads start until work arrives.
+    pub fn new(options: BlockingPoolOptions) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                options,
+                state: Mutex::new(State::default()),
+                work_available: Condvar::new(),
+            }),
+        }
+    }
+
+    /// The pool used by runtimes that are not given one of their own, and by
+    /// [`spawn_blocking`] outside a runtime.
+    pub fn shared() -> Arc<BlockingPool> {
+        static POOL: OnceLock<Arc<BlockingPool>> = OnceLock::new();
+        POOL.get_or_init(|| Arc::new(BlockingPool::new(BlockingPoolOptions::default())))
+            .clone()
+    }
+
+    /// Number of threads currently running, busy or idle.
+    pub fn threads(&self) -> usize {
+        self.shared.state.lock().unwrap().threads
+    }
+
+    /// Number of jobs waiting for a free thread.
+    pub fn queued(&self) -> usize {
+        self.shared.state.lock().unwrap().queue.len()
+    }
+
+    /// Queues `job`, handing it back if the queue is full or no thread could
+    /// be started to run it.
+    fn try_execute(&self, job: Job) -> Result<(), Job> {
+        let mut state = self.shared.state.lock().unwrap();
+        if state.queue.len() >= self.shared.options.queue_capacity {
+            return Err(job);
+        }
+        state.queue.push_back(job);
+        if state.queue.len() > state.idle && state.threads < self.shared.options.max_threads {
+            let shared = self.shared.clone();
+            let spawned = thread::Builder::new()
+                .name("miniss-blocking".to_string())
+                .spawn(move || shared.work());
+            match spawned {
+                Ok(_) => state.threads += 1,
+                Err(e) if state.threads == 0 => {
+                    tracing::warn!("Failed to start blocking pool thread: {}", e);
+                    return Err(state.queue.pop_back().expect("the job was just queued"));
+                }
+                // The running threads get to it eventually
+                Err(e) => tracing::warn!("Failed to start blocking pool thread: {}", e),
+            }
+        }
+        self.shared.work_available.notify_one();
+        Ok(())
+    }
+
+    /// Queues `job`, or hands it back and wakes `waker` once the queue has
+    /// room, so the caller can retry without holding up its thread.
+    pub(crate) fn poll_execute(&self, job: Job, waker: &Waker) -> Result<(), Job> {
+        self.try_execute(job).inspect_err(|_| {
+            let mut state = self.shared.state.lock().unwrap();
+            // The queue may have drained in between; retry straight away then
+            if state.queue.len() < self.shared.options.queue_capacity {
+                waker.wake_by_ref();
+            } else {
+                state.space_waiters.push(waker.clone());
+            }
+        })
+    }
+}
+
+impl Drop for BlockingPool {
+    fn drop(&mut self) {
+        // Threads finish the queued jobs, then exit
+        self.shared.state.lock().unwrap().shutdown = true;
+        self.shared.work_available.notify_all();
+    }
+}
+
+impl Shared {
+    /// A pool thread: runs queued jobs until it has been idle for the keep
+    /// alive, or the pool shuts down with nothing left queued.
+    fn work(&self) {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if let Some(job) = state.queue.pop_front() {
+                let waiters = std::mem::take(&mut state.space_waiters);
+                drop(state);
+                waiters.into_iter().for_each(Waker::wake);
+                // Jobs report their own panics; the thread carries on
+                let _ = catch_unwind(AssertUnwindSafe(job));
+                state = self.state.lock().unwrap();
+                continue;
+            }
+            if state.shutdown {
+                break;
+            }
+            state.idle += 1;
+            let (guard, wait) = self
+                .work_available
+                .wait_timeout(state, self.options.keep_alive)
+                .unwrap();
+            state = guard;
+            state.idle -= 1;
+            if wait.timed_out() && state.queue.is_empty() {
+                break;
+            }
+        }
+        state.threads -= 1;
+    }
+}
+
+/// Runs the blocking function `f` on the current runtime's blocking pool,
+/// returning a future of its result.
+///
+/// `f` starts as soon as a pool thread is free, whether or not the future is
+/// polled. While the pool's queue is full, the future waits for room before
+/// submitting it. Outside a runtime, [`BlockingPool::shared`] runs it.
+///
+/// # Returns
+///
+/// A [`BlockingTask`] resolving to:
+/// * `Ok(T)` - The value `f` returned
+/// * `Err(TaskError::Panic)` - `f` panicked
+/// * `Err(TaskError::Cancelled)` - The pool shut down before running `f`
+///
+/// # Examples
+///
+/// ```
+/// use rust_miniss::{spawn_blocking, Runtime};
+///
+/// let runtime = Runtime::new();
+/// let hostname = runtime.block_on(async {
+///     spawn_blocking(|| std::fs::read_to_string("/etc/hostname"))
+///         .await
+///         .expect("Blocking task panicked")
+/// });
+/// ```
+pub fn spawn_blocking<F, T>(f: F) -> BlockingTask<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let pool = crate::cpu::current_blocking_pool();
+    let (tx, rx) = oneshot::channel();
+    let job: Job = Box::new(move || {
+        let result = catch_unwind(AssertUnwindSafe(f)).map_err(TaskError::Panic);
+        let _ = tx.send(result);
+    });
+    let job = pool.try_execute(job).err();
+    BlockingTask {
+        pool,
+        job,
+        result: rx,
+    }
+}
+
+/// The future returned by [`spawn_blocking`].
+///
+/// Dropping it does not stop the function once it is running; its result is
+/// discarded.
+#[must_use = "the blocking function's result is only available by awaiting it"]
+pub struct BlockingTask<T> {
+    pool: Arc<BlockingPool>,
+    /// The job, while it waits for room in the pool's queue.
+    job: Option<Job>,
+    result: oneshot::Receiver<TaskResult<T>>,
+}
+
+impl<T> std::fmt::Debug for BlockingTask<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BlockingTask")
+            .field("submitted", &self.job.is_none())
+            .finish()
+    }
+}
+
+impl<T> Future for BlockingTask<T> {
+    type Output = TaskResult<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        if let Some(job) = this.job.take() {
+            if let Err(job) = this.pool.poll_execute(job, cx.waker()) {
+                this.job = Some(job);
+                return Poll::Pending;
+            }
+        }
+        match Pin::new(&mut this.result).poll(cx) {
+            Poll::Ready(Ok(result)) => Poll::Ready(result),
+            // The job was dropped unrun
+            Poll::Ready(Err(oneshot::Canceled)) => Poll::Ready(Err(TaskError::Cancelled)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    struct WakeSender(Mutex<mpsc::Sender<()>>);
+
+    impl futures::task::ArcWake for WakeSender {
+        fn wake_by_ref(arc_self: &Arc<Self>) {
+            let _ = arc_self.0.lock().unwrap().send(());
+        }
+    }
+
+    #[test]
+    fn test_pool_bounds_threads_and_queue() {
+        let pool = BlockingPool::new(BlockingPoolOptions {
+            max_threads: 1,
+            queue_capacity: 1,
+            keep_alive: Duration::from_millis(50),
+        });
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let (started_tx, started_rx) = mpsc::channel();
+
+        let (woken_tx, woken_rx) = mpsc::channel();
+        let waker = futures::task::waker(Arc::new(WakeSender(Mutex::new(woken_tx))));
+
+        // The only thread blocks on the first job; the second job fills the queue
+        let blocker: Job = Box::new(move || {
+            started_tx.send(()).unwrap();
+            release_rx.recv().unwrap();
+        });
+        assert!(pool.poll_execute(blocker, &waker).is_ok());
+        started_rx.recv().unwrap();
+        assert!(pool.poll_execute(Box::new(|| {}), &waker).is_ok());
+        assert_eq!(pool.threads(), 1);
+        assert_eq!(pool.queued(), 1);
+
+        // With the queue full, the job is handed back rather than run by the
+        // caller, and the caller is woken once there is room
+        let caller = thread::current().id();
+        let (ran_tx, ran_rx) = mpsc::channel();
+        let job: Job = Box::new(move || {
+            ran_tx.send(thread::current().id()).unwrap();
+        });
+        let job = pool.poll_execute(job, &waker).unwrap_err();
+        assert!(woken_rx.try_recv().is_err());
+        release_tx.send(()).unwrap();
+        woken_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(pool.poll_execute(job, &waker).is_ok());
+        assert_ne!(ran_rx.recv().unwrap(), caller);
+
+        // Idle threads exit after the keep alive
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while pool.threads() > 0 {
+            assert!(
+                std::time::Instant::now() < deadline,
+                "pool thread did not exit"
+            );
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(pool.queued(), 0);
+    }
+}
@@ -1,37 +1,366 @@
+use bytes::Bytes;
+use crossbeam_queue::ArrayQueue;
+use std::alloc::{self, Layout};
 use std::cell::RefCell;
 use std::collections::VecDeque;
-use std::{io::IoSlice, ops::Deref};
+use std::ptr::NonNull;
+use std::sync::Arc;
+use std::{
+    fmt,
+    io::IoSlice,
+    ops::{Deref, DerefMut},
+};
 
 pub const BUFFER_SIZE: usize = 4096; // Made public
 const POOL_SIZE: usize = 100;
 
-#[derive(Debug, Clone)] // Added Debug and Clone derives
-pub struct Buffer(Vec<u8>);
+/// A byte buffer handed to and from the I/O backends.
+///
+/// A buffer either owns a `Vec<u8>` (which the kernel can read into), wraps a
+/// shared `bytes::Bytes` (which can only be written out), owns an
+/// [`AlignedBuffer`] for direct I/O, or borrows a slot of memory registered
+/// with the kernel by the I/O backend. Converting between them avoids copying
+/// whenever the data is not shared.
+#[derive(Debug, Clone)]
+pub struct Buffer(Repr);
+
+#[derive(Debug)]
+enum Repr {
+    Owned(Vec<u8>),
+    Shared(Bytes),
+    Aligned(AlignedBuffer),
+    // Only the io_uring backend registers memory
+    #[cfg_attr(not(io_backend = "io_uring"), allow(dead_code))]
+    Registered(RegisteredBuf),
+}
+
+impl Clone for Repr {
+    fn clone(&self) -> Self {
+        match self {
+            Repr::Owned(vec) => Repr::Owned(vec.clone()),
+            Repr::Shared(bytes) => Repr::Shared(bytes.clone()),
+            Repr::Aligned(buf) => Repr::Aligned(buf.clone()),
+            // Registered slots are scarce, so clones get their own memory.
+            Repr::Registered(buf) => Repr::Owned(buf.as_ref().to_vec()),
+        }
+    }
+}
+
+/// Zeroed heap memory whose address and capacity are multiples of a power of
+/// two alignment, as `O_DIRECT` requires of the buffers it transfers.
+///
+/// The contents are the first `len` bytes; the rest of the capacity stays
+/// initialised, so the length can be set freely up to the capacity.
+///
+/// # Examples
+///
+/// ```
+/// use rust_miniss::buffer::AlignedBuffer;
+///
+/// let mut buf = AlignedBuffer::new(1000, 512);
+/// assert_eq!(buf.capacity(), 1024);
+/// assert_eq!(buf.as_ptr() as usize % 512, 0);
+/// buf.set_len(5);
+/// buf.copy_from_slice(b"block");
+/// assert_eq!(&buf[..], b"block");
+/// ```
+pub struct AlignedBuffer {
+    ptr: NonNull<u8>,
+    layout: Layout,
+    len: usize,
+}
+
+// SAFETY: the buffer exclusively owns its allocation.
+unsafe impl Send for AlignedBuffer {}
+unsafe impl Sync for AlignedBuffer {}
+
+impl AlignedBuffer {
+    /// Allocates a zeroed, empty buffer of at least `capacity` bytes, rounded
+    /// up to a multiple of `align`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `align` is not a power of two.
+    pub fn new(capacity: usize, align: usize) -> Self {
+        assert!(align.is_power_of_two(), "alignment must be a power of two");
+        // Never empty, so there is always an allocation to align
+        let size = capacity.max(1).next_multiple_of(align);
+        let layout = Layout::from_size_align(size, align).expect("aligned buffer too large");
+        // SAFETY: the layout has a non-zero size.
+        let ptr = unsafe { alloc::alloc_zeroed(layout) };
+        let ptr = NonNull::new(ptr).unwrap_or_else(|| alloc::handle_alloc_error(layout));
+        Self {
+            ptr,
+            layout,
+            len: 0,
+        }
+    }
+
+    /// The alignment of the address and capacity.
+    pub fn align(&self) -> usize {
+        self.layout.align()
+    }
+
+    /// Size of the allocation in bytes.
+    pub fn capacity(&self) -> usize {
+        self.layout.size()
+    }
+
+    /// Sets the length of the contents.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len > self.capacity()`.
+    pub fn set_len(&mut self, len: usize) {
+        assert!(len <= self.capacity(), "length exceeds capacity");
+        self.len = len;
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.ptr.as_ptr()
+    }
+}
+
+impl Deref for AlignedBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        // SAFETY: the allocation is initialised and `len <= capacity`.
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl DerefMut for AlignedBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        // SAFETY: as for `deref`, and `&mut self` makes the access exclusive.
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl AsRef<[u8]> for AlignedBuffer {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl Clone for AlignedBuffer {
+    fn clone(&self) -> Self {
+        let mut clone = Self::new(self.capacity(), self.align());
+        clone.set_len(self.len);
+        clone.copy_from_slice(self);
+        clone
+    }
+}
+
+impl Drop for AlignedBuffer {
+    fn drop(&mut self) {
+        // SAFETY: allocated in `new` with the same layout.
+        unsafe { alloc::dealloc(self.ptr.as_ptr(), self.layout) }
+    }
+}
+
+impl fmt::Debug for AlignedBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AlignedBuffer")
+            .field("align", &self.align())
+            .field("capacity", &self.capacity())
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl From<AlignedBuffer> for Buffer {
+    fn from(buf: AlignedBuffer) -> Self {
+        Buffer(Repr::Aligned(buf))
+    }
+}
+
+/// A contiguous arena of equally sized buffer slots whose memory an I/O
+/// backend registers with the kernel, either as fixed buffers or as a provided
+/// buffer ring.
+///
+/// Slots are handed out as [`Buffer`]s and go back on the free list when those
+/// buffers are dropped, from whichever thread drops them.
+pub(crate) struct RegisteredRegion {
+    base: NonNull<u8>,
+    layout: Layout,
+    buf_size: usize,
+    count: u16,
+    free: ArrayQueue<u16>,
+}
+
+// SAFETY: the arena is plain memory; each slot is owned by at most one
+// `Buffer` (or the kernel) at a time, and the free list is thread-safe.
+unsafe impl Send for RegisteredRegion {}
+unsafe impl Sync for RegisteredRegion {}
+
+#[cfg_attr(not(io_backend = "io_uring"), allow(dead_code))]
+impl RegisteredRegion {
+    /// Allocates `count` page-aligned slots of `buf_size` bytes each. If
+    /// `all_free` is false the slots start out owned by the caller (e.g. lent
+    /// to the kernel) and only become available once dropped as buffers.
+    pub(crate) fn new(buf_size: usize, count: u16, all_free: bool) -> Arc<Self> {
+        assert!(buf_size > 0 && count > 0, "empty registered region");
+        let layout = Layout::from_size_align(buf_size * count as usize, 4096)
+            .expect("registered region too large");
+        // SAFETY: the layout has a non-zero size.
+        let base = unsafe { alloc::alloc_zeroed(layout) };
+        let base = NonNull::new(base).unwrap_or_else(|| alloc::handle_alloc_error(layout));
+        let free = ArrayQueue::new(count as usize);
+        if all_free {
+            for index in 0..count {
+                let _ = free.push(index);
+            }
+        }
+        Arc::new(Self {
+            base,
+            layout,
+            buf_size,
+            count,
+            free,
+        })
+    }
+
+    /// Size of each slot in bytes.
+    pub(crate) fn buf_size(&self) -> usize {
+        self.buf_size
+    }
+
+    /// Start of the slot at `index`.
+    pub(crate) fn slot_ptr(&self, index: u16) -> *mut u8 {
+        assert!(index < self.count, "slot index out of range");
+        // SAFETY: the offset stays within the allocation.
+        unsafe { self.base.as_ptr().add(index as usize * self.buf_size) }
+    }
+
+    /// One iovec per slot, for `IORING_REGISTER_BUFFERS`.
+    pub(crate) fn iovecs(&self) -> Vec<libc::iovec> {
+        (0..self.count)
+            .map(|index| libc::iovec {
+                iov_base: self.slot_ptr(index).cast(),
+                iov_len: self.buf_size,
+            })
+            .collect()
+    }
+
+    /// Takes a slot off the free list without wrapping it in a buffer.
+    pub(crate) fn pop_free(&self) -> Option<u16> {
+        self.free.pop()
+    }
+
+    /// Takes a free slot as an empty buffer.
+    pub(crate) fn take(self: &Arc<Self>) -> Option<Buffer> {
+        let index = self.pop_free()?;
+        // SAFETY: the slot just came off the free list.
+        Some(unsafe { self.buffer(index, 0) })
+    }
+
+    /// Wraps slot `index`, whose first `len` bytes are initialised, in a
+    /// buffer that returns the slot to the free list when dropped.
+    ///
+    /// # Safety
+    /// The caller must own the slot: it must not be on the free list, in
+    /// another buffer, or still in use by the kernel.
+    pub(crate) unsafe fn buffer(self: &Arc<Self>, index: u16, len: usize) -> Buffer {
+        assert!(len <= self.buf_size, "length exceeds slot size");
+        Buffer(Repr::Registered(RegisteredBuf {
+            region: Arc::clone(self),
+            index,
+            len,
+        }))
+    }
+}
+
+impl Drop for RegisteredRegion {
+    fn drop(&mut self) {
+        // SAFETY: allocated in `new` with the same layout.
+        unsafe { alloc::dealloc(self.base.as_ptr(), self.layout) }
+    }
+}
+
+impl fmt::Debug for RegisteredRegion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RegisteredRegion")
+            .field("buf_size", &self.buf_size)
+            .field("count", &self.count)
+            .field("free", &self.free.len())
+            .finish()
+    }
+}
+
+/// One slot of a [`RegisteredRegion`].
+struct RegisteredBuf {
+    region: Arc<RegisteredRegion>,
+    index: u16,
+    len: usize,
+}
+
+impl RegisteredBuf {
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        // SAFETY: this buffer owns the slot and `len <= buf_size`.
+        unsafe { std::slice::from_raw_parts_mut(self.region.slot_ptr(self.index), self.len) }
+    }
+}
+
+impl AsRef<[u8]> for RegisteredBuf {
+    fn as_ref(&self) -> &[u8] {
+        // SAFETY: this buffer owns the slot and `len <= buf_size`.
+        unsafe { std::slice::from_raw_parts(self.region.slot_ptr(self.index), self.len) }
+    }
+}
+
+impl Drop for RegisteredBuf {
+    fn drop(&mut self) {
+        // The queue holds every index, so this cannot overflow.
+        let _ = self.region.free.push(self.index);
+    }
+}
+
+impl fmt::Debug for RegisteredBuf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RegisteredBuf")
+            .field("index", &self.index)
+            .field("len", &self.len)
+            .finish()
+    }
+}
 
 impl Buffer {
     /// Create a new zeroed buffer with the given capacity.
     /// This bypasses the pool, useful for specific sizes or non-pooled contexts.
     pub fn new_zeroed(capacity: usize) -> Self {
-        Buffer(vec![0; capacity])
+        Buffer(Repr::Owned(vec![0; capacity]))
+    }
+
+    /// Create an empty buffer with room for at least `capacity` bytes.
+    /// Reads fill it up to its capacity without zeroing it first.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Buffer(Repr::Owned(Vec::with_capacity(capacity)))
     }
 
-    /// Create a buffer from a slice without copying (zero-copy).
-    /// This is useful for I/O operations where we want to avoid data copying.
+    /// Create a buffer holding a copy of `slice`.
+    ///
+    /// Use `Buffer::from(Vec<u8>)` or `Buffer::from(Bytes)` to hand over data
+    /// without copying.
     pub fn from_slice(slice: &[u8]) -> Self {
-        Buffer(slice.to_vec())
+        Buffer(Repr::Owned(slice.to_vec()))
     }
 
     /// Recycle the buffer back to the per-CPU pool
-    pub fn recycle(mut self) {
-        self.0.clear(); // Clear contents for security/freshness
-        self.0
-            .reserve_exact(BUFFER_SIZE.saturating_sub(self.0.capacity())); // Try to ensure capacity
-                                                                           // Only return to pool if its capacity matches the standard BUFFER_SIZE
-        if self.0.capacity() == BUFFER_SIZE {
+    pub fn recycle(self) {
+        // Shared buffers may still be referenced elsewhere, and registered
+        // slots return to their region on drop.
+        let Repr::Owned(mut vec) = self.0 else {
+            return;
+        };
+        vec.clear(); // Clear contents for security/freshness
+        vec.reserve_exact(BUFFER_SIZE.saturating_sub(vec.capacity())); // Try to ensure capacity
+                                                                       // Only return to pool if its capacity matches the standard BUFFER_SIZE
+        if vec.capacity() == BUFFER_SIZE {
             CPU_BUFFER_POOL.with(|pool| {
                 let mut pool = pool.borrow_mut();
                 if pool.len() < POOL_SIZE {
-                    pool.push_back(self.0);
+                    pool.push_back(vec);
                 }
             });
         }
@@ -39,17 +368,17 @@ impl Buffer {
 
     /// Create an IoSlice from this buffer
     pub fn as_io_slice(&self) -> IoSlice<'_> {
-        IoSlice::new(&self.0)
+        IoSlice::new(self)
     }
 
     /// Get the length of the buffer
     pub fn len(&self) -> usize {
-        self.0.len()
+        self.as_ref().len()
     }
 
     /// Check if the buffer is empty
     pub fn is_empty(&self) -> bool {
-        self.0.is_empty()
+        self.len() == 0
     }
 
     /// Get a mutable pointer to the buffer's data.
@@ -57,36 +386,154 @@ impl Buffer {
     /// Caller must ensure the pointer is used within the buffer's bounds
     /// and that the buffer is not mutated by other means while the pointer is active.
     pub fn as_mut_ptr(&mut self) -> *mut u8 {
-        self.0.as_mut_ptr()
+        match &mut self.0 {
+            Repr::Registered(buf) => buf.region.slot_ptr(buf.index),
+            Repr::Aligned(buf) => buf.as_mut_ptr(),
+            _ => self.make_mut().as_mut_ptr(),
+        }
     }
 
     /// Get a mutable slice of the buffer's data.
     pub fn as_mut_slice(&mut self) -> &mut [u8] {
-        &mut self.0
+        if let Repr::Shared(_) = self.0 {
+            self.make_mut();
+        }
+        match &mut self.0 {
+            Repr::Owned(vec) => vec,
+            Repr::Aligned(buf) => buf,
+            Repr::Registered(buf) => buf.as_mut_slice(),
+            Repr::Shared(_) => unreachable!(),
+        }
     }
 
     /// Get a mutable slice up to the current length.
     pub fn as_mut_slice_len(&mut self) -> &mut [u8] {
-        &mut self.0[..] // Fix: remove .len() to avoid immutable borrow
+        self.as_mut_slice()
     }
 
     /// Copies data from a slice into the buffer.
     /// The buffer will be resized to match the slice's length.
     pub fn copy_from_slice(&mut self, slice: &[u8]) {
-        self.0.resize(slice.len(), 0);
-        self.0.copy_from_slice(slice);
+        match &mut self.0 {
+            Repr::Registered(buf) if slice.len() <= buf.region.buf_size => {
+                buf.len = slice.len();
+                buf.as_mut_slice().copy_from_slice(slice);
+                return;
+            }
+            Repr::Aligned(buf) if slice.len() <= buf.capacity() => {
+                buf.set_len(slice.len());
+                buf.copy_from_slice(slice);
+                return;
+            }
+            _ => {}
+        }
+        let vec = self.make_mut();
+        vec.resize(slice.len(), 0);
+        vec.copy_from_slice(slice);
     }
 
     /// Sets the length of the buffer.
     /// # Safety
     /// Caller must ensure that `new_len` is less than or equal to `capacity()`.
     pub unsafe fn set_len(&mut self, new_len: usize) {
-        self.0.set_len(new_len);
+        match &mut self.0 {
+            Repr::Registered(buf) => buf.len = new_len,
+            Repr::Aligned(buf) => buf.set_len(new_len),
+            _ => self.make_mut().set_len(new_len),
+        }
     }
 
     /// Returns the capacity of the buffer.
     pub fn capacity(&self) -> usize {
-        self.0.capacity()
+        match &self.0 {
+            Repr::Owned(vec) => vec.capacity(),
+            Repr::Shared(bytes) => bytes.len(),
+            Repr::Aligned(buf) => buf.capacity(),
+            Repr::Registered(buf) => buf.region.buf_size,
+        }
+    }
+
+    /// Returns true if the buffer's address is a multiple of `align`, as
+    /// direct I/O requires.
+    pub fn is_aligned_to(&self, align: usize) -> bool {
+        (self.as_ptr() as usize).is_multiple_of(align.max(1))
+    }
+
+    /// Returns true if the buffer lives in memory registered with the kernel
+    /// by the I/O backend, which lets it skip per-operation page mapping.
+    pub fn is_registered(&self) -> bool {
+        matches!(self.0, Repr::Registered(_))
+    }
+
+    /// The region and slot index of a registered buffer.
+    #[cfg_attr(not(io_backend = "io_uring"), allow(dead_code))]
+    pub(crate) fn registered_slot(&self) -> Option<(&Arc<RegisteredRegion>, u16)> {
+        match &self.0 {
+            Repr::Registered(buf) => Some((&buf.region, buf.index)),
+            _ => None,
+        }
+    }
+
+    /// Drops the first `cnt` bytes, e.g. the part of a write that already went out.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cnt > self.len()`.
+    pub fn advance(&mut self, cnt: usize) {
+        assert!(
+            cnt <= self.len(),
+            "cannot advance past the end of the buffer"
+        );
+        let buffer = std::mem::replace(self, Buffer(Repr::Shared(Bytes::new())));
+        self.0 = Repr::Shared(buffer.into_bytes().slice(cnt..));
+    }
+
+    /// Converts the buffer into `Bytes` without copying.
+    ///
+    /// A registered slot stays out of its region until the last `Bytes`
+    /// referencing it is dropped.
+    pub fn into_bytes(self) -> Bytes {
+        match self.0 {
+            Repr::Owned(vec) => Bytes::from(vec),
+            Repr::Shared(bytes) => bytes,
+            Repr::Aligned(buf) => Bytes::from_owner(buf),
+            Repr::Registered(buf) => Bytes::from_owner(buf),
+        }
+    }
+
+    /// Converts the buffer into a `Vec<u8>`, copying only if the data is shared
+    /// or registered.
+    pub fn into_vec(self) -> Vec<u8> {
+        match self.0 {
+            Repr::Owned(vec) => vec,
+            Repr::Shared(bytes) => Vec::from(bytes),
+            Repr::Aligned(buf) => buf.to_vec(),
+            Repr::Registered(buf) => buf.as_ref().to_vec(),
+        }
+    }
+
+    /// Returns the owned vector, first taking ownership of shared data (which
+    /// copies it only if it is referenced elsewhere) or copying a registered
+    /// slot out.
+    fn make_mut(&mut self) -> &mut Vec<u8> {
+        match &mut self.0 {
+            Repr::Owned(_) => {}
+            Repr::Shared(bytes) => self.0 = Repr::Owned(Vec::from(std::mem::take(bytes))),
+            Repr::Aligned(buf) => {
+                let mut vec = Vec::with_capacity(buf.capacity());
+                vec.extend_from_slice(buf);
+                self.0 = Repr::Owned(vec);
+            }
+            Repr::Registered(buf) => {
+                let mut vec = Vec::with_capacity(buf.region.buf_size);
+                vec.extend_from_slice(buf.as_ref());
+                self.0 = Repr::Owned(vec);
+            }
+        }
+        match &mut self.0 {
+            Repr::Owned(vec) => vec,
+            _ => unreachable!(),
+        }
     }
 }
 
@@ -94,13 +541,36 @@ impl Deref for Buffer {
     type Target = [u8];
 
     fn deref(&self) -> &[u8] {
-        &self.0
+        match &self.0 {
+            Repr::Owned(vec) => vec,
+            Repr::Shared(bytes) => bytes,
+            Repr::Aligned(buf) => buf,
+            Repr::Registered(buf) => buf.as_ref(),
+        }
     }
 }
 
 impl AsRef<[u8]> for Buffer {
     fn as_ref(&self) -> &[u8] {
-        &self.0
+        self
+    }
+}
+
+impl From<Vec<u8>> for Buffer {
+    fn from(vec: Vec<u8>) -> Self {
+        Buffer(Repr::Owned(vec))
+    }
+}
+
+impl From<Bytes> for Buffer {
+    fn from(bytes: Bytes) -> Self {
+        Buffer(Repr::Shared(bytes))
+    }
+}
+
+impl From<Buffer> for Bytes {
+    fn from(buffer: Buffer) -> Self {
+        buffer.into_bytes()
     }
 }
 
@@ -109,11 +579,22 @@ pub struct BufferPool;
 impl BufferPool {
     /// Get a buffer from the pool, or create a new one, with at least `capacity` bytes.
     /// The returned buffer will have its `len()` set to `capacity` and be zeroed.
+    ///
+    /// On a runtime thread whose backend registers fixed buffers, a free
+    /// registered buffer is preferred over the heap pool.
     pub fn get(capacity: usize) -> Buffer {
         // If the requested capacity is 0, or larger than our standard pool size,
         // or if we can't get a suitable buffer from the pool, create a new one.
         if capacity == 0 || capacity > BUFFER_SIZE {
-            return Buffer(vec![0; capacity]);
+            return Buffer::new_zeroed(capacity);
+        }
+
+        if let Some(mut buffer) = Self::registered(capacity) {
+            // SAFETY: the backend only hands out slots of at least `capacity`
+            // bytes, which are zeroed just below.
+            unsafe { buffer.set_len(capacity) };
+            buffer.as_mut_slice().fill(0);
+            return buffer;
         }
 
         CPU_BUFFER_POOL.with(|pool| {
@@ -124,13 +605,23 @@ impl BufferPool {
                 // Ensure the buffer is large enough and zeroed up to 'capacity'
                 if buffer.capacity() >= capacity {
                     buffer.resize(capacity, 0);
-                    return Buffer(buffer);
+                    return Buffer::from(buffer);
                 }
                 // If the popped buffer is too small or has wrong capacity,
                 // just drop it and create a new one. This might happen if
                 // a recycled buffer was not `BUFFER_SIZE` originally.
             }
-            Buffer(vec![0; capacity])
+            Buffer::new_zeroed(capacity)
+        })
+    }
+}
+
+impl BufferPool {
+    /// Takes a fixed buffer from the current runtime thread's I/O backend.
+    fn registered(capacity: usize) -> Option<Buffer> {
+        crate::cpu::CURRENT_CPU_IO_STATE.with(|cell| {
+            let state = cell.try_borrow().ok()?;
+            state.as_ref()?.io_backend.fixed_buffer(capacity)
         })
     }
 }
@@ -203,4 +694,83 @@ mod tests {
             assert!(pool.len() <= POOL_SIZE);
         });
     }
+
+    #[test]
+    fn test_buffer_from_vec_and_bytes_do_not_copy() {
+        let vec = vec![1u8, 2, 3];
+        let ptr = vec.as_ptr();
+        let buffer = Buffer::from(vec);
+        assert_eq!(buffer.as_ptr(), ptr);
+
+        let bytes = buffer.into_bytes();
+        assert_eq!(bytes.as_ptr(), ptr);
+
+        let buffer = Buffer::from(bytes.clone());
+        assert_eq!(buffer.as_ptr(), ptr);
+        assert_eq!(&buffer[..], &[1, 2, 3]);
+    }
+
+    #[test]
+    fn test_buffer_advance() {
+        let mut buffer = Buffer::from(b"hello world".to_vec());
+        buffer.advance(6);
+        assert_eq!(&buffer[..], b"world");
+        assert_eq!(buffer.len(), 5);
+
+        // Mutating a shared buffer takes ownership of the remaining bytes
+        buffer.as_mut_slice()[0] = b'W';
+        assert_eq!(&buffer[..], b"World");
+    }
+
+    #[test]
+    fn test_registered_slots_return_to_region() {
+        let region = RegisteredRegion::new(64, 2, true);
+        let mut first = region.take().unwrap();
+        let second = region.take().unwrap();
+        assert!(region.take().is_none());
+
+        first.copy_from_slice(b"registered");
+        assert!(first.is_registered());
+        assert_eq!(first.capacity(), 64);
+        assert_eq!(&first[..], b"registered");
+
+        // Cloning copies out instead of taking another slot
+        let copy = first.clone();
+        assert!(!copy.is_registered());
+        assert_eq!(&copy[..], b"registered");
+
+        drop(second);
+        let third = region.take().unwrap();
+        assert!(region.take().is_none());
+        drop(third);
+
+        // A slot converted to `Bytes` is released with the last reference
+        let _spare = region.take().unwrap();
+        let bytes = first.into_bytes();
+        assert_eq!(&bytes[..], b"registered");
+        assert!(region.pop_free().is_none());
+        drop(bytes);
+        assert!(region.pop_free().is_some());
+    }
+
+    #[test]
+    fn test_aligned_buffer_keeps_alignment() {
+        let mut buffer = Buffer::from(AlignedBuffer::new(100, 4096));
+        assert!(buffer.is_aligned_to(4096));
+        assert_eq!(buffer.capacity(), 4096);
+        let ptr = buffer.as_ptr();
+
+        // Writing in place and cloning both stay aligned
+        buffer.copy_from_slice(b"direct");
+        assert_eq!(buffer.as_ptr(), ptr);
+        assert_eq!(&buffer[..], b"direct");
+        let copy = buffer.clone();
+        assert!(copy.is_aligned_to(4096));
+        assert_eq!(&copy[..], b"direct");
+
+        // Converting to `Bytes` hands over the allocation
+        let bytes = buffer.into_bytes();
+        assert_eq!(bytes.as_ptr(), ptr);
+        assert_eq!(&bytes[..], b"direct");
+    }
 }
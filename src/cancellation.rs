@@ -3,13 +3,25 @@
 //! This module provides utilities for implementing cooperative task cancellation.
 //! Tasks should periodically check for cancellation and exit early when requested.
 
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::task::Waker;
 
 /// A token that can be used to signal cancellation to a task
 #[derive(Clone, Debug)]
 pub struct CancellationToken {
     cancelled: Arc<AtomicBool>,
+    /// Wakers of the futures waiting on this token, woken by `cancel`
+    waiters: Arc<Mutex<Waiters>>,
+}
+
+/// Waiting futures by registration key, so a dropped future takes its waker
+/// with it instead of leaving it until the token is cancelled.
+#[derive(Debug, Default)]
+struct Waiters {
+    next_key: u64,
+    wakers: HashMap<u64, Waker>,
 }
 
 impl CancellationToken {
@@ -17,18 +29,48 @@ impl CancellationToken {
     pub fn new() -> Self {
         Self {
             cancelled: Arc::new(AtomicBool::new(false)),
+            waiters: Arc::new(Mutex::new(Waiters::default())),
         }
     }
 
     /// Cancel the token
     pub fn cancel(&self) {
         self.cancelled.store(true, Ordering::SeqCst);
+        let waiters = std::mem::take(&mut self.waiters.lock().unwrap().wakers);
+        for waker in waiters.into_values() {
+            waker.wake();
+        }
     }
 
     /// Check if cancellation has been requested
     pub fn is_cancelled(&self) -> bool {
         self.cancelled.load(Ordering::SeqCst)
     }
+
+    /// Register (or re-register with a new waker) a future to be woken when
+    /// the token is cancelled. `key` identifies the registration and is
+    /// assigned on first use.
+    pub(crate) fn register(&self, key: &mut Option<u64>, waker: &Waker) {
+        let mut waiters = self.waiters.lock().unwrap();
+        let key = *key.get_or_insert_with(|| {
+            waiters.next_key += 1;
+            waiters.next_key
+        });
+        match waiters.wakers.get_mut(&key) {
+            Some(registered) if registered.will_wake(waker) => {}
+            Some(registered) => *registered = waker.clone(),
+            None => {
+                waiters.wakers.insert(key, waker.clone());
+            }
+        }
+    }
+
+    /// Remove the registration under `key`, if any
+    pub(crate) fn deregister(&self, key: &mut Option<u64>) {
+        if let Some(key) = key.take() {
+            self.waiters.lock().unwrap().wakers.remove(&key);
+        }
+    }
 }
 
 impl Default for CancellationToken {
@@ -41,7 +83,11 @@ impl Default for CancellationToken {
 pub trait CancellableFutureExt<T>: Sized {
     /// Wrap the future with a cancellation token
     fn cancellable(self, token: CancellationToken) -> CancellableFuture<Self> {
-        CancellableFuture { inner: self, token }
+        CancellableFuture {
+            inner: self,
+            token,
+            key: None,
+        }
     }
 }
 
@@ -51,6 +97,14 @@ impl<F, T> CancellableFutureExt<T> for F where F: std::future::Future<Output = T
 pub struct CancellableFuture<F> {
     inner: F,
     token: CancellationToken,
+    /// Registration with the token, once polled
+    key: Option<u64>,
+}
+
+impl<F> Drop for CancellableFuture<F> {
+    fn drop(&mut self) {
+        self.token.deregister(&mut self.key);
+    }
 }
 
 impl<F, T> std::future::Future for CancellableFuture<F>
@@ -73,7 +127,13 @@ where
         // Poll the inner future
         match unsafe { std::pin::Pin::new_unchecked(&mut this.inner) }.poll(cx) {
             std::task::Poll::Ready(value) => std::task::Poll::Ready(Ok(value)),
-            std::task::Poll::Pending => std::task::Poll::Pending,
+            std::task::Poll::Pending => {
+                this.token.register(&mut this.key, cx.waker());
+                if this.token.is_cancelled() {
+                    return std::task::Poll::Ready(Err(crate::task::TaskError::Cancelled));
+                }
+                std::task::Poll::Pending
+            }
         }
     }
 }
@@ -27,8 +27,28 @@ pub const CPU_THREAD_TIMEOUT_MS: u64 = 10;
 /// This should be set based on expected concurrent task count per CPU.
 pub const INITIAL_TASK_QUEUE_CAPACITY: usize = 128;
 
+/// Backlog passed to `listen(2)` for sockets created by the runtime
+///
+/// The kernel silently caps this at `net.core.somaxconn`. With one
+/// `SO_REUSEPORT` listener per core, each core gets its own queue of this size.
+pub const LISTEN_BACKLOG: i32 = 1024;
+
 /// Expected number of wakeups per timer expiration cycle
 ///
 /// This optimizes the Vec allocation for expired timer wakers.
 /// Most workloads have few simultaneous timer expirations.
 pub const EXPECTED_WAKEUP_COUNT: usize = 16;
+
+/// Number of fixed buffers the io_uring backend registers per ring
+///
+/// Each is `BUFFER_SIZE` bytes. `BufferPool::get` hands these out first on a
+/// runtime thread, and reads and writes using them skip per-operation page
+/// mapping in the kernel. Once all are in use, ordinary buffers are used.
+pub const FIXED_BUFFER_COUNT: u16 = 64;
+
+/// Number of buffers in the io_uring backend's provided buffer ring
+///
+/// Socket reads pick one of these `BUFFER_SIZE` buffers only when data
+/// arrives, so idle connections hold no read buffer. Must be a power of two.
+/// Reads that find the ring empty fall back to ordinary buffers.
+pub const PROVIDED_BUFFER_COUNT: u16 = 256;
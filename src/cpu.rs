@@ -1,5 +1,5 @@
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::atomic::{AtomicU64, Ordering};
@@ -11,7 +11,8 @@ use std::time::{Duration, Instant};
 use crossbeam_channel::{Receiver, Sender};
 use crossbeam_queue::SegQueue;
 
-use crate::io::{CompletionKind, IoError, IoProvider, IoToken, Op};
+use crate::blocking::BlockingPool;
+use crate::io::{CompletionKind, IoError, IoProvider, IoToken, Notifier, Op};
 use crate::task::{JoinHandle, Task};
 use crate::timer::TimerWheel;
 use crate::waker::{MinissWaker, TaskId};
@@ -30,6 +31,58 @@ pub struct CpuIoState {
     /// Results of completed I/O operations.
     /// Keyed by the `IoToken`. An `IoFuture`, once woken, will check this map for its result.
     pub completed_io: Mutex<HashMap<IoToken, Result<CompletionKind, IoError>>>,
+    /// Tokens of the operations a live `IoFuture` waits for. Completions of
+    /// any other single-shot operation are discarded.
+    pub awaited_io: Mutex<HashSet<IoToken>>,
+    /// Queued results of multishot operations, keyed by the `IoToken` of the
+    /// `IoStream` consuming them.
+    pub streams: Mutex<HashMap<IoToken, VecDeque<Result<CompletionKind, IoError>>>>,
+    /// The runtime's pool for blocking work, shared by all of its CPUs.
+    pub blocking_pool: Arc<BlockingPool>,
+}
+
+impl CpuIoState {
+    /// Hands drained completions to the futures and streams waiting for them,
+    /// waking their tasks.
+    pub(crate) fn complete(
+        &self,
+        completions: &mut Vec<(IoToken, Op, Result<CompletionKind, IoError>)>,
+    ) {
+        let mut wakers = self.io_wakers.lock().unwrap();
+        let mut completed = self.completed_io.lock().unwrap();
+        let mut streams = self.streams.lock().unwrap();
+        let awaited = self.awaited_io.lock().unwrap();
+        for (token, op, result) in completions.drain(..) {
+            if op.is_multishot() {
+                match streams.get_mut(&token) {
+                    Some(queue) => queue.push_back(result),
+                    // The stream was dropped while its cancellation was in flight
+                    None => {
+                        discard_completion(result);
+                        continue;
+                    }
+                }
+            } else if awaited.contains(&token) {
+                completed.insert(token, result);
+            } else {
+                // Its future was dropped, cancelling the operation
+                discard_completion(result);
+                continue;
+            }
+            if let Some(waker) = wakers.remove(&token) {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// Releases what an unconsumed completion owns: accepted connections and
+/// opened files are closed.
+pub(crate) fn discard_completion(result: Result<CompletionKind, IoError>) {
+    if let Ok(CompletionKind::Accept { fd, .. } | CompletionKind::Open { fd }) = result {
+        // SAFETY: nobody else has seen the new descriptor.
+        unsafe { libc::close(fd) };
+    }
 }
 
 impl Default for CpuIoState {
@@ -38,6 +91,9 @@ impl Default for CpuIoState {
             io_backend: Arc::new(crate::io::DummyIoBackend::new()),
             io_wakers: Mutex::new(HashMap::new()),
             completed_io: Mutex::new(HashMap::new()),
+            awaited_io: Mutex::new(HashSet::new()),
+            streams: Mutex::new(HashMap::new()),
+            blocking_pool: BlockingPool::shared(),
         }
     }
 }
@@ -48,6 +104,7 @@ impl std::fmt::Debug for CpuIoState {
             .field("io_backend", &"<IoBackend>")
             .field("io_wakers", &self.io_wakers)
             .field("completed_io", &self.completed_io)
+            .field("awaited_io", &self.awaited_io)
             .finish()
     }
 }
@@ -70,6 +127,16 @@ pub fn io_state() -> Arc<CpuIoState> {
     })
 }
 
+/// The blocking pool of the runtime running on this thread, or the shared
+/// pool outside a runtime.
+pub(crate) fn current_blocking_pool() -> Arc<BlockingPool> {
+    CURRENT_CPU_IO_STATE.with(|cell| {
+        cell.borrow()
+            .as_ref()
+            .map_or_else(BlockingPool::shared, |state| state.blocking_pool.clone())
+    })
+}
+
 /// Sets the current CPU I/O state for the current thread.
 /// This is used to initialize the runtime context for single-threaded execution.
 pub fn set_current_io_state(io_state: Arc<CpuIoState>) {
@@ -105,6 +172,10 @@ pub struct Cpu {
     io_backend: Arc<dyn IoProvider<Completion = (IoToken, Op, Result<CompletionKind, IoError>)>>,
     // New field for I/O state
     io_state: Arc<CpuIoState>,
+    // Interrupts the backend's blocking wait; signalled by wakers and senders
+    notifier: Option<Arc<Notifier>>,
+    // Reused for every drain of the I/O backend's completions
+    completions: Vec<(IoToken, Op, Result<CompletionKind, IoError>)>,
     // Task cancellation tracking
     cancelled_tasks: Arc<Mutex<HashMap<TaskId, ()>>>,
 }
@@ -145,6 +216,7 @@ impl std::fmt::Debug for CrossCpuMessage {
 pub struct CpuHandle {
     pub cpu_id: usize,
     sender: Sender<CrossCpuMessage>,
+    notifier: Option<Arc<Notifier>>,
     thread_handle: Option<ThreadJoinHandle<()>>,
 }
 
@@ -160,6 +232,9 @@ impl Cpu {
             io_backend: io_backend.clone(),
             io_wakers: Mutex::new(HashMap::new()),
             completed_io: Mutex::new(HashMap::new()),
+            awaited_io: Mutex::new(HashSet::new()),
+            streams: Mutex::new(HashMap::new()),
+            blocking_pool: BlockingPool::shared(),
         });
         Self {
             id,
@@ -169,6 +244,8 @@ impl Cpu {
             next_task_id: AtomicU64::new((id as u64) << 32),
             timer: TimerWheel::default(),
             running: true,
+            notifier: io_backend.notifier(),
+            completions: Vec::new(),
             io_backend,
             io_state,
             cancelled_tasks: Arc::new(Mutex::new(HashMap::new())),
@@ -179,6 +256,20 @@ impl Cpu {
         TaskId(self.next_task_id.fetch_add(1, Ordering::SeqCst))
     }
 
+    /// The notifier that wakes this CPU while it blocks in its I/O backend.
+    /// Pass it to [`CpuHandle::set_notifier`] so submitted messages wake it.
+    pub fn notifier(&self) -> Option<Arc<Notifier>> {
+        self.notifier.clone()
+    }
+
+    fn waker(&self, task_id: TaskId) -> Waker {
+        MinissWaker::create_waker_with_notifier(
+            task_id,
+            self.ready_queue.clone(),
+            self.notifier.clone(),
+        )
+    }
+
     pub fn spawn<F, T>(&mut self, future: F) -> JoinHandle<T>
     where
         F: Future<Output = T> + Send + 'static,
@@ -186,14 +277,19 @@ impl Cpu {
     {
         let task_id = self.next_task_id();
         let (sender, receiver) = crossbeam_channel::bounded(1);
+        let handle = JoinHandle::new(task_id, receiver);
+        let waiter = handle.waiter();
         let wrapped_future = async move {
             let result = future.await;
             let _ = sender.send(Ok(result));
+            if let Some(waker) = waiter.lock().unwrap().take() {
+                waker.wake();
+            }
         };
         let task = Task::new(task_id, wrapped_future);
         self.task_queue.insert(task_id, task);
         self.ready_queue.push(task_id);
-        JoinHandle::new(task_id, receiver)
+        handle
     }
 
     fn process_messages(&mut self) {
@@ -232,24 +328,11 @@ impl Cpu {
 
     /// Polls the I/O backend for completed operations and wakes the corresponding tasks.
     fn poll_io_completions(&mut self) -> bool {
-        let mut made_progress = false;
-        let waker = futures::task::noop_waker();
-        let mut context = Context::from_waker(&waker);
-
-        if let Poll::Ready(completions) = self.io_backend.poll_complete(&mut context) {
-            if !completions.is_empty() {
-                made_progress = true;
-                let mut wakers = self.io_state.io_wakers.lock().unwrap();
-                let mut completed = self.io_state.completed_io.lock().unwrap();
-                for (token, _op, result) in completions {
-                    completed.insert(token, result);
-                    if let Some(waker) = wakers.remove(&token) {
-                        waker.wake();
-                    }
-                }
-            }
+        if self.io_backend.drain_completions(&mut self.completions) == 0 {
+            return false;
         }
-        made_progress
+        self.io_state.complete(&mut self.completions);
+        true
     }
 
     pub fn tick(&mut self) -> bool {
@@ -265,6 +348,9 @@ impl Cpu {
                 waker.wake();
             }
         }
+        if crate::timer::wake_expired(now) {
+            made_progress = true;
+        }
 
         while let Some(task_id) = self.ready_queue.pop() {
             // Check if the task has been cancelled
@@ -287,7 +373,7 @@ impl Cpu {
 
             if let Some(mut task) = self.task_queue.remove(&task_id) {
                 made_progress = true;
-                let waker = MinissWaker::create_waker(task_id, self.ready_queue.clone());
+                let waker = self.waker(task_id);
                 let mut context = Context::from_waker(&waker);
 
                 match task.poll(&mut context) {
@@ -307,7 +393,7 @@ impl Cpu {
     }
 
     pub fn schedule_timer(&mut self, at: Instant, task_id: TaskId) {
-        let waker = MinissWaker::create_waker(task_id, self.ready_queue.clone());
+        let waker = self.waker(task_id);
         self.timer.schedule(at, waker);
     }
 
@@ -322,16 +408,7 @@ impl Cpu {
             self.tick();
 
             if self.ready_queue.is_empty() {
-                match self
-                    .message_receiver
-                    .recv_timeout(Duration::from_millis(crate::config::CPU_THREAD_TIMEOUT_MS))
-                {
-                    Ok(msg) => self.handle_message(msg),
-                    Err(crossbeam_channel::RecvTimeoutError::Disconnected) => {
-                        self.running = false;
-                    }
-                    Err(crossbeam_channel::RecvTimeoutError::Timeout) => {}
-                }
+                self.wait_for_work();
             } else {
                 while let Ok(msg) = self.message_receiver.try_recv() {
                     self.handle_message(msg);
@@ -345,6 +422,29 @@ impl Cpu {
         tracing::info!("CPU {} shutting down", self.id);
     }
 
+    /// Blocks in the I/O backend until an I/O completion, a wakeup, a message
+    /// or the next timer deadline, for at most `CPU_THREAD_TIMEOUT_MS`.
+    fn wait_for_work(&mut self) {
+        if let Some(notifier) = &self.notifier {
+            notifier.clear();
+        }
+        // Messages and wakeups that arrived before the clear left no signal
+        match self.message_receiver.try_recv() {
+            Ok(msg) => return self.handle_message(msg),
+            Err(crossbeam_channel::TryRecvError::Disconnected) => {
+                self.running = false;
+                return;
+            }
+            Err(crossbeam_channel::TryRecvError::Empty) => {}
+        }
+        if !self.ready_queue.is_empty() {
+            return;
+        }
+        let max = Duration::from_millis(crate::config::CPU_THREAD_TIMEOUT_MS);
+        let timeout = crate::timer::time_until_next_deadline().map_or(max, |t| t.min(max));
+        self.io_backend.wait(Some(timeout));
+    }
+
     #[cfg(target_os = "linux")]
     fn set_cpu_affinity(&self) {
         use nix::sched::{sched_setaffinity, CpuSet};
@@ -370,6 +470,7 @@ impl CpuHandle {
         let handle = Self {
             cpu_id,
             sender,
+            notifier: None,
             thread_handle: None,
         };
         (handle, receiver)
@@ -387,26 +488,42 @@ impl CpuHandle {
             task_id,
             task: Box::new(task),
         };
-        self.sender.send(message).map(|_| task_id)
+        self.send(message).map(|_| task_id)
     }
 
     pub fn shutdown(&self) -> Result<(), crossbeam_channel::SendError<CrossCpuMessage>> {
-        self.sender.send(CrossCpuMessage::Shutdown)
+        self.send(CrossCpuMessage::Shutdown)
     }
 
     pub fn ping(
         &self,
         from_cpu: usize,
     ) -> Result<(), crossbeam_channel::SendError<CrossCpuMessage>> {
-        self.sender
-            .send(CrossCpuMessage::Ping { reply_to: from_cpu })
+        self.send(CrossCpuMessage::Ping { reply_to: from_cpu })
     }
 
     pub fn cancel_task(
         &self,
         task_id: TaskId,
     ) -> Result<(), crossbeam_channel::SendError<CrossCpuMessage>> {
-        self.sender.send(CrossCpuMessage::CancelTask(task_id))
+        self.send(CrossCpuMessage::CancelTask(task_id))
+    }
+
+    /// Set the notifier of the CPU this handle sends to (see [`Cpu::notifier`]),
+    /// so that messages wake it while it blocks in its I/O backend.
+    pub fn set_notifier(&mut self, notifier: Option<Arc<Notifier>>) {
+        self.notifier = notifier;
+    }
+
+    fn send(
+        &self,
+        message: CrossCpuMessage,
+    ) -> Result<(), crossbeam_channel::SendError<CrossCpuMessage>> {
+        self.sender.send(message)?;
+        if let Some(notifier) = &self.notifier {
+            notifier.notify();
+        }
+        Ok(())
     }
 
     /// Get a reference to the sender for broadcasting messages
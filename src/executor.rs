@@ -19,7 +19,7 @@
 //! `TaskError::Panic` contains the panic payload for analysis.
 
 use crossbeam_queue::SegQueue;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::future::Future;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
@@ -98,7 +98,7 @@ impl Runtime {
 
         #[cfg(all(target_os = "linux", io_backend = "epoll"))]
         let io_backend = {
-            match crate::io::epoll::EpollBackend::new() {
+            match crate::io::epoll::EpollBackend::new(crate::blocking::BlockingPool::shared()) {
                 Ok(epoll) => Arc::new(epoll)
                     as Arc<
                         dyn IoProvider<
@@ -135,6 +135,9 @@ impl Runtime {
             io_backend,
             io_wakers: Mutex::new(HashMap::new()),
             completed_io: Mutex::new(HashMap::new()),
+            awaited_io: Mutex::new(HashSet::new()),
+            streams: Mutex::new(HashMap::new()),
+            blocking_pool: crate::blocking::BlockingPool::shared(),
         });
 
         // Set the current I/O state
@@ -143,50 +146,56 @@ impl Runtime {
         // Pin the future to the stack
         let mut future = Box::pin(future);
 
-        // Waker that unparks the current thread when the future makes progress.
-        // This uses the `ArcWake` trait to create a waker that holds a reference
-        // to the thread parker.
-        struct Parker(std::thread::Thread);
-        impl futures::task::ArcWake for Parker {
+        // Waker that records the wakeup and signals the backend's notifier, so
+        // a thread blocked in `IoProvider::wait` returns to poll the future.
+        struct BlockOnWaker {
+            woken: AtomicBool,
+            notifier: Option<Arc<crate::io::Notifier>>,
+        }
+        impl futures::task::ArcWake for BlockOnWaker {
             fn wake_by_ref(arc_self: &Arc<Self>) {
-                arc_self.0.unpark();
+                arc_self.woken.store(true, Ordering::Release);
+                if let Some(notifier) = &arc_self.notifier {
+                    notifier.notify();
+                }
             }
         }
-        let waker = futures::task::waker(Arc::new(Parker(std::thread::current())));
+        let notifier = io_state.io_backend.notifier();
+        let wake_state = Arc::new(BlockOnWaker {
+            woken: AtomicBool::new(true),
+            notifier: notifier.clone(),
+        });
+        let waker = futures::task::waker(wake_state.clone());
         let mut context = Context::from_waker(&waker);
+        let mut completions = Vec::new();
 
-        // Poll the future until it completes, parking the thread when pending
+        // Poll the future whenever it is woken, blocking in the backend otherwise
         loop {
-            match future.as_mut().poll(&mut context) {
-                Poll::Ready(output) => {
+            if wake_state.woken.swap(false, Ordering::AcqRel) {
+                if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
                     clear_current_io_state();
                     return output;
                 }
-                Poll::Pending => {
-                    // Check for I/O completions
-                    let noop_waker = futures::task::noop_waker();
-                    let mut io_context = Context::from_waker(&noop_waker);
-
-                    // Poll the I/O backend for completions
-                    if let Poll::Ready(completions) =
-                        io_state.io_backend.poll_complete(&mut io_context)
-                    {
-                        if !completions.is_empty() {
-                            // Process completions
-                            let mut completed = io_state.completed_io.lock().unwrap();
-                            let mut wakers = io_state.io_wakers.lock().unwrap();
-                            for (token, _op, result) in completions {
-                                completed.insert(token, result);
-                                if let Some(waker) = wakers.remove(&token) {
-                                    waker.wake();
-                                }
-                            }
-                        }
-                    }
+            }
 
-                    // Park the thread until it is woken
-                    std::thread::park_timeout(std::time::Duration::from_millis(1));
-                }
+            // Submit queued I/O and process completions
+            if io_state.io_backend.drain_completions(&mut completions) > 0 {
+                io_state.complete(&mut completions);
+            }
+
+            crate::timer::wake_expired(std::time::Instant::now());
+            if wake_state.woken.load(Ordering::Acquire) {
+                continue;
+            }
+
+            // Sleep until I/O completes, a waker fires or the next timer is due
+            if let Some(notifier) = &notifier {
+                notifier.clear();
+            }
+            if !wake_state.woken.load(Ordering::Acquire) {
+                io_state
+                    .io_backend
+                    .wait(crate::timer::time_until_next_deadline());
             }
         }
     }
@@ -266,11 +275,17 @@ impl Executor {
 
         let (sender, receiver) = crossbeam_channel::bounded(1);
 
+        let handle = JoinHandle::new(task_id, receiver);
+        let waiter = handle.waiter();
+
         // Wrap the user's future to send result through channel
         // Panics will be caught at the polling level in tick()
         let wrapped_future = async move {
             let result = future.await;
             let _ = sender.send(Ok(result));
+            if let Some(waker) = waiter.lock().unwrap().take() {
+                waker.wake();
+            }
         };
 
         // Create the task
@@ -280,7 +295,7 @@ impl Executor {
         self.tasks.insert(task_id, task);
         self.ready_queue.push(task_id);
 
-        JoinHandle::new(task_id, receiver)
+        handle
     }
 
     /// Run all ready tasks once
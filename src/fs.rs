@@ -9,12 +9,317 @@
 //! The implementation ensures memory safety and proper lifecycle management
 //! for asynchronous operations across all backends.
 
+use crate::buffer::AlignedBuffer;
 use crate::cpu::io_state;
-use crate::io::{future::IoFuture, CompletionKind, Op};
+use crate::io::{future::IoFuture, CompletionKind, IoError, Op};
+use bytes::Bytes;
 use nix::fcntl::{fcntl, FcntlArg, OFlag};
+use std::collections::VecDeque;
+use std::ffi::{CString, OsString};
 use std::io;
-use std::os::unix::io::{AsRawFd, IntoRawFd, RawFd};
-use std::path::Path;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Options for opening a file with [`OpenOptions::open`], mirroring
+/// `std::fs::OpenOptions`.
+///
+/// # Examples
+///
+/// ```
+/// use rust_miniss::{fs::OpenOptions, Runtime};
+///
+/// let runtime = Runtime::new();
+/// runtime.block_on(async {
+///     let options = OpenOptions {
+///         write: true,
+///         append: true,
+///         create: true,
+///         ..Default::default()
+///     };
+///     let file = options.open("/tmp/test_log.txt").await.expect("Failed to open file");
+/// });
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpenOptions {
+    /// Open for reading. Defaults to `true`.
+    pub read: bool,
+    /// Open for writing.
+    pub write: bool,
+    /// Open for writing at the end of the file (`O_APPEND`).
+    pub append: bool,
+    /// Truncate an existing file to length 0 (`O_TRUNC`). Needs `write`.
+    pub truncate: bool,
+    /// Create the file if it does not exist. Needs `write` or `append`.
+    pub create: bool,
+    /// Create the file, failing if it exists (`O_CREAT | O_EXCL`). Needs
+    /// `write` or `append`; overrides `create` and `truncate`.
+    pub create_new: bool,
+    /// Bypass the page cache (`O_DIRECT`, Linux only). Offsets, lengths and
+    /// buffer addresses must then be aligned to the device's block size.
+    pub direct: bool,
+    /// Complete every write only once its data is on storage (`O_DSYNC`).
+    pub dsync: bool,
+    /// Permission bits of a created file, before the umask. Defaults to `0o666`.
+    pub mode: u32,
+}
+
+impl Default for OpenOptions {
+    fn default() -> Self {
+        Self {
+            read: true,
+            write: false,
+            append: false,
+            truncate: false,
+            create: false,
+            create_new: false,
+            direct: false,
+            dsync: false,
+            mode: 0o666,
+        }
+    }
+}
+
+impl OpenOptions {
+    /// The `open(2)` flags for these options, rejecting the combinations
+    /// `std::fs::OpenOptions` rejects.
+    fn flags(&self) -> io::Result<i32> {
+        let invalid = |msg| Err(io::Error::new(io::ErrorKind::InvalidInput, msg));
+        let writable = self.write || self.append;
+        let mut flags = match (self.read, writable) {
+            (true, false) => libc::O_RDONLY,
+            (false, true) => libc::O_WRONLY,
+            (true, true) => libc::O_RDWR,
+            (false, false) => return invalid("file must be opened for reading or writing"),
+        };
+        if (self.create || self.create_new || self.truncate) && !writable {
+            return invalid("creating or truncating a file requires write access");
+        }
+        if self.truncate && self.append && !self.create_new {
+            return invalid("a file cannot be both truncated and appended to");
+        }
+        if self.append {
+            flags |= libc::O_APPEND;
+        }
+        if self.create_new {
+            flags |= libc::O_CREAT | libc::O_EXCL;
+        } else {
+            if self.create {
+                flags |= libc::O_CREAT;
+            }
+            if self.truncate {
+                flags |= libc::O_TRUNC;
+            }
+        }
+        if self.direct {
+            #[cfg(target_os = "linux")]
+            {
+                flags |= libc::O_DIRECT;
+            }
+            #[cfg(not(target_os = "linux"))]
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "O_DIRECT is only available on Linux",
+            ));
+        }
+        if self.dsync {
+            flags |= libc::O_DSYNC;
+        }
+        Ok(flags)
+    }
+
+    /// Opens the file at `path` with these options (`openat(2)`).
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the file to open
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(AsyncFile)` - Successfully opened file handle
+    /// * `Err(io::Error)` - Invalid options, or failed to open the file
+    pub async fn open<P: AsRef<Path>>(&self, path: P) -> io::Result<AsyncFile> {
+        let op = Op::OpenAt {
+            path: path_to_cstring(path.as_ref())?,
+            flags: self.flags()?,
+            mode: self.mode,
+        };
+        let mut file = match submit(op).await? {
+            // SAFETY: the descriptor was just opened and is owned by nothing else.
+            CompletionKind::Open { fd } => AsyncFile {
+                inner: unsafe { std::fs::File::from_raw_fd(fd) },
+                direct: None,
+            },
+            _ => return Err(unexpected_completion()),
+        };
+        if self.direct {
+            file.direct = Some(DirectIoAlignment::of(&file.metadata().await?));
+        }
+        Ok(file)
+    }
+}
+
+/// Metadata of a file, as returned by [`AsyncFile::metadata`] and
+/// [`metadata`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Metadata {
+    /// Size in bytes.
+    pub len: u64,
+    /// File type and permission bits (`st_mode`).
+    pub mode: u32,
+    /// Inode number.
+    pub ino: u64,
+    /// Device containing the file.
+    pub dev: u64,
+    /// Number of hard links.
+    pub nlink: u64,
+    /// Owner's user id.
+    pub uid: u32,
+    /// Owner's group id.
+    pub gid: u32,
+    /// Preferred I/O block size.
+    pub blksize: u64,
+    /// Number of 512-byte blocks allocated.
+    pub blocks: u64,
+    /// Time of last access.
+    pub accessed: SystemTime,
+    /// Time of last modification.
+    pub modified: SystemTime,
+    /// Time of creation, where the filesystem records it.
+    pub created: Option<SystemTime>,
+    /// Alignment `O_DIRECT` requires of buffer addresses, or 0 where the
+    /// kernel does not report it (before Linux 6.1, or off Linux).
+    pub dio_mem_align: u32,
+    /// Alignment `O_DIRECT` requires of file offsets and transfer lengths, or 0
+    /// where the kernel does not report it.
+    pub dio_offset_align: u32,
+}
+
+/// The fields [`Metadata`] asks `statx(2)` for.
+#[cfg(target_os = "linux")]
+pub(crate) const STATX_MASK: u32 =
+    libc::STATX_BASIC_STATS | libc::STATX_BTIME | libc::STATX_DIOALIGN;
+
+// `mode_t` and the field types of `stat` differ between platforms
+#[allow(clippy::unnecessary_cast)]
+impl Metadata {
+    /// Whether this is a regular file.
+    pub fn is_file(&self) -> bool {
+        self.file_type() == libc::S_IFREG as u32
+    }
+
+    /// Whether this is a directory.
+    pub fn is_dir(&self) -> bool {
+        self.file_type() == libc::S_IFDIR as u32
+    }
+
+    /// Whether this is a symbolic link; only possible for
+    /// [`symlink_metadata`].
+    pub fn is_symlink(&self) -> bool {
+        self.file_type() == libc::S_IFLNK as u32
+    }
+
+    /// Permission bits, including setuid, setgid and sticky.
+    pub fn permissions(&self) -> u32 {
+        self.mode & 0o7777
+    }
+
+    fn file_type(&self) -> u32 {
+        self.mode & libc::S_IFMT as u32
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(crate) fn from_stat(stat: &libc::stat) -> Self {
+        Self {
+            len: stat.st_size as u64,
+            mode: stat.st_mode as u32,
+            ino: stat.st_ino as u64,
+            dev: stat.st_dev as u64,
+            nlink: stat.st_nlink as u64,
+            uid: stat.st_uid,
+            gid: stat.st_gid,
+            blksize: stat.st_blksize as u64,
+            blocks: stat.st_blocks as u64,
+            accessed: system_time(stat.st_atime as i64, stat.st_atime_nsec as i64),
+            modified: system_time(stat.st_mtime as i64, stat.st_mtime_nsec as i64),
+            created: None,
+            dio_mem_align: 0,
+            dio_offset_align: 0,
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(crate) fn from_statx(statx: &libc::statx) -> Self {
+        let timestamp = |ts: &libc::statx_timestamp| system_time(ts.tv_sec, ts.tv_nsec as i64);
+        let dio = statx.stx_mask & libc::STATX_DIOALIGN != 0;
+        Self {
+            len: statx.stx_size,
+            mode: statx.stx_mode as u32,
+            ino: statx.stx_ino,
+            dev: libc::makedev(statx.stx_dev_major, statx.stx_dev_minor),
+            nlink: statx.stx_nlink as u64,
+            uid: statx.stx_uid,
+            gid: statx.stx_gid,
+            blksize: statx.stx_blksize as u64,
+            blocks: statx.stx_blocks,
+            accessed: timestamp(&statx.stx_atime),
+            modified: timestamp(&statx.stx_mtime),
+            created: (statx.stx_mask & libc::STATX_BTIME != 0).then(|| timestamp(&statx.stx_btime)),
+            dio_mem_align: if dio { statx.stx_dio_mem_align } else { 0 },
+            dio_offset_align: if dio { statx.stx_dio_offset_align } else { 0 },
+        }
+    }
+}
+
+/// A `SystemTime` from seconds and nanoseconds since the epoch.
+fn system_time(secs: i64, nanos: i64) -> SystemTime {
+    let since_epoch = Duration::new(secs.unsigned_abs(), 0);
+    let time = if secs >= 0 {
+        UNIX_EPOCH + since_epoch
+    } else {
+        UNIX_EPOCH - since_epoch
+    };
+    time + Duration::from_nanos(nanos.max(0) as u64)
+}
+
+/// The alignment direct I/O on a file requires, see
+/// [`AsyncFile::open_direct`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DirectIoAlignment {
+    /// Alignment of buffer addresses, in bytes.
+    pub memory: usize,
+    /// Alignment of file offsets and transfer lengths, in bytes.
+    pub offset: usize,
+}
+
+impl DirectIoAlignment {
+    /// The alignment the kernel reports for a file, falling back to its
+    /// preferred block size, which is a multiple of the logical block size,
+    /// on kernels that do not report one.
+    fn of(metadata: &Metadata) -> Self {
+        let fallback = (metadata.blksize as usize).max(512);
+        let or_fallback = |align: u32| match align {
+            0 => fallback,
+            align => align as usize,
+        };
+        Self {
+            memory: or_fallback(metadata.dio_mem_align),
+            offset: or_fallback(metadata.dio_offset_align),
+        }
+    }
+}
+
+/// An entry of a directory listed by [`read_dir`].
+#[derive(Debug, Clone)]
+pub struct DirEntry {
+    /// The full path: the listed directory joined with `file_name`.
+    pub path: PathBuf,
+    /// The entry's name within its directory.
+    pub file_name: OsString,
+    /// The entry's type; symbolic links are not followed.
+    pub file_type: std::fs::FileType,
+}
 
 /// An asynchronous file handle.
 ///
@@ -31,12 +336,18 @@ use std::path::Path;
 /// # Examples
 ///
 /// ```
-/// use rust_miniss::{fs::AsyncFile, Runtime};
+/// use rust_miniss::{fs::OpenOptions, Runtime};
 ///
 /// let runtime = Runtime::new();
 /// runtime.block_on(async {
-///     // Create a new file
-///     let file = AsyncFile::create("/tmp/test.txt").expect("Failed to create file");
+///     // Create a new file, open for reading too
+///     let options = OpenOptions {
+///         write: true,
+///         create: true,
+///         truncate: true,
+///         ..Default::default()
+///     };
+///     let file = options.open("/tmp/test.txt").await.expect("Failed to create file");
 ///     
 ///     // Write data asynchronously
 ///     let data = b"Hello, async file I/O!";
@@ -52,6 +363,8 @@ use std::path::Path;
 #[derive(Debug)]
 pub struct AsyncFile {
     inner: std::fs::File,
+    /// Set when opened with `O_DIRECT`; transfers are then checked against it.
+    direct: Option<DirectIoAlignment>,
 }
 
 impl AsyncFile {
@@ -84,7 +397,10 @@ impl AsyncFile {
         let flags = OFlag::from_bits_truncate(flags);
         let new_flags = flags | OFlag::O_NONBLOCK;
         fcntl(fd, FcntlArg::F_SETFL(new_flags)).map_err(io::Error::other)?;
-        Ok(Self { inner: file })
+        Ok(Self {
+            inner: file,
+            direct: None,
+        })
     }
 
     /// Creates a new file for asynchronous operations.
@@ -116,7 +432,72 @@ impl AsyncFile {
         let flags = OFlag::from_bits_truncate(flags);
         let new_flags = flags | OFlag::O_NONBLOCK;
         fcntl(fd, FcntlArg::F_SETFL(new_flags)).map_err(io::Error::other)?;
-        Ok(Self { inner: file })
+        Ok(Self {
+            inner: file,
+            direct: None,
+        })
+    }
+
+    /// Opens an existing file for reading and writing with direct I/O
+    /// (`O_DIRECT`), bypassing the page cache.
+    ///
+    /// Every transfer must then be aligned as the device requires: offsets
+    /// and lengths to [`DirectIoAlignment::offset`], buffer addresses to
+    /// [`DirectIoAlignment::memory`]. Buffers from
+    /// [`alloc_dma_buffer`](Self::alloc_dma_buffer) always qualify; other
+    /// transfers that are not aligned fail with `InvalidInput` before reaching
+    /// the kernel.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the file to open
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(AsyncFile)` - Successfully opened file handle
+    /// * `Err(io::Error)` - Failed to open the file, or the filesystem does
+    ///   not support direct I/O
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use rust_miniss::{fs::AsyncFile, Runtime};
+    ///
+    /// let runtime = Runtime::new();
+    /// runtime.block_on(async {
+    ///     let file = AsyncFile::open_direct("/var/lib/data.bin").await.expect("Failed to open file");
+    ///     let mut block = file.alloc_dma_buffer(4096);
+    ///     block.set_len(4096);
+    ///     block[..5].copy_from_slice(b"hello");
+    ///     file.write_at_buf(0, block).await.expect("Failed to write");
+    ///     let (_, data) = file.read_at(0, 4096).await.expect("Failed to read");
+    ///     assert_eq!(&data[..5], b"hello");
+    /// });
+    /// ```
+    pub async fn open_direct<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let options = OpenOptions {
+            write: true,
+            direct: true,
+            ..Default::default()
+        };
+        options.open(path).await
+    }
+
+    /// The alignment transfers on this file must meet, if it was opened for
+    /// direct I/O.
+    pub fn direct_io_alignment(&self) -> Option<DirectIoAlignment> {
+        self.direct
+    }
+
+    /// Allocates an empty buffer suitable for direct I/O on this file, with a
+    /// capacity of at least `len` bytes rounded up to the alignment.
+    ///
+    /// Files opened without direct I/O get a page-aligned buffer.
+    pub fn alloc_dma_buffer(&self, len: usize) -> AlignedBuffer {
+        let align = self
+            .direct
+            .map_or(4096, |align| align.memory.max(align.offset));
+        AlignedBuffer::new(len, align)
     }
 
     /// Reads data from the file at the specified offset.
@@ -151,6 +532,16 @@ impl AsyncFile {
         offset: u64,
         len: usize,
     ) -> io::Result<(usize, crate::buffer::Buffer)> {
+        if self.direct.is_some() {
+            self.check_direct(offset, [len], [])?;
+            let buf = self.alloc_dma_buffer(len).into();
+            let (bytes_read, mut buf) = self.read_direct(offset, buf).await?;
+            // The capacity may be rounded up past `len` to the memory alignment
+            let bytes_read = bytes_read.min(len);
+            // SAFETY: shrinking keeps the contents initialised.
+            unsafe { buf.set_len(bytes_read) };
+            return Ok((bytes_read, buf));
+        }
         let state = io_state();
         let op = Op::ReadFile {
             fd: self.inner.as_raw_fd(),
@@ -170,6 +561,65 @@ impl AsyncFile {
         }
     }
 
+    /// Reads data from the file at the specified offset into a caller-supplied
+    /// buffer.
+    ///
+    /// Ownership of `buf` passes to the backend for the duration of the read and
+    /// is handed back afterwards. Up to `buf.capacity()` bytes are read,
+    /// overwriting its previous contents, and its length is set to the number
+    /// of bytes read. On error the buffer is dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - Byte offset from the beginning of the file where reading should start
+    /// * `buf` - Buffer to read into
+    ///
+    /// # Returns
+    ///
+    /// * `Ok((usize, crate::buffer::Buffer))` - Tuple of (bytes_read, buf)
+    /// * `Err(io::Error)` - Failed to read from file
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_miniss::{fs::AsyncFile, Buffer, Runtime};
+    ///
+    /// let runtime = Runtime::new();
+    /// runtime.block_on(async {
+    ///     let file = AsyncFile::open("/etc/passwd").expect("Failed to open file");
+    ///     let buf = Buffer::with_capacity(1024);
+    ///     let (bytes_read, buf) = file.read_at_into(0, buf).await.expect("Failed to read");
+    ///     assert_eq!(buf.len(), bytes_read);
+    /// });
+    /// ```
+    pub async fn read_at_into(
+        &self,
+        offset: u64,
+        buf: crate::buffer::Buffer,
+    ) -> io::Result<(usize, crate::buffer::Buffer)> {
+        if self.direct.is_some() {
+            self.check_direct(offset, [buf.capacity()], [&buf])?;
+            return self.read_direct(offset, buf).await;
+        }
+        let state = io_state();
+        let op = Op::ReadInto {
+            fd: self.inner.as_raw_fd(),
+            offset,
+            buffer: buf,
+        };
+        let token = state.io_backend.submit(op);
+        let future = IoFuture::new(token);
+
+        match future.await {
+            Ok(CompletionKind::Read { bytes_read, data }) => Ok((bytes_read, data)),
+            Ok(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Unexpected completion kind",
+            )),
+            Err(e) => Err(e.into()),
+        }
+    }
+
     /// Writes data to the file at the specified offset.
     ///
     /// This function performs an asynchronous write operation starting at the given
@@ -199,14 +649,45 @@ impl AsyncFile {
     /// });
     /// ```
     pub async fn write_at(&self, offset: u64, buf: &[u8]) -> io::Result<usize> {
-        let state = io_state();
+        if self.direct.is_some() {
+            self.check_direct(offset, [buf.len()], [])?;
+            let mut buffer = crate::buffer::Buffer::from(self.alloc_dma_buffer(buf.len()));
+            buffer.copy_from_slice(buf);
+            return self.write_at_buf(offset, buffer).await;
+        }
         let mut buffer = crate::buffer::BufferPool::get(buf.len());
         buffer.copy_from_slice(buf);
+        self.write_at_buf(offset, buffer).await
+    }
 
+    /// Writes an owned buffer to the file at the specified offset.
+    ///
+    /// Unlike [`write_at`](Self::write_at), the data is handed to the backend
+    /// without copying.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - Byte offset from the beginning of the file where writing should start
+    /// * `buf` - A [`Buffer`](crate::buffer::Buffer), `Vec<u8>` or `bytes::Bytes`
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(usize)` - Number of bytes actually written
+    /// * `Err(io::Error)` - Failed to write to file
+    pub async fn write_at_buf(
+        &self,
+        offset: u64,
+        buf: impl Into<crate::buffer::Buffer>,
+    ) -> io::Result<usize> {
+        let buf = buf.into();
+        if self.direct.is_some() {
+            return self.write_vectored_at(offset, vec![buf]).await;
+        }
+        let state = io_state();
         let op = Op::WriteFile {
             fd: self.inner.as_raw_fd(),
             offset,
-            data: buffer,
+            data: buf,
         };
         let token = state.io_backend.submit(op);
         let future = IoFuture::new(token);
@@ -221,15 +702,15 @@ impl AsyncFile {
         }
     }
 
-    /// Synchronizes the file's in-core state with storage.
+    /// Reads the whole file, from offset 0 to its end.
     ///
-    /// This function ensures that all previously written data is flushed to disk.
-    /// It's an asynchronous version of `fsync(2)`.
+    /// The reads run ahead through a [`FileReader`], with several in flight
+    /// at once for large files.
     ///
     /// # Returns
     ///
-    /// * `Ok(())` - Successfully synchronized
-    /// * `Err(io::Error)` - Failed to synchronize
+    /// * `Ok(Vec<u8>)` - The file's contents
+    /// * `Err(io::Error)` - Failed to read from file
     ///
     /// # Examples
     ///
@@ -238,23 +719,112 @@ impl AsyncFile {
     ///
     /// let runtime = Runtime::new();
     /// runtime.block_on(async {
-    ///     let file = AsyncFile::create("/tmp/test.txt").expect("Failed to create file");
-    ///     let data = b"Important data";
-    ///     file.write_at(0, data).await.expect("Failed to write");
-    ///     file.sync_all().await.expect("Failed to sync");
-    ///     println!("Data safely written to disk");
+    ///     let file = AsyncFile::open("/etc/passwd").expect("Failed to open file");
+    ///     let contents = file.read_to_end().await.expect("Failed to read");
+    ///     println!("Read {} bytes", contents.len());
     /// });
     /// ```
-    pub async fn sync_all(&self) -> io::Result<()> {
+    pub async fn read_to_end(&self) -> io::Result<Vec<u8>> {
+        let len = self.metadata().await?.len as usize;
+        let mut options = BufferingOptions::default();
+        // No more reads than the file has chunks, plus the one that finds the end
+        options.queue_depth = options.queue_depth.min(len / options.chunk_size + 1);
+        let mut contents = Vec::with_capacity(len);
+        let mut reader = FileReader::new(self, 0, options);
+        while let Some(chunk) = reader.next_chunk().await? {
+            contents.extend_from_slice(&chunk);
+        }
+        Ok(contents)
+    }
+
+    /// Writes all of `buf` to the file at the specified offset, continuing
+    /// after short writes.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - Byte offset from the beginning of the file where writing should start
+    /// * `buf` - Data to write to the file
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - All of `buf` was written
+    /// * `Err(io::Error)` - Failed to write to file; `WriteZero` if the file
+    ///   stopped accepting data
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_miniss::{fs::AsyncFile, Runtime};
+    ///
+    /// let runtime = Runtime::new();
+    /// runtime.block_on(async {
+    ///     let file = AsyncFile::create("/tmp/test_write_all.txt").expect("Failed to create file");
+    ///     file.write_all_at(0, b"Hello, world!").await.expect("Failed to write");
+    /// });
+    /// ```
+    pub async fn write_all_at(&self, mut offset: u64, mut buf: &[u8]) -> io::Result<()> {
+        while !buf.is_empty() {
+            match self.write_at(offset, buf).await? {
+                0 => return Err(write_zero()),
+                written => {
+                    buf = &buf[written..];
+                    offset += written as u64;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads data from the file at the specified offset into several buffers,
+    /// filling them in order (`preadv(2)`).
+    ///
+    /// Each buffer receives up to its capacity, overwriting its previous
+    /// contents, and its length is set to the bytes it received. On error the
+    /// buffers are dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - Byte offset from the beginning of the file where reading should start
+    /// * `bufs` - Buffers to read into
+    ///
+    /// # Returns
+    ///
+    /// * `Ok((usize, Vec<crate::buffer::Buffer>))` - Tuple of (bytes_read, bufs)
+    /// * `Err(io::Error)` - Failed to read from file
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_miniss::{fs::AsyncFile, Buffer, Runtime};
+    ///
+    /// let runtime = Runtime::new();
+    /// runtime.block_on(async {
+    ///     let file = AsyncFile::open("/etc/passwd").expect("Failed to open file");
+    ///     let bufs = vec![Buffer::with_capacity(4), Buffer::with_capacity(1024)];
+    ///     let (bytes_read, bufs) = file.read_vectored_at(0, bufs).await.expect("Failed to read");
+    ///     assert_eq!(bufs[0].len() + bufs[1].len(), bytes_read);
+    /// });
+    /// ```
+    pub async fn read_vectored_at(
+        &self,
+        offset: u64,
+        bufs: Vec<crate::buffer::Buffer>,
+    ) -> io::Result<(usize, Vec<crate::buffer::Buffer>)> {
+        self.check_direct(offset, bufs.iter().map(|buf| buf.capacity()), &bufs)?;
         let state = io_state();
-        let op = Op::Fsync {
+        let op = Op::Readv {
             fd: self.inner.as_raw_fd(),
+            offset,
+            buffers: bufs,
         };
         let token = state.io_backend.submit(op);
         let future = IoFuture::new(token);
 
         match future.await {
-            Ok(CompletionKind::Fsync) => Ok(()),
+            Ok(CompletionKind::Readv {
+                bytes_read,
+                buffers,
+            }) => Ok((bytes_read, buffers)),
             Ok(_) => Err(io::Error::new(
                 io::ErrorKind::InvalidData,
                 "Unexpected completion kind",
@@ -262,16 +832,1079 @@ impl AsyncFile {
             Err(e) => Err(e.into()),
         }
     }
-}
 
-impl AsRawFd for AsyncFile {
-    fn as_raw_fd(&self) -> RawFd {
-        self.inner.as_raw_fd()
-    }
-}
+    /// Writes several buffers to the file at the specified offset, in order, as
+    /// one operation (`pwritev(2)`).
+    ///
+    /// The buffers are handed to the backend without copying.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - Byte offset from the beginning of the file where writing should start
+    /// * `bufs` - Buffers to write
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(usize)` - Number of bytes actually written, across all buffers
+    /// * `Err(io::Error)` - Failed to write to file
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_miniss::{fs::AsyncFile, Buffer, Runtime};
+    ///
+    /// let runtime = Runtime::new();
+    /// runtime.block_on(async {
+    ///     let file = AsyncFile::create("/tmp/test_vectored.txt").expect("Failed to create file");
+    ///     let bufs = vec![Buffer::from(b"Hello, ".to_vec()), Buffer::from(b"world!".to_vec())];
+    ///     let written = file.write_vectored_at(0, bufs).await.expect("Failed to write");
+    ///     assert_eq!(written, 13);
+    /// });
+    /// ```
+    pub async fn write_vectored_at(
+        &self,
+        offset: u64,
+        bufs: Vec<crate::buffer::Buffer>,
+    ) -> io::Result<usize> {
+        self.check_direct(offset, bufs.iter().map(|buf| buf.len()), &bufs)?;
+        let state = io_state();
+        let op = Op::Writev {
+            fd: self.inner.as_raw_fd(),
+            offset,
+            buffers: bufs,
+        };
+        let token = state.io_backend.submit(op);
+        let future = IoFuture::new(token);
 
-impl IntoRawFd for AsyncFile {
-    fn into_raw_fd(self) -> RawFd {
-        self.inner.into_raw_fd()
+        match future.await {
+            Ok(CompletionKind::Write { bytes_written }) => Ok(bytes_written),
+            Ok(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Unexpected completion kind",
+            )),
+            Err(e) => Err(e.into()),
+        }
     }
+
+    /// Synchronizes the file's in-core state with storage.
+    ///
+    /// This function ensures that all previously written data is flushed to disk.
+    /// It's an asynchronous version of `fsync(2)`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - Successfully synchronized
+    /// * `Err(io::Error)` - Failed to synchronize
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_miniss::{fs::AsyncFile, Runtime};
+    ///
+    /// let runtime = Runtime::new();
+    /// runtime.block_on(async {
+    ///     let file = AsyncFile::create("/tmp/test.txt").expect("Failed to create file");
+    ///     let data = b"Important data";
+    ///     file.write_at(0, data).await.expect("Failed to write");
+    ///     file.sync_all().await.expect("Failed to sync");
+    ///     println!("Data safely written to disk");
+    /// });
+    /// ```
+    pub async fn sync_all(&self) -> io::Result<()> {
+        let state = io_state();
+        let op = Op::Fsync {
+            fd: self.inner.as_raw_fd(),
+        };
+        let token = state.io_backend.submit(op);
+        let future = IoFuture::new(token);
+
+        match future.await {
+            Ok(CompletionKind::Fsync) => Ok(()),
+            Ok(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Unexpected completion kind",
+            )),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes a buffer at the specified offset and synchronizes the file, as
+    /// one linked submission.
+    ///
+    /// The sync only starts once the write has completed in full; on backends
+    /// without linked operations both are submitted together. If the write
+    /// fails or is short, the sync is cancelled and an error is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - Byte offset from the beginning of the file where writing should start
+    /// * `buf` - A [`Buffer`](crate::buffer::Buffer), `Vec<u8>` or `bytes::Bytes`
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(usize)` - Number of bytes written and synchronized
+    /// * `Err(io::Error)` - The write or the sync failed
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_miniss::{fs::AsyncFile, Runtime};
+    ///
+    /// let runtime = Runtime::new();
+    /// runtime.block_on(async {
+    ///     let file = AsyncFile::create("/tmp/test_synced.txt").expect("Failed to create file");
+    ///     let written = file
+    ///         .write_at_synced(0, b"Important data".to_vec())
+    ///         .await
+    ///         .expect("Failed to write and sync");
+    ///     assert_eq!(written, 14);
+    /// });
+    /// ```
+    pub async fn write_at_synced(
+        &self,
+        offset: u64,
+        buf: impl Into<crate::buffer::Buffer>,
+    ) -> io::Result<usize> {
+        let state = io_state();
+        let fd = self.inner.as_raw_fd();
+        let data = buf.into();
+        // Direct writes go through `pwritev`, see `read_direct`
+        let write = if self.direct.is_some() {
+            self.check_direct(offset, [data.len()], [&data])?;
+            Op::Writev {
+                fd,
+                offset,
+                buffers: vec![data],
+            }
+        } else {
+            Op::WriteFile { fd, offset, data }
+        };
+        let tokens = state
+            .io_backend
+            .submit_linked(vec![write, Op::Fsync { fd }]);
+
+        // Both futures exist before either is awaited, so neither completion
+        // is discarded for arriving early
+        let (written, synced) = (IoFuture::new(tokens[0]), IoFuture::new(tokens[1]));
+        let written = written.await;
+        let synced = synced.await;
+
+        match (written, synced) {
+            (
+                Ok(
+                    CompletionKind::WriteFile { bytes_written }
+                    | CompletionKind::Write { bytes_written },
+                ),
+                Ok(CompletionKind::Fsync),
+            ) => Ok(bytes_written),
+            (Err(e), _) | (_, Err(e)) => Err(e.into()),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Unexpected completion kind",
+            )),
+        }
+    }
+
+    /// Synchronizes the file's data with storage, without the metadata that is
+    /// not needed to read it back, such as the modification time
+    /// (`fdatasync(2)`).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - Successfully synchronized
+    /// * `Err(io::Error)` - Failed to synchronize
+    pub async fn sync_data(&self) -> io::Result<()> {
+        let op = Op::SyncData {
+            fd: self.inner.as_raw_fd(),
+        };
+        match submit(op).await? {
+            CompletionKind::Fsync => Ok(()),
+            _ => Err(unexpected_completion()),
+        }
+    }
+
+    /// Queries the file's metadata (`statx(2)`).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Metadata)` - The file's size, type, permissions and timestamps
+    /// * `Err(io::Error)` - Failed to query the file
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_miniss::{fs::AsyncFile, Runtime};
+    ///
+    /// let runtime = Runtime::new();
+    /// runtime.block_on(async {
+    ///     let file = AsyncFile::open("/etc/passwd").expect("Failed to open file");
+    ///     let metadata = file.metadata().await.expect("Failed to query file");
+    ///     assert!(metadata.is_file());
+    ///     println!("{} bytes", metadata.len);
+    /// });
+    /// ```
+    pub async fn metadata(&self) -> io::Result<Metadata> {
+        let op = Op::Statx {
+            dir_fd: self.inner.as_raw_fd(),
+            path: CString::default(),
+            flags: libc::AT_EMPTY_PATH,
+        };
+        match submit(op).await? {
+            CompletionKind::Metadata(metadata) => Ok(metadata),
+            _ => Err(unexpected_completion()),
+        }
+    }
+
+    /// Truncates or extends the file to `len` bytes (`ftruncate(2)`); the
+    /// extension reads as zeros.
+    ///
+    /// # Arguments
+    ///
+    /// * `len` - The new size in bytes
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The file now has `len` bytes
+    /// * `Err(io::Error)` - Failed to resize the file, e.g. it is not open for writing
+    pub async fn set_len(&self, len: u64) -> io::Result<()> {
+        submit_done(Op::Ftruncate {
+            fd: self.inner.as_raw_fd(),
+            len,
+        })
+        .await
+    }
+
+    /// Allocates storage for `len` bytes at `offset` (`fallocate(2)`, Linux
+    /// only), extending the file if they lie past its end.
+    ///
+    /// Later writes to the range cannot fail for lack of space, and the file's
+    /// blocks are more likely to be contiguous.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - Byte offset from the beginning of the file where the range starts
+    /// * `len` - Length of the range in bytes
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The range is allocated
+    /// * `Err(io::Error)` - Failed to allocate, e.g. the filesystem is full
+    pub async fn fallocate(&self, offset: u64, len: u64) -> io::Result<()> {
+        submit_done(Op::Fallocate {
+            fd: self.inner.as_raw_fd(),
+            mode: 0,
+            offset,
+            len,
+        })
+        .await
+    }
+
+    /// Closes the file through the backend (`close(2)`), reporting errors that
+    /// dropping the file would ignore.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - Successfully closed
+    /// * `Err(io::Error)` - The close failed; the descriptor is released regardless
+    pub async fn close(self) -> io::Result<()> {
+        let op = Op::Close {
+            fd: self.inner.into_raw_fd(),
+        };
+        match submit(op).await? {
+            CompletionKind::Close => Ok(()),
+            _ => Err(unexpected_completion()),
+        }
+    }
+}
+
+impl AsyncFile {
+    /// Reads into one aligned buffer with `preadv`, which every backend
+    /// submits as is, where the plain read paths may stage the data through
+    /// buffers of their own.
+    async fn read_direct(
+        &self,
+        offset: u64,
+        buf: crate::buffer::Buffer,
+    ) -> io::Result<(usize, crate::buffer::Buffer)> {
+        let (bytes_read, mut bufs) = self.read_vectored_at(offset, vec![buf]).await?;
+        let buf = bufs.pop().ok_or_else(unexpected_completion)?;
+        Ok((bytes_read, buf))
+    }
+
+    /// The size of a read or write that stays aligned for direct I/O on this
+    /// file.
+    fn block_size(&self) -> usize {
+        self.direct
+            .map_or(1, |align| align.memory.max(align.offset))
+    }
+
+    /// Submits a read of `len` bytes at `offset` without waiting for it;
+    /// [`read_completion`] unpacks the result.
+    fn submit_read(&self, offset: u64, len: usize) -> IoFuture {
+        let fd = self.inner.as_raw_fd();
+        // Direct reads go through `preadv`, see `read_direct`
+        let op = match self.direct {
+            Some(_) => Op::Readv {
+                fd,
+                offset,
+                buffers: vec![self.alloc_dma_buffer(len).into()],
+            },
+            None => Op::ReadFile { fd, offset, len },
+        };
+        IoFuture::new(io_state().io_backend.submit(op))
+    }
+
+    /// Submits a write of `data` at `offset` without waiting for it;
+    /// [`write_completion`] unpacks the result.
+    fn submit_write(&self, offset: u64, data: crate::buffer::Buffer) -> io::Result<IoFuture> {
+        let fd = self.inner.as_raw_fd();
+        let op = match self.direct {
+            Some(_) => {
+                self.check_direct(offset, [data.len()], [&data])?;
+                Op::Writev {
+                    fd,
+                    offset,
+                    buffers: vec![data],
+                }
+            }
+            None => Op::WriteFile { fd, offset, data },
+        };
+        Ok(IoFuture::new(io_state().io_backend.submit(op)))
+    }
+
+    /// Checks a transfer at `offset` against the alignment of a file opened
+    /// for direct I/O: the offset and every length must be multiples of the
+    /// offset alignment, and every buffer must start at a multiple of the
+    /// memory alignment. Files opened without direct I/O accept anything.
+    fn check_direct<'a>(
+        &self,
+        offset: u64,
+        lens: impl IntoIterator<Item = usize>,
+        bufs: impl IntoIterator<Item = &'a crate::buffer::Buffer>,
+    ) -> io::Result<()> {
+        let Some(align) = self.direct else {
+            return Ok(());
+        };
+        let misaligned = |what: &str, align: usize| {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("direct I/O {what} is not aligned to {align} bytes"),
+            ))
+        };
+        if !offset.is_multiple_of(align.offset as u64) {
+            return misaligned("offset", align.offset);
+        }
+        if lens
+            .into_iter()
+            .any(|len| !len.is_multiple_of(align.offset))
+        {
+            return misaligned("length", align.offset);
+        }
+        if !bufs.into_iter().all(|buf| buf.is_aligned_to(align.memory)) {
+            return misaligned("buffer address", align.memory);
+        }
+        Ok(())
+    }
+}
+
+impl AsRawFd for AsyncFile {
+    fn as_raw_fd(&self) -> RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+impl IntoRawFd for AsyncFile {
+    fn into_raw_fd(self) -> RawFd {
+        self.inner.into_raw_fd()
+    }
+}
+
+/// The data read by an [`AsyncFile::submit_read`].
+fn read_completion(
+    result: Result<CompletionKind, IoError>,
+) -> io::Result<(usize, crate::buffer::Buffer)> {
+    match result? {
+        CompletionKind::ReadFile { bytes_read, data } => Ok((bytes_read, data)),
+        CompletionKind::Readv {
+            bytes_read,
+            mut buffers,
+        } => Ok((bytes_read, buffers.pop().ok_or_else(unexpected_completion)?)),
+        _ => Err(unexpected_completion()),
+    }
+}
+
+/// The bytes written by an [`AsyncFile::submit_write`].
+fn write_completion(result: Result<CompletionKind, IoError>) -> io::Result<usize> {
+    match result? {
+        CompletionKind::WriteFile { bytes_written } | CompletionKind::Write { bytes_written } => {
+            Ok(bytes_written)
+        }
+        _ => Err(unexpected_completion()),
+    }
+}
+
+/// How a [`FileReader`] reads ahead or a [`FileWriter`] writes behind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferingOptions {
+    /// Size of each read or write. Defaults to 64 KiB; rounded up to the
+    /// alignment of files opened for direct I/O.
+    pub chunk_size: usize,
+    /// Number of reads or writes kept in flight at once. Defaults to 4.
+    pub queue_depth: usize,
+}
+
+impl Default for BufferingOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: 64 * 1024,
+            queue_depth: 4,
+        }
+    }
+}
+
+impl BufferingOptions {
+    /// The chunk size and queue depth to use for `file`: at least one of
+    /// each, with chunks aligned for direct I/O.
+    fn for_file(self, file: &AsyncFile) -> (usize, usize) {
+        let block = file.block_size();
+        let chunk_size = self.chunk_size.max(1).div_ceil(block) * block;
+        (chunk_size, self.queue_depth.max(1))
+    }
+}
+
+/// Reads a file sequentially in chunks, keeping several reads ahead of the
+/// one being consumed in flight.
+///
+/// A read shorter than the chunk size marks the end of the file.
+///
+/// Dropping the reader before the end abandons its outstanding reads.
+///
+/// # Examples
+///
+/// ```
+/// use rust_miniss::{fs::{AsyncFile, BufferingOptions, FileReader}, Runtime};
+///
+/// let runtime = Runtime::new();
+/// runtime.block_on(async {
+///     let file = AsyncFile::open("/etc/passwd").expect("Failed to open file");
+///     let mut reader = FileReader::new(&file, 0, BufferingOptions::default());
+///     while let Some(chunk) = reader.next_chunk().await.expect("Failed to read") {
+///         println!("Read {} bytes", chunk.len());
+///     }
+/// });
+/// ```
+#[derive(Debug)]
+pub struct FileReader<'a> {
+    file: &'a AsyncFile,
+    chunk_size: usize,
+    queue_depth: usize,
+    /// Offset of the next chunk to return.
+    position: u64,
+    /// Offset of the next read to submit.
+    next_offset: u64,
+    in_flight: VecDeque<IoFuture>,
+    done: bool,
+}
+
+impl<'a> FileReader<'a> {
+    /// Creates a reader of `file` starting at `offset`. No reads are
+    /// submitted until the first [`next_chunk`](Self::next_chunk).
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The file to read
+    /// * `offset` - Byte offset from the beginning of the file where reading should start
+    /// * `options` - Chunk size and number of reads to keep in flight
+    pub fn new(file: &'a AsyncFile, offset: u64, options: BufferingOptions) -> Self {
+        let (chunk_size, queue_depth) = options.for_file(file);
+        Self {
+            file,
+            chunk_size,
+            queue_depth,
+            position: offset,
+            next_offset: offset,
+            in_flight: VecDeque::with_capacity(queue_depth),
+            done: false,
+        }
+    }
+
+    /// Returns the next chunk of the file, or `None` at its end.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(Buffer))` - The next chunk, at most the chunk size
+    /// * `Ok(None)` - The end of the file was reached
+    /// * `Err(io::Error)` - A read failed; the reader then ends
+    pub async fn next_chunk(&mut self) -> io::Result<Option<crate::buffer::Buffer>> {
+        if self.done {
+            return Ok(None);
+        }
+        while self.in_flight.len() < self.queue_depth {
+            let read = self.file.submit_read(self.next_offset, self.chunk_size);
+            self.in_flight.push_back(read);
+            self.next_offset += self.chunk_size as u64;
+        }
+        let read = self
+            .in_flight
+            .pop_front()
+            .expect("a read was just submitted");
+        let result = read_completion(read.await);
+        match result {
+            Ok((bytes_read, data)) if bytes_read == self.chunk_size => {
+                self.position += bytes_read as u64;
+                Ok(Some(data))
+            }
+            Ok((0, _)) => {
+                self.finish().await;
+                Ok(None)
+            }
+            Ok((bytes_read, data)) => {
+                self.finish().await;
+                self.position += bytes_read as u64;
+                Ok(Some(data))
+            }
+            Err(e) => {
+                self.finish().await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Offset of the next byte [`next_chunk`](Self::next_chunk) returns.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Ends the reader, waiting out the reads past the end so none of their
+    /// completions is left behind.
+    async fn finish(&mut self) {
+        self.done = true;
+        while let Some(read) = self.in_flight.pop_front() {
+            let _ = read.await;
+        }
+    }
+}
+
+/// A write submitted by a [`FileWriter`], with its data kept to finish a
+/// short write.
+#[derive(Debug)]
+struct PendingWrite {
+    offset: u64,
+    data: Bytes,
+    write: IoFuture,
+}
+
+/// Writes a file sequentially, gathering small writes into chunks and
+/// keeping several chunk writes in flight while the caller carries on.
+///
+/// Written data only reaches the file once it has been
+/// [`flush`](Self::flush)ed; dropping the writer before that loses what is
+/// still buffered. On files opened for direct I/O, every chunk, including the
+/// last one flushed, must be aligned.
+///
+/// # Examples
+///
+/// ```
+/// use rust_miniss::{fs::{AsyncFile, BufferingOptions, FileWriter}, Runtime};
+///
+/// let runtime = Runtime::new();
+/// runtime.block_on(async {
+///     let file = AsyncFile::create("/tmp/test_file_writer.txt").expect("Failed to create file");
+///     let mut writer = FileWriter::new(&file, 0, BufferingOptions::default());
+///     for line in ["first\n", "second\n"] {
+///         writer.write(line.as_bytes()).await.expect("Failed to write");
+///     }
+///     writer.flush().await.expect("Failed to flush");
+///     assert_eq!(writer.position(), 13);
+/// });
+/// ```
+#[derive(Debug)]
+pub struct FileWriter<'a> {
+    file: &'a AsyncFile,
+    chunk_size: usize,
+    queue_depth: usize,
+    /// Offset of the first byte of `buffer`.
+    offset: u64,
+    /// The chunk being gathered, if any.
+    buffer: Option<AlignedBuffer>,
+    in_flight: VecDeque<PendingWrite>,
+}
+
+impl<'a> FileWriter<'a> {
+    /// Creates a writer to `file` starting at `offset`.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The file to write
+    /// * `offset` - Byte offset from the beginning of the file where writing should start
+    /// * `options` - Chunk size and number of writes to keep in flight
+    pub fn new(file: &'a AsyncFile, offset: u64, options: BufferingOptions) -> Self {
+        let (chunk_size, queue_depth) = options.for_file(file);
+        Self {
+            file,
+            chunk_size,
+            queue_depth,
+            offset,
+            buffer: None,
+            in_flight: VecDeque::with_capacity(queue_depth),
+        }
+    }
+
+    /// Appends `data`, submitting every chunk it fills. Waits only while
+    /// the queue of writes in flight is full.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - `data` was buffered or submitted
+    /// * `Err(io::Error)` - An earlier write failed
+    pub async fn write(&mut self, mut data: &[u8]) -> io::Result<()> {
+        while !data.is_empty() {
+            let buffer = self
+                .buffer
+                .get_or_insert_with(|| self.file.alloc_dma_buffer(self.chunk_size));
+            let start = buffer.len();
+            let take = data.len().min(self.chunk_size - start);
+            buffer.set_len(start + take);
+            buffer[start..].copy_from_slice(&data[..take]);
+            data = &data[take..];
+            if buffer.len() == self.chunk_size {
+                self.submit_buffer().await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Appends an owned buffer, which is written as is without copying once
+    /// the data gathered before it has been submitted.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - `buf` was submitted
+    /// * `Err(io::Error)` - An earlier write failed, or `buf` is misaligned
+    ///   for direct I/O
+    pub async fn write_buf(&mut self, buf: impl Into<crate::buffer::Buffer>) -> io::Result<()> {
+        let buf = buf.into();
+        if buf.is_empty() {
+            return Ok(());
+        }
+        self.submit_buffer().await?;
+        self.submit(buf.into_bytes()).await
+    }
+
+    /// Submits the data gathered so far and waits for every write in flight.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - Everything written so far is in the file
+    /// * `Err(io::Error)` - A write failed
+    pub async fn flush(&mut self) -> io::Result<()> {
+        self.submit_buffer().await?;
+        while !self.in_flight.is_empty() {
+            self.complete_next().await?;
+        }
+        Ok(())
+    }
+
+    /// Offset just past the last byte written, including what is still
+    /// buffered.
+    pub fn position(&self) -> u64 {
+        self.offset + self.buffer.as_ref().map_or(0, |buffer| buffer.len() as u64)
+    }
+
+    /// Submits the chunk being gathered, if it holds any data.
+    async fn submit_buffer(&mut self) -> io::Result<()> {
+        match self.buffer.take() {
+            Some(buffer) if !buffer.is_empty() => {
+                self.submit(crate::buffer::Buffer::from(buffer).into_bytes())
+                    .await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Submits `data` at the current offset, first waiting for room in the
+    /// queue.
+    async fn submit(&mut self, data: Bytes) -> io::Result<()> {
+        while self.in_flight.len() >= self.queue_depth {
+            self.complete_next().await?;
+        }
+        let write = self.file.submit_write(self.offset, data.clone().into())?;
+        self.in_flight.push_back(PendingWrite {
+            offset: self.offset,
+            data: data.clone(),
+            write,
+        });
+        self.offset += data.len() as u64;
+        Ok(())
+    }
+
+    /// Waits for the oldest write, finishing it if it was short. On failure
+    /// the remaining writes are waited out before the error is returned.
+    async fn complete_next(&mut self) -> io::Result<()> {
+        let Some(pending) = self.in_flight.pop_front() else {
+            return Ok(());
+        };
+        let result = match write_completion(pending.write.await) {
+            Ok(written) if written == pending.data.len() => Ok(()),
+            Ok(written) => {
+                self.file
+                    .write_all_at(pending.offset + written as u64, &pending.data[written..])
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        if result.is_err() {
+            while let Some(pending) = self.in_flight.pop_front() {
+                let _ = pending.write.await;
+            }
+        }
+        result
+    }
+}
+
+/// Queries the metadata of the file at `path`, following symbolic links
+/// (`statx(2)`).
+///
+/// # Arguments
+///
+/// * `path` - Path to the file
+///
+/// # Returns
+///
+/// * `Ok(Metadata)` - The file's size, type, permissions and timestamps
+/// * `Err(io::Error)` - Failed to query the file, e.g. it does not exist
+///
+/// # Examples
+///
+/// ```
+/// use rust_miniss::{fs, Runtime};
+///
+/// let runtime = Runtime::new();
+/// runtime.block_on(async {
+///     let metadata = fs::metadata("/tmp").await.expect("Failed to query /tmp");
+///     assert!(metadata.is_dir());
+/// });
+/// ```
+pub async fn metadata<P: AsRef<Path>>(path: P) -> io::Result<Metadata> {
+    statx_path(path.as_ref(), 0).await
+}
+
+/// Queries the metadata of the file at `path` without following a final
+/// symbolic link, like `lstat(2)`.
+///
+/// # Arguments
+///
+/// * `path` - Path to the file or link
+///
+/// # Returns
+///
+/// * `Ok(Metadata)` - The metadata of the link itself if `path` names one
+/// * `Err(io::Error)` - Failed to query the file, e.g. it does not exist
+pub async fn symlink_metadata<P: AsRef<Path>>(path: P) -> io::Result<Metadata> {
+    statx_path(path.as_ref(), libc::AT_SYMLINK_NOFOLLOW).await
+}
+
+/// Renames `from` to `to`, replacing `to` if it exists (`renameat(2)`).
+///
+/// # Arguments
+///
+/// * `from` - The current path
+/// * `to` - The new path, on the same filesystem
+///
+/// # Returns
+///
+/// * `Ok(())` - Successfully renamed
+/// * `Err(io::Error)` - Failed to rename, e.g. `from` does not exist
+///
+/// # Examples
+///
+/// ```
+/// use rust_miniss::{fs, Runtime};
+///
+/// let runtime = Runtime::new();
+/// runtime.block_on(async {
+///     std::fs::write("/tmp/test_rename_from.txt", b"data").unwrap();
+///     fs::rename("/tmp/test_rename_from.txt", "/tmp/test_rename_to.txt")
+///         .await
+///         .expect("Failed to rename");
+/// });
+/// ```
+pub async fn rename<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> io::Result<()> {
+    submit_done(Op::RenameAt {
+        from: path_to_cstring(from.as_ref())?,
+        to: path_to_cstring(to.as_ref())?,
+    })
+    .await
+}
+
+/// Removes the file at `path` (`unlinkat(2)`).
+///
+/// # Arguments
+///
+/// * `path` - Path to the file
+///
+/// # Returns
+///
+/// * `Ok(())` - Successfully removed
+/// * `Err(io::Error)` - Failed to remove, e.g. `path` is a directory
+pub async fn remove_file<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    submit_done(Op::UnlinkAt {
+        path: path_to_cstring(path.as_ref())?,
+        flags: 0,
+    })
+    .await
+}
+
+/// Removes the empty directory at `path` (`unlinkat(2)` with `AT_REMOVEDIR`).
+///
+/// # Arguments
+///
+/// * `path` - Path to the directory
+///
+/// # Returns
+///
+/// * `Ok(())` - Successfully removed
+/// * `Err(io::Error)` - Failed to remove, e.g. the directory is not empty
+pub async fn remove_dir<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    submit_done(Op::UnlinkAt {
+        path: path_to_cstring(path.as_ref())?,
+        flags: libc::AT_REMOVEDIR,
+    })
+    .await
+}
+
+/// Creates a directory at `path` (`mkdirat(2)`), with permissions `0o777`
+/// before the umask.
+///
+/// # Arguments
+///
+/// * `path` - Path of the new directory; its parent must exist
+///
+/// # Returns
+///
+/// * `Ok(())` - Successfully created
+/// * `Err(io::Error)` - Failed to create, e.g. `path` already exists
+///
+/// # Examples
+///
+/// ```
+/// use rust_miniss::{fs, Runtime};
+///
+/// let runtime = Runtime::new();
+/// runtime.block_on(async {
+///     let dir = std::env::temp_dir().join(format!("miniss-{}", std::process::id()));
+///     fs::create_dir(&dir).await.expect("Failed to create directory");
+///     fs::remove_dir(&dir).await.expect("Failed to remove directory");
+/// });
+/// ```
+pub async fn create_dir<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    submit_done(Op::MkdirAt {
+        path: path_to_cstring(path.as_ref())?,
+        mode: 0o777,
+    })
+    .await
+}
+
+/// Lists the entries of the directory at `path`, without `.` and `..`.
+///
+/// io_uring has no operation for reading directories, so the listing runs on
+/// the runtime's blocking pool.
+///
+/// # Arguments
+///
+/// * `path` - Path to the directory
+///
+/// # Returns
+///
+/// * `Ok(Vec<DirEntry>)` - The entries, in the order the filesystem returns them
+/// * `Err(io::Error)` - Failed to read the directory
+///
+/// # Examples
+///
+/// ```
+/// use rust_miniss::{fs, Runtime};
+///
+/// let runtime = Runtime::new();
+/// runtime.block_on(async {
+///     for entry in fs::read_dir("/etc").await.expect("Failed to read /etc") {
+///         println!("{:?}", entry.file_name);
+///     }
+/// });
+/// ```
+pub async fn read_dir<P: AsRef<Path>>(path: P) -> io::Result<Vec<DirEntry>> {
+    let path = path.as_ref().to_path_buf();
+    let listing = crate::blocking::spawn_blocking(move || {
+        std::fs::read_dir(path)?
+            .map(|entry| {
+                let entry = entry?;
+                Ok(DirEntry {
+                    path: entry.path(),
+                    file_name: entry.file_name(),
+                    file_type: entry.file_type()?,
+                })
+            })
+            .collect()
+    });
+    listing
+        .await
+        .map_err(|_| io::Error::other("directory listing panicked"))?
+}
+
+/// Reads the whole file at `path`.
+///
+/// # Arguments
+///
+/// * `path` - Path to the file
+///
+/// # Returns
+///
+/// * `Ok(Vec<u8>)` - The file's contents
+/// * `Err(io::Error)` - Failed to open or read the file
+///
+/// # Examples
+///
+/// ```
+/// use rust_miniss::{fs, Runtime};
+///
+/// let runtime = Runtime::new();
+/// runtime.block_on(async {
+///     let contents = fs::read("/etc/passwd").await.expect("Failed to read file");
+///     println!("Read {} bytes", contents.len());
+/// });
+/// ```
+pub async fn read<P: AsRef<Path>>(path: P) -> io::Result<Vec<u8>> {
+    OpenOptions::default().open(path).await?.read_to_end().await
+}
+
+/// Writes `contents` as the whole of the file at `path`, creating it if it
+/// does not exist and truncating it if it does.
+///
+/// # Arguments
+///
+/// * `path` - Path to the file
+/// * `contents` - The data to write
+///
+/// # Returns
+///
+/// * `Ok(())` - All of `contents` was written
+/// * `Err(io::Error)` - Failed to open or write the file
+///
+/// # Examples
+///
+/// ```
+/// use rust_miniss::{fs, Runtime};
+///
+/// let runtime = Runtime::new();
+/// runtime.block_on(async {
+///     fs::write("/tmp/test_fs_write.txt", "Hello, world!").await.expect("Failed to write file");
+/// });
+/// ```
+pub async fn write<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> io::Result<()> {
+    let options = OpenOptions {
+        write: true,
+        create: true,
+        truncate: true,
+        ..Default::default()
+    };
+    let file = options.open(path).await?;
+    file.write_all_at(0, contents.as_ref()).await
+}
+
+/// Copies the contents of the file at `from` to `to`, creating `to` with
+/// the same permission bits if it does not exist and truncating it if it
+/// does.
+///
+/// Reads from `from` run ahead while the writes to `to` complete behind
+/// them, through a [`FileReader`] and a [`FileWriter`].
+///
+/// # Arguments
+///
+/// * `from` - Path to the file to copy
+/// * `to` - Path to copy it to
+///
+/// # Returns
+///
+/// * `Ok(u64)` - The number of bytes copied
+/// * `Err(io::Error)` - `from` is not a regular file, or opening, reading or
+///   writing failed
+///
+/// # Examples
+///
+/// ```
+/// use rust_miniss::{fs, Runtime};
+///
+/// let runtime = Runtime::new();
+/// runtime.block_on(async {
+///     let copied = fs::copy("/etc/passwd", "/tmp/test_fs_copy.txt").await.expect("Failed to copy");
+///     println!("Copied {} bytes", copied);
+/// });
+/// ```
+pub async fn copy<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> io::Result<u64> {
+    let source = OpenOptions::default().open(from).await?;
+    let metadata = source.metadata().await?;
+    if !metadata.is_file() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the source path is not a regular file",
+        ));
+    }
+    let options = OpenOptions {
+        write: true,
+        create: true,
+        truncate: true,
+        mode: metadata.permissions(),
+        ..Default::default()
+    };
+    let destination = options.open(to).await?;
+
+    let mut reader = FileReader::new(&source, 0, BufferingOptions::default());
+    let mut writer = FileWriter::new(&destination, 0, BufferingOptions::default());
+    while let Some(chunk) = reader.next_chunk().await? {
+        writer.write_buf(chunk).await?;
+    }
+    writer.flush().await?;
+    Ok(writer.position())
+}
+
+async fn statx_path(path: &Path, flags: i32) -> io::Result<Metadata> {
+    let op = Op::Statx {
+        dir_fd: libc::AT_FDCWD,
+        path: path_to_cstring(path)?,
+        flags,
+    };
+    match submit(op).await? {
+        CompletionKind::Metadata(metadata) => Ok(metadata),
+        _ => Err(unexpected_completion()),
+    }
+}
+
+/// Submits `op` and waits for its completion.
+async fn submit(op: Op) -> io::Result<CompletionKind> {
+    let token = io_state().io_backend.submit(op);
+    IoFuture::new(token).await.map_err(Into::into)
+}
+
+/// Submits an operation that completes with `CompletionKind::Done`.
+async fn submit_done(op: Op) -> io::Result<()> {
+    match submit(op).await? {
+        CompletionKind::Done => Ok(()),
+        _ => Err(unexpected_completion()),
+    }
+}
+
+fn write_zero() -> io::Error {
+    io::Error::new(io::ErrorKind::WriteZero, "failed to write whole buffer")
+}
+
+fn unexpected_completion() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "Unexpected completion kind")
+}
+
+fn path_to_cstring(path: &Path) -> io::Result<CString> {
+    CString::new(path.as_os_str().as_bytes()).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "path contains an interior nul byte",
+        )
+    })
 }
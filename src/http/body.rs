@@ -0,0 +1,375 @@
+//! Request and response bodies that need not fit in memory.
+//!
+//! A [`Body`] is either held in memory, produced by a `Stream` of
+//! [`Buffer`] chunks, a range of an [`AsyncFile`], or, for requests served by
+//! [`HttpConnection::serve`](super::HttpConnection::serve), still arriving on
+//! the connection. Streamed bodies are pulled one chunk at a time, so a slow
+//! reader holds back the writer instead of piling data up in between.
+
+use super::parser::{BodyDecoder, Decoded};
+use crate::buffer::Buffer;
+use crate::fs::AsyncFile;
+use crate::net::AsyncTcpStream;
+use bytes::{Bytes, BytesMut};
+use futures::{Stream, StreamExt};
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+/// Size of the pieces a file body is read in by [`Body::chunk`].
+const FILE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// The chunks of a streamed body
+///
+/// Only ever polled through `&mut`; the mutex just makes bodies `Sync`
+/// without requiring it of the stream.
+type ChunkStream = Mutex<Pin<Box<dyn Stream<Item = io::Result<Buffer>> + Send>>>;
+
+/// The body of a request or response
+///
+/// Bodies in memory compare equal to the bytes they hold.
+///
+/// # Examples
+///
+/// ```
+/// use futures::stream;
+/// use rust_miniss::http::Body;
+/// use rust_miniss::{Buffer, Response, StatusCode};
+///
+/// // Sent with chunked transfer encoding, one chunk at a time
+/// let chunks = stream::iter(["hello, ", "world"].map(|s| Ok(Buffer::from_slice(s.as_bytes()))));
+/// let response = Response::new(StatusCode::OK).with_body(Body::from_stream(chunks));
+/// assert_eq!(response.body.content_length(), None);
+/// ```
+#[derive(Default)]
+pub struct Body {
+    kind: Kind,
+}
+
+#[derive(Default)]
+enum Kind {
+    #[default]
+    Empty,
+    Full(Bytes),
+    Stream(ChunkStream),
+    File {
+        /// Boxed to keep requests and responses small
+        file: Box<AsyncFile>,
+        offset: u64,
+        len: u64,
+    },
+    Incoming(IncomingBody),
+}
+
+impl Body {
+    /// An empty body
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    /// A body of the chunks `stream` yields, sent as they are produced
+    pub fn from_stream<S>(stream: S) -> Self
+    where
+        S: Stream<Item = io::Result<Buffer>> + Send + 'static,
+    {
+        Self {
+            kind: Kind::Stream(Mutex::new(Box::pin(stream))),
+        }
+    }
+
+    /// A body of the `len` bytes of `file` starting at `offset`
+    ///
+    /// Sent straight from the page cache with `sendfile(2)`.
+    pub fn from_file(file: AsyncFile, offset: u64, len: u64) -> Self {
+        Self {
+            kind: Kind::File {
+                file: Box::new(file),
+                offset,
+                len,
+            },
+        }
+    }
+
+    /// A body of the whole file at `path`
+    pub async fn from_path<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = AsyncFile::open(path)?;
+        let len = file.metadata().await?.len;
+        Ok(Self::from_file(file, 0, len))
+    }
+
+    /// A body arriving on the connection
+    pub(crate) fn incoming(incoming: IncomingBody) -> Self {
+        Self {
+            kind: Kind::Incoming(incoming),
+        }
+    }
+
+    /// The body's length, if known in advance
+    pub fn content_length(&self) -> Option<u64> {
+        match &self.kind {
+            Kind::Empty => Some(0),
+            Kind::Full(bytes) => Some(bytes.len() as u64),
+            Kind::Stream(_) => None,
+            Kind::File { len, .. } => Some(*len),
+            Kind::Incoming(incoming) => incoming.content_length(),
+        }
+    }
+
+    /// The body's bytes, if it is held in memory
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match &self.kind {
+            Kind::Empty => Some(&[]),
+            Kind::Full(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// The next chunk of the body, or `None` once it has all been read
+    ///
+    /// A body still arriving on the connection is read from it only as far as
+    /// needed for this chunk; one in memory is returned in one piece.
+    pub async fn chunk(&mut self) -> io::Result<Option<Buffer>> {
+        match &mut self.kind {
+            Kind::Empty => Ok(None),
+            Kind::Full(_) => match std::mem::take(&mut self.kind) {
+                Kind::Full(bytes) => Ok(Some(bytes.into())),
+                _ => unreachable!(),
+            },
+            Kind::Stream(stream) => {
+                let stream = stream.get_mut().unwrap_or_else(|e| e.into_inner());
+                stream.next().await.transpose()
+            }
+            Kind::File { file, offset, len } => {
+                if *len == 0 {
+                    return Ok(None);
+                }
+                let want = (*len).min(FILE_CHUNK_SIZE as u64) as usize;
+                let (read, buffer) = file.read_at(*offset, want).await?;
+                if read == 0 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "file ended before the body",
+                    ));
+                }
+                *offset += read as u64;
+                *len -= read as u64;
+                Ok(Some(buffer))
+            }
+            Kind::Incoming(incoming) => incoming.chunk().await,
+        }
+    }
+
+    /// Read the whole body into memory
+    pub async fn collect(mut self) -> io::Result<Bytes> {
+        if let Kind::Full(bytes) = self.kind {
+            return Ok(bytes);
+        }
+        let mut collected = BytesMut::new();
+        while let Some(chunk) = self.chunk().await? {
+            collected.extend_from_slice(&chunk);
+        }
+        Ok(collected.freeze())
+    }
+
+    /// A copy of the body, if it is held in memory
+    pub(crate) fn try_clone(&self) -> Option<Body> {
+        match &self.kind {
+            Kind::Empty => Some(Body::empty()),
+            Kind::Full(bytes) => Some(bytes.clone().into()),
+            _ => None,
+        }
+    }
+
+    /// Take the body apart for sending
+    pub(crate) fn into_parts(self) -> BodyParts {
+        match self.kind {
+            Kind::Empty => BodyParts::Full(Bytes::new()),
+            Kind::Full(bytes) => BodyParts::Full(bytes),
+            Kind::File { file, offset, len } => BodyParts::File { file, offset, len },
+            kind => BodyParts::Chunks(Body { kind }),
+        }
+    }
+}
+
+/// How a body is written to the connection
+pub(crate) enum BodyParts {
+    Full(Bytes),
+    File {
+        file: Box<AsyncFile>,
+        offset: u64,
+        len: u64,
+    },
+    /// Of unknown length, written chunk by chunk
+    Chunks(Body),
+}
+
+impl fmt::Debug for Body {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            Kind::Empty => f.write_str("Body::Empty"),
+            Kind::Full(bytes) => f.debug_tuple("Body::Full").field(bytes).finish(),
+            Kind::Stream(_) => f.write_str("Body::Stream"),
+            Kind::File { offset, len, .. } => f
+                .debug_struct("Body::File")
+                .field("offset", offset)
+                .field("len", len)
+                .finish(),
+            Kind::Incoming(incoming) => f
+                .debug_struct("Body::Incoming")
+                .field("content_length", &incoming.content_length())
+                .finish(),
+        }
+    }
+}
+
+impl<T: AsRef<[u8]> + ?Sized> PartialEq<T> for Body {
+    fn eq(&self, other: &T) -> bool {
+        self.as_bytes() == Some(other.as_ref())
+    }
+}
+
+impl From<()> for Body {
+    fn from(_: ()) -> Self {
+        Self::empty()
+    }
+}
+
+impl From<Bytes> for Body {
+    fn from(bytes: Bytes) -> Self {
+        Self {
+            kind: Kind::Full(bytes),
+        }
+    }
+}
+
+impl From<Buffer> for Body {
+    fn from(buffer: Buffer) -> Self {
+        buffer.into_bytes().into()
+    }
+}
+
+impl From<Vec<u8>> for Body {
+    fn from(vec: Vec<u8>) -> Self {
+        Bytes::from(vec).into()
+    }
+}
+
+impl From<String> for Body {
+    fn from(string: String) -> Self {
+        Bytes::from(string).into()
+    }
+}
+
+impl From<&[u8]> for Body {
+    fn from(slice: &[u8]) -> Self {
+        Bytes::copy_from_slice(slice).into()
+    }
+}
+
+impl<const N: usize> From<&[u8; N]> for Body {
+    fn from(array: &[u8; N]) -> Self {
+        Bytes::copy_from_slice(array).into()
+    }
+}
+
+impl From<&str> for Body {
+    fn from(string: &str) -> Self {
+        string.as_bytes().into()
+    }
+}
+
+/// A request body read from the connection as it is consumed
+pub(crate) struct IncomingBody {
+    state: Arc<Mutex<IncomingState>>,
+}
+
+/// Shared between the body and the connection, which takes the bytes after
+/// the body back once the handler is done
+pub(crate) struct IncomingState {
+    stream: Arc<AsyncTcpStream>,
+    /// Received bytes not yet decoded, including any of pipelined requests
+    buffer: BytesMut,
+    decoder: BodyDecoder,
+    /// The connection has moved on to the next request
+    detached: bool,
+}
+
+impl IncomingBody {
+    /// A body decoded by `decoder` from `buffer` and, once that runs out,
+    /// from `stream`
+    pub(crate) fn new(
+        stream: Arc<AsyncTcpStream>,
+        buffer: BytesMut,
+        decoder: BodyDecoder,
+    ) -> (Self, Arc<Mutex<IncomingState>>) {
+        let state = Arc::new(Mutex::new(IncomingState {
+            stream,
+            buffer,
+            decoder,
+            detached: false,
+        }));
+        (
+            Self {
+                state: state.clone(),
+            },
+            state,
+        )
+    }
+
+    fn content_length(&self) -> Option<u64> {
+        self.state.lock().unwrap().decoder.content_length()
+    }
+
+    async fn chunk(&mut self) -> io::Result<Option<Buffer>> {
+        loop {
+            let stream = {
+                let mut state = self.state.lock().unwrap();
+                if state.detached {
+                    return Err(io::Error::other(
+                        "request body read after its response was sent",
+                    ));
+                }
+                let IncomingState {
+                    buffer, decoder, ..
+                } = &mut *state;
+                match decoder.decode(buffer)? {
+                    Decoded::Data(data) => return Ok(Some(data.into())),
+                    Decoded::Done => return Ok(None),
+                    Decoded::NeedMore => state.stream.clone(),
+                }
+            };
+            let (read, buffer) = stream.read().await?;
+            if read == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "Connection closed in the middle of a request body",
+                ));
+            }
+            self.state
+                .lock()
+                .unwrap()
+                .buffer
+                .extend_from_slice(&buffer[..read]);
+        }
+    }
+}
+
+impl IncomingState {
+    /// Hand the buffered bytes back to the connection, discarding what is
+    /// left of the body among them
+    ///
+    /// Returns the bytes after the body, or `None` if part of it has not
+    /// arrived yet, in which case the connection cannot be reused.
+    pub(crate) fn detach(&mut self) -> Option<BytesMut> {
+        self.detached = true;
+        loop {
+            match self.decoder.decode(&mut self.buffer) {
+                Ok(Decoded::Data(_)) => {}
+                Ok(Decoded::Done) => return Some(std::mem::take(&mut self.buffer)),
+                Ok(Decoded::NeedMore) | Err(_) => return None,
+            }
+        }
+    }
+}
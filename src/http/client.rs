@@ -0,0 +1,492 @@
+//! An HTTP/1.1 client.
+//!
+//! [`Client`] sends requests over [`AsyncTcpStream`] and parses each response
+//! as it arrives, its body framed by `Content-Length`, chunked transfer
+//! coding or the server closing the connection. Connections that can be
+//! reused are kept in a pool per core, keyed by host and port: a connection
+//! is only ever used on the core that opened it, so the pool needs no locks.
+
+use super::parser::{parse_response_head, Decoded};
+use super::{head_bytes, header, header_field, keeps_alive, write_message};
+use super::{Body, HeaderMap, HeaderValue, Method, ParseError, ParserLimits, Response, StatusCode};
+use crate::blocking::spawn_blocking;
+use crate::net::AsyncTcpStream;
+use crate::timer;
+use bytes::BytesMut;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io;
+use std::net::{Shutdown, SocketAddr, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+thread_local! {
+    /// Idle connections opened on this thread's core, by `host:port`.
+    static POOL: RefCell<HashMap<String, Vec<Idle>>> = RefCell::new(HashMap::new());
+}
+
+/// How a [`Client`] times requests and keeps connections
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientOptions {
+    /// How long a request may take, from connecting to the end of the
+    /// response body. `None` waits forever. Defaults to 30 seconds.
+    pub timeout: Option<Duration>,
+    /// Idle connections kept per host on each core. Defaults to 8.
+    pub max_idle_per_host: usize,
+    /// How long an idle connection is kept for reuse. Defaults to 90 seconds.
+    pub idle_timeout: Duration,
+    /// Size limits on responses. Defaults to [`ParserLimits::default`].
+    pub limits: ParserLimits,
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Some(Duration::from_secs(30)),
+            max_idle_per_host: 8,
+            idle_timeout: Duration::from_secs(90),
+            limits: ParserLimits::default(),
+        }
+    }
+}
+
+/// Sends HTTP/1.1 requests, reusing connections
+///
+/// Only `http://` URLs are supported. A request on a pooled connection the
+/// server has meanwhile closed is retried once on a new connection, if its
+/// body is held in memory.
+///
+/// # Examples
+///
+/// ```no_run
+/// use rust_miniss::http::Client;
+/// use rust_miniss::{Method, Runtime};
+/// use std::time::Duration;
+///
+/// let runtime = Runtime::new();
+/// runtime.block_on(async {
+///     let client = Client::new();
+///     let page = client.get("http://127.0.0.1:8080/").await.unwrap();
+///     println!("{}", page.status);
+///
+///     let created = client
+///         .request(Method::POST, "http://127.0.0.1:8080/items")
+///         .with_header("content-type", "application/json")
+///         .with_body(r#"{"name":"miniss"}"#)
+///         .with_timeout(Duration::from_secs(2))
+///         .send()
+///         .await
+///         .unwrap();
+///     println!("{:?}", created.body);
+/// });
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Client {
+    options: ClientOptions,
+}
+
+impl Client {
+    /// Create a client with the default options
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a client with `options`
+    pub fn with_options(options: ClientOptions) -> Self {
+        Self { options }
+    }
+
+    /// Start a `method` request to `url`, sent by [`ClientRequest::send`]
+    pub fn request(&self, method: Method, url: &str) -> ClientRequest<'_> {
+        ClientRequest {
+            client: self,
+            method,
+            url: url.to_string(),
+            headers: HeaderMap::new(),
+            body: Body::empty(),
+            timeout: self.options.timeout,
+        }
+    }
+
+    /// Send a GET request to `url`
+    pub async fn get(&self, url: &str) -> io::Result<Response> {
+        self.request(Method::GET, url).send().await
+    }
+}
+
+/// A request being put together, created by [`Client::request`]
+#[derive(Debug)]
+pub struct ClientRequest<'a> {
+    client: &'a Client,
+    method: Method,
+    url: String,
+    headers: HeaderMap,
+    body: Body,
+    timeout: Option<Duration>,
+}
+
+impl ClientRequest<'_> {
+    /// Add a header, replacing any earlier one of the same name
+    ///
+    /// # Panics
+    ///
+    /// If `name` or `value` is not a valid header name or value.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        let (name, value) = header_field(name, value);
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Set the body; one of unknown length is sent chunked
+    pub fn with_body(mut self, body: impl Into<Body>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Fail with `TimedOut` if the response has not been read in full
+    /// within `timeout`, instead of the client's
+    /// [`timeout`](ClientOptions::timeout)
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Send the request and read the response, body included
+    pub async fn send(self) -> io::Result<Response> {
+        match self.timeout {
+            Some(timeout) => timer::timeout(timeout, self.exchange())
+                .await
+                .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "request timed out"))?,
+            None => self.exchange().await,
+        }
+    }
+
+    async fn exchange(mut self) -> io::Result<Response> {
+        let target = Target::parse(&self.url)?;
+        let options = &self.client.options;
+        if !self.headers.contains_key(header::CONTENT_LENGTH)
+            && !self.headers.contains_key(header::TRANSFER_ENCODING)
+        {
+            match self.body.content_length() {
+                // Only methods that usually carry a body announce an empty one
+                Some(0) if !matches!(self.method, Method::POST | Method::PUT | Method::PATCH) => {}
+                Some(len) => {
+                    self.headers
+                        .insert(header::CONTENT_LENGTH, HeaderValue::from(len));
+                }
+                None => {
+                    self.headers.insert(
+                        header::TRANSFER_ENCODING,
+                        HeaderValue::from_static("chunked"),
+                    );
+                }
+            }
+        }
+        if !self.headers.contains_key(header::HOST) {
+            let host = HeaderValue::try_from(target.authority.as_str())
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid host"))?;
+            self.headers.insert(header::HOST, host);
+        }
+        let start_line = format!("{} {} HTTP/1.1", self.method, target.path);
+        let exchange = Exchange {
+            head: head_bytes(&start_line, &self.headers),
+            headers: &self.headers,
+            head_request: self.method == Method::HEAD,
+            limits: &options.limits,
+        };
+
+        let mut body = self.body;
+        if let Some(connection) = checkout(&target.key, options.idle_timeout) {
+            let replay = body.try_clone();
+            match exchange.run(connection, body).await {
+                Ok((response, connection)) => {
+                    checkin(&target.key, connection, options.max_idle_per_host);
+                    return Ok(response);
+                }
+                // The server closed the idle connection as the request went
+                // out; it never saw it
+                Err(Failure {
+                    answered: false,
+                    error,
+                }) => body = replay.ok_or(error)?,
+                Err(Failure { error, .. }) => return Err(error),
+            }
+        }
+
+        let stream = AsyncTcpStream::connect(target.resolve().await?).await?;
+        let connection = Connection {
+            stream,
+            buffer: BytesMut::new(),
+        };
+        let (response, connection) = exchange.run(connection, body).await.map_err(|f| f.error)?;
+        checkin(&target.key, connection, options.max_idle_per_host);
+        Ok(response)
+    }
+}
+
+/// Where a request goes, taken from its URL
+struct Target {
+    /// `host[:port]` as given, for the `host` header
+    authority: String,
+    /// `host:port`, keying the pool
+    key: String,
+    /// Path and query string
+    path: String,
+}
+
+impl Target {
+    fn parse(url: &str) -> io::Result<Self> {
+        let invalid = |message| io::Error::new(io::ErrorKind::InvalidInput, message);
+        let scheme_len = url
+            .find("://")
+            .ok_or_else(|| invalid("URL without scheme"))?;
+        if !url[..scheme_len].eq_ignore_ascii_case("http") {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "only http:// URLs are supported",
+            ));
+        }
+        let rest = &url[scheme_len + 3..];
+        let rest = rest.split('#').next().unwrap_or_default();
+        let (authority, path) = match rest.find(['/', '?']) {
+            Some(end) => rest.split_at(end),
+            None => (rest, ""),
+        };
+        if authority.is_empty() || authority.contains('@') {
+            return Err(invalid("URL without a valid host"));
+        }
+        // An IPv6 host is bracketed, so its colons are not the port's
+        let port_colon = match authority.rfind(']') {
+            Some(end) => authority[end..].find(':').map(|i| end + i),
+            None => authority.rfind(':'),
+        };
+        let key = match port_colon {
+            Some(colon) => {
+                authority[colon + 1..]
+                    .parse::<u16>()
+                    .map_err(|_| invalid("URL with an invalid port"))?;
+                authority.to_ascii_lowercase()
+            }
+            None => format!("{}:80", authority.to_ascii_lowercase()),
+        };
+        let path = match path {
+            "" => "/".to_string(),
+            path if path.starts_with('?') => format!("/{path}"),
+            path => path.to_string(),
+        };
+        Ok(Self {
+            authority: authority.to_string(),
+            key,
+            path,
+        })
+    }
+
+    /// The address to connect to; host names are looked up in the blocking
+    /// pool
+    async fn resolve(&self) -> io::Result<SocketAddr> {
+        if let Ok(addr) = self.key.parse() {
+            return Ok(addr);
+        }
+        let key = self.key.clone();
+        let addrs = spawn_blocking(move || key.to_socket_addrs().map(|addrs| addrs.collect()))
+            .await
+            .map_err(|_| io::Error::other("address lookup failed"))?;
+        let addrs: Vec<SocketAddr> = addrs?;
+        addrs
+            .into_iter()
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "host name has no addresses"))
+    }
+}
+
+/// A connection to a server, shut down when dropped rather than pooled
+struct Connection {
+    stream: AsyncTcpStream,
+    /// Bytes received but not yet parsed
+    buffer: BytesMut,
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        // A read still submitted to the backend would keep the socket open
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+struct Idle {
+    connection: Connection,
+    since: Instant,
+}
+
+/// A pooled connection to `key` that has not been idle too long
+fn checkout(key: &str, idle_timeout: Duration) -> Option<Connection> {
+    POOL.with(|pool| {
+        let mut pool = pool.borrow_mut();
+        let idle = pool.get_mut(key)?;
+        // The most recently used first; if it expired, so did the rest
+        let connection = idle
+            .pop()
+            .filter(|entry| entry.since.elapsed() < idle_timeout)
+            .map(|entry| entry.connection);
+        if connection.is_none() {
+            pool.remove(key);
+        }
+        connection
+    })
+}
+
+/// Keep `connection` for reuse, unless `key` already has `max_idle` waiting
+fn checkin(key: &str, connection: Option<Connection>, max_idle: usize) {
+    let Some(connection) = connection else {
+        return;
+    };
+    POOL.with(|pool| {
+        let mut pool = pool.borrow_mut();
+        let idle = pool.entry(key.to_string()).or_default();
+        if idle.len() < max_idle {
+            idle.push(Idle {
+                connection,
+                since: Instant::now(),
+            });
+        }
+    });
+}
+
+/// Why [`Exchange::run`] failed
+struct Failure {
+    error: io::Error,
+    /// Whether any of a response arrived
+    answered: bool,
+}
+
+impl From<io::Error> for Failure {
+    fn from(error: io::Error) -> Self {
+        Self {
+            error,
+            answered: true,
+        }
+    }
+}
+
+/// One request sent and its response read on a connection
+struct Exchange<'a> {
+    head: Vec<u8>,
+    headers: &'a HeaderMap,
+    head_request: bool,
+    limits: &'a ParserLimits,
+}
+
+impl Exchange<'_> {
+    /// The response, and the connection if it can be reused
+    async fn run(
+        &self,
+        mut connection: Connection,
+        body: Body,
+    ) -> Result<(Response, Option<Connection>), Failure> {
+        let unanswered = |error| Failure {
+            error,
+            answered: false,
+        };
+        write_message(&connection.stream, self.head.clone(), self.headers, body)
+            .await
+            .map_err(unanswered)?;
+
+        let mut answered = !connection.buffer.is_empty();
+        let (mut response, mut decoder) = loop {
+            let head = parse_response_head(&mut connection.buffer, self.limits, self.head_request)
+                .map_err(invalid_response)?;
+            match head {
+                // Interim responses such as 100 Continue precede the real one
+                Some((response, _)) if matches!(response.status.as_u16(), 100 | 102..=199) => {}
+                Some(head) => break head,
+                None => {
+                    if !read_more(&mut connection)
+                        .await
+                        .map_err(|error| Failure { error, answered })?
+                    {
+                        return Err(Failure {
+                            error: io::Error::new(
+                                io::ErrorKind::UnexpectedEof,
+                                "Connection closed before the response",
+                            ),
+                            answered,
+                        });
+                    }
+                    answered = true;
+                }
+            }
+        };
+
+        let mut body = BytesMut::new();
+        loop {
+            match decoder
+                .decode(&mut connection.buffer)
+                .map_err(invalid_response)?
+            {
+                Decoded::Data(data) => body.extend_from_slice(&data),
+                Decoded::Done => break,
+                Decoded::NeedMore => {
+                    if !read_more(&mut connection).await? {
+                        if decoder.ends_at_close() {
+                            break;
+                        }
+                        return Err(io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            "Connection closed in the middle of a response body",
+                        )
+                        .into());
+                    }
+                }
+            }
+        }
+        response.body = Body::from(body.freeze());
+
+        let reusable = !decoder.ends_at_close()
+            && response.status != StatusCode::SWITCHING_PROTOCOLS
+            && keeps_alive(response.version, &response.headers)
+            && connection.buffer.is_empty();
+        Ok((response, reusable.then_some(connection)))
+    }
+}
+
+/// Read more of the response into the connection's buffer; `false` at the
+/// end of the stream
+async fn read_more(connection: &mut Connection) -> io::Result<bool> {
+    let (bytes_read, buffer) = connection.stream.read().await?;
+    connection
+        .buffer
+        .extend_from_slice(&buffer.as_ref()[..bytes_read]);
+    Ok(bytes_read > 0)
+}
+
+fn invalid_response(error: ParseError) -> Failure {
+    let message = match error {
+        ParseError::BadRequest(reason) => reason,
+        ParseError::PayloadTooLarge => "response body too large",
+        ParseError::HeadersTooLarge => "response head too large",
+    };
+    io::Error::new(io::ErrorKind::InvalidData, message).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_targets_parse_from_urls() {
+        let target = Target::parse("http://Example.com:8080/a/b?x=1#frag").unwrap();
+        assert_eq!(target.authority, "Example.com:8080");
+        assert_eq!(target.key, "example.com:8080");
+        assert_eq!(target.path, "/a/b?x=1");
+
+        let target = Target::parse("http://[::1]?q").unwrap();
+        assert_eq!(target.key, "[::1]:80");
+        assert_eq!(target.path, "/?q");
+        assert!(target.key.parse::<SocketAddr>().is_ok());
+
+        let https = Target::parse("https://example.com/").err().unwrap();
+        assert_eq!(https.kind(), io::ErrorKind::Unsupported);
+        assert!(Target::parse("example.com/").is_err());
+        assert!(Target::parse("http://host:99999/").is_err());
+        assert!(Target::parse("http://user@host/").is_err());
+    }
+}
@@ -0,0 +1,496 @@
+//! Serving a directory of files.
+//!
+//! [`FileServer`] answers GET and HEAD requests with the files under its root
+//! directory. File bodies are sent with `sendfile(2)` where the backend
+//! supports it, without passing through user space. Responses carry
+//! `ETag` and `Last-Modified` validators, so conditional requests can be
+//! answered with 304 Not Modified, and a single byte range can be requested
+//! with `Range`.
+
+use super::{header, percent_decode, Body, HttpHandler, Method, Request, Response, StatusCode};
+use crate::fs::{self, AsyncFile};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How a [`FileServer`] finds and caches files
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileServerOptions {
+    /// Files served for a request naming a directory, tried in order. A
+    /// directory without any of them is answered with 404 Not Found.
+    /// Defaults to `index.html`.
+    pub index_files: Vec<String>,
+    /// How long clients may cache files without revalidating them, sent as
+    /// `cache-control: max-age`. `None` sends no `cache-control`. Defaults to
+    /// `None`.
+    pub max_age: Option<Duration>,
+}
+
+impl Default for FileServerOptions {
+    fn default() -> Self {
+        Self {
+            index_files: vec!["index.html".to_string()],
+            max_age: None,
+        }
+    }
+}
+
+/// Serves the files under a directory
+///
+/// The file served is the one at the request path relative to the root or,
+/// under a [`Router`](super::Router) route ending in a bare `*`, the part of
+/// the path the `*` matched. Paths with `..` segments are answered with 404
+/// Not Found, so only files under the root can be reached. The content type
+/// is guessed from the file extension.
+///
+/// # Examples
+///
+/// ```
+/// use rust_miniss::http::{FileServer, Router};
+///
+/// // `/assets/css/site.css` serves `public/css/site.css`
+/// let router = Router::new().get("/assets/*", FileServer::new("public"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct FileServer {
+    root: PathBuf,
+    options: FileServerOptions,
+}
+
+impl FileServer {
+    /// Serve the files under `root` with the default options
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self::with_options(root, FileServerOptions::default())
+    }
+
+    /// Serve the files under `root` with `options`
+    pub fn with_options(root: impl Into<PathBuf>, options: FileServerOptions) -> Self {
+        Self {
+            root: root.into(),
+            options,
+        }
+    }
+
+    /// The path under the root that `relative` names, unless it would leave
+    /// the root
+    fn resolve(&self, relative: &str) -> Option<PathBuf> {
+        let mut path = self.root.clone();
+        for segment in relative.split('/') {
+            match segment {
+                "" | "." => {}
+                ".." => return None,
+                _ if segment.contains('\0') => return None,
+                _ => path.push(segment),
+            }
+        }
+        Some(path)
+    }
+
+    /// The first index file of the directory at `path`
+    async fn index(&self, path: &Path) -> Option<PathBuf> {
+        for name in &self.options.index_files {
+            let index = path.join(name);
+            if let Ok(metadata) = fs::metadata(&index).await {
+                if metadata.is_file() {
+                    return Some(index);
+                }
+            }
+        }
+        None
+    }
+
+    /// The response to `request` for the file at `path`
+    async fn serve_file(&self, request: &Request, path: &Path) -> io::Result<Response> {
+        let file = AsyncFile::open(path)?;
+        let metadata = file.metadata().await?;
+        let len = metadata.len;
+        let modified = metadata
+            .modified
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let etag = format!("\"{:x}-{:x}-{:x}\"", metadata.ino, len, modified.as_nanos());
+        let last_modified = http_date(metadata.modified);
+
+        let mut response = if is_fresh(request, &etag, modified.as_secs()) {
+            // The length the full response would have, as a 304 may say
+            let mut response = Response::new(StatusCode::NOT_MODIFIED);
+            response.headers.insert(header::CONTENT_LENGTH, len.into());
+            response
+        } else {
+            let range = match request.headers.get(header::RANGE) {
+                Some(range) if if_range_holds(request, &etag, modified.as_secs()) => {
+                    byte_range(range.to_str().unwrap_or_default(), len)
+                }
+                _ => ByteRange::Whole,
+            };
+            let response = match range {
+                ByteRange::Whole => {
+                    Response::new(StatusCode::OK).with_body(Body::from_file(file, 0, len))
+                }
+                ByteRange::Part { start, end } => Response::new(StatusCode::PARTIAL_CONTENT)
+                    .with_header("content-range", &format!("bytes {start}-{end}/{len}"))
+                    .with_body(Body::from_file(file, start, end - start + 1)),
+                ByteRange::Unsatisfiable => {
+                    return Ok(Response::new(StatusCode::RANGE_NOT_SATISFIABLE)
+                        .with_header("content-range", &format!("bytes */{len}")));
+                }
+            };
+            response.with_header("content-type", content_type(path))
+        };
+
+        response = response
+            .with_header("accept-ranges", "bytes")
+            .with_header("etag", &etag)
+            .with_header("last-modified", &last_modified);
+        if let Some(max_age) = self.options.max_age {
+            response =
+                response.with_header("cache-control", &format!("max-age={}", max_age.as_secs()));
+        }
+        if request.method == Method::HEAD {
+            // Keeps the content-length of the body a GET would get
+            response.body = Body::empty();
+        }
+        Ok(response)
+    }
+}
+
+impl HttpHandler for FileServer {
+    async fn handle(&self, request: Request) -> Response {
+        if request.method != Method::GET && request.method != Method::HEAD {
+            return Response::new(StatusCode::METHOD_NOT_ALLOWED)
+                .with_header("allow", "GET, HEAD")
+                .with_body("Method Not Allowed");
+        }
+        let relative = match request.params.get("*") {
+            Some(relative) => relative.clone(),
+            None => percent_decode(&request.path),
+        };
+        let Some(mut path) = self.resolve(&relative) else {
+            return not_found();
+        };
+
+        let metadata = match fs::metadata(&path).await {
+            Ok(metadata) => metadata,
+            Err(error) => return error_response(&error),
+        };
+        if metadata.is_dir() {
+            if !request.path.ends_with('/') {
+                // Relative links in the index resolve against the directory
+                // only if its path ends with a slash
+                let mut location = format!("{}/", request.path);
+                if let Some(query) = &request.query_string {
+                    location.push('?');
+                    location.push_str(query);
+                }
+                return match header::HeaderValue::try_from(location) {
+                    Ok(location) => {
+                        let mut response = Response::new(StatusCode::MOVED_PERMANENTLY);
+                        response.headers.insert(header::LOCATION, location);
+                        response
+                    }
+                    Err(_) => not_found(),
+                };
+            }
+            match self.index(&path).await {
+                Some(index) => path = index,
+                None => return not_found(),
+            }
+        } else if !metadata.is_file() {
+            return not_found();
+        }
+
+        match self.serve_file(&request, &path).await {
+            Ok(response) => response,
+            Err(error) => error_response(&error),
+        }
+    }
+}
+
+fn not_found() -> Response {
+    Response::new(StatusCode::NOT_FOUND).with_body("Not Found")
+}
+
+/// The response to failing to open or query a file
+fn error_response(error: &io::Error) -> Response {
+    match error.kind() {
+        io::ErrorKind::NotFound | io::ErrorKind::NotADirectory => not_found(),
+        io::ErrorKind::PermissionDenied => {
+            Response::new(StatusCode::FORBIDDEN).with_body("Forbidden")
+        }
+        _ => Response::new(StatusCode::INTERNAL_SERVER_ERROR).with_body("Internal Server Error"),
+    }
+}
+
+/// Whether the client's cached copy, as described by its conditional
+/// headers, is still current. `If-None-Match` takes precedence over
+/// `If-Modified-Since`.
+fn is_fresh(request: &Request, etag: &str, modified: u64) -> bool {
+    if let Some(tags) = request.headers.get(header::IF_NONE_MATCH) {
+        // Weak comparison: a `W/` prefix does not matter
+        return tags.to_str().is_ok_and(|tags| {
+            tags.split(',')
+                .map(str::trim)
+                .any(|tag| tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == etag)
+        });
+    }
+    request
+        .headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|since| parse_http_date(since.to_str().ok()?))
+        .is_some_and(|since| modified <= since)
+}
+
+/// Whether a `Range` header applies: without `If-Range`, or if the
+/// validator it names is still current
+fn if_range_holds(request: &Request, etag: &str, modified: u64) -> bool {
+    let Some(validator) = request.headers.get(header::IF_RANGE) else {
+        return true;
+    };
+    let Ok(validator) = validator.to_str() else {
+        return false;
+    };
+    if validator.starts_with('"') {
+        // Strong comparison
+        validator == etag
+    } else {
+        parse_http_date(validator) == Some(modified)
+    }
+}
+
+/// The part of a file a `Range` header asks for
+#[derive(Debug, PartialEq, Eq)]
+enum ByteRange {
+    /// The whole file: no range, or one that cannot be served partially
+    Whole,
+    /// The bytes from `start` to `end`, both included
+    Part { start: u64, end: u64 },
+    /// A range lying wholly past the end of the file
+    Unsatisfiable,
+}
+
+/// The part of a file of `len` bytes that the `Range` header `value` asks
+/// for
+///
+/// Only a single range of bytes is served partially; a malformed header, or
+/// one with several ranges, gets the whole file.
+fn byte_range(value: &str, len: u64) -> ByteRange {
+    let Some(spec) = value
+        .get(..6)
+        .filter(|unit| unit.eq_ignore_ascii_case("bytes="))
+        .map(|_| value[6..].trim())
+    else {
+        return ByteRange::Whole;
+    };
+    let Some((first, last)) = spec.split_once('-').filter(|_| !spec.contains(',')) else {
+        return ByteRange::Whole;
+    };
+    let (first, last) = (first.trim(), last.trim());
+
+    if first.is_empty() {
+        // The final `last` bytes
+        return match last.parse::<u64>() {
+            Ok(0) => ByteRange::Unsatisfiable,
+            Ok(_) if len == 0 => ByteRange::Unsatisfiable,
+            Ok(suffix) => ByteRange::Part {
+                start: len.saturating_sub(suffix),
+                end: len - 1,
+            },
+            Err(_) => ByteRange::Whole,
+        };
+    }
+    let Ok(start) = first.parse::<u64>() else {
+        return ByteRange::Whole;
+    };
+    let end = match last {
+        "" => u64::MAX,
+        last => match last.parse::<u64>() {
+            Ok(end) if end >= start => end,
+            _ => return ByteRange::Whole,
+        },
+    };
+    if start >= len {
+        return ByteRange::Unsatisfiable;
+    }
+    ByteRange::Part {
+        start,
+        end: end.min(len - 1),
+    }
+}
+
+/// The content type of the file at `path`, guessed from its extension
+fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "txt" => "text/plain; charset=utf-8",
+        "md" => "text/markdown; charset=utf-8",
+        "csv" => "text/csv; charset=utf-8",
+        "xml" => "application/xml",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "ico" => "image/x-icon",
+        "wasm" => "application/wasm",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        "tar" => "application/x-tar",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "mp3" => "audio/mpeg",
+        "ogg" => "audio/ogg",
+        "wav" => "audio/wav",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        _ => "application/octet-stream",
+    }
+}
+
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// `time` as an HTTP date, such as `Sun, 06 Nov 1994 08:49:37 GMT`
+fn http_date(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let days = secs / 86_400;
+    let (year, month, day) = civil_from_days(days as i64);
+    format!(
+        "{}, {:02} {} {} {:02}:{:02}:{:02} GMT",
+        // The epoch was a Thursday
+        WEEKDAYS[((days + 4) % 7) as usize],
+        day,
+        MONTHS[month as usize - 1],
+        year,
+        secs % 86_400 / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+/// The seconds since the epoch of an HTTP date in the preferred format, the
+/// one [`http_date`] writes
+fn parse_http_date(date: &str) -> Option<u64> {
+    let mut fields = date.split_ascii_whitespace();
+    let (_weekday, day, month, year, time, zone) = (
+        fields.next()?,
+        fields.next()?,
+        fields.next()?,
+        fields.next()?,
+        fields.next()?,
+        fields.next()?,
+    );
+    if zone != "GMT" || fields.next().is_some() {
+        return None;
+    }
+    let day: u32 = day.parse().ok().filter(|day| (1..=31).contains(day))?;
+    let month = MONTHS.iter().position(|name| *name == month)? as u32 + 1;
+    let year: i64 = year.parse().ok()?;
+    let mut time = time.split(':').map(|field| field.parse::<u64>().ok());
+    let (hours, minutes, seconds) = (time.next()??, time.next()??, time.next()??);
+    if time.next().is_some() || hours > 23 || minutes > 59 || seconds > 60 {
+        return None;
+    }
+    let days = u64::try_from(days_from_civil(year, month, day)).ok()?;
+    Some(days * 86_400 + hours * 3600 + minutes * 60 + seconds)
+}
+
+/// The year, month and day `days` days after 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    // Howard Hinnant's algorithm, counting in 400-year eras from 0000-03-01
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// The days from 1970-01-01 to the given date; the inverse of
+/// [`civil_from_days`]
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let shifted_month = i64::from(if month > 2 { month - 3 } else { month + 9 });
+    let day_of_year = (153 * shifted_month + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_http_dates_round_trip() {
+        let date = UNIX_EPOCH + Duration::from_secs(784_111_777);
+        assert_eq!(http_date(date), "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(
+            parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"),
+            Some(784_111_777)
+        );
+        assert_eq!(http_date(UNIX_EPOCH), "Thu, 01 Jan 1970 00:00:00 GMT");
+        let leap_day = UNIX_EPOCH + Duration::from_secs(1_709_164_800);
+        assert_eq!(http_date(leap_day), "Thu, 29 Feb 2024 00:00:00 GMT");
+        assert_eq!(parse_http_date(&http_date(leap_day)), Some(1_709_164_800));
+        // Obsolete formats are not accepted
+        assert_eq!(parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT"), None);
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 UTC"), None);
+    }
+
+    #[test]
+    fn test_byte_ranges_parse() {
+        let part = |start, end| ByteRange::Part { start, end };
+        assert_eq!(byte_range("bytes=0-3", 10), part(0, 3));
+        assert_eq!(byte_range("bytes=4-", 10), part(4, 9));
+        assert_eq!(byte_range("bytes=5-100", 10), part(5, 9));
+        assert_eq!(byte_range("bytes=-3", 10), part(7, 9));
+        assert_eq!(byte_range("bytes=-30", 10), part(0, 9));
+        assert_eq!(byte_range("bytes=10-", 10), ByteRange::Unsatisfiable);
+        assert_eq!(byte_range("bytes=-0", 10), ByteRange::Unsatisfiable);
+        assert_eq!(byte_range("bytes=-1", 0), ByteRange::Unsatisfiable);
+        assert_eq!(byte_range("bytes=3-1", 10), ByteRange::Whole);
+        assert_eq!(byte_range("bytes=0-1,4-5", 10), ByteRange::Whole);
+        assert_eq!(byte_range("items=0-1", 10), ByteRange::Whole);
+        assert_eq!(byte_range("bytes=x-", 10), ByteRange::Whole);
+    }
+
+    #[test]
+    fn test_paths_stay_under_the_root() {
+        let server = FileServer::new("/srv");
+        assert_eq!(
+            server.resolve("/a/./b.txt"),
+            Some(PathBuf::from("/srv/a/b.txt"))
+        );
+        assert_eq!(server.resolve("/"), Some(PathBuf::from("/srv")));
+        assert_eq!(server.resolve("/a/../../etc/passwd"), None);
+        assert_eq!(server.resolve("../etc"), None);
+        assert_eq!(server.resolve("/a\0b"), None);
+    }
+}
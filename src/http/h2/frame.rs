@@ -0,0 +1,269 @@
+//! HTTP/2 frame layout (RFC 9113, section 4 and 6): parsing frame headers
+//! and appending frames to an outgoing buffer.
+
+use bytes::{Buf, Bytes};
+
+/// Length of the header every frame starts with
+pub(crate) const HEADER_LEN: usize = 9;
+
+/// The frame payload size every endpoint must accept
+pub(crate) const DEFAULT_MAX_FRAME_SIZE: u32 = 16_384;
+/// The largest frame payload size a setting may allow
+pub(crate) const MAX_MAX_FRAME_SIZE: u32 = (1 << 24) - 1;
+/// Flow-control windows start at this size unless settings say otherwise
+pub(crate) const DEFAULT_WINDOW_SIZE: u32 = 65_535;
+/// The largest a flow-control window may grow
+pub(crate) const MAX_WINDOW_SIZE: u32 = (1 << 31) - 1;
+
+pub(crate) const DATA: u8 = 0x0;
+pub(crate) const HEADERS: u8 = 0x1;
+pub(crate) const PRIORITY: u8 = 0x2;
+pub(crate) const RST_STREAM: u8 = 0x3;
+pub(crate) const SETTINGS: u8 = 0x4;
+pub(crate) const PUSH_PROMISE: u8 = 0x5;
+pub(crate) const PING: u8 = 0x6;
+pub(crate) const GOAWAY: u8 = 0x7;
+pub(crate) const WINDOW_UPDATE: u8 = 0x8;
+pub(crate) const CONTINUATION: u8 = 0x9;
+
+pub(crate) const END_STREAM: u8 = 0x1;
+pub(crate) const ACK: u8 = 0x1;
+pub(crate) const END_HEADERS: u8 = 0x4;
+pub(crate) const PADDED: u8 = 0x8;
+pub(crate) const PRIORITY_FLAG: u8 = 0x20;
+
+pub(crate) const SETTINGS_HEADER_TABLE_SIZE: u16 = 0x1;
+pub(crate) const SETTINGS_ENABLE_PUSH: u16 = 0x2;
+pub(crate) const SETTINGS_MAX_CONCURRENT_STREAMS: u16 = 0x3;
+pub(crate) const SETTINGS_INITIAL_WINDOW_SIZE: u16 = 0x4;
+pub(crate) const SETTINGS_MAX_FRAME_SIZE: u16 = 0x5;
+pub(crate) const SETTINGS_MAX_HEADER_LIST_SIZE: u16 = 0x6;
+
+/// Error codes of `RST_STREAM` and `GOAWAY` frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Reason {
+    NoError = 0x0,
+    ProtocolError = 0x1,
+    InternalError = 0x2,
+    FlowControlError = 0x3,
+    StreamClosed = 0x5,
+    FrameSizeError = 0x6,
+    RefusedStream = 0x7,
+    CompressionError = 0x9,
+    EnhanceYourCalm = 0xb,
+}
+
+/// The header of a frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Head {
+    pub(crate) len: usize,
+    pub(crate) kind: u8,
+    pub(crate) flags: u8,
+    pub(crate) stream_id: u32,
+}
+
+impl Head {
+    /// Parse the header at the start of `data`, which holds at least
+    /// [`HEADER_LEN`] bytes
+    pub(crate) fn parse(data: &[u8]) -> Self {
+        Self {
+            len: u32::from_be_bytes([0, data[0], data[1], data[2]]) as usize,
+            kind: data[3],
+            flags: data[4],
+            // The reserved bit is ignored
+            stream_id: u32::from_be_bytes([data[5], data[6], data[7], data[8]]) & MAX_WINDOW_SIZE,
+        }
+    }
+
+    pub(crate) fn has(&self, flag: u8) -> bool {
+        self.flags & flag != 0
+    }
+}
+
+/// Strip the padding of a `PADDED` frame, or `None` if the padding is longer
+/// than the payload
+pub(crate) fn unpad(head: &Head, mut payload: Bytes) -> Option<Bytes> {
+    if !head.has(PADDED) {
+        return Some(payload);
+    }
+    let pad_len = *payload.first()? as usize;
+    payload.advance(1);
+    let len = payload.len().checked_sub(pad_len)?;
+    payload.truncate(len);
+    Some(payload)
+}
+
+/// The `(identifier, value)` pairs of a `SETTINGS` payload, whose length is
+/// a multiple of 6
+pub(crate) fn settings(payload: &[u8]) -> impl Iterator<Item = (u16, u32)> + '_ {
+    payload.chunks_exact(6).map(|setting| {
+        (
+            u16::from_be_bytes([setting[0], setting[1]]),
+            u32::from_be_bytes([setting[2], setting[3], setting[4], setting[5]]),
+        )
+    })
+}
+
+/// Frames waiting to be sent
+#[derive(Debug, Default)]
+pub(crate) struct Outbox {
+    bytes: Vec<u8>,
+}
+
+impl Outbox {
+    pub(crate) fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// The bytes of all frames so far, leaving the outbox empty
+    pub(crate) fn take(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.bytes)
+    }
+
+    fn frame_head(&mut self, len: usize, kind: u8, flags: u8, stream_id: u32) {
+        self.bytes
+            .extend_from_slice(&(len as u32).to_be_bytes()[1..]);
+        self.bytes.push(kind);
+        self.bytes.push(flags);
+        self.bytes.extend_from_slice(&stream_id.to_be_bytes());
+    }
+
+    pub(crate) fn settings(&mut self, settings: &[(u16, u32)]) {
+        self.frame_head(settings.len() * 6, SETTINGS, 0, 0);
+        for &(id, value) in settings {
+            self.bytes.extend_from_slice(&id.to_be_bytes());
+            self.bytes.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+
+    pub(crate) fn settings_ack(&mut self) {
+        self.frame_head(0, SETTINGS, ACK, 0);
+    }
+
+    pub(crate) fn ping_ack(&mut self, data: &[u8]) {
+        self.frame_head(data.len(), PING, ACK, 0);
+        self.bytes.extend_from_slice(data);
+    }
+
+    pub(crate) fn window_update(&mut self, stream_id: u32, increment: u32) {
+        self.frame_head(4, WINDOW_UPDATE, 0, stream_id);
+        self.bytes.extend_from_slice(&increment.to_be_bytes());
+    }
+
+    pub(crate) fn rst_stream(&mut self, stream_id: u32, reason: Reason) {
+        self.frame_head(4, RST_STREAM, 0, stream_id);
+        self.bytes.extend_from_slice(&(reason as u32).to_be_bytes());
+    }
+
+    pub(crate) fn goaway(&mut self, last_stream_id: u32, reason: Reason) {
+        self.frame_head(8, GOAWAY, 0, 0);
+        self.bytes.extend_from_slice(&last_stream_id.to_be_bytes());
+        self.bytes.extend_from_slice(&(reason as u32).to_be_bytes());
+    }
+
+    pub(crate) fn data(&mut self, stream_id: u32, data: &[u8], end_stream: bool) {
+        let flags = if end_stream { END_STREAM } else { 0 };
+        self.frame_head(data.len(), DATA, flags, stream_id);
+        self.bytes.extend_from_slice(data);
+    }
+
+    /// A `HEADERS` frame with `block`, followed by as many `CONTINUATION`
+    /// frames as it takes to keep each within `max_frame_size`
+    pub(crate) fn headers(
+        &mut self,
+        stream_id: u32,
+        block: &[u8],
+        end_stream: bool,
+        max_frame_size: usize,
+    ) {
+        let mut fragments = block.chunks(max_frame_size).peekable();
+        let mut kind = HEADERS;
+        let mut flags = if end_stream { END_STREAM } else { 0 };
+        loop {
+            let fragment = fragments.next().unwrap_or_default();
+            if fragments.peek().is_none() {
+                flags |= END_HEADERS;
+            }
+            self.frame_head(fragment.len(), kind, flags, stream_id);
+            self.bytes.extend_from_slice(fragment);
+            if flags & END_HEADERS != 0 {
+                return;
+            }
+            kind = CONTINUATION;
+            flags = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_heads_round_trip() {
+        let mut out = Outbox::default();
+        out.window_update(3, 1000);
+        let out = out.take();
+        assert_eq!(
+            out,
+            [0, 0, 4, WINDOW_UPDATE, 0, 0, 0, 0, 3, 0, 0, 0x03, 0xe8]
+        );
+        assert_eq!(
+            Head::parse(&out),
+            Head {
+                len: 4,
+                kind: WINDOW_UPDATE,
+                flags: 0,
+                stream_id: 3
+            }
+        );
+        // The reserved bit is not part of the stream identifier
+        let head = Head::parse(&[0x01, 0x00, 0x00, DATA, END_STREAM, 0x80, 0, 0, 1]);
+        assert_eq!((head.len, head.stream_id), (65_536, 1));
+        assert!(head.has(END_STREAM));
+    }
+
+    #[test]
+    fn test_header_blocks_are_split_into_continuations() {
+        let mut out = Outbox::default();
+        out.headers(1, &[7; 10], true, 4);
+        let out = out.take();
+        let mut rest = &out[..];
+        let mut frames = Vec::new();
+        while !rest.is_empty() {
+            let head = Head::parse(rest);
+            frames.push((head.kind, head.flags, head.len));
+            rest = &rest[HEADER_LEN + head.len..];
+        }
+        assert_eq!(
+            frames,
+            [
+                (HEADERS, END_STREAM, 4),
+                (CONTINUATION, 0, 4),
+                (CONTINUATION, END_HEADERS, 2),
+            ]
+        );
+
+        let mut out = Outbox::default();
+        out.headers(1, &[], false, 4);
+        assert_eq!(out.take(), [0, 0, 0, HEADERS, END_HEADERS, 0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_padding_is_stripped() {
+        let padded = Head {
+            len: 6,
+            kind: DATA,
+            flags: PADDED,
+            stream_id: 1,
+        };
+        let payload = Bytes::from_static(&[2, b'h', b'i', b'!', 0, 0]);
+        assert_eq!(unpad(&padded, payload).unwrap(), "hi!");
+        assert!(unpad(&padded, Bytes::from_static(&[6, 0, 0])).is_none());
+        assert!(unpad(&padded, Bytes::new()).is_none());
+    }
+}
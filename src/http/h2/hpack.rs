@@ -0,0 +1,494 @@
+//! HPACK header compression (RFC 7541).
+//!
+//! The [`Decoder`] keeps the dynamic table the peer's encoder fills. The
+//! [`Encoder`] never adds to one: it refers to the static table where it can
+//! and otherwise sends literals, Huffman-coded when that is shorter, so it
+//! holds no state the peer's table size setting could invalidate.
+
+use super::huffman;
+use bytes::Bytes;
+use std::collections::VecDeque;
+use std::fmt;
+
+/// Entries every table starts with, indexed from 1
+#[rustfmt::skip]
+const STATIC_TABLE: [(&str, &str); 61] = [
+    (":authority", ""),
+    (":method", "GET"),
+    (":method", "POST"),
+    (":path", "/"),
+    (":path", "/index.html"),
+    (":scheme", "http"),
+    (":scheme", "https"),
+    (":status", "200"),
+    (":status", "204"),
+    (":status", "206"),
+    (":status", "304"),
+    (":status", "400"),
+    (":status", "404"),
+    (":status", "500"),
+    ("accept-charset", ""),
+    ("accept-encoding", "gzip, deflate"),
+    ("accept-language", ""),
+    ("accept-ranges", ""),
+    ("accept", ""),
+    ("access-control-allow-origin", ""),
+    ("age", ""),
+    ("allow", ""),
+    ("authorization", ""),
+    ("cache-control", ""),
+    ("content-disposition", ""),
+    ("content-encoding", ""),
+    ("content-language", ""),
+    ("content-length", ""),
+    ("content-location", ""),
+    ("content-range", ""),
+    ("content-type", ""),
+    ("cookie", ""),
+    ("date", ""),
+    ("etag", ""),
+    ("expect", ""),
+    ("expires", ""),
+    ("from", ""),
+    ("host", ""),
+    ("if-match", ""),
+    ("if-modified-since", ""),
+    ("if-none-match", ""),
+    ("if-range", ""),
+    ("if-unmodified-since", ""),
+    ("last-modified", ""),
+    ("link", ""),
+    ("location", ""),
+    ("max-forwards", ""),
+    ("proxy-authenticate", ""),
+    ("proxy-authorization", ""),
+    ("range", ""),
+    ("referer", ""),
+    ("refresh", ""),
+    ("retry-after", ""),
+    ("server", ""),
+    ("set-cookie", ""),
+    ("strict-transport-security", ""),
+    ("transfer-encoding", ""),
+    ("user-agent", ""),
+    ("via", ""),
+    ("vary", ""),
+    ("www-authenticate", ""),
+];
+
+/// Bytes an entry takes up in a dynamic table besides its name and value
+const ENTRY_OVERHEAD: usize = 32;
+
+/// A header block that cannot be decoded; the connection cannot continue,
+/// as the peer's dynamic table is now unknown
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DecodeError(&'static str);
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "HPACK: {}", self.0)
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// A decoded header field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Field {
+    pub(crate) name: Bytes,
+    pub(crate) value: Bytes,
+}
+
+impl Field {
+    /// The size the field counts for against table and header list limits
+    pub(crate) fn size(&self) -> usize {
+        self.name.len() + self.value.len() + ENTRY_OVERHEAD
+    }
+}
+
+/// Decodes header blocks, keeping the dynamic table between them
+#[derive(Debug)]
+pub(crate) struct Decoder {
+    /// Newest entry first
+    table: VecDeque<Field>,
+    size: usize,
+    max_size: usize,
+    /// The largest size the peer may set with a table size update
+    size_limit: usize,
+}
+
+impl Decoder {
+    /// A decoder whose dynamic table may hold up to `size_limit` bytes, as
+    /// announced in `SETTINGS_HEADER_TABLE_SIZE`
+    pub(crate) fn new(size_limit: usize) -> Self {
+        Self {
+            table: VecDeque::new(),
+            size: 0,
+            max_size: size_limit,
+            size_limit,
+        }
+    }
+
+    /// Decode a complete header block, or `None` once its fields add up to
+    /// more than `max_list_size`. The rest of such a block is still decoded,
+    /// as the dynamic table must stay in step with the peer's, but its
+    /// fields are no longer collected.
+    pub(crate) fn decode(
+        &mut self,
+        mut block: &[u8],
+        max_list_size: usize,
+    ) -> Result<Option<Vec<Field>>, DecodeError> {
+        let mut fields = Some(Vec::new());
+        let mut list_size = 0;
+        let mut seen_field = false;
+        while let Some(&first) = block.first() {
+            let field = if first & 0x80 != 0 {
+                // Indexed field
+                let index = decode_int(&mut block, 7)?;
+                self.get(index)?
+            } else if first & 0x40 != 0 {
+                // Literal added to the table
+                let field = self.literal(&mut block, 6)?;
+                self.insert(field.clone());
+                field
+            } else if first & 0x20 != 0 {
+                // Table size update, only before the first field
+                if seen_field {
+                    return Err(DecodeError("table size update after a field"));
+                }
+                let max_size = decode_int(&mut block, 5)?;
+                if max_size > self.size_limit {
+                    return Err(DecodeError("table size update above the limit"));
+                }
+                self.max_size = max_size;
+                self.evict(0);
+                continue;
+            } else {
+                // Literal not added to the table, or never to be
+                self.literal(&mut block, 4)?
+            };
+            seen_field = true;
+            list_size += field.size();
+            if list_size > max_list_size {
+                fields = None;
+            }
+            if let Some(fields) = &mut fields {
+                fields.push(field);
+            }
+        }
+        Ok(fields)
+    }
+
+    /// The entry at `index` of the static table followed by the dynamic one
+    fn get(&self, index: usize) -> Result<Field, DecodeError> {
+        match index {
+            0 => Err(DecodeError("index 0")),
+            1..=61 => {
+                let (name, value) = STATIC_TABLE[index - 1];
+                Ok(Field {
+                    name: Bytes::from_static(name.as_bytes()),
+                    value: Bytes::from_static(value.as_bytes()),
+                })
+            }
+            _ => self
+                .table
+                .get(index - 62)
+                .cloned()
+                .ok_or(DecodeError("index beyond the table")),
+        }
+    }
+
+    /// A literal field whose name index has a `prefix`-bit prefix
+    fn literal(&self, block: &mut &[u8], prefix: u8) -> Result<Field, DecodeError> {
+        let name = match decode_int(block, prefix)? {
+            0 => decode_string(block)?,
+            index => self.get(index)?.name,
+        };
+        let value = decode_string(block)?;
+        Ok(Field { name, value })
+    }
+
+    fn insert(&mut self, field: Field) {
+        let size = field.size();
+        if size > self.max_size {
+            // Too large for the table; adding it empties the table instead
+            self.table.clear();
+            self.size = 0;
+            return;
+        }
+        self.evict(size);
+        self.size += size;
+        self.table.push_front(field);
+    }
+
+    /// Evict the oldest entries until `room` more bytes fit
+    fn evict(&mut self, room: usize) {
+        while self.size + room > self.max_size {
+            match self.table.pop_back() {
+                Some(field) => self.size -= field.size(),
+                None => break,
+            }
+        }
+    }
+}
+
+/// Encodes header blocks without a dynamic table
+#[derive(Debug, Default)]
+pub(crate) struct Encoder;
+
+impl Encoder {
+    /// Append the encoding of a field to `block`; a `sensitive` one is
+    /// marked never to be indexed by intermediaries either
+    pub(crate) fn encode(&self, name: &[u8], value: &[u8], sensitive: bool, block: &mut Vec<u8>) {
+        let mut name_index = 0;
+        for (i, (static_name, static_value)) in STATIC_TABLE.iter().enumerate() {
+            if static_name.as_bytes() != name {
+                continue;
+            }
+            if static_value.as_bytes() == value && !sensitive {
+                encode_int(i + 1, 7, 0x80, block);
+                return;
+            }
+            if name_index == 0 {
+                name_index = i + 1;
+            }
+        }
+        let flags = if sensitive { 0x10 } else { 0x00 };
+        encode_int(name_index, 4, flags, block);
+        if name_index == 0 {
+            encode_string(name, block);
+        }
+        encode_string(value, block);
+    }
+}
+
+/// Decode an integer with a `prefix`-bit prefix from the start of `block`
+fn decode_int(block: &mut &[u8], prefix: u8) -> Result<usize, DecodeError> {
+    let (&first, mut rest) = block
+        .split_first()
+        .ok_or(DecodeError("truncated integer"))?;
+    let max_prefix = (1usize << prefix) - 1;
+    let mut value = first as usize & max_prefix;
+    if value == max_prefix {
+        let mut shift = 0;
+        loop {
+            let (&byte, after) = rest.split_first().ok_or(DecodeError("truncated integer"))?;
+            rest = after;
+            // Nothing legitimate comes close to 2^28
+            if shift > 21 {
+                return Err(DecodeError("integer too large"));
+            }
+            value += ((byte & 0x7f) as usize) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+    }
+    *block = rest;
+    Ok(value)
+}
+
+/// Append `value` with a `prefix`-bit prefix, the first byte's other bits
+/// set to `flags`
+fn encode_int(value: usize, prefix: u8, flags: u8, block: &mut Vec<u8>) {
+    let max_prefix = (1usize << prefix) - 1;
+    if value < max_prefix {
+        block.push(flags | value as u8);
+        return;
+    }
+    block.push(flags | max_prefix as u8);
+    let mut rest = value - max_prefix;
+    while rest >= 0x80 {
+        block.push((rest as u8 & 0x7f) | 0x80);
+        rest >>= 7;
+    }
+    block.push(rest as u8);
+}
+
+fn decode_string(block: &mut &[u8]) -> Result<Bytes, DecodeError> {
+    let huffman = block.first().is_some_and(|b| b & 0x80 != 0);
+    let len = decode_int(block, 7)?;
+    if len > block.len() {
+        return Err(DecodeError("truncated string"));
+    }
+    let (data, rest) = block.split_at(len);
+    *block = rest;
+    if huffman {
+        huffman::decode(data)
+            .map(Bytes::from)
+            .ok_or(DecodeError("invalid Huffman code"))
+    } else {
+        Ok(Bytes::copy_from_slice(data))
+    }
+}
+
+/// Append `data` as a string literal, Huffman-coded if that is shorter
+fn encode_string(data: &[u8], block: &mut Vec<u8>) {
+    let huffman_len = huffman::encoded_len(data);
+    if huffman_len < data.len() {
+        encode_int(huffman_len, 7, 0x80, block);
+        huffman::encode(data, block);
+    } else {
+        encode_int(data.len(), 7, 0x00, block);
+        block.extend_from_slice(data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unhex(hex: &str) -> Vec<u8> {
+        let hex: String = hex.split_whitespace().collect();
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn pairs(fields: &[Field]) -> Vec<(&str, &str)> {
+        fields
+            .iter()
+            .map(|f| {
+                (
+                    std::str::from_utf8(&f.name).unwrap(),
+                    std::str::from_utf8(&f.value).unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_integers() {
+        // RFC 7541, C.1
+        let mut block = Vec::new();
+        encode_int(10, 5, 0, &mut block);
+        encode_int(1337, 5, 0, &mut block);
+        encode_int(42, 8, 0, &mut block);
+        assert_eq!(block, [0x0a, 0x1f, 0x9a, 0x0a, 0x2a]);
+        let mut rest = &block[..];
+        assert_eq!(decode_int(&mut rest, 5), Ok(10));
+        assert_eq!(decode_int(&mut rest, 5), Ok(1337));
+        assert_eq!(decode_int(&mut rest, 8), Ok(42));
+        assert!(rest.is_empty());
+        assert!(decode_int(&mut &[0x1f, 0xff, 0xff, 0xff, 0xff, 0x0f][..], 5).is_err());
+    }
+
+    #[test]
+    fn test_rfc_7541_requests_share_the_dynamic_table() {
+        // RFC 7541, C.4: three requests, Huffman-coded
+        let mut decoder = Decoder::new(4096);
+        let first = decoder
+            .decode(
+                &unhex("8286 8441 8cf1 e3c2 e5f2 3a6b a0ab 90f4 ff"),
+                usize::MAX,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            pairs(&first),
+            [
+                (":method", "GET"),
+                (":scheme", "http"),
+                (":path", "/"),
+                (":authority", "www.example.com"),
+            ]
+        );
+        assert_eq!(decoder.size, 57);
+
+        let second = decoder
+            .decode(&unhex("8286 84be 5886 a8eb 1064 9cbf"), usize::MAX)
+            .unwrap()
+            .unwrap();
+        assert_eq!(pairs(&second)[3], (":authority", "www.example.com"));
+        assert_eq!(pairs(&second)[4], ("cache-control", "no-cache"));
+        assert_eq!(decoder.size, 110);
+
+        let third = decoder
+            .decode(
+                &unhex("8287 85bf 4088 25a8 49e9 5ba9 7d7f 8925 a849 e95b b8e8 b4bf"),
+                usize::MAX,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            pairs(&third),
+            [
+                (":method", "GET"),
+                (":scheme", "https"),
+                (":path", "/index.html"),
+                (":authority", "www.example.com"),
+                ("custom-key", "custom-value"),
+            ]
+        );
+        assert_eq!(decoder.size, 164);
+        assert_eq!(decoder.table.len(), 3);
+    }
+
+    #[test]
+    fn test_table_evicts_and_resizes() {
+        let mut decoder = Decoder::new(100);
+        // 40-byte literals with incremental indexing: a third evicts the first
+        let mut block = Vec::new();
+        let mut lens = Vec::new();
+        for value in ["one", "two"] {
+            block.push(0x40);
+            encode_string(b"x-key", &mut block);
+            encode_string(value.as_bytes(), &mut block);
+            lens.push(block.len());
+        }
+        decoder.decode(&block, usize::MAX).unwrap();
+        assert_eq!(decoder.table.len(), 2);
+        decoder.decode(&block[..lens[0]], usize::MAX).unwrap();
+        assert_eq!(decoder.table.len(), 2);
+        assert_eq!(decoder.get(62).unwrap().value, "one");
+        assert_eq!(decoder.get(63).unwrap().value, "two");
+
+        // Shrinking evicts; growing beyond the limit, or updating late, fails
+        decoder.decode(&[0x3f, 0x0d], usize::MAX).unwrap();
+        assert_eq!(decoder.table.len(), 1);
+        assert!(decoder.decode(&[0x3f, 0x46], usize::MAX).is_err());
+        assert!(decoder.decode(&[0x82, 0x20], usize::MAX).is_err());
+        assert!(decoder.decode(&[0xc0], usize::MAX).is_err());
+        assert!(decoder.decode(&[0x80], usize::MAX).is_err());
+    }
+
+    #[test]
+    fn test_oversized_lists_stop_collecting_but_update_the_table() {
+        let mut decoder = Decoder::new(4096);
+        // A thousand `:method: GET` references, 42 bytes each, then a literal
+        // added to the table
+        let mut block = vec![0x82; 1000];
+        block.push(0x40);
+        encode_string(b"x-key", &mut block);
+        encode_string(b"value", &mut block);
+        assert_eq!(decoder.decode(&block, 1000), Ok(None));
+        assert_eq!(decoder.get(62).unwrap().value, "value");
+
+        // Up to the limit, the list is decoded
+        let fields = decoder.decode(&[0x82, 0xbe], 84).unwrap().unwrap();
+        assert_eq!(pairs(&fields), [(":method", "GET"), ("x-key", "value")]);
+        assert_eq!(decoder.decode(&[0x82, 0xbe], 83), Ok(None));
+    }
+
+    #[test]
+    fn test_encoder_output_decodes() {
+        let encoder = Encoder;
+        let mut block = Vec::new();
+        let fields: [(&str, &str, bool); 4] = [
+            (":status", "200", false),
+            ("content-type", "text/plain; charset=utf-8", false),
+            ("x-custom", "Value With Spaces", false),
+            ("set-cookie", "session=secret", true),
+        ];
+        for (name, value, sensitive) in fields {
+            encoder.encode(name.as_bytes(), value.as_bytes(), sensitive, &mut block);
+        }
+        // The status is a single static table index
+        assert_eq!(block[0], 0x88);
+        let decoded = Decoder::new(0).decode(&block, usize::MAX).unwrap().unwrap();
+        let expected: Vec<_> = fields.iter().map(|&(n, v, _)| (n, v)).collect();
+        assert_eq!(pairs(&decoded), expected);
+    }
+}
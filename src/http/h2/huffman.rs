@@ -0,0 +1,202 @@
+//! The Huffman code of HPACK string literals (RFC 7541, Appendix B).
+//!
+//! The code is canonical, so it is fully described by the length of each
+//! symbol's code: codes are assigned in increasing order of length, and
+//! within a length in increasing order of symbol. Symbol 256 is EOS, which
+//! never appears in a string; its leading bits pad the last byte.
+
+/// Code length in bits of each byte value, then of EOS
+#[rustfmt::skip]
+const CODE_LENGTHS: [u8; 257] = [
+    // 0-31: control characters
+    13, 23, 28, 28, 28, 28, 28, 28, 28, 24, 30, 28, 28, 30, 28, 28,
+    28, 28, 28, 28, 28, 28, 30, 28, 28, 28, 28, 28, 28, 28, 28, 28,
+    // 32-127: printable ASCII, then DEL
+     6, 10, 10, 12, 13,  6,  8, 11, 10, 10,  8, 11,  8,  6,  6,  6,
+     5,  5,  5,  6,  6,  6,  6,  6,  6,  6,  7,  8, 15,  6, 12, 10,
+    13,  6,  7,  7,  7,  7,  7,  7,  7,  7,  7,  7,  7,  7,  7,  7,
+     7,  7,  7,  7,  7,  7,  7,  7,  8,  7,  8, 13, 19, 13, 14,  6,
+    15,  5,  6,  5,  6,  5,  6,  6,  6,  5,  7,  7,  6,  6,  6,  5,
+     6,  7,  6,  5,  5,  6,  7,  7,  7,  7,  7, 15, 11, 14, 13, 28,
+    // 128-255
+    20, 22, 20, 20, 22, 22, 22, 23, 22, 23, 23, 23, 23, 23, 24, 23,
+    24, 24, 22, 23, 24, 23, 23, 23, 23, 21, 22, 23, 22, 23, 23, 24,
+    22, 21, 20, 22, 22, 23, 23, 21, 23, 22, 22, 24, 21, 22, 23, 23,
+    21, 21, 22, 21, 23, 22, 23, 23, 20, 22, 22, 22, 23, 22, 22, 23,
+    26, 26, 20, 19, 22, 23, 22, 25, 26, 26, 26, 27, 27, 26, 24, 25,
+    19, 21, 26, 27, 27, 26, 27, 24, 21, 21, 26, 26, 28, 27, 27, 27,
+    20, 24, 20, 21, 22, 21, 21, 23, 22, 22, 25, 25, 24, 24, 26, 23,
+    26, 27, 26, 26, 27, 27, 27, 27, 27, 28, 27, 27, 27, 27, 27, 26,
+    // EOS
+    30,
+];
+
+const EOS: u16 = 256;
+const MAX_LENGTH: usize = 30;
+
+/// The code tables derived from [`CODE_LENGTHS`]
+struct Code {
+    /// The code of each symbol, in its low bits
+    codes: [u32; 257],
+    /// The first code of each length
+    first_code: [u32; MAX_LENGTH + 1],
+    /// How many codes there are of each length
+    count: [u16; MAX_LENGTH + 1],
+    /// Where the symbols of each length start in `symbols`
+    offset: [u16; MAX_LENGTH + 1],
+    /// The symbols ordered by code
+    symbols: [u16; 257],
+}
+
+static CODE: Code = Code::canonical();
+
+impl Code {
+    const fn canonical() -> Self {
+        let mut count = [0u16; MAX_LENGTH + 1];
+        let mut symbol = 0;
+        while symbol < 257 {
+            count[CODE_LENGTHS[symbol] as usize] += 1;
+            symbol += 1;
+        }
+
+        let mut first_code = [0u32; MAX_LENGTH + 1];
+        let mut offset = [0u16; MAX_LENGTH + 1];
+        let mut len = 1;
+        while len <= MAX_LENGTH {
+            first_code[len] = (first_code[len - 1] + count[len - 1] as u32) << 1;
+            offset[len] = offset[len - 1] + count[len - 1];
+            len += 1;
+        }
+
+        let mut codes = [0u32; 257];
+        let mut symbols = [0u16; 257];
+        let mut assigned = [0u16; MAX_LENGTH + 1];
+        let mut symbol = 0;
+        while symbol < 257 {
+            let len = CODE_LENGTHS[symbol] as usize;
+            codes[symbol] = first_code[len] + assigned[len] as u32;
+            symbols[(offset[len] + assigned[len]) as usize] = symbol as u16;
+            assigned[len] += 1;
+            symbol += 1;
+        }
+
+        Self {
+            codes,
+            first_code,
+            count,
+            offset,
+            symbols,
+        }
+    }
+}
+
+/// Length in bytes of `data` once encoded
+pub(crate) fn encoded_len(data: &[u8]) -> usize {
+    let bits: usize = data
+        .iter()
+        .map(|&b| CODE_LENGTHS[b as usize] as usize)
+        .sum();
+    bits.div_ceil(8)
+}
+
+/// Append the encoding of `data` to `out`
+pub(crate) fn encode(data: &[u8], out: &mut Vec<u8>) {
+    let mut bits: u64 = 0;
+    let mut pending = 0;
+    for &byte in data {
+        let len = CODE_LENGTHS[byte as usize] as u32;
+        bits = (bits << len) | CODE.codes[byte as usize] as u64;
+        pending += len;
+        while pending >= 8 {
+            pending -= 8;
+            out.push((bits >> pending) as u8);
+        }
+    }
+    if pending > 0 {
+        // Padded with the most significant bits of EOS, which are all ones
+        let padding = 8 - pending;
+        out.push(((bits << padding) | ((1 << padding) - 1)) as u8);
+    }
+}
+
+/// Decode `data`, or `None` if it is not a valid encoding: it contains EOS,
+/// or ends with more than 7 bits of padding or padding that is not all ones
+pub(crate) fn decode(data: &[u8]) -> Option<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(data.len() * 8 / 5);
+    let mut code: u32 = 0;
+    let mut len = 0;
+    for &byte in data {
+        for shift in (0..8).rev() {
+            code = (code << 1) | ((byte >> shift) & 1) as u32;
+            len += 1;
+            let index = code.wrapping_sub(CODE.first_code[len]);
+            if index < CODE.count[len] as u32 {
+                let symbol = CODE.symbols[(CODE.offset[len] as u32 + index) as usize];
+                if symbol == EOS {
+                    return None;
+                }
+                decoded.push(symbol as u8);
+                code = 0;
+                len = 0;
+            } else if len == MAX_LENGTH {
+                return None;
+            }
+        }
+    }
+    // What is left must be a prefix of EOS: at most 7 one bits
+    (len < 8 && code == (1 << len) - 1).then_some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    #[test]
+    fn test_rfc_7541_examples() {
+        let examples = [
+            ("www.example.com", "f1e3c2e5f23a6ba0ab90f4ff"),
+            ("no-cache", "a8eb10649cbf"),
+            ("custom-key", "25a849e95ba97d7f"),
+            ("custom-value", "25a849e95bb8e8b4bf"),
+            ("302", "6402"),
+            ("private", "aec3771a4b"),
+            (
+                "Mon, 21 Oct 2013 20:13:21 GMT",
+                "d07abe941054d444a8200595040b8166e082a62d1bff",
+            ),
+            (
+                "https://www.example.com",
+                "9d29ad171863c78f0b97c8e9ae82ae43d3",
+            ),
+        ];
+        for (text, encoding) in examples {
+            let mut encoded = Vec::new();
+            encode(text.as_bytes(), &mut encoded);
+            assert_eq!(hex(&encoded), encoding, "{text}");
+            assert_eq!(encoded_len(text.as_bytes()), encoded.len());
+            assert_eq!(decode(&encoded).unwrap(), text.as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_every_byte_round_trips() {
+        let data: Vec<u8> = (0..=255).chain((0..=255).rev()).collect();
+        let mut encoded = Vec::new();
+        encode(&data, &mut encoded);
+        assert_eq!(decode(&encoded).unwrap(), data);
+    }
+
+    #[test]
+    fn test_invalid_padding_is_rejected() {
+        // "a" is 00011: padded with zeros, then with a whole byte of ones
+        assert_eq!(decode(&[0x1f]).unwrap(), b"a");
+        assert!(decode(&[0x18]).is_none());
+        assert!(decode(&[0x1f, 0xff]).is_none());
+        // EOS itself
+        assert!(decode(&[0xff, 0xff, 0xff, 0xfc]).is_none());
+    }
+}
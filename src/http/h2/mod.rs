@@ -0,0 +1,1349 @@
+//! HTTP/2 over cleartext TCP ("h2c", RFC 9113).
+//!
+//! [`HttpConnection::serve`](super::HttpConnection::serve) switches to HTTP/2
+//! when a client opens the connection with the HTTP/2 preface ("prior
+//! knowledge") or asks for it with `Upgrade: h2c`; [`Http2Connection`]
+//! serves a connection known to carry HTTP/2 from its first byte. Each
+//! stream's request goes to the same [`HttpHandler`] as HTTP/1.1 requests,
+//! and each response is sent as soon as it is ready, interleaved with the
+//! others within the flow-control windows the client grants.
+//!
+//! A connection is driven by one future on the core that accepted it: the
+//! handlers of its streams run concurrently inside that future rather than
+//! as tasks of their own, so no part of a connection crosses cores.
+
+mod frame;
+mod hpack;
+mod huffman;
+
+use super::{header, ConnectionOptions, HttpHandler, ParseError, Request, Response, StatusCode};
+use super::{Body, BodyParts, HeaderMap, HeaderName, HeaderValue, Method, Version};
+use crate::buffer::Buffer;
+use crate::cancellation::CancellationToken;
+use crate::net::AsyncTcpStream;
+use crate::timer::SleepFuture;
+use bytes::{Buf, Bytes, BytesMut};
+use frame::{Head, Outbox, Reason};
+use futures::channel::mpsc;
+use futures::stream::FuturesUnordered;
+use futures::StreamExt;
+use hpack::{Decoder, Encoder, Field};
+use std::collections::HashMap;
+use std::future::{poll_fn, Future};
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// What a client sends before its first frame
+pub(crate) const PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+/// Size of the dynamic table HPACK decoding keeps, the protocol's default
+const HEADER_TABLE_SIZE: usize = 4096;
+
+/// Bytes of frames queued for sending beyond which no more are produced, nor
+/// frames read, until they have been written
+const OUTBOX_HIGH_WATER: usize = 64 * 1024;
+
+/// Header fields that only mean something to a single HTTP/1.1 connection
+const CONNECTION_SPECIFIC: [&str; 5] = [
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// The settings a server announces to HTTP/2 clients
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Http2Options {
+    /// Streams a client may have open at once; further streams are refused.
+    /// Defaults to 100.
+    pub max_concurrent_streams: u32,
+    /// Bytes of a request body a client may send on a stream ahead of the
+    /// handler reading them. Defaults to 65535.
+    pub initial_window_size: u32,
+    /// Bytes of request bodies a client may send across all its streams
+    /// ahead of the handlers reading them. Defaults to 1 MiB.
+    pub connection_window_size: u32,
+    /// Largest frame payload accepted, from 16384 to 16777215. Defaults to
+    /// 16384.
+    pub max_frame_size: u32,
+    /// Largest header list accepted, counting 32 bytes per field on top of
+    /// its name and value; a larger one is answered with 431 Request Header
+    /// Fields Too Large. Defaults to 16 KiB.
+    pub max_header_list_size: u32,
+}
+
+impl Default for Http2Options {
+    fn default() -> Self {
+        Self {
+            max_concurrent_streams: 100,
+            initial_window_size: frame::DEFAULT_WINDOW_SIZE,
+            connection_window_size: 1024 * 1024,
+            max_frame_size: frame::DEFAULT_MAX_FRAME_SIZE,
+            max_header_list_size: 16 * 1024,
+        }
+    }
+}
+
+/// HTTP/2 server connection handler, for clients with prior knowledge
+pub struct Http2Connection {
+    stream: Arc<AsyncTcpStream>,
+    options: ConnectionOptions,
+    shutdown: Option<CancellationToken>,
+}
+
+impl Http2Connection {
+    pub fn new(stream: AsyncTcpStream) -> Self {
+        Self {
+            stream: Arc::new(stream),
+            options: ConnectionOptions::default(),
+            shutdown: None,
+        }
+    }
+
+    /// Set how long [`serve`](Self::serve) keeps the connection open, and
+    /// the settings it announces; with no
+    /// [`http2`](ConnectionOptions::http2) options, the defaults
+    pub fn with_options(mut self, options: ConnectionOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Stop [`serve`](Self::serve) once `token` is cancelled
+    ///
+    /// The client is sent a `GOAWAY`, and the connection closes once the
+    /// streams it had opened have been answered.
+    pub fn with_shutdown(mut self, token: CancellationToken) -> Self {
+        self.shutdown = Some(token);
+        self
+    }
+
+    /// Serve the streams the client opens with `handler` until the
+    /// connection should close
+    ///
+    /// The client must start with the connection preface. Streams are
+    /// handled concurrently, each request body read only as its handler
+    /// consumes it and each response body sent as the client's flow-control
+    /// windows allow. The connection closes with a `GOAWAY` once the
+    /// [`max_requests`](ConnectionOptions::max_requests) streams have been
+    /// answered, no stream has been open for the
+    /// [`idle_timeout`](ConnectionOptions::idle_timeout), or the
+    /// [shutdown token](Self::with_shutdown) is cancelled.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The connection ended normally: the client closed it or
+    ///   sent `GOAWAY`, it sat idle, or the server is shutting down
+    /// * `Err(e)` - Reading or writing failed, or the client broke the
+    ///   protocol, which it was told with a `GOAWAY` first
+    pub async fn serve<H: HttpHandler>(&mut self, handler: &H) -> io::Result<()> {
+        let result = serve(
+            self.stream.clone(),
+            BytesMut::new(),
+            &self.options,
+            self.shutdown.clone(),
+            handler,
+            None,
+        )
+        .await;
+        self.close();
+        result
+    }
+
+    /// Shut the socket down, so the client sees the connection close even
+    /// while an abandoned read still holds it open
+    pub(crate) fn close(&self) {
+        let _ = self.stream.shutdown(std::net::Shutdown::Both);
+    }
+}
+
+/// The `SETTINGS` payload of a request asking to upgrade to h2c, if it can
+///
+/// It can if it asks with `Upgrade: h2c`, lists `HTTP2-Settings` in
+/// `Connection`, carries exactly one valid `HTTP2-Settings` field, and has a
+/// body of known length that fits in a stream's window.
+pub(crate) fn h2c_settings(request: &Request, options: &Http2Options) -> Option<Vec<u8>> {
+    let headers = &request.headers;
+    if !super::has_token(headers, &header::UPGRADE, "h2c")
+        || !super::has_token(headers, &header::CONNECTION, "http2-settings")
+    {
+        return None;
+    }
+    let mut fields = headers.get_all("http2-settings").iter();
+    let (Some(settings), None) = (fields.next(), fields.next()) else {
+        return None;
+    };
+    let length = request.body.content_length()?;
+    if length > options.initial_window_size as u64 {
+        return None;
+    }
+    let settings = decode_base64url(settings.as_bytes())?;
+    settings.len().is_multiple_of(6).then_some(settings)
+}
+
+/// Serve HTTP/2 on `stream`, whose first bytes, starting with the preface,
+/// are in `buffer`
+///
+/// An `upgraded` connection switched from HTTP/1.1 with the request given,
+/// which becomes stream 1, and the settings its `HTTP2-Settings` carried.
+pub(crate) async fn serve<H: HttpHandler>(
+    stream: Arc<AsyncTcpStream>,
+    buffer: BytesMut,
+    options: &ConnectionOptions,
+    shutdown: Option<CancellationToken>,
+    handler: &H,
+    upgraded: Option<(Request, Vec<u8>)>,
+) -> io::Result<()> {
+    let mut connection = Connection::new(stream, buffer, options, shutdown, handler);
+    if let Some((request, settings)) = upgraded {
+        connection.upgraded(request, &settings);
+    }
+    connection.process();
+    poll_fn(|cx| connection.poll_run(cx)).await
+}
+
+type HandlerFuture<'h> = Pin<Box<dyn Future<Output = (u32, Response)> + Send + 'h>>;
+type ReadFuture = Pin<Box<dyn Future<Output = io::Result<(usize, Buffer)>> + Send>>;
+type WriteFuture = Pin<Box<dyn Future<Output = io::Result<()>> + Send>>;
+type ChunkFuture = Pin<Box<dyn Future<Output = (Body, io::Result<Option<Buffer>>)> + Send>>;
+
+/// An error that ends the connection, sent to the client with `GOAWAY`
+struct ConnectionError(Reason, &'static str);
+
+type FrameResult = Result<(), ConnectionError>;
+
+/// A stream the client opened and has not been fully answered
+struct StreamState {
+    /// Feeds the request body to the handler, until the client ends it
+    body: Option<mpsc::UnboundedSender<io::Result<Bytes>>>,
+    /// The client has ended its side of the stream
+    remote_closed: bool,
+    /// The request is `HEAD`, so the response has no body
+    head: bool,
+    /// The request's `content-length`, checked against the body received
+    content_length: Option<u64>,
+    received: u64,
+    /// Bytes the client may send before we grant more
+    recv_window: i64,
+    /// Body bytes passed to the handler and not yet read, which the
+    /// connection window is owed once they are
+    unconsumed: usize,
+    /// Bytes we may send before the client grants more
+    send_window: i64,
+    /// The response body being sent, once the handler has answered
+    response: Option<Outgoing>,
+}
+
+impl StreamState {
+    /// End the request body with an error, if the client has not ended it
+    fn abort_body(&mut self, message: &'static str) {
+        if let Some(body) = self.body.take() {
+            let _ =
+                body.unbounded_send(Err(io::Error::new(io::ErrorKind::ConnectionReset, message)));
+        }
+    }
+}
+
+/// A response body on its way out
+struct Outgoing {
+    /// Pulled from the body but not sent yet
+    pending: Bytes,
+    source: Source,
+}
+
+enum Source {
+    Body(Body),
+    /// Waiting for the body's next chunk
+    Reading(ChunkFuture),
+    /// All of the body has been pulled
+    Done,
+}
+
+impl Outgoing {
+    fn new(body: Body) -> Self {
+        match body.into_parts() {
+            BodyParts::Full(bytes) => Self {
+                pending: bytes,
+                source: Source::Done,
+            },
+            BodyParts::File { file, offset, len } => Self {
+                pending: Bytes::new(),
+                source: Source::Body(Body::from_file(*file, offset, len)),
+            },
+            BodyParts::Chunks(body) => Self {
+                pending: Bytes::new(),
+                source: Source::Body(body),
+            },
+        }
+    }
+}
+
+/// The state of a connection, polled as a whole by the one future serving it
+struct Connection<'h, H> {
+    handler: &'h H,
+    stream: Arc<AsyncTcpStream>,
+    settings: Http2Options,
+    idle_timeout: Option<Duration>,
+    max_requests: Option<usize>,
+    shutdown: CancellationToken,
+    shutdown_key: Option<u64>,
+
+    /// Received bytes not yet processed
+    input: BytesMut,
+    preface_received: bool,
+    /// The client's first frame, which must be `SETTINGS`, is still to come
+    awaiting_settings: bool,
+    /// Kept across polls, as dropping a read in flight loses its data
+    reading: Option<ReadFuture>,
+    eof: bool,
+    outbox: Outbox,
+    writing: Option<WriteFuture>,
+    idle: Option<Pin<Box<SleepFuture>>>,
+
+    decoder: Decoder,
+    encoder: Encoder,
+    /// A header block split across `CONTINUATION` frames: its stream,
+    /// whether it ends the stream, and the fragments so far
+    continuation: Option<(u32, bool, BytesMut)>,
+    streams: HashMap<u32, StreamState>,
+    handlers: FuturesUnordered<HandlerFuture<'h>>,
+    /// Request body bytes handlers have consumed, to be granted back
+    credits: mpsc::UnboundedSender<(u32, usize)>,
+    consumed: mpsc::UnboundedReceiver<(u32, usize)>,
+    last_stream_id: u32,
+    served: usize,
+
+    /// Bytes the client may send on the connection before we grant more
+    recv_window: i64,
+    /// Bytes we may send on the connection before the client grants more
+    send_window: i64,
+    peer_initial_window_size: u32,
+    peer_max_frame_size: usize,
+    /// Our settings have been acknowledged, so the client applies them
+    settings_acked: bool,
+    /// `GOAWAY` has been sent: no further streams are accepted
+    going_away: bool,
+    /// The client broke the protocol; the connection closes once the
+    /// `GOAWAY` saying so is written
+    failed: Option<io::Error>,
+}
+
+impl<'h, H: HttpHandler> Connection<'h, H> {
+    fn new(
+        stream: Arc<AsyncTcpStream>,
+        input: BytesMut,
+        options: &ConnectionOptions,
+        shutdown: Option<CancellationToken>,
+        handler: &'h H,
+    ) -> Self {
+        let settings = options.http2.clone().unwrap_or_default();
+        let mut outbox = Outbox::default();
+        outbox.settings(&[
+            (
+                frame::SETTINGS_MAX_CONCURRENT_STREAMS,
+                settings.max_concurrent_streams,
+            ),
+            (
+                frame::SETTINGS_INITIAL_WINDOW_SIZE,
+                settings.initial_window_size.min(frame::MAX_WINDOW_SIZE),
+            ),
+            (
+                frame::SETTINGS_MAX_FRAME_SIZE,
+                settings
+                    .max_frame_size
+                    .clamp(frame::DEFAULT_MAX_FRAME_SIZE, frame::MAX_MAX_FRAME_SIZE),
+            ),
+            (
+                frame::SETTINGS_MAX_HEADER_LIST_SIZE,
+                settings.max_header_list_size,
+            ),
+        ]);
+        // The connection window starts at the default whatever the settings
+        let connection_window_size = settings
+            .connection_window_size
+            .clamp(frame::DEFAULT_WINDOW_SIZE, frame::MAX_WINDOW_SIZE);
+        if connection_window_size > frame::DEFAULT_WINDOW_SIZE {
+            outbox.window_update(0, connection_window_size - frame::DEFAULT_WINDOW_SIZE);
+        }
+        let (credits, consumed) = mpsc::unbounded();
+        Self {
+            handler,
+            stream,
+            settings,
+            idle_timeout: options.idle_timeout,
+            max_requests: options.max_requests,
+            shutdown: shutdown.unwrap_or_default(),
+            shutdown_key: None,
+            input,
+            preface_received: false,
+            awaiting_settings: true,
+            reading: None,
+            eof: false,
+            outbox,
+            writing: None,
+            idle: None,
+            decoder: Decoder::new(HEADER_TABLE_SIZE),
+            encoder: Encoder,
+            continuation: None,
+            streams: HashMap::new(),
+            handlers: FuturesUnordered::new(),
+            credits,
+            consumed,
+            last_stream_id: 0,
+            served: 0,
+            recv_window: connection_window_size as i64,
+            send_window: frame::DEFAULT_WINDOW_SIZE as i64,
+            peer_initial_window_size: frame::DEFAULT_WINDOW_SIZE,
+            peer_max_frame_size: frame::DEFAULT_MAX_FRAME_SIZE as usize,
+            settings_acked: false,
+            going_away: false,
+            failed: None,
+        }
+    }
+
+    /// Take `request`, sent over HTTP/1.1 with `settings` before switching,
+    /// as the request of stream 1
+    fn upgraded(&mut self, mut request: Request, settings: &[u8]) {
+        if let Err(ConnectionError(reason, message)) = self.apply_settings(settings) {
+            self.fail(reason, message);
+            return;
+        }
+        for name in CONNECTION_SPECIFIC.iter().chain(&["http2-settings", "te"]) {
+            request.headers.remove(*name);
+        }
+        request.version = Version::HTTP_2;
+        request.upgrade = None;
+        self.last_stream_id = 1;
+        let mut stream = self.new_stream(&request);
+        stream.remote_closed = true;
+        self.streams.insert(1, stream);
+        self.dispatch(1, request);
+    }
+
+    fn poll_run(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        loop {
+            let mut progress = false;
+
+            if !self.going_away {
+                if self.shutdown.is_cancelled() {
+                    self.go_away(Reason::NoError);
+                    progress = true;
+                } else {
+                    self.shutdown.register(&mut self.shutdown_key, cx.waker());
+                }
+            }
+
+            if self.failed.is_none() && !self.eof && self.outbox.len() < OUTBOX_HIGH_WATER {
+                let stream = self.stream.clone();
+                let read = self
+                    .reading
+                    .get_or_insert_with(|| Box::pin(async move { stream.read().await }));
+                if let Poll::Ready(result) = read.as_mut().poll(cx) {
+                    self.reading = None;
+                    progress = true;
+                    let (bytes_read, buffer) = result?;
+                    if bytes_read == 0 {
+                        self.client_closed();
+                    } else {
+                        self.input.extend_from_slice(&buffer[..bytes_read]);
+                        self.process();
+                    }
+                }
+            }
+
+            while let Poll::Ready(Some((id, response))) = self.handlers.poll_next_unpin(cx) {
+                self.respond(id, response);
+                progress = true;
+            }
+
+            while let Poll::Ready(Some((id, consumed))) = self.consumed.poll_next_unpin(cx) {
+                // A stream that is gone granted back what was unread then
+                if let Some(stream) = self.streams.get_mut(&id) {
+                    stream.unconsumed -= consumed;
+                    if !stream.remote_closed {
+                        stream.recv_window += consumed as i64;
+                        self.outbox.window_update(id, consumed as u32);
+                    }
+                    self.release(consumed);
+                }
+                progress = true;
+            }
+
+            progress |= self.send_bodies(cx);
+
+            if self.writing.is_none() && !self.outbox.is_empty() {
+                let stream = self.stream.clone();
+                let bytes = self.outbox.take();
+                self.writing = Some(Box::pin(async move { stream.write_all(&bytes).await }));
+            }
+            if let Some(write) = &mut self.writing {
+                if let Poll::Ready(result) = write.as_mut().poll(cx) {
+                    self.writing = None;
+                    result?;
+                    progress = true;
+                }
+            }
+
+            let flushed = self.writing.is_none() && self.outbox.is_empty();
+            let quiet = self.streams.is_empty() && self.handlers.is_empty();
+            if flushed {
+                if let Some(error) = self.failed.take() {
+                    return Poll::Ready(Err(error));
+                }
+                if quiet && (self.going_away || self.eof) {
+                    return Poll::Ready(Ok(()));
+                }
+            }
+
+            match self.idle_timeout {
+                Some(idle_timeout) if quiet && !self.going_away => {
+                    let idle = self
+                        .idle
+                        .get_or_insert_with(|| Box::pin(SleepFuture::new(idle_timeout)));
+                    if idle.as_mut().poll(cx).is_ready() {
+                        self.idle = None;
+                        self.go_away(Reason::NoError);
+                        progress = true;
+                    }
+                }
+                _ => self.idle = None,
+            }
+
+            if !progress {
+                return Poll::Pending;
+            }
+        }
+    }
+
+    /// Handle the complete frames received so far
+    fn process(&mut self) {
+        if let Err(ConnectionError(reason, message)) = self.process_frames() {
+            self.fail(reason, message);
+        }
+    }
+
+    fn process_frames(&mut self) -> FrameResult {
+        if !self.preface_received {
+            if self.input.len() < PREFACE.len() {
+                return match PREFACE.starts_with(&self.input) {
+                    true => Ok(()),
+                    false => Err(ConnectionError(Reason::ProtocolError, "invalid preface")),
+                };
+            }
+            if !self.input.starts_with(PREFACE) {
+                return Err(ConnectionError(Reason::ProtocolError, "invalid preface"));
+            }
+            self.input.advance(PREFACE.len());
+            self.preface_received = true;
+        }
+        while self.failed.is_none() && self.input.len() >= frame::HEADER_LEN {
+            let head = Head::parse(&self.input);
+            if head.len
+                > self
+                    .settings
+                    .max_frame_size
+                    .max(frame::DEFAULT_MAX_FRAME_SIZE) as usize
+            {
+                return Err(ConnectionError(Reason::FrameSizeError, "frame too large"));
+            }
+            if self.input.len() < frame::HEADER_LEN + head.len {
+                break;
+            }
+            self.input.advance(frame::HEADER_LEN);
+            let payload = self.input.split_to(head.len).freeze();
+            self.frame(head, payload)?;
+        }
+        Ok(())
+    }
+
+    fn frame(&mut self, head: Head, payload: Bytes) -> FrameResult {
+        if self.awaiting_settings {
+            if head.kind != frame::SETTINGS || head.has(frame::ACK) {
+                return Err(ConnectionError(
+                    Reason::ProtocolError,
+                    "first frame is not SETTINGS",
+                ));
+            }
+            self.awaiting_settings = false;
+        }
+        if let Some((id, ..)) = &self.continuation {
+            if head.kind != frame::CONTINUATION || head.stream_id != *id {
+                return Err(ConnectionError(
+                    Reason::ProtocolError,
+                    "header block interrupted",
+                ));
+            }
+        }
+        let is_connection_frame = matches!(
+            head.kind,
+            frame::SETTINGS | frame::PING | frame::GOAWAY | frame::WINDOW_UPDATE
+        );
+        if !is_connection_frame && head.stream_id == 0 && head.kind <= frame::CONTINUATION {
+            return Err(ConnectionError(
+                Reason::ProtocolError,
+                "stream frame on stream 0",
+            ));
+        }
+        match head.kind {
+            frame::DATA => self.on_data(head, payload),
+            frame::HEADERS => self.on_headers(head, payload),
+            frame::PRIORITY if head.len != 5 => {
+                self.reset(head.stream_id, Reason::FrameSizeError);
+                Ok(())
+            }
+            frame::RST_STREAM => self.on_rst_stream(head, payload),
+            frame::SETTINGS => self.on_settings(head, payload),
+            frame::PUSH_PROMISE => Err(ConnectionError(
+                Reason::ProtocolError,
+                "clients cannot push",
+            )),
+            frame::PING => self.on_ping(head, payload),
+            frame::GOAWAY if head.stream_id != 0 => {
+                Err(ConnectionError(Reason::ProtocolError, "GOAWAY on a stream"))
+            }
+            frame::GOAWAY => {
+                // The client opens no more streams; finish those it has
+                self.go_away(Reason::NoError);
+                Ok(())
+            }
+            frame::WINDOW_UPDATE => self.on_window_update(head, payload),
+            frame::CONTINUATION => self.on_continuation(head, payload),
+            // Priorities are not acted on; unknown frame types are ignored
+            _ => Ok(()),
+        }
+    }
+
+    fn on_data(&mut self, head: Head, payload: Bytes) -> FrameResult {
+        let id = head.stream_id;
+        if head.len as i64 > self.recv_window {
+            return Err(ConnectionError(
+                Reason::FlowControlError,
+                "connection window exceeded",
+            ));
+        }
+        self.recv_window -= head.len as i64;
+        let data = frame::unpad(&head, payload)
+            .ok_or(ConnectionError(Reason::ProtocolError, "invalid padding"))?;
+        let Some(stream) = self.streams.get_mut(&id) else {
+            if id > self.last_stream_id {
+                return Err(ConnectionError(
+                    Reason::ProtocolError,
+                    "DATA on an idle stream",
+                ));
+            }
+            // A stream already closed or reset: its data is dropped
+            self.release(head.len);
+            return Ok(());
+        };
+        if stream.remote_closed {
+            self.release(head.len);
+            self.reset(id, Reason::StreamClosed);
+            return Ok(());
+        }
+        stream.recv_window -= head.len as i64;
+        stream.received += data.len() as u64;
+        if stream.recv_window < 0 {
+            self.release(head.len);
+            self.reset(id, Reason::FlowControlError);
+            return Ok(());
+        }
+        if stream
+            .content_length
+            .is_some_and(|length| stream.received > length)
+        {
+            self.release(head.len);
+            self.reset(id, Reason::ProtocolError);
+            return Ok(());
+        }
+
+        // Padding, and data no handler will read, are granted back at once
+        let mut unused = head.len - data.len();
+        if !data.is_empty() {
+            let delivered = stream
+                .body
+                .as_ref()
+                .is_some_and(|body| body.unbounded_send(Ok(data.clone())).is_ok());
+            if delivered {
+                stream.unconsumed += data.len();
+            } else {
+                unused += data.len();
+            }
+        }
+        if head.has(frame::END_STREAM) {
+            self.end_request_body(id);
+        } else if unused > 0 {
+            stream.recv_window += unused as i64;
+            self.outbox.window_update(id, unused as u32);
+        }
+        self.release(unused);
+        Ok(())
+    }
+
+    /// Grant the client `len` more bytes on the connection
+    fn release(&mut self, len: usize) {
+        if len > 0 {
+            self.recv_window += len as i64;
+            self.outbox.window_update(0, len as u32);
+        }
+    }
+
+    /// Forget stream `id`, granting back the body bytes it left unread
+    fn remove_stream(&mut self, id: u32) -> Option<StreamState> {
+        let stream = self.streams.remove(&id)?;
+        self.release(stream.unconsumed);
+        Some(stream)
+    }
+
+    fn on_headers(&mut self, head: Head, payload: Bytes) -> FrameResult {
+        let mut block = frame::unpad(&head, payload)
+            .ok_or(ConnectionError(Reason::ProtocolError, "invalid padding"))?;
+        if head.has(frame::PRIORITY_FLAG) {
+            if block.len() < 5 {
+                return Err(ConnectionError(
+                    Reason::FrameSizeError,
+                    "HEADERS too short for its priority",
+                ));
+            }
+            block.advance(5);
+        }
+        let end_stream = head.has(frame::END_STREAM);
+        if head.has(frame::END_HEADERS) {
+            self.on_header_block(head.stream_id, end_stream, &block)
+        } else {
+            self.continuation = Some((head.stream_id, end_stream, BytesMut::from(&block[..])));
+            Ok(())
+        }
+    }
+
+    fn on_continuation(&mut self, head: Head, payload: Bytes) -> FrameResult {
+        let Some((id, end_stream, mut block)) = self.continuation.take() else {
+            return Err(ConnectionError(
+                Reason::ProtocolError,
+                "CONTINUATION without HEADERS",
+            ));
+        };
+        block.extend_from_slice(&payload);
+        // This only bounds the bytes buffered; the list a block decodes to may
+        // be far larger, and is bounded while decoding
+        if block.len() > self.settings.max_header_list_size as usize {
+            return Err(ConnectionError(
+                Reason::EnhanceYourCalm,
+                "header block too large",
+            ));
+        }
+        if head.has(frame::END_HEADERS) {
+            self.on_header_block(id, end_stream, &block)
+        } else {
+            self.continuation = Some((id, end_stream, block));
+            Ok(())
+        }
+    }
+
+    /// A complete header block: a request opening a stream, or trailers
+    fn on_header_block(&mut self, id: u32, end_stream: bool, block: &[u8]) -> FrameResult {
+        let fields = self
+            .decoder
+            .decode(block, self.settings.max_header_list_size as usize)
+            .map_err(|_| ConnectionError(Reason::CompressionError, "invalid header block"))?;
+
+        if let Some(stream) = self.streams.get(&id) {
+            // Trailers, which are not passed on, must end the request
+            if stream.remote_closed {
+                self.reset(id, Reason::StreamClosed);
+            } else if !end_stream {
+                self.reset(id, Reason::ProtocolError);
+            } else {
+                self.end_request_body(id);
+            }
+            return Ok(());
+        }
+        if id <= self.last_stream_id {
+            // A stream already closed or reset
+            return Ok(());
+        }
+        if id.is_multiple_of(2) {
+            return Err(ConnectionError(
+                Reason::ProtocolError,
+                "even stream identifier",
+            ));
+        }
+        self.last_stream_id = id;
+        if self.going_away {
+            return Ok(());
+        }
+        if self.streams.len() >= self.settings.max_concurrent_streams as usize {
+            self.outbox.rst_stream(id, Reason::RefusedStream);
+            return Ok(());
+        }
+
+        let Some(fields) = fields else {
+            let mut stream = self.new_stream(&Request::new(Method::GET, "/".to_string()));
+            stream.remote_closed = end_stream;
+            self.streams.insert(id, stream);
+            self.respond(id, ParseError::HeadersTooLarge.to_response());
+            return Ok(());
+        };
+        let Some(mut request) = request_from_fields(fields) else {
+            self.outbox.rst_stream(id, Reason::ProtocolError);
+            return Ok(());
+        };
+        let mut stream = self.new_stream(&request);
+        if end_stream {
+            stream.remote_closed = true;
+            if stream.content_length.is_some_and(|length| length > 0) {
+                self.outbox.rst_stream(id, Reason::ProtocolError);
+                return Ok(());
+            }
+        } else {
+            let (body, chunks) = mpsc::unbounded();
+            stream.body = Some(body);
+            let credits = self.credits.clone();
+            request.body = Body::from_stream(chunks.map(move |chunk| -> io::Result<Buffer> {
+                let chunk: Bytes = chunk?;
+                let _ = credits.unbounded_send((id, chunk.len()));
+                Ok(chunk.into())
+            }));
+        }
+        self.streams.insert(id, stream);
+        self.dispatch(id, request);
+        Ok(())
+    }
+
+    fn new_stream(&self, request: &Request) -> StreamState {
+        let initial_window_size = match self.settings_acked {
+            true => self.settings.initial_window_size,
+            // Until then the client may go by the default
+            false => self
+                .settings
+                .initial_window_size
+                .max(frame::DEFAULT_WINDOW_SIZE),
+        };
+        StreamState {
+            body: None,
+            remote_closed: false,
+            head: request.method == Method::HEAD,
+            content_length: request
+                .headers
+                .get(header::CONTENT_LENGTH)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse().ok()),
+            received: 0,
+            recv_window: initial_window_size as i64,
+            unconsumed: 0,
+            send_window: self.peer_initial_window_size as i64,
+            response: None,
+        }
+    }
+
+    /// Hand the request of stream `id` to the handler
+    fn dispatch(&mut self, id: u32, request: Request) {
+        let handler = self.handler;
+        self.handlers
+            .push(Box::pin(async move { (id, handler.handle(request).await) }));
+        self.served += 1;
+        if self.max_requests.is_some_and(|max| self.served >= max) {
+            self.go_away(Reason::NoError);
+        }
+    }
+
+    /// The client has ended the request body of stream `id`
+    fn end_request_body(&mut self, id: u32) {
+        let Some(stream) = self.streams.get_mut(&id) else {
+            return;
+        };
+        stream.remote_closed = true;
+        if stream
+            .content_length
+            .is_some_and(|length| length != stream.received)
+        {
+            self.reset(id, Reason::ProtocolError);
+            return;
+        }
+        // Dropping the sender ends the body once what was sent is read
+        stream.body = None;
+    }
+
+    fn on_rst_stream(&mut self, head: Head, payload: Bytes) -> FrameResult {
+        if payload.len() != 4 {
+            return Err(ConnectionError(
+                Reason::FrameSizeError,
+                "RST_STREAM is not 4 bytes",
+            ));
+        }
+        if head.stream_id > self.last_stream_id {
+            return Err(ConnectionError(
+                Reason::ProtocolError,
+                "RST_STREAM on an idle stream",
+            ));
+        }
+        if let Some(mut stream) = self.remove_stream(head.stream_id) {
+            stream.abort_body("stream reset by the client");
+        }
+        Ok(())
+    }
+
+    fn on_settings(&mut self, head: Head, payload: Bytes) -> FrameResult {
+        if head.stream_id != 0 {
+            return Err(ConnectionError(
+                Reason::ProtocolError,
+                "SETTINGS on a stream",
+            ));
+        }
+        if head.has(frame::ACK) {
+            if !payload.is_empty() {
+                return Err(ConnectionError(
+                    Reason::FrameSizeError,
+                    "SETTINGS acknowledgement with a payload",
+                ));
+            }
+            self.settings_acked = true;
+            return Ok(());
+        }
+        if !payload.len().is_multiple_of(6) {
+            return Err(ConnectionError(
+                Reason::FrameSizeError,
+                "SETTINGS length is not a multiple of 6",
+            ));
+        }
+        self.apply_settings(&payload)?;
+        self.outbox.settings_ack();
+        Ok(())
+    }
+
+    fn apply_settings(&mut self, payload: &[u8]) -> FrameResult {
+        for (id, value) in frame::settings(payload) {
+            match id {
+                frame::SETTINGS_ENABLE_PUSH if value > 1 => {
+                    return Err(ConnectionError(
+                        Reason::ProtocolError,
+                        "invalid SETTINGS_ENABLE_PUSH",
+                    ))
+                }
+                frame::SETTINGS_INITIAL_WINDOW_SIZE => {
+                    if value > frame::MAX_WINDOW_SIZE {
+                        return Err(ConnectionError(
+                            Reason::FlowControlError,
+                            "invalid SETTINGS_INITIAL_WINDOW_SIZE",
+                        ));
+                    }
+                    // Open streams' windows move by the difference
+                    let delta = value as i64 - self.peer_initial_window_size as i64;
+                    for stream in self.streams.values_mut() {
+                        stream.send_window += delta;
+                        if stream.send_window > frame::MAX_WINDOW_SIZE as i64 {
+                            return Err(ConnectionError(
+                                Reason::FlowControlError,
+                                "stream window too large",
+                            ));
+                        }
+                    }
+                    self.peer_initial_window_size = value;
+                }
+                frame::SETTINGS_MAX_FRAME_SIZE => {
+                    if !(frame::DEFAULT_MAX_FRAME_SIZE..=frame::MAX_MAX_FRAME_SIZE).contains(&value)
+                    {
+                        return Err(ConnectionError(
+                            Reason::ProtocolError,
+                            "invalid SETTINGS_MAX_FRAME_SIZE",
+                        ));
+                    }
+                    self.peer_max_frame_size = value as usize;
+                }
+                // Responses are encoded without a dynamic table, and the
+                // rest concern what a client accepts from servers that push
+                frame::SETTINGS_HEADER_TABLE_SIZE
+                | frame::SETTINGS_MAX_CONCURRENT_STREAMS
+                | frame::SETTINGS_MAX_HEADER_LIST_SIZE => {}
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn on_ping(&mut self, head: Head, payload: Bytes) -> FrameResult {
+        if head.stream_id != 0 {
+            return Err(ConnectionError(Reason::ProtocolError, "PING on a stream"));
+        }
+        if payload.len() != 8 {
+            return Err(ConnectionError(
+                Reason::FrameSizeError,
+                "PING is not 8 bytes",
+            ));
+        }
+        if !head.has(frame::ACK) {
+            self.outbox.ping_ack(&payload);
+        }
+        Ok(())
+    }
+
+    fn on_window_update(&mut self, head: Head, payload: Bytes) -> FrameResult {
+        if payload.len() != 4 {
+            return Err(ConnectionError(
+                Reason::FrameSizeError,
+                "WINDOW_UPDATE is not 4 bytes",
+            ));
+        }
+        let increment = (payload.clone().get_u32() & frame::MAX_WINDOW_SIZE) as i64;
+        let id = head.stream_id;
+        if id == 0 {
+            if increment == 0 {
+                return Err(ConnectionError(Reason::ProtocolError, "WINDOW_UPDATE of 0"));
+            }
+            self.send_window += increment;
+            if self.send_window > frame::MAX_WINDOW_SIZE as i64 {
+                return Err(ConnectionError(
+                    Reason::FlowControlError,
+                    "connection window too large",
+                ));
+            }
+        } else if let Some(stream) = self.streams.get_mut(&id) {
+            stream.send_window += increment;
+            if increment == 0 {
+                self.reset(id, Reason::ProtocolError);
+            } else if stream.send_window > frame::MAX_WINDOW_SIZE as i64 {
+                self.reset(id, Reason::FlowControlError);
+            }
+        } else if id > self.last_stream_id {
+            return Err(ConnectionError(
+                Reason::ProtocolError,
+                "WINDOW_UPDATE on an idle stream",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Send the head of the response to stream `id`, and set its body up to
+    /// be sent
+    fn respond(&mut self, id: u32, mut response: Response) {
+        let Some(stream) = self.streams.get_mut(&id) else {
+            // Reset while the handler was running
+            return;
+        };
+        let status = response.status;
+        let no_content = matches!(status, StatusCode::NO_CONTENT | StatusCode::NOT_MODIFIED);
+        if !no_content && !response.headers.contains_key(header::CONTENT_LENGTH) {
+            if let Some(length) = response.body.content_length() {
+                response
+                    .headers
+                    .insert(header::CONTENT_LENGTH, HeaderValue::from(length));
+            }
+        }
+
+        let mut block = Vec::new();
+        self.encoder
+            .encode(b":status", status.as_str().as_bytes(), false, &mut block);
+        for (name, value) in &response.headers {
+            if !CONNECTION_SPECIFIC.contains(&name.as_str()) {
+                self.encoder.encode(
+                    name.as_str().as_bytes(),
+                    value.as_bytes(),
+                    value.is_sensitive(),
+                    &mut block,
+                );
+            }
+        }
+        let end_stream = stream.head || no_content || response.body.content_length() == Some(0);
+        self.outbox
+            .headers(id, &block, end_stream, self.peer_max_frame_size);
+        if end_stream {
+            self.finish(id);
+        } else {
+            stream.response = Some(Outgoing::new(response.body));
+        }
+    }
+
+    /// Send what the windows allow of the response bodies being sent
+    ///
+    /// Returns whether anything was sent or pulled from a body.
+    fn send_bodies(&mut self, cx: &mut Context<'_>) -> bool {
+        let mut progress = false;
+        let mut finished = Vec::new();
+        let mut failed = Vec::new();
+        for (&id, stream) in &mut self.streams {
+            let Some(outgoing) = &mut stream.response else {
+                continue;
+            };
+            loop {
+                if outgoing.pending.is_empty() {
+                    match std::mem::replace(&mut outgoing.source, Source::Done) {
+                        Source::Body(mut body) => {
+                            outgoing.source = Source::Reading(Box::pin(async move {
+                                let chunk = body.chunk().await;
+                                (body, chunk)
+                            }));
+                        }
+                        Source::Reading(mut read) => match read.as_mut().poll(cx) {
+                            Poll::Pending => {
+                                outgoing.source = Source::Reading(read);
+                                break;
+                            }
+                            Poll::Ready((body, Ok(Some(chunk)))) => {
+                                outgoing.pending = chunk.into_bytes();
+                                outgoing.source = Source::Body(body);
+                                progress = true;
+                            }
+                            Poll::Ready((_, Ok(None))) => progress = true,
+                            Poll::Ready((_, Err(e))) => {
+                                tracing::debug!("HTTP/2 response body failed: {}", e);
+                                failed.push(id);
+                                break;
+                            }
+                        },
+                        Source::Done => {
+                            if self.outbox.len() >= OUTBOX_HIGH_WATER {
+                                break;
+                            }
+                            self.outbox.data(id, &[], true);
+                            finished.push(id);
+                            progress = true;
+                            break;
+                        }
+                    }
+                    continue;
+                }
+
+                let allowed = (outgoing.pending.len() as i64)
+                    .min(stream.send_window)
+                    .min(self.send_window)
+                    .min(self.peer_max_frame_size as i64);
+                if allowed <= 0 || self.outbox.len() >= OUTBOX_HIGH_WATER {
+                    break;
+                }
+                let data = outgoing.pending.split_to(allowed as usize);
+                let end_stream =
+                    outgoing.pending.is_empty() && matches!(outgoing.source, Source::Done);
+                self.outbox.data(id, &data, end_stream);
+                stream.send_window -= allowed;
+                self.send_window -= allowed;
+                progress = true;
+                if end_stream {
+                    finished.push(id);
+                    break;
+                }
+            }
+        }
+        for id in finished {
+            self.finish(id);
+        }
+        for id in failed {
+            self.reset(id, Reason::InternalError);
+        }
+        progress
+    }
+
+    /// The response to stream `id` has been sent in full
+    fn finish(&mut self, id: u32) {
+        if let Some(mut stream) = self.remove_stream(id) {
+            if !stream.remote_closed {
+                // The rest of the request is not needed
+                stream.abort_body("response sent before the request body ended");
+                self.outbox.rst_stream(id, Reason::NoError);
+            }
+        }
+    }
+
+    /// End stream `id` early
+    fn reset(&mut self, id: u32, reason: Reason) {
+        self.outbox.rst_stream(id, reason);
+        if let Some(mut stream) = self.remove_stream(id) {
+            stream.abort_body("stream reset");
+        }
+    }
+
+    /// Tell the client no further streams will be accepted
+    fn go_away(&mut self, reason: Reason) {
+        if !self.going_away {
+            self.going_away = true;
+            self.outbox.goaway(self.last_stream_id, reason);
+        }
+    }
+
+    /// The client broke the protocol: tell it why and close the connection
+    fn fail(&mut self, reason: Reason, message: &'static str) {
+        self.going_away = true;
+        self.outbox.goaway(self.last_stream_id, reason);
+        for (_, mut stream) in self.streams.drain() {
+            stream.abort_body("connection failed");
+        }
+        self.failed = Some(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("HTTP/2 {reason:?}: {message}"),
+        ));
+    }
+
+    /// The client will send nothing more; streams still being answered are
+    fn client_closed(&mut self) {
+        self.eof = true;
+        self.continuation = None;
+        for stream in self.streams.values_mut() {
+            stream.abort_body("Connection closed in the middle of a request body");
+            stream.remote_closed = true;
+        }
+    }
+}
+
+impl<H> Drop for Connection<'_, H> {
+    fn drop(&mut self) {
+        self.shutdown.deregister(&mut self.shutdown_key);
+    }
+}
+
+/// The request a header block describes, or `None` if it is malformed
+fn request_from_fields(fields: Vec<Field>) -> Option<Request> {
+    let (mut method, mut scheme, mut path, mut authority) = (None, None, None, None);
+    let mut headers = HeaderMap::with_capacity(fields.len());
+    let mut cookies = Vec::new();
+    for Field { name, value } in fields {
+        if let Some(pseudo) = name.strip_prefix(b":") {
+            // Pseudo-header fields come first, each at most once
+            if !headers.is_empty() || !cookies.is_empty() {
+                return None;
+            }
+            let slot = match pseudo {
+                b"method" => &mut method,
+                b"scheme" => &mut scheme,
+                b"path" => &mut path,
+                b"authority" => &mut authority,
+                _ => return None,
+            };
+            if slot.replace(value).is_some() {
+                return None;
+            }
+            continue;
+        }
+        if name.iter().any(u8::is_ascii_uppercase) {
+            return None;
+        }
+        let name = HeaderName::from_bytes(&name).ok()?;
+        let connection_specific = CONNECTION_SPECIFIC.contains(&name.as_str());
+        if connection_specific || (name == header::TE && value != "trailers") {
+            return None;
+        }
+        let value = HeaderValue::from_maybe_shared(value).ok()?;
+        if name == header::COOKIE {
+            cookies.push(value);
+        } else {
+            headers.append(name, value);
+        }
+    }
+
+    let method = Method::from_bytes(&method?).ok()?;
+    scheme?;
+    let path = String::from_utf8(path?.to_vec()).ok()?;
+    if path.is_empty() {
+        return None;
+    }
+    if let Some(authority) = authority {
+        if !headers.contains_key(header::HOST) {
+            headers.insert(
+                header::HOST,
+                HeaderValue::from_maybe_shared(authority).ok()?,
+            );
+        }
+    }
+    // Cookies may be split across fields, but HTTP/1.1 has them in one
+    if !cookies.is_empty() {
+        let joined: Vec<&[u8]> = cookies.iter().map(HeaderValue::as_bytes).collect();
+        headers.insert(
+            header::COOKIE,
+            HeaderValue::from_bytes(&joined.join(&b"; "[..])).ok()?,
+        );
+    }
+    let mut request = Request::new(method, path);
+    request.version = Version::HTTP_2;
+    request.headers = headers;
+    Some(request)
+}
+
+/// Decode unpadded base64url, as `HTTP2-Settings` is sent
+fn decode_base64url(text: &[u8]) -> Option<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(text.len() * 3 / 4);
+    let mut bits: u32 = 0;
+    let mut count = 0;
+    for &c in text.iter().take_while(|&&c| c != b'=') {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'-' => 62,
+            b'_' => 63,
+            _ => return None,
+        };
+        bits = (bits << 6) | value as u32;
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            decoded.push((bits >> count) as u8);
+        }
+    }
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &'static str, value: &'static str) -> Field {
+        Field {
+            name: Bytes::from_static(name.as_bytes()),
+            value: Bytes::from_static(value.as_bytes()),
+        }
+    }
+
+    fn get(path: &'static str) -> Vec<Field> {
+        vec![
+            field(":method", "GET"),
+            field(":scheme", "http"),
+            field(":path", path),
+            field(":authority", "example.com"),
+        ]
+    }
+
+    #[test]
+    fn test_request_from_fields() {
+        let mut fields = get("/search?q=h2");
+        fields.push(field("cookie", "a=1"));
+        fields.push(field("accept", "*/*"));
+        fields.push(field("cookie", "b=2"));
+        let request = request_from_fields(fields).unwrap();
+        assert_eq!(request.method, Method::GET);
+        assert_eq!(request.path, "/search");
+        assert_eq!(request.query["q"], "h2");
+        assert_eq!(request.version, Version::HTTP_2);
+        assert_eq!(request.headers["host"], "example.com");
+        assert_eq!(request.headers["cookie"], "a=1; b=2");
+    }
+
+    #[test]
+    fn test_malformed_requests_are_refused() {
+        let malformed = [
+            vec![field(":method", "GET"), field(":path", "/")],
+            [get("/"), vec![field(":path", "/again")]].concat(),
+            [vec![field("accept", "*/*")], get("/")].concat(),
+            [get("/"), vec![field(":status", "200")]].concat(),
+            [get("/"), vec![field("connection", "keep-alive")]].concat(),
+            [get("/"), vec![field("te", "gzip")]].concat(),
+            [get("/"), vec![field("Accept", "*/*")]].concat(),
+            get(""),
+        ];
+        for fields in malformed {
+            let names: Vec<_> = fields.iter().map(|f| f.name.clone()).collect();
+            assert!(request_from_fields(fields).is_none(), "{names:?}");
+        }
+        let trailers = [get("/"), vec![field("te", "trailers")]].concat();
+        assert!(request_from_fields(trailers).is_some());
+    }
+
+    #[test]
+    fn test_decode_base64url() {
+        assert_eq!(
+            decode_base64url(b"AAMAAABkAAQAAP__").unwrap(),
+            [0, 3, 0, 0, 0, 100, 0, 4, 0, 0, 255, 255]
+        );
+        assert_eq!(decode_base64url(b"aGk=").unwrap(), b"hi");
+        assert!(decode_base64url(b"a+b/").is_none());
+    }
+}
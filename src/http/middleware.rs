@@ -0,0 +1,224 @@
+//! Middleware wrapping an [`HttpHandler`].
+//!
+//! A [`Middleware`] sees each request before the handler it wraps and decides
+//! whether and how to pass it on through [`Next`]; it also sees the response
+//! on the way back. [`HttpHandler::layer`] wraps any handler, a [`Router`](super::Router)
+//! included, and layers compose: the one added last runs first.
+
+use super::router::DynHandler;
+use super::{HttpHandler, Request, Response, StatusCode};
+use futures::FutureExt;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::time::Instant;
+
+/// Code running around a handler
+pub trait Middleware: Send + Sync + 'static {
+    /// Handle `request`, passing it on to the wrapped handler with `next`
+    /// if and when appropriate
+    fn call<'a>(
+        &'a self,
+        request: Request,
+        next: Next<'a>,
+    ) -> impl Future<Output = Response> + Send + 'a;
+}
+
+/// The rest of the chain after a [`Middleware`]
+pub struct Next<'a> {
+    handler: &'a dyn DynHandler,
+}
+
+impl Next<'_> {
+    /// Pass `request` on and return the response
+    pub async fn run(self, request: Request) -> Response {
+        self.handler.handle_dyn(request).await
+    }
+}
+
+/// A handler wrapped in a middleware, created by [`HttpHandler::layer`]
+pub struct Layered<M, H> {
+    middleware: M,
+    handler: H,
+}
+
+impl<M: Middleware, H: HttpHandler> Layered<M, H> {
+    /// Wrap `handler` in `middleware`
+    pub fn new(middleware: M, handler: H) -> Self {
+        Self {
+            middleware,
+            handler,
+        }
+    }
+}
+
+impl<M: Middleware, H: HttpHandler> HttpHandler for Layered<M, H> {
+    fn handle(&self, request: Request) -> impl Future<Output = Response> + Send {
+        self.middleware.call(
+            request,
+            Next {
+                handler: &self.handler,
+            },
+        )
+    }
+}
+
+/// Logs every request with its response status and how long it took
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Logger;
+
+impl Middleware for Logger {
+    async fn call<'a>(&'a self, request: Request, next: Next<'a>) -> Response {
+        let started = Instant::now();
+        let method = request.method.clone();
+        let path = request.path.clone();
+        let response = next.run(request).await;
+        tracing::info!(
+            "{} {} -> {} in {:?}",
+            method,
+            path,
+            response.status.as_u16(),
+            started.elapsed()
+        );
+        response
+    }
+}
+
+/// Reports how long the handler took in a `server-timing` header
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Timing;
+
+impl Middleware for Timing {
+    async fn call<'a>(&'a self, request: Request, next: Next<'a>) -> Response {
+        let started = Instant::now();
+        let response = next.run(request).await;
+        let millis = started.elapsed().as_secs_f64() * 1000.0;
+        response.with_header("server-timing", &format!("app;dur={millis:.3}"))
+    }
+}
+
+/// Lets a request through only if a check accepts it
+///
+/// # Examples
+///
+/// ```
+/// use rust_miniss::http::Auth;
+/// use rust_miniss::{HttpHandler, Response, StaticHandler, StatusCode};
+///
+/// let admin = StaticHandler::new("secret", "text/plain").layer(Auth::new(|request| {
+///     match request.headers.get("authorization") {
+///         Some(value) if value == "Bearer letmein" => Ok(()),
+///         _ => Err(Response::new(StatusCode::UNAUTHORIZED).with_body("Unauthorized")),
+///     }
+/// }));
+/// ```
+pub struct Auth<F> {
+    check: F,
+}
+
+impl<F> Auth<F>
+where
+    F: Fn(&Request) -> Result<(), Response> + Send + Sync + 'static,
+{
+    /// Pass requests `check` accepts on; answer the others with the response
+    /// it rejects them with
+    pub fn new(check: F) -> Self {
+        Self { check }
+    }
+}
+
+impl<F> Middleware for Auth<F>
+where
+    F: Fn(&Request) -> Result<(), Response> + Send + Sync + 'static,
+{
+    async fn call<'a>(&'a self, request: Request, next: Next<'a>) -> Response {
+        match (self.check)(&request) {
+            Ok(()) => next.run(request).await,
+            Err(rejection) => rejection,
+        }
+    }
+}
+
+/// Answers with 500 Internal Server Error when the handler panics, instead of
+/// letting the panic take down the connection, or with [`Server::run`](super::Server::run)
+/// the whole server
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CatchPanic;
+
+impl Middleware for CatchPanic {
+    async fn call<'a>(&'a self, request: Request, next: Next<'a>) -> Response {
+        match AssertUnwindSafe(next.run(request)).catch_unwind().await {
+            Ok(response) => response,
+            Err(payload) => {
+                let message = payload
+                    .downcast_ref::<&str>()
+                    .copied()
+                    .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+                    .unwrap_or("unknown panic");
+                tracing::error!("HTTP handler panicked: {}", message);
+                Response::new(StatusCode::INTERNAL_SERVER_ERROR).with_body("Internal Server Error")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{handler_fn, Method, Router};
+    use futures::executor::block_on;
+
+    /// Appends its tag to an `x-trace` header on the way out
+    struct Tag(&'static str);
+
+    impl Middleware for Tag {
+        async fn call<'a>(&'a self, request: Request, next: Next<'a>) -> Response {
+            let mut response = next.run(request).await;
+            let trace = match response.headers.remove("x-trace") {
+                Some(trace) => format!("{},{}", trace.to_str().unwrap(), self.0),
+                None => self.0.to_string(),
+            };
+            response.with_header("x-trace", &trace)
+        }
+    }
+
+    #[test]
+    // Auth rejects with a whole Response by design
+    #[allow(clippy::result_large_err)]
+    fn test_layers_compose_around_router() {
+        let router = Router::new()
+            .get(
+                "/ok",
+                handler_fn(|_| async { Response::new(StatusCode::OK).with_body("ok") }),
+            )
+            .get("/boom", handler_fn(|_| async { panic!("handler failed") }));
+        let app = router
+            .layer(CatchPanic)
+            .layer(Tag("inner"))
+            .layer(Auth::new(|request: &Request| {
+                if request.query.contains_key("key") {
+                    Ok(())
+                } else {
+                    Err(Response::new(StatusCode::UNAUTHORIZED))
+                }
+            }))
+            .layer(Timing)
+            .layer(Tag("outer"));
+        let call = |target: &str| block_on(app.handle(Request::new(Method::GET, target.into())));
+
+        let ok = call("/ok?key=1");
+        assert_eq!(ok.body, b"ok");
+        assert_eq!(ok.headers["x-trace"], "inner,outer");
+        assert!(ok.headers["server-timing"]
+            .to_str()
+            .unwrap()
+            .starts_with("app;dur="));
+
+        let denied = call("/ok");
+        assert_eq!(denied.status, StatusCode::UNAUTHORIZED);
+        assert_eq!(denied.headers["x-trace"], "outer");
+
+        let panicked = call("/boom?key=1");
+        assert_eq!(panicked.status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(panicked.headers["x-trace"], "inner,outer");
+    }
+}
@@ -0,0 +1,909 @@
+//! HTTP request and response handling for the miniss runtime.
+//!
+//! This module provides high-level HTTP abstractions built on top of the async TCP functionality.
+//! It supports incremental HTTP/1.1 request parsing and response generation.
+//!
+//! Methods, status codes, versions and header maps are the [`http`](::http)
+//! crate's, and [`Request`] and [`Response`] convert to and from its
+//! `Request<Body>` and `Response<Body>` without losing anything, so handlers
+//! can work with crates built on it.
+
+use crate::buffer::Buffer;
+use crate::cancellation::{CancellableFutureExt, CancellationToken};
+use crate::net::AsyncTcpStream;
+use crate::timer;
+use body::{BodyParts, IncomingBody, IncomingState};
+use bytes::BytesMut;
+use parser::BodyDecoder;
+use std::collections::HashMap;
+use std::fmt::{self, Write as _};
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::str;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+mod body;
+mod client;
+mod files;
+mod h2;
+mod middleware;
+mod parser;
+mod router;
+mod server;
+mod websocket;
+
+pub use ::http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode, Uri, Version};
+pub use body::Body;
+pub use client::{Client, ClientOptions, ClientRequest};
+pub use files::{FileServer, FileServerOptions};
+pub use h2::{Http2Connection, Http2Options};
+pub use middleware::{Auth, CatchPanic, Layered, Logger, Middleware, Next, Timing};
+pub use parser::{ParseError, ParserLimits, RequestParser};
+pub use router::{handler_fn, HandlerFn, Router};
+pub use server::{Server, ServerBuilder, ServerHandle};
+pub use websocket::{Message, WebSocket, WebSocketOptions, WebSocketUpgrade};
+
+/// HTTP request
+#[derive(Debug)]
+pub struct Request {
+    pub method: Method,
+    /// The request target without its query string
+    pub path: String,
+    /// The query string as received, without the `?`
+    pub query_string: Option<String>,
+    pub version: Version,
+    /// Header fields; a repeated field keeps all its values
+    pub headers: HeaderMap,
+    /// Percent-decoded query string parameters; of a repeated name, the last
+    /// value wins
+    pub query: HashMap<String, String>,
+    /// Path parameters captured by the [`Router`] route that matched
+    pub params: HashMap<String, String>,
+    /// The body; under [`HttpConnection::serve`], still arriving on the
+    /// connection and read as it is consumed
+    pub body: Body,
+    /// Takes over the connection after a 101 Switching Protocols response,
+    /// for a request asking to upgrade under [`HttpConnection::serve`]
+    upgrade: Option<Upgrade>,
+}
+
+impl Request {
+    /// Create a request for `target`, whose query string, if any, is parsed
+    /// into [`query`](Self::query)
+    pub fn new(method: Method, target: String) -> Self {
+        let (path, query_string) = match target.split_once('?') {
+            Some((path, query)) => (path.to_string(), Some(query.to_string())),
+            None => (target, None),
+        };
+        Self {
+            method,
+            path,
+            query: query_string.as_deref().map(parse_query).unwrap_or_default(),
+            query_string,
+            version: Version::HTTP_11,
+            headers: HeaderMap::new(),
+            params: HashMap::new(),
+            body: Body::empty(),
+            upgrade: None,
+        }
+    }
+
+    /// The path parameter `name` captured by the matching route
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params.get(name).map(String::as_str)
+    }
+
+    /// Whether the client asked for the connection to stay open after this
+    /// request: HTTP/1.1 unless it sent `Connection: close`, HTTP/1.0 only if
+    /// it sent `Connection: keep-alive`.
+    pub fn wants_keep_alive(&self) -> bool {
+        keeps_alive(self.version, &self.headers)
+    }
+
+    /// Parse a complete HTTP request from bytes
+    ///
+    /// Bytes after the request are ignored. Fails with `UnexpectedEof` if the
+    /// request is incomplete, and with `InvalidData` wrapping a
+    /// [`ParseError`] if it is invalid.
+    pub fn parse(data: &[u8]) -> io::Result<Self> {
+        let mut parser = RequestParser::new(ParserLimits::default());
+        parser.feed(data);
+        parser
+            .parse()?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "Incomplete request"))
+    }
+}
+
+/// Whether a message of `version` with `headers` leaves the connection open
+/// after it: HTTP/1.1 unless it says `Connection: close`, HTTP/1.0 only if it
+/// says `Connection: keep-alive`
+pub(crate) fn keeps_alive(version: Version, headers: &HeaderMap) -> bool {
+    if version == Version::HTTP_10 {
+        has_token(headers, &header::CONNECTION, "keep-alive")
+    } else {
+        !has_token(headers, &header::CONNECTION, "close")
+    }
+}
+
+/// Whether any of the comma-separated values of the `name` fields is `token`
+pub(crate) fn has_token(headers: &HeaderMap, name: &HeaderName, token: &str) -> bool {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|t| t.trim().eq_ignore_ascii_case(token))
+}
+
+/// Whether the body is sent with chunked transfer coding: the last coding
+/// applied is `chunked`
+pub(crate) fn is_chunked(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::TRANSFER_ENCODING)
+        .iter()
+        .next_back()
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.rsplit(',').next())
+        .is_some_and(|coding| coding.trim().eq_ignore_ascii_case("chunked"))
+}
+
+/// `name` and `value` as a header field
+///
+/// # Panics
+///
+/// Panics if `name` is not a valid header name or `value` not a valid
+/// header value.
+pub(crate) fn header_field(name: &str, value: &str) -> (HeaderName, HeaderValue) {
+    let name =
+        HeaderName::try_from(name).unwrap_or_else(|_| panic!("invalid header name {name:?}"));
+    let value = HeaderValue::try_from(value)
+        .unwrap_or_else(|_| panic!("invalid value {value:?} for header {name}"));
+    (name, value)
+}
+
+/// Parse a query string of `name=value` pairs separated by `&`
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (
+                percent_decode(&name.replace('+', " ")),
+                percent_decode(&value.replace('+', " ")),
+            )
+        })
+        .collect()
+}
+
+/// Decode `%XX` escapes, leaving malformed ones as they are
+pub(crate) fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| bytes.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// HTTP response
+#[derive(Debug)]
+pub struct Response {
+    pub status: StatusCode,
+    pub version: Version,
+    /// Header fields; a repeated field keeps all its values
+    pub headers: HeaderMap,
+    pub body: Body,
+}
+
+impl Response {
+    pub fn new(status: StatusCode) -> Self {
+        let mut response = Self {
+            status,
+            version: Version::HTTP_11,
+            headers: HeaderMap::new(),
+            body: Body::empty(),
+        };
+
+        // Add default headers
+        response
+            .headers
+            .insert(header::SERVER, HeaderValue::from_static("miniss/1.0"));
+        response
+    }
+
+    /// Set the body, and the `content-length` header if its length is known
+    ///
+    /// A body of unknown length is sent with chunked transfer encoding.
+    pub fn with_body(mut self, body: impl Into<Body>) -> Self {
+        let body = body.into();
+        match body.content_length() {
+            Some(len) => self
+                .headers
+                .insert(header::CONTENT_LENGTH, HeaderValue::from(len)),
+            None => self.headers.remove(header::CONTENT_LENGTH),
+        };
+        self.body = body;
+        self
+    }
+
+    /// Set a header, replacing any of the same name
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` or `value` is not valid in a header.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        let (name, value) = header_field(name, value);
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Add a header, keeping any of the same name, as repeated fields such
+    /// as `set-cookie` need
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` or `value` is not valid in a header.
+    pub fn append_header(mut self, name: &str, value: &str) -> Self {
+        let (name, value) = header_field(name, value);
+        self.headers.append(name, value);
+        self
+    }
+
+    /// Convert response to bytes for sending over TCP
+    ///
+    /// Only a body held in memory is included.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.head_bytes();
+        bytes.extend_from_slice(self.body.as_bytes().unwrap_or_default());
+        bytes
+    }
+
+    /// Add the header framing the body: `content-length` if its length is
+    /// known, otherwise `transfer-encoding: chunked` if `chunked` allows it
+    ///
+    /// Returns whether the body's end can be found without closing the
+    /// connection.
+    fn frame_body(&mut self, chunked: bool) -> bool {
+        if self.headers.contains_key(header::CONTENT_LENGTH)
+            || self.headers.contains_key(header::TRANSFER_ENCODING)
+        {
+            return true;
+        }
+        match self.body.content_length() {
+            Some(len) => {
+                self.headers
+                    .insert(header::CONTENT_LENGTH, HeaderValue::from(len));
+                true
+            }
+            None if chunked => {
+                self.headers.insert(
+                    header::TRANSFER_ENCODING,
+                    HeaderValue::from_static("chunked"),
+                );
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The status line and headers, up to and including the blank line
+    pub fn head_bytes(&self) -> Vec<u8> {
+        let status_line = format!(
+            "{:?} {} {}",
+            self.version,
+            self.status.as_str(),
+            self.status.canonical_reason().unwrap_or_default()
+        );
+        head_bytes(&status_line, &self.headers)
+    }
+}
+
+/// A request or status line and the header fields, up to and including the
+/// blank line
+pub(crate) fn head_bytes(start_line: &str, headers: &HeaderMap) -> Vec<u8> {
+    let mut head = Vec::with_capacity(start_line.len() + 32 * headers.len() + 4);
+    head.extend_from_slice(start_line.as_bytes());
+    head.extend_from_slice(b"\r\n");
+    for (name, value) in headers {
+        head.extend_from_slice(name.as_str().as_bytes());
+        head.extend_from_slice(b": ");
+        head.extend_from_slice(value.as_bytes());
+        head.extend_from_slice(b"\r\n");
+    }
+    head.extend_from_slice(b"\r\n");
+    head
+}
+
+/// Path parameters, kept in the extensions of an `http::Request` converted
+/// from a [`Request`] so that converting back restores them
+#[derive(Debug, Clone)]
+struct PathParams(HashMap<String, String>);
+
+impl From<Request> for ::http::Request<Body> {
+    fn from(request: Request) -> Self {
+        let target = match &request.query_string {
+            Some(query) => format!("{}?{}", request.path, query),
+            None => request.path,
+        };
+        let mut converted = ::http::Request::new(request.body);
+        *converted.method_mut() = request.method;
+        *converted.uri_mut() = target_uri(&target);
+        *converted.version_mut() = request.version;
+        *converted.headers_mut() = request.headers;
+        if !request.params.is_empty() {
+            converted
+                .extensions_mut()
+                .insert(PathParams(request.params));
+        }
+        if let Some(upgrade) = request.upgrade {
+            converted.extensions_mut().insert(upgrade);
+        }
+        converted
+    }
+}
+
+impl<B: Into<Body>> From<::http::Request<B>> for Request {
+    fn from(request: ::http::Request<B>) -> Self {
+        let (mut parts, body) = request.into_parts();
+        let target = parts
+            .uri
+            .path_and_query()
+            .map_or("/", |target| target.as_str());
+        let mut converted = Request::new(parts.method, target.to_string());
+        converted.version = parts.version;
+        converted.headers = parts.headers;
+        if let Some(PathParams(params)) = parts.extensions.remove() {
+            converted.params = params;
+        }
+        converted.upgrade = parts.extensions.remove();
+        converted.body = body.into();
+        converted
+    }
+}
+
+impl From<Response> for ::http::Response<Body> {
+    fn from(response: Response) -> Self {
+        let mut converted = ::http::Response::new(response.body);
+        *converted.status_mut() = response.status;
+        *converted.version_mut() = response.version;
+        *converted.headers_mut() = response.headers;
+        converted
+    }
+}
+
+impl<B: Into<Body>> From<::http::Response<B>> for Response {
+    fn from(response: ::http::Response<B>) -> Self {
+        let (parts, body) = response.into_parts();
+        Self {
+            status: parts.status,
+            version: parts.version,
+            headers: parts.headers,
+            body: body.into(),
+        }
+    }
+}
+
+/// What takes over a connection once it has switched protocols: given the
+/// stream and the bytes already read past the request
+pub(crate) type OnUpgrade =
+    Box<dyn FnOnce(Arc<AsyncTcpStream>, BytesMut) -> UpgradeFuture + Send + 'static>;
+
+pub(crate) type UpgradeFuture = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+/// The slot a handler puts an [`OnUpgrade`] in, shared with the connection
+/// the request arrived on
+#[derive(Clone, Default)]
+pub(crate) struct Upgrade(Arc<Mutex<Option<OnUpgrade>>>);
+
+impl Upgrade {
+    /// Run `on_upgrade` on the connection after the response is sent, if it
+    /// is 101 Switching Protocols
+    pub(crate) fn set(&self, on_upgrade: OnUpgrade) {
+        *self.0.lock().unwrap() = Some(on_upgrade);
+    }
+
+    fn take(&self) -> Option<OnUpgrade> {
+        self.0.lock().unwrap().take()
+    }
+}
+
+impl fmt::Debug for Upgrade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Upgrade").finish_non_exhaustive()
+    }
+}
+
+/// `target` as a URI, percent-encoding any bytes a URI cannot hold
+fn target_uri(target: &str) -> Uri {
+    Uri::try_from(target).unwrap_or_else(|_| {
+        let mut encoded = String::with_capacity(target.len());
+        for byte in target.bytes() {
+            if byte.is_ascii_graphic() && !b"\"#<>\\^`{|}".contains(&byte) {
+                encoded.push(byte as char);
+            } else {
+                let _ = write!(encoded, "%{byte:02X}");
+            }
+        }
+        Uri::try_from(encoded).unwrap_or_else(|_| Uri::from_static("/"))
+    })
+}
+
+/// How long [`HttpConnection::serve`] keeps a connection open
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionOptions {
+    /// How long to wait for the next request before closing the connection.
+    /// `None` waits forever. Defaults to 60 seconds.
+    pub idle_timeout: Option<Duration>,
+    /// Requests served before the connection is closed. `None` serves any
+    /// number. Defaults to 1000.
+    pub max_requests: Option<usize>,
+    /// Settings for clients that switch to HTTP/2, with prior knowledge or
+    /// `Upgrade: h2c`. `None` serves HTTP/1.x only. Defaults to
+    /// `Some(Http2Options::default())`.
+    pub http2: Option<Http2Options>,
+}
+
+impl Default for ConnectionOptions {
+    fn default() -> Self {
+        Self {
+            idle_timeout: Some(Duration::from_secs(60)),
+            max_requests: Some(1000),
+            http2: Some(Http2Options::default()),
+        }
+    }
+}
+
+/// What a connection waiting for a request received
+// Only ever held briefly on the stack
+#[allow(clippy::large_enum_variant)]
+enum Incoming {
+    /// The head of a request, whose body the decoder reads
+    Request(Request, BodyDecoder),
+    /// The HTTP/2 connection preface
+    Http2,
+}
+
+/// HTTP server connection handler
+pub struct HttpConnection {
+    /// Shared with the body of the request being handled
+    stream: Arc<AsyncTcpStream>,
+    parser: RequestParser,
+    options: ConnectionOptions,
+    shutdown: Option<CancellationToken>,
+}
+
+impl HttpConnection {
+    pub fn new(stream: AsyncTcpStream) -> Self {
+        Self::with_limits(stream, ParserLimits::default())
+    }
+
+    /// Create a connection handler whose requests are parsed within `limits`
+    pub fn with_limits(stream: AsyncTcpStream, limits: ParserLimits) -> Self {
+        Self {
+            stream: Arc::new(stream),
+            parser: RequestParser::new(limits),
+            options: ConnectionOptions::default(),
+            shutdown: None,
+        }
+    }
+
+    /// Set how long [`serve`](Self::serve) keeps the connection open
+    pub fn with_options(mut self, options: ConnectionOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Stop [`serve`](Self::serve) once `token` is cancelled
+    ///
+    /// A connection waiting for a request closes at once; one handling a
+    /// request closes after sending its response.
+    pub fn with_shutdown(mut self, token: CancellationToken) -> Self {
+        self.shutdown = Some(token);
+        self
+    }
+
+    /// Serve requests with `handler` until the connection should close
+    ///
+    /// Requests are handled one at a time, so responses to pipelined requests
+    /// go out in the order the requests arrived. Each request's body is read
+    /// from the connection only as the handler consumes it; if the handler
+    /// leaves part of it unreceived, the connection closes after the
+    /// response. Response bodies of unknown length are sent chunked, or to
+    /// HTTP/1.0 clients delimited by closing the connection. It closes after
+    /// a response when the request or response carries `Connection: close`,
+    /// an HTTP/1.0 client did not ask for keep-alive, or the
+    /// [`max_requests`](ConnectionOptions::max_requests) have been served;
+    /// the last response then says `Connection: close`. It also closes when
+    /// no request arrives within the
+    /// [`idle_timeout`](ConnectionOptions::idle_timeout), or the
+    /// [shutdown token](Self::with_shutdown) is cancelled.
+    ///
+    /// A 101 Switching Protocols response to a request that asked to upgrade,
+    /// such as one built by [`WebSocketUpgrade::on_upgrade`], hands the
+    /// connection over for good: it closes once what took it over is done.
+    ///
+    /// A client that opens the connection with the HTTP/2 preface, or whose
+    /// request asks for `Upgrade: h2c`, is served HTTP/2 from then on, as by
+    /// [`Http2Connection::serve`], unless the
+    /// [`http2`](ConnectionOptions::http2) options are `None`. An upgrading
+    /// request is read in full and answered on stream 1; one whose body is
+    /// not known to fit in a stream's window stays on HTTP/1.1.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The connection ended normally: the client closed it
+    ///   between requests, it sat idle, one side asked to close it, or the
+    ///   server is shutting down
+    /// * `Err(e)` - Reading or writing failed, the client closed it in the
+    ///   middle of a request, or sent an invalid one. Invalid requests are
+    ///   answered with their [`ParseError::to_response`] first
+    pub async fn serve<H: HttpHandler>(&mut self, handler: &H) -> io::Result<()> {
+        let result = self.serve_requests(handler).await;
+        self.close();
+        result
+    }
+
+    /// Shut the socket down, so the client sees the connection close even
+    /// while an abandoned read still holds it open
+    pub(crate) fn close(&self) {
+        let _ = self.stream.shutdown(std::net::Shutdown::Both);
+    }
+
+    /// The head of the next request, or `None` when the connection went idle
+    /// or the server is shutting down
+    async fn next_request(&mut self, first: bool) -> Option<io::Result<Incoming>> {
+        let shutdown = self.shutdown.clone().unwrap_or_default();
+        let idle_timeout = self.options.idle_timeout;
+        let http2 = first && self.options.http2.is_some();
+        let read = self.read_head(http2).cancellable(shutdown);
+        let read = match idle_timeout {
+            Some(idle) => timer::timeout(idle, read).await.ok()?,
+            None => read.await,
+        };
+        read.ok()
+    }
+
+    async fn serve_requests<H: HttpHandler>(&mut self, handler: &H) -> io::Result<()> {
+        let mut served = 0;
+        loop {
+            let Some(next) = self.next_request(served == 0).await else {
+                return Ok(());
+            };
+            let (mut request, decoder) = match next {
+                Ok(Incoming::Request(request, decoder)) => (request, decoder),
+                Ok(Incoming::Http2) => {
+                    let buffer = self.parser.take_buffer();
+                    let stream = self.stream.clone();
+                    let shutdown = self.shutdown.clone();
+                    return h2::serve(stream, buffer, &self.options, shutdown, handler, None).await;
+                }
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof && self.parser.is_empty() => {
+                    return Ok(())
+                }
+                Err(e) => {
+                    if let Some(parse_error) =
+                        e.get_ref().and_then(|e| e.downcast_ref::<ParseError>())
+                    {
+                        self.send_response(parse_error.to_response()).await?;
+                    }
+                    return Err(e);
+                }
+            };
+
+            served += 1;
+            let http_10 = request.version == Version::HTTP_10;
+            let mut keep_alive = request.wants_keep_alive()
+                && self.options.max_requests.is_none_or(|max| served < max);
+            let (body, body_state) =
+                IncomingBody::new(self.stream.clone(), self.parser.take_buffer(), decoder);
+            request.body = Body::incoming(body);
+            let h2c_settings = (self.options.http2.as_ref())
+                .and_then(|options| h2::h2c_settings(&request, options));
+            if let Some(settings) = h2c_settings {
+                return self
+                    .upgrade_to_http2(request, body_state, settings, handler)
+                    .await;
+            }
+            let upgrade = request
+                .headers
+                .contains_key(header::UPGRADE)
+                .then(Upgrade::default);
+            request.upgrade = upgrade.clone();
+            let mut response = handler.handle(request).await;
+
+            // A 101 is sent as the handler made it, and hands the connection
+            // over to what the handler set up to take it
+            let on_upgrade = upgrade
+                .filter(|_| response.status == StatusCode::SWITCHING_PROTOCOLS)
+                .and_then(|upgrade| upgrade.take());
+            if on_upgrade.is_none() {
+                if self.shutdown.as_ref().is_some_and(|t| t.is_cancelled()) {
+                    keep_alive = false;
+                }
+                if has_token(&response.headers, &header::CONNECTION, "close") {
+                    keep_alive = false;
+                }
+                // HTTP/1.0 has no chunked encoding; the close ends the body then
+                if !response.frame_body(!http_10) {
+                    keep_alive = false;
+                }
+                if !keep_alive {
+                    response = response.with_header("connection", "close");
+                } else if http_10 {
+                    response = response.with_header("connection", "keep-alive");
+                }
+            }
+            self.write_response(response).await?;
+
+            // The response may have been streamed from the request body, so
+            // the rest of the connection is only taken back now
+            let after_body = body_state.lock().unwrap().detach();
+            if let Some(on_upgrade) = on_upgrade {
+                if let Some(buffer) = after_body {
+                    on_upgrade(self.stream.clone(), buffer).await;
+                }
+                return Ok(());
+            }
+            match after_body {
+                Some(buffer) if keep_alive => self.parser.restore_buffer(buffer),
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Read and parse the next HTTP request from the connection
+    ///
+    /// Reads until the request is complete, however it is split across
+    /// segments; bytes of pipelined requests after it are kept for the next
+    /// call. An invalid request fails with `InvalidData` wrapping a
+    /// [`ParseError`], whose [`to_response`](ParseError::to_response) is the
+    /// answer to send before closing. A connection closed between requests
+    /// fails with `UnexpectedEof`.
+    pub async fn read_request(&mut self) -> io::Result<Request> {
+        loop {
+            if let Some(request) = self.parser.parse()? {
+                return Ok(request);
+            }
+            let (bytes_read, buffer) = self.stream.read().await?;
+            if bytes_read == 0 {
+                let message = if self.parser.is_empty() {
+                    "Connection closed"
+                } else {
+                    "Connection closed in the middle of a request"
+                };
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, message));
+            }
+            self.parser.feed(&buffer.as_ref()[..bytes_read]);
+        }
+    }
+
+    /// Read until the head of the next request is complete, leaving its
+    /// body to be read through the returned decoder, or, if `http2` allows,
+    /// until the HTTP/2 preface has arrived
+    async fn read_head(&mut self, http2: bool) -> io::Result<Incoming> {
+        loop {
+            let buffered = self.parser.buffered();
+            if http2 && buffered.starts_with(h2::PREFACE) {
+                return Ok(Incoming::Http2);
+            }
+            let maybe_preface = http2 && h2::PREFACE.starts_with(buffered);
+            if buffered.is_empty() || !maybe_preface {
+                if let Some((request, decoder)) = self.parser.parse_head()? {
+                    return Ok(Incoming::Request(request, decoder));
+                }
+            }
+            let (bytes_read, buffer) = self.stream.read().await?;
+            if bytes_read == 0 {
+                let message = if self.parser.is_empty() {
+                    "Connection closed"
+                } else {
+                    "Connection closed in the middle of a request"
+                };
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, message));
+            }
+            self.parser.feed(&buffer.as_ref()[..bytes_read]);
+        }
+    }
+
+    /// Answer `request`, which asked to upgrade to h2c with `settings`, with
+    /// 101 Switching Protocols, then serve HTTP/2 with the request as stream 1
+    async fn upgrade_to_http2<H: HttpHandler>(
+        &mut self,
+        mut request: Request,
+        body_state: Arc<Mutex<IncomingState>>,
+        settings: Vec<u8>,
+        handler: &H,
+    ) -> io::Result<()> {
+        request.body = std::mem::take(&mut request.body).collect().await?.into();
+        let buffer = body_state.lock().unwrap().detach().unwrap_or_default();
+        let switching = Response::new(StatusCode::SWITCHING_PROTOCOLS)
+            .with_header("connection", "Upgrade")
+            .with_header("upgrade", "h2c");
+        self.write_response(switching).await?;
+        let upgraded = Some((request, settings));
+        let stream = self.stream.clone();
+        let shutdown = self.shutdown.clone();
+        h2::serve(stream, buffer, &self.options, shutdown, handler, upgraded).await
+    }
+
+    /// Send HTTP response to the connection
+    ///
+    /// A body in memory goes out with the head in one vectored write, without
+    /// copying it; a file body with `sendfile(2)`. A streamed body is sent
+    /// chunked, each chunk written before the next is pulled from the stream,
+    /// unless the response already has a `content-length`.
+    pub async fn send_response(&self, mut response: Response) -> io::Result<()> {
+        response.frame_body(true);
+        self.write_response(response).await
+    }
+
+    /// Write the response, chunk-encoding the body if its head says so
+    async fn write_response(&self, response: Response) -> io::Result<()> {
+        let head = response.head_bytes();
+        write_message(&self.stream, head, &response.headers, response.body).await
+    }
+}
+
+/// Write a request or response head and its body, chunk-encoding the body
+/// if `headers` say so
+pub(crate) async fn write_message(
+    stream: &AsyncTcpStream,
+    head: Vec<u8>,
+    headers: &HeaderMap,
+    body: Body,
+) -> io::Result<()> {
+    let chunked = is_chunked(headers);
+    match body.into_parts() {
+        BodyParts::Full(bytes) if chunked && !bytes.is_empty() => {
+            let size = format!("{:x}\r\n", bytes.len());
+            stream
+                .write_all_vectored(vec![
+                    head.into(),
+                    size.into_bytes().into(),
+                    bytes.into(),
+                    Buffer::from_slice(b"\r\n0\r\n\r\n"),
+                ])
+                .await
+        }
+        BodyParts::Full(_) if chunked => {
+            stream.write_all(&[&head[..], b"0\r\n\r\n"].concat()).await
+        }
+        BodyParts::Full(bytes) => {
+            stream
+                .write_all_vectored(vec![head.into(), bytes.into()])
+                .await
+        }
+        BodyParts::File { file, offset, len } => {
+            stream.write_all(&head).await?;
+            let len = usize::try_from(len)
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "file too large"))?;
+            let sent = stream.sendfile(&file, offset, len).await?;
+            if sent < len {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "file ended before the body",
+                ));
+            }
+            Ok(())
+        }
+        BodyParts::Chunks(mut body) => {
+            stream.write_all(&head).await?;
+            while let Some(chunk) = body.chunk().await? {
+                if chunk.is_empty() {
+                    continue;
+                }
+                if chunked {
+                    let size = format!("{:x}\r\n", chunk.len());
+                    stream
+                        .write_all_vectored(vec![
+                            size.into_bytes().into(),
+                            chunk,
+                            Buffer::from_slice(b"\r\n"),
+                        ])
+                        .await?;
+                } else {
+                    stream.write_all_buf(chunk).await?;
+                }
+            }
+            if chunked {
+                stream.write_all(b"0\r\n\r\n").await?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Simple HTTP server trait for handling requests
+pub trait HttpHandler: Send + Sync + 'static {
+    fn handle(&self, request: Request) -> impl std::future::Future<Output = Response> + Send;
+
+    /// Wrap this handler in `middleware`, which sees every request first
+    fn layer<M: Middleware>(self, middleware: M) -> Layered<M, Self>
+    where
+        Self: Sized,
+    {
+        Layered::new(middleware, self)
+    }
+}
+
+/// Basic echo handler that returns the request details
+pub struct EchoHandler;
+
+impl HttpHandler for EchoHandler {
+    async fn handle(&self, request: Request) -> Response {
+        let Request {
+            method,
+            path,
+            version,
+            headers,
+            body,
+            ..
+        } = request;
+        let body = match body.collect().await {
+            Ok(body) => body,
+            Err(e) => {
+                return match e.get_ref().and_then(|e| e.downcast_ref::<ParseError>()) {
+                    Some(parse_error) => parse_error.to_response(),
+                    None => Response::new(StatusCode::BAD_REQUEST).with_body(e.to_string()),
+                }
+            }
+        };
+        let body = format!(
+            "Method: {}\nPath: {}\nVersion: {:?}\nHeaders: {:#?}\nBody: {}\n",
+            method,
+            path,
+            version,
+            headers,
+            String::from_utf8_lossy(&body)
+        );
+
+        Response::new(StatusCode::OK)
+            .with_header("content-type", "text/plain")
+            .with_body(body)
+    }
+}
+
+/// Static file/text handler
+pub struct StaticHandler {
+    content: String,
+    content_type: String,
+}
+
+impl StaticHandler {
+    pub fn new(content: impl Into<String>, content_type: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+            content_type: content_type.into(),
+        }
+    }
+}
+
+impl HttpHandler for StaticHandler {
+    fn handle(&self, _request: Request) -> impl std::future::Future<Output = Response> + Send {
+        let content = self.content.clone();
+        let content_type = self.content_type.clone();
+
+        async move {
+            Response::new(StatusCode::OK)
+                .with_header("content-type", &content_type)
+                .with_body(content)
+        }
+    }
+}
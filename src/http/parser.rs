@@ -0,0 +1,651 @@
+//! Incremental HTTP/1.1 request parsing.
+//!
+//! [`RequestParser`] accumulates bytes as they arrive from the connection and
+//! yields each request once its head and body are complete. The head is
+//! parsed with `httparse`; the body is framed by `Content-Length` or
+//! `Transfer-Encoding: chunked`. Bytes past the end of a request stay
+//! buffered for the next one, so pipelined requests are parsed in order.
+//! Response heads for [`Client`](super::Client) are parsed the same way, with
+//! bodies that may also run until the connection closes.
+
+use super::{header, is_chunked, Body, Method, Request, Response, StatusCode, Version};
+use super::{HeaderMap, HeaderName, HeaderValue};
+use bytes::{Buf, Bytes, BytesMut};
+use std::fmt;
+use std::io;
+
+/// Size limits a [`RequestParser`] enforces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParserLimits {
+    /// Most header fields in a request. Defaults to 64.
+    pub max_headers: usize,
+    /// Most bytes of request line and headers, and likewise of each chunk
+    /// size line and of the trailer section. Defaults to 16 KiB.
+    pub max_head_size: usize,
+    /// Most bytes of decoded body. Defaults to 8 MiB.
+    pub max_body_size: usize,
+}
+
+impl Default for ParserLimits {
+    fn default() -> Self {
+        Self {
+            max_headers: 64,
+            max_head_size: 16 * 1024,
+            max_body_size: 8 * 1024 * 1024,
+        }
+    }
+}
+
+/// Why a request could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The request is malformed; answered with 400 Bad Request.
+    BadRequest(&'static str),
+    /// The body exceeds [`ParserLimits::max_body_size`]; answered with 413
+    /// Payload Too Large.
+    PayloadTooLarge,
+    /// The head exceeds [`ParserLimits::max_head_size`] or
+    /// [`ParserLimits::max_headers`]; answered with 431 Request Header Fields
+    /// Too Large.
+    HeadersTooLarge,
+}
+
+impl ParseError {
+    /// The status code to answer the request with.
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ParseError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ParseError::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            ParseError::HeadersTooLarge => StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+        }
+    }
+
+    /// A response reporting the error that closes the connection, since the
+    /// rest of the stream cannot be framed.
+    pub fn to_response(&self) -> Response {
+        Response::new(self.status())
+            .with_header("connection", "close")
+            .with_header("content-type", "text/plain")
+            .with_body(format!("{}\n", self))
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::BadRequest(reason) => write!(f, "bad request: {}", reason),
+            ParseError::PayloadTooLarge => write!(f, "request body too large"),
+            ParseError::HeadersTooLarge => write!(f, "request header fields too large"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<ParseError> for io::Error {
+    fn from(error: ParseError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+}
+
+/// How the body of the request being parsed is delimited.
+#[derive(Debug)]
+enum Framing {
+    /// This many bytes of body are still to come.
+    Length(usize),
+    /// Chunked transfer coding, at the given point.
+    Chunked(ChunkState),
+    /// Everything until the connection closes; responses only.
+    UntilClose,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ChunkState {
+    /// Expecting a chunk size line.
+    Size,
+    /// Inside a chunk with this many bytes left.
+    Data(usize),
+    /// Expecting the CRLF closing a chunk's data.
+    DataEnd,
+    /// After the last chunk, expecting trailer fields or the final CRLF.
+    Trailers,
+    /// The body is complete.
+    Done,
+}
+
+/// What [`BodyDecoder::decode`] found in the buffer.
+#[derive(Debug)]
+pub(crate) enum Decoded {
+    /// The next piece of the body.
+    Data(Bytes),
+    /// The buffer ends before the body does.
+    NeedMore,
+    /// The body is complete.
+    Done,
+}
+
+/// Decodes a body from the bytes following its head, as they arrive.
+#[derive(Debug)]
+pub(crate) struct BodyDecoder {
+    framing: Framing,
+    /// The body's length, if its head gave one.
+    content_length: Option<usize>,
+    /// Decoded bytes so far, checked against `max_body_size`.
+    received: usize,
+    max_body_size: usize,
+    /// Bytes of trailer section so far; it and each chunk size line are
+    /// checked against `max_head_size`.
+    trailers: usize,
+    max_head_size: usize,
+    /// How much of the buffer the search for the end of the current chunk
+    /// size or trailer line has already covered.
+    scanned: usize,
+}
+
+impl BodyDecoder {
+    fn new(framing: Framing, limits: &ParserLimits) -> Self {
+        Self {
+            content_length: match framing {
+                Framing::Length(len) => Some(len),
+                Framing::Chunked(_) | Framing::UntilClose => None,
+            },
+            framing,
+            received: 0,
+            max_body_size: limits.max_body_size,
+            trailers: 0,
+            max_head_size: limits.max_head_size,
+            scanned: 0,
+        }
+    }
+
+    /// Whether the body ends only when the connection closes, which then
+    /// completes it rather than cutting it short.
+    pub(crate) fn ends_at_close(&self) -> bool {
+        matches!(self.framing, Framing::UntilClose)
+    }
+
+    /// The body's length, if its head gave one.
+    pub(crate) fn content_length(&self) -> Option<u64> {
+        self.content_length.map(|len| len as u64)
+    }
+
+    /// Takes the next piece of body from the front of `buffer`, without
+    /// copying it.
+    pub(crate) fn decode(&mut self, buffer: &mut BytesMut) -> Result<Decoded, ParseError> {
+        let state = match &mut self.framing {
+            Framing::Length(0) => return Ok(Decoded::Done),
+            Framing::Length(_) if buffer.is_empty() => return Ok(Decoded::NeedMore),
+            Framing::Length(remaining) => {
+                let take = (*remaining).min(buffer.len());
+                *remaining -= take;
+                self.received += take;
+                return Ok(Decoded::Data(buffer.split_to(take).freeze()));
+            }
+            Framing::UntilClose if buffer.is_empty() => return Ok(Decoded::NeedMore),
+            Framing::UntilClose => {
+                if buffer.len() > self.max_body_size - self.received {
+                    return Err(ParseError::PayloadTooLarge);
+                }
+                self.received += buffer.len();
+                return Ok(Decoded::Data(buffer.split().freeze()));
+            }
+            Framing::Chunked(state) => state,
+        };
+        loop {
+            match *state {
+                ChunkState::Size => {
+                    let complete = line_complete(buffer, &mut self.scanned);
+                    // Only an extension makes the line long
+                    if self.scanned > self.max_head_size {
+                        return Err(ParseError::BadRequest("chunk size line too long"));
+                    }
+                    if !complete {
+                        return Ok(Decoded::NeedMore);
+                    }
+                    self.scanned = 0;
+                    let Ok(httparse::Status::Complete((consumed, size))) =
+                        httparse::parse_chunk_size(buffer)
+                    else {
+                        return Err(ParseError::BadRequest("invalid chunk size"));
+                    };
+                    buffer.advance(consumed);
+                    let size = usize::try_from(size).map_err(|_| ParseError::PayloadTooLarge)?;
+                    if size > self.max_body_size - self.received {
+                        return Err(ParseError::PayloadTooLarge);
+                    }
+                    *state = if size == 0 {
+                        ChunkState::Trailers
+                    } else {
+                        ChunkState::Data(size)
+                    };
+                }
+                ChunkState::Data(_) if buffer.is_empty() => return Ok(Decoded::NeedMore),
+                ChunkState::Data(remaining) => {
+                    let take = remaining.min(buffer.len());
+                    *state = if take < remaining {
+                        ChunkState::Data(remaining - take)
+                    } else {
+                        ChunkState::DataEnd
+                    };
+                    self.received += take;
+                    return Ok(Decoded::Data(buffer.split_to(take).freeze()));
+                }
+                ChunkState::DataEnd => {
+                    if buffer.len() < 2 {
+                        return Ok(Decoded::NeedMore);
+                    }
+                    if &buffer[..2] != b"\r\n" {
+                        return Err(ParseError::BadRequest("chunk data not followed by CRLF"));
+                    }
+                    buffer.advance(2);
+                    *state = ChunkState::Size;
+                }
+                // Trailer fields are skipped, up to the empty line ending them
+                ChunkState::Trailers => {
+                    let complete = line_complete(buffer, &mut self.scanned);
+                    let line_len = if complete { self.scanned } else { buffer.len() };
+                    if self.trailers + line_len > self.max_head_size {
+                        return Err(ParseError::HeadersTooLarge);
+                    }
+                    if !complete {
+                        return Ok(Decoded::NeedMore);
+                    }
+                    if buffer.starts_with(b"\r\n") {
+                        *state = ChunkState::Done;
+                    }
+                    self.trailers += line_len;
+                    buffer.advance(std::mem::take(&mut self.scanned));
+                }
+                ChunkState::Done => return Ok(Decoded::Done),
+            }
+        }
+    }
+}
+
+/// Whether `buffer` holds a whole line, searching only past the `scanned`
+/// bytes already searched. Leaves `scanned` at the line's length, LF
+/// included, once it is complete.
+fn line_complete(buffer: &[u8], scanned: &mut usize) -> bool {
+    let from = (*scanned).min(buffer.len());
+    match buffer[from..].iter().position(|&byte| byte == b'\n') {
+        Some(end) => {
+            *scanned = from + end + 1;
+            true
+        }
+        None => {
+            *scanned = buffer.len();
+            false
+        }
+    }
+}
+
+/// Parses HTTP/1.1 requests from bytes that arrive in arbitrary pieces.
+///
+/// # Examples
+///
+/// ```
+/// use rust_miniss::http::{ParserLimits, RequestParser};
+///
+/// let mut parser = RequestParser::new(ParserLimits::default());
+/// parser.feed(b"POST /upload HTTP/1.1\r\nContent-Len");
+/// assert!(parser.parse().unwrap().is_none());
+/// parser.feed(b"gth: 5\r\n\r\nhello");
+/// let request = parser.parse().unwrap().expect("request is complete");
+/// assert_eq!(request.path, "/upload");
+/// assert_eq!(request.body, b"hello");
+/// ```
+#[derive(Debug)]
+pub struct RequestParser {
+    limits: ParserLimits,
+    buffer: BytesMut,
+    /// The request whose head has been parsed, while its body arrives.
+    pending: Option<(Request, BodyDecoder, BytesMut)>,
+}
+
+impl RequestParser {
+    /// Creates a parser enforcing `limits`.
+    pub fn new(limits: ParserLimits) -> Self {
+        Self {
+            limits,
+            buffer: BytesMut::new(),
+            pending: None,
+        }
+    }
+
+    /// Appends bytes received from the connection.
+    pub fn feed(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// The bytes received and not yet parsed
+    pub(crate) fn buffered(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    /// Whether no bytes of a next request have been received.
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty() && self.pending.is_none()
+    }
+
+    /// Parses the next request from the bytes fed so far.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(Request))` - A complete request; bytes after it stay buffered
+    /// * `Ok(None)` - More bytes are needed
+    /// * `Err(ParseError)` - The request is invalid or exceeds the limits; the
+    ///   connection cannot be parsed further
+    pub fn parse(&mut self) -> Result<Option<Request>, ParseError> {
+        if self.pending.is_none() {
+            match self.parse_head()? {
+                Some((request, decoder)) => {
+                    self.pending = Some((request, decoder, BytesMut::new()))
+                }
+                None => return Ok(None),
+            }
+        }
+        let (_, decoder, body) = self.pending.as_mut().expect("head was just parsed");
+        loop {
+            match decoder.decode(&mut self.buffer)? {
+                Decoded::Data(data) => body.extend_from_slice(&data),
+                Decoded::NeedMore => return Ok(None),
+                Decoded::Done => break,
+            }
+        }
+        let (mut request, _, body) = self.pending.take().expect("head was just parsed");
+        request.body = Body::from(body.freeze());
+        Ok(Some(request))
+    }
+
+    /// Moves the received bytes out, for a body to be read from.
+    pub(crate) fn take_buffer(&mut self) -> BytesMut {
+        std::mem::take(&mut self.buffer)
+    }
+
+    /// Puts back the bytes after a body read from what
+    /// [`take_buffer`](Self::take_buffer) moved out.
+    pub(crate) fn restore_buffer(&mut self, buffer: BytesMut) {
+        self.buffer = buffer;
+    }
+
+    /// Parses the request line and headers, once they are complete, leaving
+    /// the body to the returned decoder.
+    ///
+    /// Must not be called while [`parse`](Self::parse) is part way through a
+    /// request.
+    pub(crate) fn parse_head(&mut self) -> Result<Option<(Request, BodyDecoder)>, ParseError> {
+        let mut headers = vec![httparse::EMPTY_HEADER; self.limits.max_headers];
+        let mut parsed = httparse::Request::new(&mut headers);
+        let head_len = match parsed.parse(&self.buffer) {
+            Ok(httparse::Status::Complete(len)) if len > self.limits.max_head_size => {
+                return Err(ParseError::HeadersTooLarge)
+            }
+            Ok(httparse::Status::Complete(len)) => len,
+            Ok(httparse::Status::Partial) if self.buffer.len() > self.limits.max_head_size => {
+                return Err(ParseError::HeadersTooLarge)
+            }
+            Ok(httparse::Status::Partial) => return Ok(None),
+            Err(httparse::Error::TooManyHeaders) => return Err(ParseError::HeadersTooLarge),
+            Err(_) => return Err(ParseError::BadRequest("malformed request head")),
+        };
+
+        let method = Method::from_bytes(parsed.method.unwrap_or_default().as_bytes())
+            .map_err(|_| ParseError::BadRequest("invalid method"))?;
+        let mut request = Request::new(method, parsed.path.unwrap_or_default().to_string());
+        request.version = version(parsed.version);
+        request.headers = collect_headers(parsed.headers)?;
+        let framing = framing(&request.headers, self.limits.max_body_size)?;
+        self.buffer.advance(head_len);
+        let decoder = BodyDecoder::new(framing, &self.limits);
+        Ok(Some((request, decoder)))
+    }
+}
+
+/// Parses a response's status line and headers from the front of `buffer`,
+/// once they are complete, leaving the body to the returned decoder.
+///
+/// The answer to a HEAD request, `head_request`, has no body whatever its
+/// headers say.
+pub(crate) fn parse_response_head(
+    buffer: &mut BytesMut,
+    limits: &ParserLimits,
+    head_request: bool,
+) -> Result<Option<(Response, BodyDecoder)>, ParseError> {
+    let mut headers = vec![httparse::EMPTY_HEADER; limits.max_headers];
+    let mut parsed = httparse::Response::new(&mut headers);
+    let head_len = match parsed.parse(buffer) {
+        Ok(httparse::Status::Complete(len)) if len > limits.max_head_size => {
+            return Err(ParseError::HeadersTooLarge)
+        }
+        Ok(httparse::Status::Complete(len)) => len,
+        Ok(httparse::Status::Partial) if buffer.len() > limits.max_head_size => {
+            return Err(ParseError::HeadersTooLarge)
+        }
+        Ok(httparse::Status::Partial) => return Ok(None),
+        Err(httparse::Error::TooManyHeaders) => return Err(ParseError::HeadersTooLarge),
+        Err(_) => return Err(ParseError::BadRequest("malformed response head")),
+    };
+
+    let status = StatusCode::from_u16(parsed.code.unwrap_or_default())
+        .map_err(|_| ParseError::BadRequest("invalid status code"))?;
+    let response = Response {
+        status,
+        version: version(parsed.version),
+        headers: collect_headers(parsed.headers)?,
+        body: Body::empty(),
+    };
+    buffer.advance(head_len);
+
+    let headers = &response.headers;
+    let no_body = head_request
+        || status.is_informational()
+        || status == StatusCode::NO_CONTENT
+        || status == StatusCode::NOT_MODIFIED;
+    let framing = if no_body {
+        Framing::Length(0)
+    } else if is_chunked(headers) {
+        Framing::Chunked(ChunkState::Size)
+    } else if headers.contains_key(header::TRANSFER_ENCODING) {
+        // A response may end a coding other than chunked with the close
+        Framing::UntilClose
+    } else if headers.contains_key(header::CONTENT_LENGTH) {
+        framing(headers, limits.max_body_size)?
+    } else {
+        Framing::UntilClose
+    };
+    Ok(Some((response, BodyDecoder::new(framing, limits))))
+}
+
+/// The version `httparse` reports as its minor version.
+fn version(minor: Option<u8>) -> Version {
+    match minor {
+        Some(0) => Version::HTTP_10,
+        _ => Version::HTTP_11,
+    }
+}
+
+/// Header fields in the order received, repeated fields kept apart.
+fn collect_headers(headers: &[httparse::Header<'_>]) -> Result<HeaderMap, ParseError> {
+    let mut collected = HeaderMap::with_capacity(headers.len());
+    for header in headers {
+        let name = HeaderName::from_bytes(header.name.as_bytes())
+            .map_err(|_| ParseError::BadRequest("invalid header name"))?;
+        let value = HeaderValue::from_bytes(header.value)
+            .map_err(|_| ParseError::BadRequest("invalid header value"))?;
+        collected.append(name, value);
+    }
+    Ok(collected)
+}
+
+/// How the body is delimited, rejecting ambiguous or unsupported framing.
+fn framing(headers: &HeaderMap, max_body_size: usize) -> Result<Framing, ParseError> {
+    if headers.contains_key(header::TRANSFER_ENCODING) {
+        // Both would let a proxy and this server disagree on where the
+        // request ends
+        if headers.contains_key(header::CONTENT_LENGTH) {
+            return Err(ParseError::BadRequest(
+                "both content-length and transfer-encoding",
+            ));
+        }
+        if !is_chunked(headers) {
+            return Err(ParseError::BadRequest("unsupported transfer-encoding"));
+        }
+        return Ok(Framing::Chunked(ChunkState::Size));
+    }
+    if !headers.contains_key(header::CONTENT_LENGTH) {
+        return Ok(Framing::Length(0));
+    }
+    // Repeated fields, and lists within one, must all agree
+    let mut lengths = headers
+        .get_all(header::CONTENT_LENGTH)
+        .iter()
+        .flat_map(|value| value.to_str().unwrap_or("x").split(','))
+        .map(|length| length.trim().parse::<usize>());
+    let first = lengths.next().and_then(Result::ok);
+    let length = match first {
+        Some(first) if lengths.all(|length| length == Ok(first)) => first,
+        _ => return Err(ParseError::BadRequest("invalid content-length")),
+    };
+    if length > max_body_size {
+        return Err(ParseError::PayloadTooLarge);
+    }
+    Ok(Framing::Length(length))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_all(data: &[u8]) -> Result<Option<Request>, ParseError> {
+        let mut parser = RequestParser::new(ParserLimits::default());
+        parser.feed(data);
+        parser.parse()
+    }
+
+    #[test]
+    fn test_binary_body_survives_byte_by_byte_feeding() {
+        let body: Vec<u8> = (0..=255u8).chain(b"\r\n\n\r".iter().copied()).collect();
+        let mut data = format!(
+            "PUT /blob HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            body.len()
+        )
+        .into_bytes();
+        data.extend_from_slice(&body);
+
+        let mut parser = RequestParser::new(ParserLimits::default());
+        let (last, rest) = data.split_last().unwrap();
+        for byte in rest {
+            parser.feed(std::slice::from_ref(byte));
+            assert!(parser.parse().unwrap().is_none());
+        }
+        parser.feed(std::slice::from_ref(last));
+        let request = parser.parse().unwrap().unwrap();
+        assert_eq!(request.method, Method::PUT);
+        assert_eq!(request.body, body);
+        assert!(parser.is_empty());
+    }
+
+    #[test]
+    fn test_chunked_body_and_pipelined_request() {
+        let mut parser = RequestParser::new(ParserLimits::default());
+        parser.feed(
+            b"POST /chunks HTTP/1.1\r\nTransfer-Encoding: chunked\r\nX-A: 1\r\nx-a: 2\r\n\r\n\
+              5;ext=1\r\nhello\r\n7\r\n, world\r\n0\r\nTrailer: yes\r\n\r\n\
+              GET /next HTTP/1.0\r\n\r\n",
+        );
+        let first = parser.parse().unwrap().unwrap();
+        assert_eq!(first.body, b"hello, world");
+        let repeated: Vec<_> = first.headers.get_all("x-a").iter().collect();
+        assert_eq!(repeated, ["1", "2"]);
+        let second = parser.parse().unwrap().unwrap();
+        assert_eq!(second.path, "/next");
+        assert_eq!(second.version, Version::HTTP_10);
+        assert!(parser.parse().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_errors_map_to_status_codes() {
+        let bad = parse_all(b"GET / HTTP/1.1\r\nContent-Length: x\r\n\r\n").unwrap_err();
+        assert_eq!(bad.status(), StatusCode::BAD_REQUEST);
+        let smuggled = parse_all(
+            b"POST / HTTP/1.1\r\nContent-Length: 3\r\nTransfer-Encoding: chunked\r\n\r\n",
+        );
+        assert_eq!(smuggled.unwrap_err().status(), StatusCode::BAD_REQUEST);
+        let garbage = parse_all(b"\x01\x02 nonsense\r\n\r\n").unwrap_err();
+        assert_eq!(garbage.status(), StatusCode::BAD_REQUEST);
+
+        let limits = ParserLimits {
+            max_headers: 2,
+            max_head_size: 64,
+            max_body_size: 4,
+        };
+        let parse_limited = |data: &[u8]| {
+            let mut parser = RequestParser::new(limits);
+            parser.feed(data);
+            parser.parse()
+        };
+        let too_long = parse_limited(b"POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\n");
+        assert_eq!(too_long.unwrap_err(), ParseError::PayloadTooLarge);
+        let too_chunky =
+            parse_limited(b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\n");
+        assert_eq!(too_chunky.unwrap_err(), ParseError::PayloadTooLarge);
+        let too_many = parse_limited(b"GET / HTTP/1.1\r\nA: 1\r\nB: 2\r\nC: 3\r\n\r\n");
+        assert_eq!(too_many.unwrap_err(), ParseError::HeadersTooLarge);
+        // An unfinished head past the limit fails without waiting for the rest
+        let huge = format!("GET /{} HTTP/1.1\r\n", "a".repeat(100));
+        let too_big = parse_limited(huge.as_bytes()).unwrap_err();
+        assert_eq!(
+            too_big.status(),
+            StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE
+        );
+    }
+
+    #[test]
+    fn test_chunk_extensions_and_trailers_are_limited() {
+        let limits = ParserLimits {
+            max_head_size: 64,
+            ..ParserLimits::default()
+        };
+        let head = b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n";
+
+        // Both fail as soon as they pass the limit, fed in small pieces
+        let mut parser = RequestParser::new(limits);
+        parser.feed(head);
+        parser.feed(b"5;ext=");
+        let mut result = parser.parse();
+        for _ in 0..64 {
+            parser.feed(b"x");
+            result = parser.parse();
+        }
+        assert_eq!(
+            result.unwrap_err(),
+            ParseError::BadRequest("chunk size line too long")
+        );
+
+        let mut parser = RequestParser::new(limits);
+        parser.feed(head);
+        parser.feed(b"0\r\n");
+        let mut result = parser.parse();
+        for _ in 0..8 {
+            parser.feed(b"Trailer: a\r\n");
+            result = parser.parse();
+        }
+        assert_eq!(result.unwrap_err(), ParseError::HeadersTooLarge);
+
+        // Within the limit, split across pieces, they are skipped
+        let mut parser = RequestParser::new(limits);
+        parser.feed(head);
+        for piece in [
+            &b"3;e"[..],
+            b"xt=1\r",
+            b"\nabc\r\n0\r\nTrai",
+            b"ler: a\r",
+            b"\n\r\n",
+        ] {
+            assert!(parser.parse().unwrap().is_none());
+            parser.feed(piece);
+        }
+        assert_eq!(parser.parse().unwrap().unwrap().body, b"abc");
+        assert!(parser.is_empty());
+    }
+}
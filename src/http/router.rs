@@ -0,0 +1,349 @@
+//! Routing requests to handlers by method and path.
+//!
+//! A [`Router`] holds routes of a method, a path pattern and a handler, and is
+//! itself an [`HttpHandler`]. Patterns are made of `/`-separated segments:
+//! literal ones match themselves, `:name` matches any one segment and
+//! `*name` (or a bare `*`) matches the rest of the path. What a pattern
+//! captures ends up in [`Request::params`].
+
+use super::{header, percent_decode, Body, HttpHandler, Method, Request, Response, StatusCode};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+
+/// The future of a type-erased handler
+pub(crate) type BoxFuture<'a> = Pin<Box<dyn Future<Output = Response> + Send + 'a>>;
+
+/// [`HttpHandler`] in a form usable as a trait object, so handlers of
+/// different types can be stored together
+pub(crate) trait DynHandler: Send + Sync + 'static {
+    fn handle_dyn(&self, request: Request) -> BoxFuture<'_>;
+}
+
+impl<H: HttpHandler> DynHandler for H {
+    fn handle_dyn(&self, request: Request) -> BoxFuture<'_> {
+        Box::pin(self.handle(request))
+    }
+}
+
+/// An [`HttpHandler`] calling an async function, created by [`handler_fn`]
+pub struct HandlerFn<F>(F);
+
+/// Use the async function `f` as an [`HttpHandler`]
+///
+/// # Examples
+///
+/// ```
+/// use rust_miniss::http::{handler_fn, Router};
+/// use rust_miniss::{Request, Response, StatusCode};
+///
+/// let router = Router::new().get(
+///     "/hello/:name",
+///     handler_fn(|request: Request| async move {
+///         let greeting = format!("Hello, {}!", request.param("name").unwrap());
+///         Response::new(StatusCode::OK).with_body(greeting)
+///     }),
+/// );
+/// ```
+pub fn handler_fn<F, Fut>(f: F) -> HandlerFn<F>
+where
+    F: Fn(Request) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Response> + Send,
+{
+    HandlerFn(f)
+}
+
+impl<F, Fut> HttpHandler for HandlerFn<F>
+where
+    F: Fn(Request) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Response> + Send,
+{
+    fn handle(&self, request: Request) -> impl Future<Output = Response> + Send {
+        (self.0)(request)
+    }
+}
+
+/// One segment of a route pattern
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Param(String),
+    Wildcard(String),
+}
+
+struct Route {
+    method: Method,
+    segments: Vec<Segment>,
+    handler: Box<dyn DynHandler>,
+}
+
+impl Route {
+    /// The parameters captured if `path` matches the pattern
+    fn captures(&self, path: &str) -> Option<HashMap<String, String>> {
+        let mut parts = path.split('/').filter(|part| !part.is_empty());
+        let mut params = HashMap::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(literal) => {
+                    if parts.next()? != literal {
+                        return None;
+                    }
+                }
+                Segment::Param(name) => {
+                    params.insert(name.clone(), percent_decode(parts.next()?));
+                }
+                Segment::Wildcard(name) => {
+                    let rest: Vec<_> = parts.by_ref().collect();
+                    params.insert(name.clone(), percent_decode(&rest.join("/")));
+                }
+            }
+        }
+        parts.next().is_none().then_some(params)
+    }
+}
+
+/// Dispatches requests to the route matching their method and path
+///
+/// Routes are tried in the order they were added; the first whose pattern and
+/// method match handles the request. A HEAD request no HEAD route matches is
+/// handled by the first matching GET route, and answered without the body.
+/// A path matched only by routes of other methods is answered with 405
+/// Method Not Allowed and an `allow` header, any other with the
+/// [fallback](Self::fallback), by default 404 Not Found.
+///
+/// # Examples
+///
+/// ```
+/// use rust_miniss::http::{handler_fn, Router};
+/// use rust_miniss::{Response, StaticHandler, StatusCode};
+///
+/// let router = Router::new()
+///     .get("/", StaticHandler::new("home", "text/plain"))
+///     .get("/users/:id", handler_fn(|request| async move {
+///         Response::new(StatusCode::OK).with_body(request.params["id"].clone())
+///     }))
+///     .get("/static/*file", StaticHandler::new("file", "text/plain"));
+/// ```
+#[derive(Default)]
+pub struct Router {
+    routes: Vec<Route>,
+    fallback: Option<Box<dyn DynHandler>>,
+}
+
+impl Router {
+    /// Create a router without routes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Route `method` requests whose path matches `pattern` to `handler`
+    ///
+    /// # Panics
+    ///
+    /// Panics if a wildcard segment is not the last one of `pattern`, or a
+    /// parameter is unnamed.
+    pub fn route<H: HttpHandler>(mut self, method: Method, pattern: &str, handler: H) -> Self {
+        let segments: Vec<_> = pattern
+            .split('/')
+            .filter(|part| !part.is_empty())
+            .map(|part| {
+                if let Some(name) = part.strip_prefix(':') {
+                    assert!(!name.is_empty(), "unnamed parameter in route {pattern:?}");
+                    Segment::Param(name.to_string())
+                } else if let Some(name) = part.strip_prefix('*') {
+                    let name = if name.is_empty() { "*" } else { name };
+                    Segment::Wildcard(name.to_string())
+                } else {
+                    Segment::Literal(part.to_string())
+                }
+            })
+            .collect();
+        if let Some(position) = segments
+            .iter()
+            .position(|s| matches!(s, Segment::Wildcard(_)))
+        {
+            assert!(
+                position == segments.len() - 1,
+                "wildcard must be the last segment of route {pattern:?}"
+            );
+        }
+        self.routes.push(Route {
+            method,
+            segments,
+            handler: Box::new(handler),
+        });
+        self
+    }
+
+    /// Route GET requests matching `pattern` to `handler`
+    pub fn get<H: HttpHandler>(self, pattern: &str, handler: H) -> Self {
+        self.route(Method::GET, pattern, handler)
+    }
+
+    /// Route POST requests matching `pattern` to `handler`
+    pub fn post<H: HttpHandler>(self, pattern: &str, handler: H) -> Self {
+        self.route(Method::POST, pattern, handler)
+    }
+
+    /// Route PUT requests matching `pattern` to `handler`
+    pub fn put<H: HttpHandler>(self, pattern: &str, handler: H) -> Self {
+        self.route(Method::PUT, pattern, handler)
+    }
+
+    /// Route DELETE requests matching `pattern` to `handler`
+    pub fn delete<H: HttpHandler>(self, pattern: &str, handler: H) -> Self {
+        self.route(Method::DELETE, pattern, handler)
+    }
+
+    /// Route PATCH requests matching `pattern` to `handler`
+    pub fn patch<H: HttpHandler>(self, pattern: &str, handler: H) -> Self {
+        self.route(Method::PATCH, pattern, handler)
+    }
+
+    /// Handle requests no route matches with `handler` instead of a 404
+    pub fn fallback<H: HttpHandler>(mut self, handler: H) -> Self {
+        self.fallback = Some(Box::new(handler));
+        self
+    }
+}
+
+impl HttpHandler for Router {
+    async fn handle(&self, mut request: Request) -> Response {
+        let mut allowed: Vec<String> = Vec::new();
+        let mut get_route = None;
+        for route in &self.routes {
+            let Some(params) = route.captures(&request.path) else {
+                continue;
+            };
+            if route.method == request.method {
+                request.params = params;
+                return route.handler.handle_dyn(request).await;
+            }
+            if route.method == Method::GET {
+                get_route.get_or_insert((route, params));
+            }
+            let mut methods = vec![route.method.to_string()];
+            if route.method == Method::GET {
+                methods.push(Method::HEAD.to_string());
+            }
+            for method in methods {
+                if !allowed.contains(&method) {
+                    allowed.push(method);
+                }
+            }
+        }
+
+        if let Some((route, params)) = get_route.filter(|_| request.method == Method::HEAD) {
+            request.params = params;
+            let mut response = route.handler.handle_dyn(request).await;
+            // Keeps the content-length of the body a GET would get
+            if let Some(len) = response.body.content_length() {
+                response
+                    .headers
+                    .entry(header::CONTENT_LENGTH)
+                    .or_insert(len.into());
+            }
+            response.body = Body::empty();
+            return response;
+        }
+
+        if !allowed.is_empty() {
+            return Response::new(StatusCode::METHOD_NOT_ALLOWED)
+                .with_header("allow", &allowed.join(", "))
+                .with_body("Method Not Allowed");
+        }
+        match &self.fallback {
+            Some(fallback) => fallback.handle_dyn(request).await,
+            None => Response::new(StatusCode::NOT_FOUND).with_body("Not Found"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::StaticHandler;
+    use futures::executor::block_on;
+
+    fn echo_params() -> impl HttpHandler {
+        handler_fn(|request: Request| async move {
+            let mut params: Vec<_> = request
+                .params
+                .iter()
+                .chain(request.query.iter())
+                .map(|(name, value)| format!("{name}={value}"))
+                .collect();
+            params.sort();
+            Response::new(StatusCode::OK).with_body(params.join("&"))
+        })
+    }
+
+    fn get(router: &Router, method: Method, target: &str) -> Response {
+        block_on(router.handle(Request::new(method, target.to_string())))
+    }
+
+    #[test]
+    fn test_routes_capture_params_wildcards_and_query() {
+        let router = Router::new()
+            .get("/users/new", StaticHandler::new("form", "text/plain"))
+            .get("/users/:id", echo_params())
+            .get("/users/:id/posts/:post", echo_params())
+            .get("/files/*path", echo_params());
+
+        assert_eq!(get(&router, Method::GET, "/users/new").body, b"form");
+        assert_eq!(get(&router, Method::GET, "/users/42/").body, b"id=42");
+        let post = get(&router, Method::GET, "/users/7/posts/a%20b?sort=new&q=x+y");
+        assert_eq!(post.body, b"id=7&post=a b&q=x y&sort=new");
+        let file = get(&router, Method::GET, "/files/css/site.css");
+        assert_eq!(file.body, b"path=css/site.css");
+        assert_eq!(
+            get(&router, Method::GET, "/users/7/comments").status,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[test]
+    fn test_method_mismatch_is_405_and_fallback_replaces_404() {
+        let router = Router::new()
+            .get("/items", echo_params())
+            .post("/items", echo_params())
+            .fallback(StaticHandler::new("nothing here", "text/plain"));
+
+        let rejected = get(&router, Method::DELETE, "/items");
+        assert_eq!(rejected.status, StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(rejected.headers["allow"], "GET, HEAD, POST");
+        let missing = get(&router, Method::GET, "/other");
+        assert_eq!(missing.status, StatusCode::OK);
+        assert_eq!(missing.body, b"nothing here");
+    }
+
+    #[test]
+    fn test_head_falls_back_to_get_routes() {
+        let router = Router::new()
+            .get("/items/:id", echo_params())
+            .route(
+                Method::HEAD,
+                "/custom",
+                StaticHandler::new("head", "text/plain"),
+            )
+            .get("/custom", StaticHandler::new("get", "text/plain"));
+
+        // The GET route answers, without the body but with its length
+        let head = get(&router, Method::HEAD, "/items/7");
+        assert_eq!(head.status, StatusCode::OK);
+        assert!(head.body.content_length() == Some(0));
+        assert_eq!(head.headers[header::CONTENT_LENGTH], "4");
+        // A HEAD route takes precedence
+        assert_eq!(get(&router, Method::HEAD, "/custom").body, b"head");
+
+        let rejected = get(&router, Method::POST, "/items/7");
+        assert_eq!(rejected.status, StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(rejected.headers["allow"], "GET, HEAD");
+    }
+
+    #[test]
+    #[should_panic(expected = "wildcard must be the last segment")]
+    fn test_wildcard_must_be_last() {
+        let _ = Router::new().get("/files/*path/edit", echo_params());
+    }
+}
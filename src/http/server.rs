@@ -0,0 +1,371 @@
+//! An HTTP server running an [`HttpHandler`] on every connection.
+//!
+//! [`Server::run`] serves from a single future, for the single-threaded
+//! [`Runtime`](crate::Runtime); [`Server::start`] runs one `SO_REUSEPORT`
+//! accept loop per core of a [`MultiCoreRuntime`], each serving its
+//! connections on its own core. Either way a [`ServerHandle`] shuts the server
+//! down.
+
+use super::{ConnectionOptions, HttpConnection, HttpHandler, ParserLimits};
+use crate::cancellation::{CancellableFutureExt, CancellationToken};
+use crate::multicore::{spawn_local, MultiCoreRuntime};
+use crate::net::{AsyncTcpListener, AsyncTcpStream, Incoming};
+use crate::timer;
+use futures::stream::{FuturesUnordered, StreamExt};
+use std::future::{poll_fn, Future};
+use std::io;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+/// Configures and binds a [`Server`]
+pub struct ServerBuilder<H> {
+    handler: H,
+    max_connections: usize,
+    connection_options: ConnectionOptions,
+    limits: ParserLimits,
+}
+
+impl<H: HttpHandler> ServerBuilder<H> {
+    /// Most connections served at once, across all cores. Further connections
+    /// wait in the listen backlog. Defaults to 10000.
+    pub fn max_connections(mut self, max_connections: usize) -> Self {
+        self.max_connections = max_connections.max(1);
+        self
+    }
+
+    /// Keep-alive settings of every connection
+    pub fn connection_options(mut self, options: ConnectionOptions) -> Self {
+        self.connection_options = options;
+        self
+    }
+
+    /// Limits requests are parsed within
+    pub fn parser_limits(mut self, limits: ParserLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Bind the server to `addr`
+    ///
+    /// The listener joins a `SO_REUSEPORT` group, which
+    /// [`Server::start`] extends with one listener per core. A port of 0 picks
+    /// a free one; see [`ServerHandle::local_addr`].
+    pub fn bind(self, addr: SocketAddr) -> io::Result<Server<H>> {
+        let listener = AsyncTcpListener::bind_reuseport(addr)?;
+        let local_addr = listener.local_addr()?;
+        Ok(Server {
+            listener,
+            shared: Arc::new(Shared {
+                handler: self.handler,
+                connection_options: self.connection_options,
+                limits: self.limits,
+                state: Arc::new(ServerState {
+                    local_addr,
+                    max_connections: self.max_connections,
+                    active: AtomicUsize::new(0),
+                    waiters: Mutex::new(Vec::new()),
+                    stop: CancellationToken::new(),
+                    abort: CancellationToken::new(),
+                }),
+            }),
+        })
+    }
+}
+
+/// An HTTP server bound to an address, ready to run
+///
+/// # Examples
+///
+/// ```no_run
+/// use rust_miniss::http::Server;
+/// use rust_miniss::{EchoHandler, Runtime};
+///
+/// let runtime = Runtime::new();
+/// let server = Server::builder(EchoHandler)
+///     .max_connections(1024)
+///     .bind("127.0.0.1:8080".parse().unwrap())
+///     .expect("Failed to bind");
+/// runtime.block_on(server.run());
+/// ```
+pub struct Server<H> {
+    listener: AsyncTcpListener,
+    shared: Arc<Shared<H>>,
+}
+
+/// What every connection of a server needs
+struct Shared<H> {
+    handler: H,
+    connection_options: ConnectionOptions,
+    limits: ParserLimits,
+    state: Arc<ServerState>,
+}
+
+/// Connection accounting and shutdown signals, shared with the handles
+struct ServerState {
+    local_addr: SocketAddr,
+    max_connections: usize,
+    active: AtomicUsize,
+    /// Accept loops waiting for a free slot and shutdowns waiting for the
+    /// connections to drain, woken whenever a connection ends
+    waiters: Mutex<Vec<Waker>>,
+    /// Stops accepting and closes connections after their current request
+    stop: CancellationToken,
+    /// Closes connections at once
+    abort: CancellationToken,
+}
+
+impl<H: HttpHandler> Server<H> {
+    /// Start configuring a server that answers requests with `handler`
+    pub fn builder(handler: H) -> ServerBuilder<H> {
+        ServerBuilder {
+            handler,
+            max_connections: 10_000,
+            connection_options: ConnectionOptions::default(),
+            limits: ParserLimits::default(),
+        }
+    }
+
+    /// A handle for shutting the server down
+    pub fn handle(&self) -> ServerHandle {
+        ServerHandle {
+            state: self.shared.state.clone(),
+        }
+    }
+
+    /// Serve connections on the current thread until the server is shut down
+    ///
+    /// Every connection is driven by the returned future, so this suits the
+    /// single-threaded [`Runtime`](crate::Runtime). It completes once the
+    /// server has stopped accepting and all connections have closed.
+    pub async fn run(self) {
+        let mut acceptor = Acceptor::new(&self.listener, &self.shared.state);
+        let mut connections = FuturesUnordered::new();
+        let mut accepting = true;
+        poll_fn(|cx| {
+            while accepting {
+                match acceptor.poll_accept(cx) {
+                    Poll::Ready(Some(stream)) => {
+                        connections.push(serve_connection(stream, self.shared.clone()))
+                    }
+                    Poll::Ready(None) => accepting = false,
+                    Poll::Pending => break,
+                }
+            }
+            while let Poll::Ready(Some(())) = connections.poll_next_unpin(cx) {}
+            if !accepting && connections.is_empty() {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await
+    }
+
+    /// Serve connections on every core of `runtime`
+    ///
+    /// Each core gets its own listener in the server's `SO_REUSEPORT` group
+    /// and runs its own accept loop; its connections never leave it. Returns
+    /// once the accept loops are spawned.
+    pub fn start(self, runtime: &MultiCoreRuntime) -> crate::error::Result<ServerHandle> {
+        let handle = self.handle();
+        let local_addr = handle.local_addr();
+        let mut listeners = Vec::with_capacity(runtime.cpu_count());
+        listeners.push(self.listener);
+        for _ in 1..runtime.cpu_count() {
+            listeners.push(AsyncTcpListener::bind_reuseport(local_addr)?);
+        }
+
+        for (core_id, listener) in listeners.into_iter().enumerate() {
+            let shared = self.shared.clone();
+            runtime.spawn_on(core_id, async move {
+                let mut acceptor = Acceptor::new(&listener, &shared.state);
+                while let Some(stream) = poll_fn(|cx| acceptor.poll_accept(cx)).await {
+                    if let Err(e) = spawn_local(serve_connection(stream, shared.clone())) {
+                        tracing::warn!("Core {} failed to spawn connection: {}", core_id, e);
+                    }
+                }
+            })?;
+        }
+
+        tracing::info!(
+            "Serving HTTP on {} with {} listeners",
+            local_addr,
+            runtime.cpu_count()
+        );
+        Ok(handle)
+    }
+}
+
+/// Shuts down a running [`Server`]
+#[derive(Clone)]
+pub struct ServerHandle {
+    state: Arc<ServerState>,
+}
+
+impl ServerHandle {
+    /// The address the server is bound to
+    pub fn local_addr(&self) -> SocketAddr {
+        self.state.local_addr
+    }
+
+    /// Number of connections being served
+    pub fn active_connections(&self) -> usize {
+        self.state.active.load(Ordering::Acquire)
+    }
+
+    /// Shut the server down gracefully
+    ///
+    /// Stops accepting at once. Idle connections close; those handling a
+    /// request send its response, then close. Connections still open after
+    /// `deadline` are closed without waiting further.
+    ///
+    /// # Returns
+    ///
+    /// `true` if every connection finished within `deadline`, `false` if some
+    /// had to be cut off.
+    pub async fn shutdown(&self, deadline: Duration) -> bool {
+        self.state.stop.cancel();
+        if timer::timeout(deadline, self.drained()).await.is_ok() {
+            return true;
+        }
+        self.state.abort.cancel();
+        self.drained().await;
+        false
+    }
+
+    /// Completes once no connection is open
+    fn drained(&self) -> impl Future<Output = ()> + '_ {
+        poll_fn(|cx| {
+            if self.state.active.load(Ordering::Acquire) == 0 {
+                return Poll::Ready(());
+            }
+            self.state.waiters.lock().unwrap().push(cx.waker().clone());
+            if self.state.active.load(Ordering::Acquire) == 0 {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+    }
+}
+
+impl ServerState {
+    /// Take a connection slot, or register to be woken when one frees up
+    fn poll_acquire(&self, cx: &mut Context<'_>) -> Poll<()> {
+        let try_acquire = || {
+            self.active
+                .fetch_update(Ordering::AcqRel, Ordering::Acquire, |active| {
+                    (active < self.max_connections).then_some(active + 1)
+                })
+                .is_ok()
+        };
+        if try_acquire() {
+            return Poll::Ready(());
+        }
+        self.waiters.lock().unwrap().push(cx.waker().clone());
+        // A connection may have ended in between
+        if try_acquire() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+
+    fn release(&self) {
+        self.active.fetch_sub(1, Ordering::AcqRel);
+        let waiters = std::mem::take(&mut *self.waiters.lock().unwrap());
+        waiters.into_iter().for_each(Waker::wake);
+    }
+}
+
+/// One listener's accept loop: hands out connections while a slot is free
+/// and the server is not shutting down
+struct Acceptor<'a> {
+    state: &'a ServerState,
+    /// Dropped, which stops accepting, once the server shuts down
+    incoming: Option<Incoming<'a>>,
+    /// A connection accepted while every slot was taken
+    waiting: Option<AsyncTcpStream>,
+    /// Registration with the stop token
+    stop_key: Option<u64>,
+}
+
+impl<'a> Acceptor<'a> {
+    fn new(listener: &'a AsyncTcpListener, state: &'a ServerState) -> Self {
+        Self {
+            state,
+            incoming: Some(listener.incoming()),
+            waiting: None,
+            stop_key: None,
+        }
+    }
+
+    /// The next connection, holding a slot released when it is served, or
+    /// `None` once the server shuts down
+    fn poll_accept(&mut self, cx: &mut Context<'_>) -> Poll<Option<AsyncTcpStream>> {
+        self.state.stop.register(&mut self.stop_key, cx.waker());
+        loop {
+            if self.state.stop.is_cancelled() {
+                self.incoming = None;
+                self.waiting = None;
+                return Poll::Ready(None);
+            }
+            let Some(incoming) = self.incoming.as_mut() else {
+                return Poll::Ready(None);
+            };
+            if self.waiting.is_none() {
+                match incoming.poll_next_unpin(cx) {
+                    Poll::Ready(Some(Ok((stream, _)))) => self.waiting = Some(stream),
+                    Poll::Ready(Some(Err(e))) => {
+                        tracing::warn!("HTTP server accept failed: {}", e);
+                        continue;
+                    }
+                    Poll::Ready(None) => {
+                        self.incoming = None;
+                        continue;
+                    }
+                    Poll::Pending => return Poll::Pending,
+                }
+            }
+            return match self.state.poll_acquire(cx) {
+                Poll::Ready(()) => Poll::Ready(self.waiting.take()),
+                Poll::Pending => Poll::Pending,
+            };
+        }
+    }
+}
+
+impl Drop for Acceptor<'_> {
+    fn drop(&mut self) {
+        self.state.stop.deregister(&mut self.stop_key);
+    }
+}
+
+/// Releases a connection slot when dropped
+struct Slot(Arc<ServerState>);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.release();
+    }
+}
+
+/// Serve one accepted connection, which holds a slot, until it closes
+async fn serve_connection<H: HttpHandler>(stream: AsyncTcpStream, shared: Arc<Shared<H>>) {
+    let _slot = Slot(shared.state.clone());
+    let mut connection = HttpConnection::with_limits(stream, shared.limits)
+        .with_options(shared.connection_options.clone())
+        .with_shutdown(shared.state.stop.clone());
+    match connection
+        .serve(&shared.handler)
+        .cancellable(shared.state.abort.clone())
+        .await
+    {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => tracing::debug!("HTTP connection failed: {}", e),
+        Err(_) => connection.close(),
+    }
+}
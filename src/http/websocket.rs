@@ -0,0 +1,818 @@
+//! WebSocket connections (RFC 6455) taken over from HTTP/1.1 requests.
+//!
+//! A handler answers an `Upgrade: websocket` request with the response
+//! [`WebSocketUpgrade::on_upgrade`] builds. Once [`HttpConnection::serve`]
+//! has sent it, the connection stops carrying HTTP and is handed to the
+//! callback as a [`WebSocket`], which sends and receives messages until the
+//! close handshake. Pings are answered as they arrive, and messages split
+//! into fragments are put back together.
+//!
+//! [`HttpConnection::serve`]: super::HttpConnection::serve
+
+use super::{has_token, header, Method, Request, Response, StatusCode, Upgrade, Version};
+use crate::buffer::Buffer;
+use crate::net::AsyncTcpStream;
+use bytes::{Buf, Bytes, BytesMut};
+use futures::lock::Mutex;
+use std::future::Future;
+use std::io;
+use std::net::Shutdown;
+use std::sync::Arc;
+
+/// Appended to the client's key to derive `Sec-WebSocket-Accept`
+const ACCEPT_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+const CONTINUATION: u8 = 0x0;
+const TEXT: u8 = 0x1;
+const BINARY: u8 = 0x2;
+const CLOSE: u8 = 0x8;
+const PING: u8 = 0x9;
+const PONG: u8 = 0xA;
+
+/// Close codes sent when the peer breaks the protocol
+const PROTOCOL_ERROR: u16 = 1002;
+const INVALID_PAYLOAD: u16 = 1007;
+const MESSAGE_TOO_BIG: u16 = 1009;
+
+/// Limits on the messages a [`WebSocket`] sends and receives
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebSocketOptions {
+    /// Largest message accepted, once its fragments are put together; a
+    /// larger one closes the connection with 1009 Message Too Big. Defaults
+    /// to 16 MiB.
+    pub max_message_size: usize,
+    /// Largest frame payload sent; longer messages are split into fragments.
+    /// `None` sends each message as one frame. Defaults to `None`.
+    pub max_frame_size: Option<usize>,
+}
+
+impl Default for WebSocketOptions {
+    fn default() -> Self {
+        Self {
+            max_message_size: 16 * 1024 * 1024,
+            max_frame_size: None,
+        }
+    }
+}
+
+/// A message sent or received on a [`WebSocket`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Text(String),
+    Binary(Bytes),
+}
+
+impl From<String> for Message {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
+impl From<&str> for Message {
+    fn from(text: &str) -> Self {
+        Self::Text(text.to_string())
+    }
+}
+
+impl From<Bytes> for Message {
+    fn from(data: Bytes) -> Self {
+        Self::Binary(data)
+    }
+}
+
+impl From<Vec<u8>> for Message {
+    fn from(data: Vec<u8>) -> Self {
+        Self::Binary(data.into())
+    }
+}
+
+impl From<&[u8]> for Message {
+    fn from(data: &[u8]) -> Self {
+        Self::Binary(Bytes::copy_from_slice(data))
+    }
+}
+
+/// A validated WebSocket handshake request, to be accepted with
+/// [`on_upgrade`](Self::on_upgrade)
+///
+/// # Examples
+///
+/// ```
+/// use rust_miniss::http::{handler_fn, Router, WebSocketUpgrade};
+/// use rust_miniss::Request;
+///
+/// let router = Router::new().get(
+///     "/echo",
+///     handler_fn(|mut request: Request| async move {
+///         match WebSocketUpgrade::from_request(&mut request) {
+///             Ok(upgrade) => upgrade.on_upgrade(|socket| async move {
+///                 while let Ok(Some(message)) = socket.recv().await {
+///                     if socket.send(message).await.is_err() {
+///                         break;
+///                     }
+///                 }
+///             }),
+///             Err(rejection) => rejection,
+///         }
+///     }),
+/// );
+/// ```
+#[derive(Debug)]
+pub struct WebSocketUpgrade {
+    key: String,
+    offered: Vec<String>,
+    protocol: Option<String>,
+    options: WebSocketOptions,
+    upgrade: Upgrade,
+}
+
+impl WebSocketUpgrade {
+    /// Validate `request` as a WebSocket handshake
+    ///
+    /// Fails with the response to reject it with: 400 Bad Request if it is
+    /// not a valid handshake, 426 Upgrade Required if it asks for a protocol
+    /// version other than 13, and 500 Internal Server Error if it did not
+    /// arrive through [`HttpConnection::serve`](super::HttpConnection::serve),
+    /// so there is no connection to take over.
+    // The rejection is meant to be returned from the handler as it is
+    #[allow(clippy::result_large_err)]
+    pub fn from_request(request: &mut Request) -> Result<Self, Response> {
+        let headers = &request.headers;
+        if request.method != Method::GET
+            || request.version < Version::HTTP_11
+            || !has_token(headers, &header::UPGRADE, "websocket")
+            || !has_token(headers, &header::CONNECTION, "upgrade")
+        {
+            return Err(
+                Response::new(StatusCode::BAD_REQUEST).with_body("Not a WebSocket handshake")
+            );
+        }
+        if headers
+            .get(header::SEC_WEBSOCKET_VERSION)
+            .is_none_or(|version| version != "13")
+        {
+            return Err(Response::new(StatusCode::UPGRADE_REQUIRED)
+                .with_header("sec-websocket-version", "13")
+                .with_body("Unsupported WebSocket version"));
+        }
+        let Some(key) = headers
+            .get(header::SEC_WEBSOCKET_KEY)
+            .and_then(|key| key.to_str().ok())
+            .filter(|key| is_valid_key(key))
+        else {
+            return Err(
+                Response::new(StatusCode::BAD_REQUEST).with_body("Invalid Sec-WebSocket-Key")
+            );
+        };
+        let key = key.to_string();
+        let offered = headers
+            .get_all(header::SEC_WEBSOCKET_PROTOCOL)
+            .iter()
+            .filter_map(|protocols| protocols.to_str().ok())
+            .flat_map(|protocols| protocols.split(','))
+            .map(str::trim)
+            .filter(|protocol| !protocol.is_empty())
+            .map(str::to_string)
+            .collect();
+        let Some(upgrade) = request.upgrade.take() else {
+            return Err(Response::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .with_body("Connection cannot be upgraded"));
+        };
+        Ok(Self {
+            key,
+            offered,
+            protocol: None,
+            options: WebSocketOptions::default(),
+            upgrade,
+        })
+    }
+
+    /// The subprotocols the client offered, in its order of preference
+    pub fn protocols(&self) -> &[String] {
+        &self.offered
+    }
+
+    /// Speak the first of `supported` that the client offered, if any
+    pub fn with_protocols(mut self, supported: &[&str]) -> Self {
+        self.protocol = supported
+            .iter()
+            .find(|protocol| self.offered.iter().any(|offered| offered == *protocol))
+            .map(|protocol| protocol.to_string());
+        self
+    }
+
+    /// Set the limits of the [`WebSocket`]
+    pub fn with_options(mut self, options: WebSocketOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Accept the handshake: the 101 Switching Protocols response to return
+    /// from the handler, after which `callback` is run with the connection
+    ///
+    /// The connection closes when the future `callback` returns completes.
+    pub fn on_upgrade<F, Fut>(self, callback: F) -> Response
+    where
+        F: FnOnce(WebSocket) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let mut response = Response::new(StatusCode::SWITCHING_PROTOCOLS)
+            .with_header("upgrade", "websocket")
+            .with_header("connection", "Upgrade")
+            .with_header("sec-websocket-accept", &accept_key(&self.key));
+        if let Some(protocol) = &self.protocol {
+            response = response.with_header("sec-websocket-protocol", protocol);
+        }
+        let Self {
+            protocol,
+            options,
+            upgrade,
+            ..
+        } = self;
+        upgrade.set(Box::new(move |stream, buffer| {
+            Box::pin(callback(WebSocket::new(stream, buffer, protocol, options)))
+        }));
+        response
+    }
+}
+
+/// The server end of a WebSocket connection
+///
+/// Both [`send`](Self::send) and [`recv`](Self::recv) take `&self`, so
+/// messages can be pushed to the client while waiting for its next one, from
+/// futures joined together or tasks sharing the socket. A `recv` that is
+/// dropped before it completes may lose the frame it was reading.
+pub struct WebSocket {
+    stream: Arc<AsyncTcpStream>,
+    reader: Mutex<Reader>,
+    /// Held while writing frames, so those of concurrent senders do not
+    /// interleave; whether a close frame has been sent
+    writer: Mutex<bool>,
+    protocol: Option<String>,
+    options: WebSocketOptions,
+}
+
+/// What has been read of the incoming frames
+struct Reader {
+    buffer: BytesMut,
+    /// The opcode and payload so far of a message arriving in fragments
+    fragments: Option<(u8, BytesMut)>,
+    /// Set once the peer's close frame arrived or reading failed
+    closed: bool,
+}
+
+impl WebSocket {
+    fn new(
+        stream: Arc<AsyncTcpStream>,
+        buffer: BytesMut,
+        protocol: Option<String>,
+        options: WebSocketOptions,
+    ) -> Self {
+        Self {
+            stream,
+            reader: Mutex::new(Reader {
+                buffer,
+                fragments: None,
+                closed: false,
+            }),
+            writer: Mutex::new(false),
+            protocol,
+            options,
+        }
+    }
+
+    /// The subprotocol agreed on in the handshake
+    pub fn protocol(&self) -> Option<&str> {
+        self.protocol.as_deref()
+    }
+
+    /// The next message, or `None` once the close handshake is done
+    ///
+    /// A close frame from the client is answered with one of the same code.
+    /// A client breaking the protocol is sent a close frame with the code of
+    /// the violation, and reading fails with `InvalidData`.
+    pub async fn recv(&self) -> io::Result<Option<Message>> {
+        let mut reader = self.reader.lock().await;
+        if reader.closed {
+            return Ok(None);
+        }
+        match self.next_message(&mut reader).await {
+            Ok(message) => Ok(message),
+            Err(RecvError::Io(error)) => {
+                reader.closed = true;
+                Err(error)
+            }
+            Err(RecvError::Protocol(violation)) => {
+                reader.closed = true;
+                self.write_close(Some(violation.code), violation.reason)
+                    .await?;
+                let _ = self.stream.shutdown(Shutdown::Both);
+                Err(io::Error::new(io::ErrorKind::InvalidData, violation.reason))
+            }
+        }
+    }
+
+    /// Send `message`, split into fragments if longer than
+    /// [`max_frame_size`](WebSocketOptions::max_frame_size)
+    ///
+    /// Fails with `NotConnected` once a close frame has been sent.
+    pub async fn send(&self, message: impl Into<Message>) -> io::Result<()> {
+        let (opcode, payload) = match message.into() {
+            Message::Text(text) => (TEXT, Bytes::from(text)),
+            Message::Binary(data) => (BINARY, data),
+        };
+        let frame_size = self
+            .options
+            .max_frame_size
+            .filter(|&size| size > 0 && payload.len() > size);
+        let frames = match frame_size {
+            Some(size) => {
+                let count = payload.len().div_ceil(size);
+                (0..count)
+                    .map(|i| {
+                        let opcode = if i == 0 { opcode } else { CONTINUATION };
+                        let end = payload.len().min((i + 1) * size);
+                        (i + 1 == count, opcode, payload.slice(i * size..end))
+                    })
+                    .collect()
+            }
+            None => vec![(true, opcode, payload)],
+        };
+        self.write_data(frames).await
+    }
+
+    /// Send a ping with up to 125 bytes of `payload`; the client answers
+    /// with a pong, which [`recv`](Self::recv) skips
+    pub async fn ping(&self, payload: impl Into<Bytes>) -> io::Result<()> {
+        let payload = payload.into();
+        if payload.len() > 125 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "ping payload longer than 125 bytes",
+            ));
+        }
+        self.write_data(vec![(true, PING, payload)]).await
+    }
+
+    /// Start the close handshake with `code` and `reason`, then wait for the
+    /// client's close frame, dropping any messages that arrive before it
+    ///
+    /// A client that never answers keeps this waiting; wrap it in
+    /// [`timeout`](crate::timer::timeout) to bound the wait.
+    pub async fn close(&self, code: u16, reason: &str) -> io::Result<()> {
+        if reason.len() > 123 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "close reason longer than 123 bytes",
+            ));
+        }
+        self.write_close(Some(code), reason).await?;
+        while self.recv().await?.is_some() {}
+        Ok(())
+    }
+
+    async fn next_message(&self, reader: &mut Reader) -> Result<Option<Message>, RecvError> {
+        loop {
+            let Some(frame) = decode_frame(&mut reader.buffer, self.options.max_message_size)?
+            else {
+                let (bytes_read, buffer) = self.stream.read().await?;
+                if bytes_read == 0 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "Connection closed without a close frame",
+                    )
+                    .into());
+                }
+                reader
+                    .buffer
+                    .extend_from_slice(&buffer.as_ref()[..bytes_read]);
+                continue;
+            };
+
+            let (opcode, data) = match frame.opcode {
+                PING => {
+                    self.write_frames(vec![(true, PONG, frame.payload)]).await?;
+                    continue;
+                }
+                PONG => continue,
+                CLOSE => {
+                    reader.closed = true;
+                    let code = close_code(&frame.payload)?;
+                    // Answered with the same code unless this end started
+                    // the handshake
+                    self.write_close(code, "").await?;
+                    let _ = self.stream.shutdown(Shutdown::Both);
+                    return Ok(None);
+                }
+                _ if frame.opcode != CONTINUATION && reader.fragments.is_some() => {
+                    return Err(violation(PROTOCOL_ERROR, "expected a continuation frame"));
+                }
+                _ if frame.opcode != CONTINUATION && frame.fin => (frame.opcode, frame.payload),
+                _ if frame.opcode != CONTINUATION => {
+                    reader.fragments = Some((frame.opcode, BytesMut::from(&frame.payload[..])));
+                    continue;
+                }
+                _ => {
+                    let Some((opcode, mut data)) = reader.fragments.take() else {
+                        return Err(violation(PROTOCOL_ERROR, "unexpected continuation frame"));
+                    };
+                    if data.len() + frame.payload.len() > self.options.max_message_size {
+                        return Err(violation(MESSAGE_TOO_BIG, "message too big"));
+                    }
+                    data.extend_from_slice(&frame.payload);
+                    if !frame.fin {
+                        reader.fragments = Some((opcode, data));
+                        continue;
+                    }
+                    (opcode, data.freeze())
+                }
+            };
+            return match opcode {
+                TEXT => match String::from_utf8(data.into()) {
+                    Ok(text) => Ok(Some(Message::Text(text))),
+                    Err(_) => Err(violation(INVALID_PAYLOAD, "text message is not UTF-8")),
+                },
+                _ => Ok(Some(Message::Binary(data))),
+            };
+        }
+    }
+
+    /// Write data or ping frames, failing once a close frame has been sent
+    async fn write_data(&self, frames: Vec<(bool, u8, Bytes)>) -> io::Result<()> {
+        if self.write_frames(frames).await? {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "WebSocket is closing",
+            ))
+        }
+    }
+
+    /// Send a close frame, unless one has been sent already
+    async fn write_close(&self, code: Option<u16>, reason: &str) -> io::Result<()> {
+        let mut payload = Vec::with_capacity(2 + reason.len());
+        if let Some(code) = code {
+            payload.extend_from_slice(&code.to_be_bytes());
+            payload.extend_from_slice(reason.as_bytes());
+        }
+        self.write_frames(vec![(true, CLOSE, payload.into())])
+            .await
+            .map(drop)
+    }
+
+    /// Write `frames` of fin bit, opcode and payload together; `false`,
+    /// writing nothing, once a close frame has been sent
+    async fn write_frames(&self, frames: Vec<(bool, u8, Bytes)>) -> io::Result<bool> {
+        let mut close_sent = self.writer.lock().await;
+        if *close_sent {
+            return Ok(false);
+        }
+        let mut buffers: Vec<Buffer> = Vec::with_capacity(frames.len() * 2);
+        for (fin, opcode, payload) in frames {
+            *close_sent |= opcode == CLOSE;
+            buffers.push(frame_head(fin, opcode, payload.len()).into());
+            if !payload.is_empty() {
+                buffers.push(payload.into());
+            }
+        }
+        self.stream.write_all_vectored(buffers).await?;
+        Ok(true)
+    }
+}
+
+impl std::fmt::Debug for WebSocket {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebSocket")
+            .field("protocol", &self.protocol)
+            .field("options", &self.options)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A frame as received, its payload unmasked
+#[derive(Debug, PartialEq, Eq)]
+struct Frame {
+    fin: bool,
+    opcode: u8,
+    payload: Bytes,
+}
+
+/// A breach of the protocol, and the close code reporting it
+#[derive(Debug, PartialEq, Eq)]
+struct Violation {
+    code: u16,
+    reason: &'static str,
+}
+
+fn violation(code: u16, reason: &'static str) -> RecvError {
+    RecvError::Protocol(Violation { code, reason })
+}
+
+enum RecvError {
+    Io(io::Error),
+    Protocol(Violation),
+}
+
+impl From<io::Error> for RecvError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<Violation> for RecvError {
+    fn from(violation: Violation) -> Self {
+        Self::Protocol(violation)
+    }
+}
+
+/// Take the next whole frame from the front of `buffer`, or `None` until
+/// more of it has arrived
+///
+/// Frames from a client must be masked.
+fn decode_frame(buffer: &mut BytesMut, max_payload: usize) -> Result<Option<Frame>, Violation> {
+    let breach = |reason| Violation {
+        code: PROTOCOL_ERROR,
+        reason,
+    };
+    let [first, second, ..] = buffer[..] else {
+        return Ok(None);
+    };
+    let fin = first & 0x80 != 0;
+    let opcode = first & 0x0F;
+    if first & 0x70 != 0 {
+        return Err(breach("reserved bits set"));
+    }
+    if !matches!(opcode, CONTINUATION | TEXT | BINARY | CLOSE | PING | PONG) {
+        return Err(breach("unknown opcode"));
+    }
+    if second & 0x80 == 0 {
+        return Err(breach("frame from client not masked"));
+    }
+    let (len, len_size) = match second & 0x7F {
+        126 if buffer.len() >= 4 => (u64::from(u16::from_be_bytes([buffer[2], buffer[3]])), 2),
+        127 if buffer.len() >= 10 => {
+            let mut len = [0; 8];
+            len.copy_from_slice(&buffer[2..10]);
+            (u64::from_be_bytes(len), 8)
+        }
+        126 | 127 => return Ok(None),
+        len => (u64::from(len), 0),
+    };
+    // Control frames may come between fragments, so they are never split
+    if opcode & 0x8 != 0 && (len > 125 || !fin) {
+        return Err(breach("control frame fragmented or too long"));
+    }
+    if len > max_payload as u64 {
+        return Err(Violation {
+            code: MESSAGE_TOO_BIG,
+            reason: "message too big",
+        });
+    }
+
+    let mask_start = 2 + len_size;
+    let frame_len = mask_start + 4 + len as usize;
+    if buffer.len() < frame_len {
+        buffer.reserve(frame_len - buffer.len());
+        return Ok(None);
+    }
+    let mut mask = [0; 4];
+    mask.copy_from_slice(&buffer[mask_start..mask_start + 4]);
+    buffer.advance(mask_start + 4);
+    let mut payload = buffer.split_to(len as usize);
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[i % 4];
+    }
+    Ok(Some(Frame {
+        fin,
+        opcode,
+        payload: payload.freeze(),
+    }))
+}
+
+/// The head of an unmasked frame, as a server sends them
+fn frame_head(fin: bool, opcode: u8, len: usize) -> Vec<u8> {
+    let mut head = Vec::with_capacity(10);
+    head.push(if fin { 0x80 } else { 0 } | opcode);
+    match len {
+        0..=125 => head.push(len as u8),
+        126..=0xFFFF => {
+            head.push(126);
+            head.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        _ => {
+            head.push(127);
+            head.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    head
+}
+
+/// The status code of a close frame's `payload`, if it has one
+fn close_code(payload: &[u8]) -> Result<Option<u16>, Violation> {
+    let [high, low, reason @ ..] = payload else {
+        return match payload.len() {
+            0 => Ok(None),
+            _ => Err(Violation {
+                code: PROTOCOL_ERROR,
+                reason: "close frame too short",
+            }),
+        };
+    };
+    let code = u16::from_be_bytes([*high, *low]);
+    // Codes that are reserved or only for reporting locally
+    if !matches!(code, 1000..=1003 | 1007..=1014 | 3000..=4999) {
+        return Err(Violation {
+            code: PROTOCOL_ERROR,
+            reason: "invalid close code",
+        });
+    }
+    if std::str::from_utf8(reason).is_err() {
+        return Err(Violation {
+            code: INVALID_PAYLOAD,
+            reason: "close reason is not UTF-8",
+        });
+    }
+    Ok(Some(code))
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Whether `key` is the base64 encoding of 16 bytes
+fn is_valid_key(key: &str) -> bool {
+    key.len() == 24
+        && key.ends_with("==")
+        && key.bytes().take(22).all(|byte| BASE64.contains(&byte))
+}
+
+/// The `Sec-WebSocket-Accept` answering the client's `key`
+fn accept_key(key: &str) -> String {
+    base64(&sha1(format!("{key}{ACCEPT_GUID}").as_bytes()))
+}
+
+fn base64(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let mut group = [0; 3];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let bits = u32::from_be_bytes([0, group[0], group[1], group[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64[(bits >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// The SHA-1 digest of `data`, which the handshake needs even though SHA-1
+/// is otherwise broken
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [
+        0x6745_2301,
+        0xEFCD_AB89,
+        0x98BA_DCFE,
+        0x1032_5476,
+        0xC3D2_E1F0,
+    ];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+
+    for block in message.chunks_exact(64) {
+        let mut words = [0u32; 80];
+        for (word, bytes) in words.iter_mut().zip(block.chunks_exact(4)) {
+            *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        for i in 16..80 {
+            words[i] = (words[i - 3] ^ words[i - 8] ^ words[i - 14] ^ words[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+        for (i, word) in words.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A82_7999),
+                20..=39 => (b ^ c ^ d, 0x6ED9_EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1B_BCDC),
+                _ => (b ^ c ^ d, 0xCA62_C1D6),
+            };
+            let next = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            (a, b, c, d, e) = (next, a, b.rotate_left(30), c, d);
+        }
+        for (value, add) in state.iter_mut().zip([a, b, c, d, e]) {
+            *value = value.wrapping_add(add);
+        }
+    }
+
+    let mut digest = [0; 20];
+    for (bytes, value) in digest.chunks_exact_mut(4).zip(state) {
+        bytes.copy_from_slice(&value.to_be_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A frame as a client sends it, masked with `mask`
+    fn masked(fin: bool, opcode: u8, payload: &[u8], mask: [u8; 4]) -> BytesMut {
+        let mut frame = BytesMut::from(&frame_head(fin, opcode, payload.len())[..]);
+        frame[1] |= 0x80;
+        frame.extend_from_slice(&mask);
+        frame.extend(
+            payload
+                .iter()
+                .enumerate()
+                .map(|(i, byte)| byte ^ mask[i % 4]),
+        );
+        frame
+    }
+
+    #[test]
+    fn test_handshake_accept_key() {
+        // The example of RFC 6455, section 1.3
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert!(is_valid_key("dGhlIHNhbXBsZSBub25jZQ=="));
+        assert!(!is_valid_key("dGhlIHNhbXBsZSBub25jZQ"));
+        assert!(!is_valid_key("dGhlIHNhbXBsZSBub25jZ!=="));
+    }
+
+    #[test]
+    fn test_frames_decode_in_pieces() {
+        let payload: Vec<u8> = (0..300).map(|i| i as u8).collect();
+        let mut wire = masked(false, BINARY, &payload, [1, 2, 3, 4]);
+        wire.extend_from_slice(&masked(true, PING, b"hi", [9, 8, 7, 6]));
+
+        // Nothing is taken until a whole frame has arrived
+        let mut buffer = BytesMut::new();
+        let mut frames = Vec::new();
+        for byte in wire {
+            buffer.extend_from_slice(&[byte]);
+            if let Some(frame) = decode_frame(&mut buffer, 1024).unwrap() {
+                frames.push(frame);
+            }
+        }
+        assert!(buffer.is_empty());
+        assert_eq!(
+            frames,
+            [
+                Frame {
+                    fin: false,
+                    opcode: BINARY,
+                    payload: payload.into()
+                },
+                Frame {
+                    fin: true,
+                    opcode: PING,
+                    payload: Bytes::from_static(b"hi")
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_protocol_violations() {
+        let code = |mut frame: BytesMut| decode_frame(&mut frame, 16).unwrap_err().code;
+        let mut unmasked = BytesMut::from(&frame_head(true, TEXT, 2)[..]);
+        unmasked.extend_from_slice(b"hi");
+        assert_eq!(code(unmasked), PROTOCOL_ERROR);
+        let mut reserved = masked(true, TEXT, b"hi", [0; 4]);
+        reserved[0] |= 0x40;
+        assert_eq!(code(reserved), PROTOCOL_ERROR);
+        assert_eq!(code(masked(true, 0x3, b"", [0; 4])), PROTOCOL_ERROR);
+        assert_eq!(code(masked(false, PING, b"", [0; 4])), PROTOCOL_ERROR);
+        assert_eq!(
+            code(masked(true, BINARY, &[0; 17], [0; 4])),
+            MESSAGE_TOO_BIG
+        );
+
+        assert_eq!(close_code(b""), Ok(None));
+        assert_eq!(close_code(&[0x03, 0xE8, b'o', b'k']), Ok(Some(1000)));
+        assert_eq!(close_code(&[0x03]).unwrap_err().code, PROTOCOL_ERROR);
+        assert_eq!(
+            close_code(&1005u16.to_be_bytes()).unwrap_err().code,
+            PROTOCOL_ERROR
+        );
+        assert_eq!(
+            close_code(&[0x03, 0xE8, 0xFF]).unwrap_err().code,
+            INVALID_PAYLOAD
+        );
+    }
+}
@@ -12,48 +12,24 @@
 //! Like the `uring` backend, this module uses `UnsafeCell` and `unsafe` trait impls
 //! to manage thread-local state within the `IoBackend` trait's `&self` methods.
 
-use crate::io::{CompletionKind, IoError, IoProvider, IoToken, Op};
+use crate::blocking::{BlockingPool, Job};
+use crate::buffer::Buffer;
+use crate::io::{
+    connect_syscall, file_syscall, fs_syscall, push_datagrams, raw_to_socket_addr,
+    socket_addr_to_raw, transfer_syscall, vectored_syscall, CompletionKind, Datagram, IoError,
+    IoProvider, IoToken, Op, UdpMessage,
+};
+use crate::io::{notifier::poll_readable, Notifier};
+#[cfg(target_os = "linux")]
+use crate::io::{set_udp_segment_cmsg, udp_gro_segment_size, UdpControlBuffer};
 use mio::{unix::SourceFd, Events, Interest, Poll, Token};
 use std::cell::UnsafeCell;
-use std::collections::HashMap;
-use std::io::{self, Read, Seek, SeekFrom, Write};
-use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
-use std::sync::Arc;
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll as TaskPoll};
 use std::time::Duration;
-use threadpool::ThreadPool;
-
-/// Safe file descriptor wrapper that ensures RAII compliance
-#[derive(Debug)]
-struct SafeFdFile {
-    file: Option<std::fs::File>,
-    fd: RawFd,
-}
-
-impl SafeFdFile {
-    /// Create a new SafeFdFile from a raw fd
-    fn new(fd: RawFd) -> Self {
-        let file = unsafe { std::fs::File::from_raw_fd(fd) };
-        Self {
-            file: Some(file),
-            fd,
-        }
-    }
-
-    /// Get mutable reference to the file
-    fn as_mut(&mut self) -> &mut std::fs::File {
-        self.file.as_mut().expect("File should be available")
-    }
-}
-
-impl Drop for SafeFdFile {
-    fn drop(&mut self) {
-        // Ensure the file is properly closed when SafeFdFile is dropped
-        if let Some(file) = self.file.take() {
-            drop(file); // This will close the fd
-        }
-    }
-}
 
 /// An `epoll` based `IoBackend` implementation using `mio`.
 #[derive(Debug)]
@@ -69,12 +45,27 @@ pub struct EpollBackend {
     udp_recv_buffers: UnsafeCell<HashMap<Token, crate::buffer::Buffer>>,
     /// A counter to generate unique `mio::Token` values.
     next_token: UnsafeCell<usize>,
-    /// Thread pool for blocking file I/O operations
-    thread_pool: Arc<ThreadPool>,
-    /// Completed operations from the thread pool
-    completed_ops: UnsafeCell<Vec<(IoToken, Op, Result<CompletionKind, IoError>)>>,
+    /// The runtime's pool, shared with the other cores, for blocking file
+    /// I/O operations
+    blocking_pool: Arc<BlockingPool>,
+    /// Operations completed on this thread without waiting for readiness
+    completed_ops: UnsafeCell<Vec<IoCompletion>>,
+    /// Blocking operations waiting for room in the pool's queue, in
+    /// submission order
+    deferred: UnsafeCell<VecDeque<Job>>,
+    /// Operations completed by the blocking pool
+    pool_completions: Arc<Mutex<Vec<IoCompletion>>>,
+    /// Registered with the poll so remote wakeups and blocking pool
+    /// completions interrupt `wait`
+    notifier: Arc<Notifier>,
 }
 
+/// An operation's token, the operation, and its result.
+type IoCompletion = (IoToken, Op, Result<CompletionKind, IoError>);
+
+/// `mio` token of the notifier; never handed out for operations.
+const WAKEUP_TOKEN: Token = Token(usize::MAX);
+
 // SAFETY: The `EpollBackend` is designed to be thread-local. It is created within a
 // thread and must not be moved or accessed from another. The `Send` and `Sync` markers
 // are required to satisfy the `IoBackend` trait bounds. The thread-per-core
@@ -83,26 +74,79 @@ unsafe impl Send for EpollBackend {}
 unsafe impl Sync for EpollBackend {}
 
 impl EpollBackend {
-    /// Creates a new `EpollBackend`.
-    pub fn new() -> io::Result<Self> {
-        // Create a small thread pool for blocking I/O operations
-        let pool_size = std::cmp::max(2, num_cpus::get() / 4);
-        let thread_pool = Arc::new(ThreadPool::new(pool_size));
+    /// Creates a new `EpollBackend` that runs blocking file operations on
+    /// `blocking_pool`.
+    pub fn new(blocking_pool: Arc<BlockingPool>) -> io::Result<Self> {
+        let poll = Poll::new()?;
+        let notifier = Arc::new(Notifier::new()?);
+        poll.registry().register(
+            &mut SourceFd(&notifier.as_raw_fd()),
+            WAKEUP_TOKEN,
+            Interest::READABLE,
+        )?;
 
         Ok(Self {
-            poll: UnsafeCell::new(Poll::new()?),
+            poll: UnsafeCell::new(poll),
             events: UnsafeCell::new(Events::with_capacity(1024)),
             pending_ops: UnsafeCell::new(HashMap::new()),
             udp_recv_buffers: UnsafeCell::new(HashMap::new()),
             next_token: UnsafeCell::new(0),
-            thread_pool,
+            blocking_pool,
             completed_ops: UnsafeCell::new(Vec::new()),
+            deferred: UnsafeCell::new(VecDeque::new()),
+            pool_completions: Arc::new(Mutex::new(Vec::new())),
+            notifier,
         })
     }
 }
 
+impl EpollBackend {
+    /// Runs `work` on `op` in the blocking pool, handing the completion back
+    /// to this backend and waking its core.
+    ///
+    /// While the pool's queue is full the operation waits here, never on
+    /// this core's thread, and is queued once the pool wakes the core.
+    fn run_blocking(
+        &self,
+        io_token: IoToken,
+        mut op: Op,
+        work: impl FnOnce(&mut Op) -> Result<CompletionKind, IoError> + Send + 'static,
+    ) {
+        let pool_completions = self.pool_completions.clone();
+        let notifier = self.notifier.clone();
+        // SAFETY: We have exclusive, single-threaded access.
+        let deferred = unsafe { &mut *self.deferred.get() };
+        deferred.push_back(Box::new(move || {
+            let result = work(&mut op);
+            pool_completions
+                .lock()
+                .unwrap()
+                .push((io_token, op, result));
+            notifier.notify();
+        }));
+        self.submit_deferred();
+    }
+
+    /// Queues deferred blocking operations, in order, until the pool's
+    /// queue is full.
+    fn submit_deferred(&self) {
+        // SAFETY: We have exclusive, single-threaded access.
+        let deferred = unsafe { &mut *self.deferred.get() };
+        if deferred.is_empty() {
+            return;
+        }
+        let waker = futures::task::waker(self.notifier.clone());
+        while let Some(job) = deferred.pop_front() {
+            if let Err(job) = self.blocking_pool.poll_execute(job, &waker) {
+                deferred.push_front(job);
+                break;
+            }
+        }
+    }
+}
+
 impl IoProvider for EpollBackend {
-    type Completion = (IoToken, Op, Result<CompletionKind, IoError>);
+    type Completion = IoCompletion;
 
     fn submit(&self, op: Op) -> IoToken {
         let io_token = IoToken::new();
@@ -116,18 +160,36 @@ impl IoProvider for EpollBackend {
         *next_token += 1;
 
         match &op {
-            Op::Accept { fd } => {
+            // Opens, metadata and directory operations block, so they run in
+            // the thread pool
+            Op::OpenAt { .. }
+            | Op::Statx { .. }
+            | Op::SyncData { .. }
+            | Op::Fallocate { .. }
+            | Op::Ftruncate { .. }
+            | Op::RenameAt { .. }
+            | Op::UnlinkAt { .. }
+            | Op::MkdirAt { .. } => {
+                self.run_blocking(io_token, op, |op| fs_syscall(op).map_err(IoError::Io));
+            }
+            // A blocking connect in the pool saves waiting for writability
+            Op::Connect { .. } => {
+                self.run_blocking(io_token, op, |op| connect_syscall(op).map_err(IoError::Io));
+            }
+            // Stay registered until cancelled; every readiness event is drained
+            Op::AcceptMulti { fd } | Op::RecvMulti { fd } => {
                 let mut source = mio::unix::SourceFd(fd);
                 if let Err(e) = poll
                     .registry()
                     .register(&mut source, mio_token, Interest::READABLE)
                 {
-                    eprintln!("Failed to register fd with mio: {}", e);
+                    let completed_ops = unsafe { &mut *self.completed_ops.get() };
+                    completed_ops.push((io_token, op.clone(), Err(IoError::Io(e))));
                 } else {
                     pending_ops.insert(mio_token, (io_token, op));
                 }
             }
-            Op::Read { fd, .. } => {
+            Op::Accept { fd } => {
                 let mut source = mio::unix::SourceFd(fd);
                 if let Err(e) = poll
                     .registry()
@@ -138,83 +200,65 @@ impl IoProvider for EpollBackend {
                     pending_ops.insert(mio_token, (io_token, op));
                 }
             }
-            Op::Write { fd, .. } => {
-                let mut source = mio::unix::SourceFd(fd);
-                if let Err(e) = poll
-                    .registry()
-                    .register(&mut source, mio_token, Interest::WRITABLE)
-                {
-                    eprintln!("Failed to register fd with mio: {}", e);
+            Op::Read { fd, .. } | Op::ReadInto { fd, .. } | Op::Write { fd, .. } => {
+                let interest = if matches!(op, Op::Write { .. }) {
+                    Interest::WRITABLE
                 } else {
-                    pending_ops.insert(mio_token, (io_token, op));
+                    Interest::READABLE
+                };
+                let mut source = mio::unix::SourceFd(fd);
+                match poll.registry().register(&mut source, mio_token, interest) {
+                    Ok(()) => {
+                        pending_ops.insert(mio_token, (io_token, op));
+                    }
+                    // epoll can't watch regular files, which are always
+                    // ready, so their IO blocks in the thread pool instead
+                    Err(e) if e.raw_os_error() == Some(libc::EPERM) => {
+                        self.run_blocking(io_token, op, |op| file_syscall(op).map_err(IoError::Io));
+                    }
+                    Err(e) => {
+                        let completed_ops = unsafe { &mut *self.completed_ops.get() };
+                        completed_ops.push((io_token, op, Err(IoError::Io(e))));
+                    }
                 }
             }
-            // File operations are handled asynchronously in thread pool
-            Op::ReadFile { fd, offset, len } => {
-                let completed_ops_ptr = self.completed_ops.get();
-                let thread_pool = self.thread_pool.clone();
-                let op_clone = op.clone();
-                let io_token_copy = io_token;
-                let fd_copy = *fd;
-                let offset_copy = *offset;
-                let len_copy = *len;
-
-                thread_pool.execute(move || {
-                    let result = {
-                        // Use SafeFdFile to ensure RAII compliance
-                        let mut safe_file = SafeFdFile::new(fd_copy);
-                        let res = safe_file
-                            .as_mut()
-                            .seek(SeekFrom::Start(offset_copy))
-                            .and_then(|_| {
-                                let mut buf = crate::buffer::BufferPool::get(len_copy);
-                                safe_file.as_mut().write(&buf).map(|bytes_written| {
-                                    CompletionKind::WriteFile { bytes_written }
-                                })
-                            });
-                        // SafeFdFile will be automatically dropped here, ensuring fd is closed
-                        res.map_err(IoError::Io)
-                    };
-
-                    // SAFETY: We know this pointer is valid as long as EpollBackend exists
-                    unsafe {
-                        (*completed_ops_ptr).push((io_token_copy, op_clone, result));
-                    }
-                });
+            // File IO blocks, so it runs in the thread pool
+            Op::ReadFile { .. } | Op::WriteFile { .. } | Op::Fsync { .. } => {
+                self.run_blocking(io_token, op, |op| file_syscall(op).map_err(IoError::Io));
             }
-            Op::WriteFile { fd, offset, data } => {
-                let completed_ops_ptr = self.completed_ops.get();
-                let thread_pool = self.thread_pool.clone();
-                let op_clone = op.clone();
-                let io_token_copy = io_token;
-                let fd_copy = *fd;
-                let offset_copy = *offset;
-                let data_clone = data.clone();
-
-                thread_pool.execute(move || {
-                    let result = {
-                        use std::os::unix::io::FromRawFd;
-                        let mut safe_file = SafeFdFile::new(fd_copy);
-                        let res = safe_file
-                            .as_mut()
-                            .seek(SeekFrom::Start(offset_copy))
-                            .and_then(|_| {
-                                let mut buf = crate::buffer::BufferPool::get(len_copy);
-                                safe_file.as_mut().write(&buf).map(|bytes_written| {
-                                    CompletionKind::WriteFile { bytes_written }
-                                })
-                            });
-                        // SafeFdFile will be automatically dropped here
-                        res.map_err(IoError::Io)
-                    };
-
-                    // SAFETY: We know this pointer is valid as long as EpollBackend exists
-                    unsafe {
-                        (*completed_ops_ptr).push((io_token_copy, op_clone, result));
+            // Positional vectored file IO runs in the thread pool
+            Op::Readv { .. } | Op::Writev { .. } => {
+                self.run_blocking(io_token, op, |op| vectored_syscall(op).map_err(IoError::Io));
+            }
+            // Splices may wait on either end, so they wait in the thread pool
+            Op::Splice { fd_in, fd_out, .. } => {
+                let (fd_in, fd_out) = (*fd_in, *fd_out);
+                self.run_blocking(io_token, op, move |op| loop {
+                    match transfer_syscall(op) {
+                        Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                            wait_for_splice(fd_in, fd_out)
+                        }
+                        result => break result.map_err(IoError::Io),
                     }
                 });
             }
-            Op::UdpRecv { fd, buffer } => {
+            Op::RecvMsg { fd, .. }
+            | Op::SendMsg { fd, .. }
+            | Op::Sendfile { fd_out: fd, .. }
+            | Op::SendZc { fd, .. } => {
+                let interest = if matches!(op, Op::RecvMsg { .. }) {
+                    Interest::READABLE
+                } else {
+                    Interest::WRITABLE
+                };
+                let mut source = mio::unix::SourceFd(fd);
+                if let Err(e) = poll.registry().register(&mut source, mio_token, interest) {
+                    eprintln!("Failed to register fd with mio: {}", e);
+                } else {
+                    pending_ops.insert(mio_token, (io_token, op));
+                }
+            }
+            Op::UdpRecv { fd, buffer, .. } => {
                 // Register the UDP socket for read events
                 let mut source = mio::unix::SourceFd(fd);
                 if let Err(e) =
@@ -244,28 +288,37 @@ impl IoProvider for EpollBackend {
                 // Store the data buffer and address with the op. The op contains the Buffer.
                 pending_ops.insert(mio_token, (io_token, op));
             }
-            Op::Fsync { fd } => {
-                // Fsync remains synchronous.
-                let _result = {
-                    use std::os::unix::io::FromRawFd;
-                    let safe_file = SafeFdFile::new(*fd);
-                    let res = safe_file.as_mut().metadata().map(|_| CompletionKind::Fsync);
-                    // SafeFdFile will be automatically dropped here
-                    res.map(|_| CompletionKind::Fsync).map_err(IoError::Io)
-                };
+            Op::UdpRecvBatch { fd, .. } => {
+                // Drained with a single recvmmsg once the socket is readable
+                let mut source = mio::unix::SourceFd(fd);
+                if let Err(e) =
+                    poll.registry()
+                        .register(&mut source, mio_token, mio::Interest::READABLE)
+                {
+                    eprintln!("Failed to register UDP socket with epoll: {}", e);
+                }
+                pending_ops.insert(mio_token, (io_token, op));
+            }
+            Op::UdpSendBatch { fd, .. } => {
+                // Flushed with a single sendmmsg once the socket is writable
+                let mut source = mio::unix::SourceFd(fd);
+                if let Err(e) =
+                    poll.registry()
+                        .register(&mut source, mio_token, mio::Interest::WRITABLE)
+                {
+                    eprintln!("Failed to register UDP socket with epoll for send: {}", e);
+                }
                 pending_ops.insert(mio_token, (io_token, op));
             }
             Op::Close { fd } => {
-                // Close remains synchronous.
-                let _result = {
-                    let res = unsafe { libc::close(*fd) };
-                    if res == -1 {
-                        Err(IoError::Io(io::Error::last_os_error()))
-                    } else {
-                        Ok(CompletionKind::Close)
-                    }
+                // Close doesn't block, so it completes at once
+                let result = if unsafe { libc::close(*fd) } == -1 {
+                    Err(IoError::Io(io::Error::last_os_error()))
+                } else {
+                    Ok(CompletionKind::Close)
                 };
-                pending_ops.insert(mio_token, (io_token, op));
+                let completed_ops = unsafe { &mut *self.completed_ops.get() };
+                completed_ops.push((io_token, op, result));
             }
         };
 
@@ -280,9 +333,11 @@ impl IoProvider for EpollBackend {
         let completed_ops = unsafe { &mut *self.completed_ops.get() };
 
         let mut completions = Vec::new();
+        self.submit_deferred();
 
-        // First, collect any completed operations from the thread pool
-        completions.extend(completed_ops.drain(..));
+        // First, collect any operations that completed without readiness
+        completions.append(completed_ops);
+        completions.append(&mut self.pool_completions.lock().unwrap());
 
         // Poll for events with a non-blocking timeout.
         if let Err(e) = poll.poll(events, Some(Duration::from_millis(0))) {
@@ -298,10 +353,72 @@ impl IoProvider for EpollBackend {
         // First, handle any events from mio
         for event in events.iter() {
             let mio_token = event.token();
-            if let Some((io_token, op)) = pending_ops.remove(&mio_token) {
+            // Wakeups only interrupt `wait`; the run loop clears the notifier
+            if mio_token == WAKEUP_TOKEN {
+                continue;
+            }
+            if let Some((io_token, op)) = pending_ops.get(&mio_token) {
+                if op.is_multishot() {
+                    if !drain_multishot(*io_token, op, &mut completions) {
+                        let mut source = SourceFd(&op.as_raw_fd());
+                        let _ = poll.registry().deregister(&mut source);
+                        pending_ops.remove(&mio_token);
+                    }
+                    continue;
+                }
+            }
+            if let Some((io_token, mut op)) = pending_ops.remove(&mio_token) {
                 let mut source = SourceFd(&op.as_raw_fd());
                 let _ = poll.registry().deregister(&mut source);
 
+                // Vectored socket IO fills or drains the op's own buffers
+                if matches!(
+                    op,
+                    Op::RecvMsg { .. }
+                        | Op::SendMsg { .. }
+                        | Op::Sendfile { .. }
+                        | Op::SendZc { .. }
+                ) {
+                    let attempt = if matches!(op, Op::Sendfile { .. } | Op::SendZc { .. }) {
+                        transfer_syscall(&op)
+                    } else {
+                        vectored_syscall(&mut op)
+                    };
+                    let result = match attempt {
+                        Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                            let interest = if matches!(op, Op::RecvMsg { .. }) {
+                                Interest::READABLE
+                            } else {
+                                Interest::WRITABLE
+                            };
+                            let mut source = SourceFd(&op.as_raw_fd());
+                            if let Err(reg_err) =
+                                poll.registry().register(&mut source, mio_token, interest)
+                            {
+                                eprintln!("Failed to re-register fd with epoll: {}", reg_err);
+                            }
+                            pending_ops.insert(mio_token, (io_token, op));
+                            continue;
+                        }
+                        result => result.map_err(IoError::Io),
+                    };
+                    completions.push((io_token, op, result));
+                    continue;
+                }
+
+                // Reads into a caller-supplied buffer hand that same buffer back
+                if let Op::ReadInto { fd, buffer, .. } = &mut op {
+                    let mut buffer = std::mem::replace(buffer, Buffer::new_zeroed(0));
+                    let result = syscall_read_into(*fd, &mut buffer)
+                        .map(|bytes_read| CompletionKind::Read {
+                            bytes_read,
+                            data: buffer,
+                        })
+                        .map_err(IoError::Io);
+                    completions.push((io_token, op, result));
+                    continue;
+                }
+
                 let result = match &op {
                     Op::Accept { fd } => {
                         // The file descriptor is a listening socket, so we can accept a connection.
@@ -313,72 +430,20 @@ impl IoProvider for EpollBackend {
                             Err(e) => Err(IoError::Io(e)),
                         }
                     }
-                    Op::Read { fd, offset, len } => {
-                        let mut file = unsafe { std::fs::File::from_raw_fd(*fd) };
-                        let res = file.seek(SeekFrom::Start(*offset)).and_then(|_| {
-                            let mut buf = crate::buffer::BufferPool::get(*len);
-                            file.read(buf.as_mut()).map(|bytes_read| {
-                                unsafe {
-                                    buf.set_len(bytes_read);
-                                }
-                                CompletionKind::Read {
-                                    bytes_read,
-                                    data: buf,
-                                }
-                            })
-                        });
-                        // Explicitly close the file to avoid fd conflicts
-                        drop(file);
-                        res.map_err(IoError::Io)
-                    }
-                    Op::Write { fd, offset, data } => {
-                        let mut file = unsafe { std::fs::File::from_raw_fd(*fd) };
-                        let res = file
-                            .seek(SeekFrom::Start(*offset))
-                            .and_then(|_| file.write(data.as_ref())) // Write from Buffer
-                            .map(|bytes_written| CompletionKind::Write { bytes_written });
-                        // Explicitly close the file to avoid fd conflicts
-                        drop(file);
-                        res.map_err(IoError::Io)
+                    Op::Read { fd, len, .. } => {
+                        let mut data = crate::buffer::BufferPool::get(*len);
+                        syscall_read_into(*fd, &mut data)
+                            .map(|bytes_read| CompletionKind::Read { bytes_read, data })
+                            .map_err(IoError::Io)
                     }
-                    Op::ReadFile { fd, offset, len } => {
-                        // This path should ideally not be hit as these are handled sync in submit
-                        // but included for completeness if event-driven file I/O were enabled.
-                        let mut file = unsafe { std::fs::File::from_raw_fd(*fd) };
-                        let res = file.seek(SeekFrom::Start(*offset)).and_then(|_| {
-                            let mut buf = crate::buffer::BufferPool::get(*len);
-                            file.read(buf.as_mut()).map(|bytes_read| {
-                                unsafe {
-                                    buf.set_len(bytes_read);
-                                }
-                                CompletionKind::ReadFile {
-                                    bytes_read,
-                                    data: buf,
-                                }
-                            })
-                        });
-                        // Explicitly close the file to avoid fd conflicts
-                        drop(file);
-                        res.map_err(IoError::Io)
-                    }
-                    Op::WriteFile { fd, offset, data } => {
-                        // This path should ideally not be hit.
-                        let mut file = unsafe { std::fs::File::from_raw_fd(*fd) };
-                        let res = file
-                            .seek(SeekFrom::Start(*offset))
-                            .and_then(|_| file.write(data.as_ref()))
-                            .map(|bytes_written| CompletionKind::WriteFile { bytes_written });
-                        // Explicitly drop the file to ensure fd is properly closed
-                        drop(file);
-                        res.map_err(IoError::Io)
-                    }
-                    Op::UdpRecv { fd, .. } => {
+                    Op::Write { fd, data, .. } => syscall_write(*fd, data)
+                        .map(|bytes_written| CompletionKind::Write { bytes_written })
+                        .map_err(IoError::Io),
+                    Op::UdpRecv { fd, flags, .. } => {
                         // Matched by `event.token()`
                         let udp_recv_buffers = unsafe { &mut *self.udp_recv_buffers.get() };
                         if let Some(mut buffer) = udp_recv_buffers.remove(&mio_token) {
-                            use std::os::unix::io::FromRawFd;
-                            let socket = unsafe { std::net::UdpSocket::from_raw_fd(*fd) };
-                            let res = match socket.recv_from(buffer.as_mut()) {
+                            let res = match syscall_recvfrom(*fd, buffer.as_mut_slice(), *flags) {
                                 Ok((bytes_read, addr)) => {
                                     unsafe {
                                         buffer.set_len(bytes_read);
@@ -408,7 +473,6 @@ impl IoProvider for EpollBackend {
                                 Err(e) => Err(e),
                             }
                             .map_err(IoError::Io);
-                            // Socket will be automatically dropped here
                             res
                         } else {
                             Err(IoError::Other(
@@ -417,20 +481,46 @@ impl IoProvider for EpollBackend {
                         }
                     }
                     Op::UdpSend { fd, data, addr } => {
-                        // Perform UDP send operation
-                        use std::os::unix::io::FromRawFd;
-                        let socket = unsafe { std::net::UdpSocket::from_raw_fd(*fd) };
-                        let res = socket.send_to(data.as_ref(), *addr).map(|bytes_written| {
-                            CompletionKind::UdpSend {
+                        // Perform UDP send operation on the borrowed fd
+                        syscall_sendto(*fd, data.as_ref(), addr.as_ref())
+                            .map(|bytes_written| CompletionKind::UdpSend {
                                 bytes_written,
                                 data: data.clone(),
-                            }
-                        });
-                        // Explicitly drop the socket to ensure fd is properly closed
-                        drop(socket);
-                        res.map_err(IoError::Io)
+                            })
+                            .map_err(IoError::Io)
                     }
-                    // Fsync and Close are not handled in this path currently.
+                    Op::UdpRecvBatch {
+                        fd,
+                        max_datagrams,
+                        buf_size,
+                    } => match syscall_recvmmsg(*fd, *max_datagrams, *buf_size) {
+                        Ok(datagrams) => Ok(CompletionKind::UdpRecvBatch { datagrams }),
+                        Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                            let mut mio_source = SourceFd(fd);
+                            if let Err(reg_err) = poll.registry().register(
+                                &mut mio_source,
+                                mio_token,
+                                mio::Interest::READABLE,
+                            ) {
+                                eprintln!(
+                                    "Failed to re-register UDP socket with epoll: {}",
+                                    reg_err
+                                );
+                            }
+                            pending_ops.insert(mio_token, (io_token, op.clone()));
+                            continue; // Don't add to completions if WouldBlock
+                        }
+                        Err(e) => Err(IoError::Io(e)),
+                    },
+                    Op::UdpSendBatch { fd, messages } => syscall_sendmmsg(*fd, messages)
+                        .map(
+                            |(messages_sent, bytes_written)| CompletionKind::UdpSendBatch {
+                                messages_sent,
+                                bytes_written,
+                            },
+                        )
+                        .map_err(IoError::Io),
+                    // Every other operation completes without readiness
                     _ => continue,
                 };
 
@@ -438,57 +528,46 @@ impl IoProvider for EpollBackend {
             }
         }
 
-        // Handle any pending synchronous operations (Fsync, Close) - ReadFile/WriteFile now handled by thread pool
-        // We need to create a temporary vector to avoid borrowing issues
-        let pending_tokens: Vec<_> = pending_ops.keys().cloned().collect();
-        for mio_token in pending_tokens {
-            if let Some((_io_token, op)) = pending_ops.get(&mio_token) {
-                match op {
-                    Op::Fsync { fd } => {
-                        // Perform fsync synchronously
-                        let result = {
-                            use std::os::unix::io::FromRawFd;
-                            let file = unsafe { std::fs::File::from_raw_fd(*fd) };
-                            let res = file.sync_all();
-                            // Explicitly close the file to avoid fd conflicts
-                            drop(file); // Prevent drop from closing the fd
-                            res.map(|_| CompletionKind::Fsync).map_err(IoError::Io)
-                        };
-                        // Remove from pending ops and add to completions
-                        if let Some((io_token, op)) = pending_ops.remove(&mio_token) {
-                            completions.push((io_token, op, result));
-                        }
-                    }
-                    Op::Close { fd } => {
-                        // Perform close synchronously
-                        let result = {
-                            let res = unsafe { libc::close(*fd) };
-                            if res == -1 {
-                                Err(IoError::Io(io::Error::last_os_error()))
-                            } else {
-                                Ok(CompletionKind::Close)
-                            }
-                        };
-                        // Remove from pending ops and add to completions
-                        if let Some((io_token, op)) = pending_ops.remove(&mio_token) {
-                            completions.push((io_token, op, result));
-                        }
-                    }
-                    _ => {} // Other operations are handled by mio events or thread pool
-                }
-            }
-        }
-
         if completions.is_empty() {
             TaskPoll::Pending
         } else {
             TaskPoll::Ready(completions)
         }
     }
+
+    fn wait(&self, timeout: Option<Duration>) {
+        // SAFETY: We have exclusive, single-threaded access.
+        let completed_ops = unsafe { &*self.completed_ops.get() };
+        if !completed_ops.is_empty() || !self.pool_completions.lock().unwrap().is_empty() {
+            return;
+        }
+        // The epoll fd polls readable once any registered fd, including the
+        // notifier, has an event to report
+        let poll = unsafe { &*self.poll.get() };
+        poll_readable(poll.as_raw_fd(), timeout);
+    }
+
+    fn cancel(&self, token: IoToken) {
+        // SAFETY: We have exclusive, single-threaded access.
+        let poll = unsafe { &mut *self.poll.get() };
+        let pending_ops = unsafe { &mut *self.pending_ops.get() };
+        let mio_token = pending_ops
+            .iter()
+            .find(|(_, (io_token, _))| *io_token == token)
+            .map(|(mio_token, _)| *mio_token);
+        if let Some((_, op)) = mio_token.and_then(|mio_token| pending_ops.remove(&mio_token)) {
+            let mut source = SourceFd(&op.as_raw_fd());
+            let _ = poll.registry().deregister(&mut source);
+        }
+    }
+
+    fn notifier(&self) -> Option<Arc<Notifier>> {
+        Some(self.notifier.clone())
+    }
 }
 
 fn syscall_accept(fd: RawFd) -> io::Result<(RawFd, std::net::SocketAddr)> {
-    let mut storage: libc::sockaddr_storage = std::mem::MaybeUninit::zeroed().assume_init();
+    let mut storage: libc::sockaddr_storage = unsafe { std::mem::zeroed() };
     let mut len = std::mem::size_of_val(&storage) as libc::socklen_t;
     let new_fd = unsafe { libc::accept(fd, &mut storage as *mut _ as *mut _, &mut len) };
 
@@ -527,19 +606,329 @@ fn syscall_accept(fd: RawFd) -> io::Result<(RawFd, std::net::SocketAddr)> {
     Ok((new_fd, addr))
 }
 
+/// `read(2)` into the spare capacity of `buffer` on a borrowed fd, setting its
+/// length to the number of bytes read.
+/// Runs a multishot operation until its socket would block, pushing a
+/// completion per connection or chunk. Returns whether it is still running,
+/// i.e. it neither failed nor reached end of file.
+fn drain_multishot(io_token: IoToken, op: &Op, completions: &mut Vec<IoCompletion>) -> bool {
+    loop {
+        let result = match *op {
+            Op::AcceptMulti { fd } => match syscall_accept(fd) {
+                Ok((fd, addr)) => Ok(CompletionKind::Accept {
+                    fd,
+                    addr: Some(addr),
+                }),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return true,
+                Err(e) => Err(e),
+            },
+            Op::RecvMulti { fd } => {
+                let mut buffer = crate::buffer::BufferPool::get(crate::buffer::BUFFER_SIZE);
+                match syscall_read_into(fd, &mut buffer) {
+                    Ok(bytes_read) => Ok(CompletionKind::Read {
+                        bytes_read,
+                        data: buffer,
+                    }),
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => return true,
+                    Err(e) => Err(e),
+                }
+            }
+            _ => return false,
+        };
+        let ended = matches!(
+            result,
+            Err(_) | Ok(CompletionKind::Read { bytes_read: 0, .. })
+        );
+        completions.push((io_token, op.clone(), result.map_err(IoError::Io)));
+        if ended {
+            return false;
+        }
+    }
+}
+
+fn syscall_read_into(fd: RawFd, buffer: &mut Buffer) -> io::Result<usize> {
+    let n = unsafe {
+        libc::read(
+            fd,
+            buffer.as_mut_ptr() as *mut libc::c_void,
+            buffer.capacity(),
+        )
+    };
+    if n < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: the kernel initialised the first `n` bytes, and `n <= capacity`.
+    unsafe { buffer.set_len(n as usize) };
+    Ok(n as usize)
+}
+
+/// `write(2)` of `buf` on a borrowed fd.
+fn syscall_write(fd: RawFd, buf: &[u8]) -> io::Result<usize> {
+    let n = unsafe { libc::write(fd, buf.as_ptr() as *const libc::c_void, buf.len()) };
+    if n < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(n as usize)
+}
+
+/// Blocks until a splice that would have blocked can make progress: until
+/// `fd_in` has data or, if it already has, until `fd_out` has room.
+fn wait_for_splice(fd_in: RawFd, fd_out: RawFd) {
+    let mut pollfd = libc::pollfd {
+        fd: fd_in,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    // SAFETY: `pollfd` is a valid array of one entry.
+    if unsafe { libc::poll(&mut pollfd, 1, 0) } > 0 {
+        pollfd = libc::pollfd {
+            fd: fd_out,
+            events: libc::POLLOUT,
+            revents: 0,
+        };
+    }
+    // SAFETY: as above.
+    unsafe { libc::poll(&mut pollfd, 1, -1) };
+}
+
+/// `recvfrom(2)` on a borrowed fd. Unlike going through `std::net::UdpSocket`,
+/// this never takes ownership of (and closes) the descriptor.
+fn syscall_recvfrom(
+    fd: RawFd,
+    buf: &mut [u8],
+    flags: i32,
+) -> io::Result<(usize, std::net::SocketAddr)> {
+    let mut storage: libc::sockaddr_storage = unsafe { std::mem::zeroed() };
+    let mut len = std::mem::size_of_val(&storage) as libc::socklen_t;
+    let n = unsafe {
+        libc::recvfrom(
+            fd,
+            buf.as_mut_ptr() as *mut libc::c_void,
+            buf.len(),
+            flags,
+            &mut storage as *mut _ as *mut libc::sockaddr,
+            &mut len,
+        )
+    };
+    if n < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok((n as usize, raw_to_socket_addr(&storage, len)?))
+}
+
+/// `sendto(2)` on a borrowed fd, or `send(2)` to the connected peer when `addr`
+/// is `None`.
+fn syscall_sendto(fd: RawFd, buf: &[u8], addr: Option<&std::net::SocketAddr>) -> io::Result<usize> {
+    let n = match addr {
+        Some(addr) => {
+            let (storage, len) = socket_addr_to_raw(addr);
+            unsafe {
+                libc::sendto(
+                    fd,
+                    buf.as_ptr() as *const libc::c_void,
+                    buf.len(),
+                    0,
+                    &storage as *const _ as *const libc::sockaddr,
+                    len,
+                )
+            }
+        }
+        None => unsafe { libc::send(fd, buf.as_ptr() as *const libc::c_void, buf.len(), 0) },
+    };
+    if n < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(n as usize)
+}
+
+/// Receives up to `max_datagrams` datagrams with one `recvmmsg(2)`.
+///
+/// Fails with `WouldBlock` if nothing is queued.
+#[cfg(target_os = "linux")]
+fn syscall_recvmmsg(fd: RawFd, max_datagrams: usize, buf_size: usize) -> io::Result<Vec<Datagram>> {
+    let count = max_datagrams.max(1);
+    let mut payloads = vec![0u8; count * buf_size];
+    let mut addrs: Vec<libc::sockaddr_storage> = vec![unsafe { std::mem::zeroed() }; count];
+    let mut controls: Vec<UdpControlBuffer> = vec![[0; 8]; count];
+    let mut iovs: Vec<libc::iovec> = payloads
+        .chunks_mut(buf_size.max(1))
+        .take(count)
+        .map(|chunk| libc::iovec {
+            iov_base: chunk.as_mut_ptr() as *mut libc::c_void,
+            iov_len: buf_size,
+        })
+        .collect();
+    let mut headers: Vec<libc::mmsghdr> = (0..count)
+        .map(|i| {
+            let mut header: libc::mmsghdr = unsafe { std::mem::zeroed() };
+            header.msg_hdr.msg_name = &mut addrs[i] as *mut _ as *mut libc::c_void;
+            header.msg_hdr.msg_namelen = std::mem::size_of::<libc::sockaddr_storage>() as _;
+            header.msg_hdr.msg_iov = &mut iovs[i];
+            header.msg_hdr.msg_iovlen = 1;
+            header.msg_hdr.msg_control = controls[i].as_mut_ptr() as *mut libc::c_void;
+            header.msg_hdr.msg_controllen = std::mem::size_of::<UdpControlBuffer>() as _;
+            header
+        })
+        .collect();
+
+    let received = unsafe {
+        libc::recvmmsg(
+            fd,
+            headers.as_mut_ptr(),
+            count as libc::c_uint,
+            libc::MSG_DONTWAIT,
+            std::ptr::null_mut(),
+        )
+    };
+    if received < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut datagrams = Vec::with_capacity(received as usize);
+    for (i, header) in headers.iter().take(received as usize).enumerate() {
+        let addr = raw_to_socket_addr(&addrs[i], header.msg_hdr.msg_namelen)?;
+        let len = (header.msg_len as usize).min(buf_size);
+        let payload = &payloads[i * buf_size..i * buf_size + len];
+        push_datagrams(
+            &mut datagrams,
+            payload,
+            addr,
+            udp_gro_segment_size(&header.msg_hdr),
+        );
+    }
+    Ok(datagrams)
+}
+
+/// Portable fallback for [`syscall_recvmmsg`]: one `recvfrom(2)` per datagram.
+#[cfg(not(target_os = "linux"))]
+fn syscall_recvmmsg(fd: RawFd, max_datagrams: usize, buf_size: usize) -> io::Result<Vec<Datagram>> {
+    let mut datagrams = Vec::new();
+    let mut buf = vec![0u8; buf_size];
+    while datagrams.len() < max_datagrams.max(1) {
+        match syscall_recvfrom(fd, &mut buf, 0) {
+            Ok((len, addr)) => push_datagrams(&mut datagrams, &buf[..len], addr, None),
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock && !datagrams.is_empty() => break,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(datagrams)
+}
+
+/// Sends `messages` with one `sendmmsg(2)`, returning how many messages and
+/// bytes went out.
+#[cfg(target_os = "linux")]
+fn syscall_sendmmsg(fd: RawFd, messages: &[UdpMessage]) -> io::Result<(usize, usize)> {
+    if messages.is_empty() {
+        return Ok((0, 0));
+    }
+    let mut addrs: Vec<(libc::sockaddr_storage, libc::socklen_t)> = messages
+        .iter()
+        .map(|message| match &message.addr {
+            Some(addr) => socket_addr_to_raw(addr),
+            None => (unsafe { std::mem::zeroed() }, 0),
+        })
+        .collect();
+    let mut controls: Vec<UdpControlBuffer> = vec![[0; 8]; messages.len()];
+    let mut iovs: Vec<libc::iovec> = messages
+        .iter()
+        .map(|message| libc::iovec {
+            iov_base: message.data.as_ptr() as *mut libc::c_void,
+            iov_len: message.data.len(),
+        })
+        .collect();
+    let mut headers: Vec<libc::mmsghdr> = (0..messages.len())
+        .map(|i| {
+            let mut header: libc::mmsghdr = unsafe { std::mem::zeroed() };
+            if addrs[i].1 > 0 {
+                header.msg_hdr.msg_name = &mut addrs[i].0 as *mut _ as *mut libc::c_void;
+                header.msg_hdr.msg_namelen = addrs[i].1;
+            }
+            header.msg_hdr.msg_iov = &mut iovs[i];
+            header.msg_hdr.msg_iovlen = 1;
+            if let Some(segment_size) = messages[i].segment_size {
+                set_udp_segment_cmsg(&mut header.msg_hdr, &mut controls[i], segment_size);
+            }
+            header
+        })
+        .collect();
+
+    let sent = unsafe {
+        libc::sendmmsg(
+            fd,
+            headers.as_mut_ptr(),
+            headers.len() as libc::c_uint,
+            libc::MSG_DONTWAIT,
+        )
+    };
+    if sent < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let bytes_written = headers
+        .iter()
+        .take(sent as usize)
+        .map(|header| header.msg_len as usize)
+        .sum();
+    Ok((sent as usize, bytes_written))
+}
+
+/// Portable fallback for [`syscall_sendmmsg`]: one `sendto(2)` per message.
+#[cfg(not(target_os = "linux"))]
+fn syscall_sendmmsg(fd: RawFd, messages: &[UdpMessage]) -> io::Result<(usize, usize)> {
+    let mut sent = 0;
+    let mut bytes_written = 0;
+    for message in messages {
+        if message.segment_size.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "UDP segmentation offload is only supported on Linux",
+            ));
+        }
+        match syscall_sendto(fd, &message.data, message.addr.as_ref()) {
+            Ok(n) => {
+                sent += 1;
+                bytes_written += n;
+            }
+            Err(_) if sent > 0 => break,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok((sent, bytes_written))
+}
+
 // Helper to get RawFd from Op
 impl AsRawFd for Op {
     fn as_raw_fd(&self) -> RawFd {
         match *self {
             Op::Accept { fd } => fd,
+            Op::Connect { fd, .. } => fd,
+            Op::AcceptMulti { fd } => fd,
+            Op::RecvMulti { fd } => fd,
             Op::Read { fd, .. } => fd,
+            Op::ReadInto { fd, .. } => fd,
             Op::Write { fd, .. } => fd,
+            Op::Readv { fd, .. } => fd,
+            Op::Writev { fd, .. } => fd,
+            Op::RecvMsg { fd, .. } => fd,
+            Op::SendMsg { fd, .. } => fd,
+            Op::Splice { fd_out, .. } => fd_out,
+            Op::Sendfile { fd_out, .. } => fd_out,
+            Op::SendZc { fd, .. } => fd,
+            Op::SyncData { fd } => fd,
+            Op::Fallocate { fd, .. } => fd,
+            Op::Ftruncate { fd, .. } => fd,
+            Op::Statx { dir_fd, .. } => dir_fd,
+            // Path operations have no descriptor of their own
+            Op::OpenAt { .. } | Op::RenameAt { .. } | Op::UnlinkAt { .. } | Op::MkdirAt { .. } => {
+                libc::AT_FDCWD
+            }
             Op::Fsync { fd, .. } => fd,
             Op::Close { fd, .. } => fd,
             Op::ReadFile { fd, .. } => fd,
             Op::WriteFile { fd, .. } => fd,
             Op::UdpRecv { fd, .. } => fd, // Add UdpRecv fd
             Op::UdpSend { fd, .. } => fd, // Add UdpSend fd
+            Op::UdpRecvBatch { fd, .. } => fd,
+            Op::UdpSendBatch { fd, .. } => fd,
         }
     }
 }
@@ -1,36 +1,46 @@
-//! A future that resolves when an I/O operation completes.
+//! A future that resolves when an I/O operation completes, and a stream of the
+//! completions of a multishot operation.
 
-use crate::cpu::io_state;
-use crate::io::{CompletionKind, IoError, IoToken};
+use crate::cpu::{discard_completion, io_state};
+use crate::io::{CompletionKind, IoError, IoToken, Op};
+use futures::Stream;
+use std::collections::VecDeque;
 use std::future::Future;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
 /// A future that waits for an I/O operation, identified by an `IoToken`, to complete.
+///
+/// Create it as soon as the operation is submitted: completions of operations
+/// no `IoFuture` waits for are discarded. Dropping the future before it
+/// completes cancels the operation.
 #[derive(Debug)]
 pub struct IoFuture {
     token: IoToken,
     // We don't need a reference to the backend, just the token.
     // The result will be delivered to the CpuIoState by the runtime.
+    done: bool,
 }
 
 impl IoFuture {
     /// Creates a new `IoFuture` for a given `IoToken`.
     pub fn new(token: IoToken) -> Self {
-        Self { token }
+        io_state().awaited_io.lock().unwrap().insert(token);
+        Self { token, done: false }
     }
 }
 
 impl Future for IoFuture {
     type Output = Result<CompletionKind, IoError>;
 
-    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         // Get the I/O state for the current CPU thread.
         let state = io_state();
 
         // Check if the completion for our token is already available.
         if let Some(result) = state.completed_io.lock().unwrap().remove(&self.token) {
             // The operation is complete, return the result.
+            self.done = true;
             return Poll::Ready(result);
         }
 
@@ -49,13 +59,83 @@ impl Future for IoFuture {
 
 impl Drop for IoFuture {
     fn drop(&mut self) {
-        // When the future is dropped, we need to clean up any associated state.
-        // The most important thing is to remove the waker, if it exists, to prevent
-        // a memory leak. A more advanced implementation would also attempt to
-        // cancel the underlying I/O operation.
         let state = io_state();
         state.io_wakers.lock().unwrap().remove(&self.token);
-        // We also remove any potential completion that might have arrived just now.
-        state.completed_io.lock().unwrap().remove(&self.token);
+        state.awaited_io.lock().unwrap().remove(&self.token);
+        if self.done {
+            return;
+        }
+        let completed = state.completed_io.lock().unwrap().remove(&self.token);
+        match completed {
+            // Completed but never polled: release what it returned
+            Some(result) => discard_completion(result),
+            // Still in flight: its completion is discarded when it arrives
+            None => state.io_backend.cancel(self.token),
+        }
+    }
+}
+
+/// A stream of the completions of a multishot operation, identified by an
+/// `IoToken`.
+///
+/// The stream itself never ends: the operation's last completion is an error
+/// or, for receives, end of file. Dropping the stream cancels the operation.
+#[derive(Debug)]
+pub struct IoStream {
+    token: IoToken,
+}
+
+impl IoStream {
+    /// Submits a multishot `op` to the current CPU's backend and returns the
+    /// stream of its completions.
+    pub fn submit(op: Op) -> Self {
+        debug_assert!(op.is_multishot(), "IoStream needs a multishot operation");
+        let state = io_state();
+        let token = state.io_backend.submit(op);
+        // Completions are only queued for tokens with a registered stream
+        state.streams.lock().unwrap().insert(token, VecDeque::new());
+        Self { token }
+    }
+
+    /// The token of the underlying operation.
+    pub fn token(&self) -> IoToken {
+        self.token
+    }
+}
+
+impl Stream for IoStream {
+    type Item = Result<CompletionKind, IoError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let state = io_state();
+
+        let next = state
+            .streams
+            .lock()
+            .unwrap()
+            .get_mut(&self.token)
+            .and_then(VecDeque::pop_front);
+        if let Some(result) = next {
+            return Poll::Ready(Some(result));
+        }
+
+        state
+            .io_wakers
+            .lock()
+            .unwrap()
+            .insert(self.token, cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Drop for IoStream {
+    fn drop(&mut self) {
+        let state = io_state();
+        state.io_wakers.lock().unwrap().remove(&self.token);
+        let queued = state.streams.lock().unwrap().remove(&self.token);
+        for result in queued.into_iter().flatten() {
+            discard_completion(result);
+        }
+        state.io_backend.cancel(self.token);
     }
 }
@@ -7,13 +7,20 @@
 //! intended for single-threaded use. It is functionally identical to the epoll backend,
 //! as `mio` provides a common abstraction over both.
 
-use crate::io::{CompletionKind, IoError, IoProvider, IoToken, Op};
+use crate::buffer::Buffer;
+use crate::io::{
+    connect_syscall, fs_syscall, push_datagrams, raw_to_socket_addr, socket_addr_to_raw,
+    transfer_syscall, vectored_syscall, CompletionKind, Datagram, IoError, IoProvider, IoToken, Op,
+    UdpMessage,
+};
+use crate::io::{notifier::poll_readable, Notifier};
 use mio::{unix::SourceFd, Events, Interest, Poll, Token};
 use std::cell::UnsafeCell;
 use std::collections::HashMap;
 use std::io::{self, Read, Seek, SeekFrom, Write};
 use std::net::UdpSocket as MioUdpSocket;
 use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::sync::Arc;
 use std::task::{Context, Poll as TaskPoll};
 use std::time::Duration;
 
@@ -24,8 +31,13 @@ pub struct KqueueBackend {
     pending_ops: UnsafeCell<HashMap<Token, (IoToken, Op)>>,
     udp_recv_buffers: UnsafeCell<HashMap<Token, crate::buffer::Buffer>>,
     next_token: UnsafeCell<usize>,
+    /// Registered with the poll so remote wakeups interrupt `wait`
+    notifier: Arc<Notifier>,
 }
 
+/// `mio` token of the notifier; never handed out for operations.
+const WAKEUP_TOKEN: Token = Token(usize::MAX);
+
 // SAFETY: The `KqueueBackend` is designed to be thread-local.
 unsafe impl Send for KqueueBackend {}
 unsafe impl Sync for KqueueBackend {}
@@ -33,12 +45,21 @@ unsafe impl Sync for KqueueBackend {}
 impl KqueueBackend {
     /// Creates a new `KqueueBackend`.
     pub fn new() -> io::Result<Self> {
+        let poll = Poll::new()?;
+        let notifier = Arc::new(Notifier::new()?);
+        poll.registry().register(
+            &mut SourceFd(&notifier.as_raw_fd()),
+            WAKEUP_TOKEN,
+            Interest::READABLE,
+        )?;
+
         Ok(Self {
-            poll: UnsafeCell::new(Poll::new()?),
+            poll: UnsafeCell::new(poll),
             events: UnsafeCell::new(Events::with_capacity(1024)),
             pending_ops: UnsafeCell::new(HashMap::new()),
             udp_recv_buffers: UnsafeCell::new(HashMap::new()),
             next_token: UnsafeCell::new(0),
+            notifier,
         })
     }
 }
@@ -55,6 +76,18 @@ impl IoProvider for KqueueBackend {
         *next_token += 1;
 
         match &op {
+            // Stay registered until cancelled; every readiness event is drained
+            Op::AcceptMulti { fd } | Op::RecvMulti { fd } => {
+                let mut source = mio::unix::SourceFd(fd);
+                if let Err(e) = poll
+                    .registry()
+                    .register(&mut source, mio_token, Interest::READABLE)
+                {
+                    eprintln!("Failed to register fd with mio (kqueue): {}", e);
+                } else {
+                    pending_ops.insert(mio_token, (io_token, op));
+                }
+            }
             Op::Accept { fd } => {
                 let mut source = mio::unix::SourceFd(fd);
                 if let Err(e) = poll
@@ -77,6 +110,17 @@ impl IoProvider for KqueueBackend {
                     pending_ops.insert(mio_token, (io_token, op));
                 }
             }
+            Op::ReadInto { fd, .. } => {
+                let mut source = mio::unix::SourceFd(fd);
+                if let Err(e) = poll
+                    .registry()
+                    .register(&mut source, mio_token, Interest::READABLE)
+                {
+                    eprintln!("Failed to register fd with mio (kqueue): {}", e);
+                } else {
+                    pending_ops.insert(mio_token, (io_token, op));
+                }
+            }
             Op::Write { fd, .. } => {
                 let mut source = mio::unix::SourceFd(fd);
                 if let Err(e) = poll
@@ -88,7 +132,32 @@ impl IoProvider for KqueueBackend {
                     pending_ops.insert(mio_token, (io_token, op));
                 }
             }
+            Op::RecvMsg { fd, .. }
+            | Op::SendMsg { fd, .. }
+            | Op::Sendfile { fd_out: fd, .. }
+            | Op::SendZc { fd, .. } => {
+                let interest = if matches!(op, Op::RecvMsg { .. }) {
+                    Interest::READABLE
+                } else {
+                    Interest::WRITABLE
+                };
+                let mut source = mio::unix::SourceFd(fd);
+                if let Err(e) = poll.registry().register(&mut source, mio_token, interest) {
+                    eprintln!("Failed to register fd with mio (kqueue): {}", e);
+                } else {
+                    pending_ops.insert(mio_token, (io_token, op));
+                }
+            }
             // File operations are handled synchronously
+            Op::Readv { .. } | Op::Writev { .. } | Op::Splice { .. } => {
+                pending_ops.insert(mio_token, (io_token, op)); // Performed in poll_complete
+            }
+            fs_op if fs_op.is_fs() => {
+                pending_ops.insert(mio_token, (io_token, op)); // Performed in poll_complete
+            }
+            Op::Connect { .. } => {
+                pending_ops.insert(mio_token, (io_token, op)); // Performed in poll_complete
+            }
             Op::ReadFile { fd, offset, len } => {
                 // ReadFile remains synchronous but uses Buffer
                 let result = {
@@ -128,7 +197,7 @@ impl IoProvider for KqueueBackend {
                 };
                 pending_ops.insert(mio_token, (io_token, op)); // Still track for poll_complete pickup
             }
-            Op::UdpRecv { fd, buffer } => {
+            Op::UdpRecv { fd, buffer, .. } => {
                 // Register the UDP socket for read events
                 let mut mio_socket = unsafe { MioUdpSocket::from_raw_fd(fd) };
                 if let Err(e) =
@@ -157,6 +226,26 @@ impl IoProvider for KqueueBackend {
                 // Store the data buffer and address with the op. The op contains the Buffer.
                 pending_ops.insert(mio_token, (io_token, op));
             }
+            Op::UdpRecvBatch { fd, .. } => {
+                let mut source = SourceFd(&fd);
+                if let Err(e) =
+                    poll.registry()
+                        .register(&mut source, mio_token, mio::Interest::READABLE)
+                {
+                    eprintln!("Failed to register UDP socket with kqueue: {}", e);
+                }
+                pending_ops.insert(mio_token, (io_token, op));
+            }
+            Op::UdpSendBatch { fd, .. } => {
+                let mut source = SourceFd(&fd);
+                if let Err(e) =
+                    poll.registry()
+                        .register(&mut source, mio_token, mio::Interest::WRITABLE)
+                {
+                    eprintln!("Failed to register UDP socket with kqueue for send: {}", e);
+                }
+                pending_ops.insert(mio_token, (io_token, op));
+            }
             Op::Fsync { fd } => {
                 // Fsync remains synchronous.
                 let result = {
@@ -201,10 +290,72 @@ impl IoProvider for KqueueBackend {
         // First, handle any events from mio
         for event in events.iter() {
             let mio_token = event.token();
-            if let Some((io_token, op)) = pending_ops.remove(&mio_token) {
+            // Wakeups only interrupt `wait`; the run loop clears the notifier
+            if mio_token == WAKEUP_TOKEN {
+                continue;
+            }
+            if let Some((io_token, op)) = pending_ops.get(&mio_token) {
+                if op.is_multishot() {
+                    if !drain_multishot(*io_token, op, &mut completions) {
+                        let mut source = mio::unix::SourceFd(&op.as_raw_fd());
+                        let _ = poll.registry().deregister(&mut source);
+                        pending_ops.remove(&mio_token);
+                    }
+                    continue;
+                }
+            }
+            if let Some((io_token, mut op)) = pending_ops.remove(&mio_token) {
                 let mut source = mio::unix::SourceFd(&op.as_raw_fd());
                 let _ = poll.registry().deregister(&mut source);
 
+                // Vectored socket IO fills or drains the op's own buffers
+                if matches!(
+                    op,
+                    Op::RecvMsg { .. }
+                        | Op::SendMsg { .. }
+                        | Op::Sendfile { .. }
+                        | Op::SendZc { .. }
+                ) {
+                    let attempt = if matches!(op, Op::Sendfile { .. } | Op::SendZc { .. }) {
+                        transfer_syscall(&op)
+                    } else {
+                        vectored_syscall(&mut op)
+                    };
+                    let result = match attempt {
+                        Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                            let interest = if matches!(op, Op::RecvMsg { .. }) {
+                                Interest::READABLE
+                            } else {
+                                Interest::WRITABLE
+                            };
+                            let mut source = mio::unix::SourceFd(&op.as_raw_fd());
+                            if let Err(reg_err) =
+                                poll.registry().register(&mut source, mio_token, interest)
+                            {
+                                eprintln!("Failed to re-register fd with kqueue: {}", reg_err);
+                            }
+                            pending_ops.insert(mio_token, (io_token, op));
+                            continue;
+                        }
+                        result => result.map_err(IoError::Io),
+                    };
+                    completions.push((io_token, op, result));
+                    continue;
+                }
+
+                // Reads into a caller-supplied buffer hand that same buffer back
+                if let Op::ReadInto { fd, buffer, .. } = &mut op {
+                    let mut buffer = std::mem::replace(buffer, Buffer::new_zeroed(0));
+                    let result = syscall_read_into(*fd, &mut buffer)
+                        .map(|bytes_read| CompletionKind::Read {
+                            bytes_read,
+                            data: buffer,
+                        })
+                        .map_err(IoError::Io);
+                    completions.push((io_token, op, result));
+                    continue;
+                }
+
                 let result = match &op {
                     Op::Accept { fd } => match syscall_accept(*fd) {
                         Ok((new_fd, addr)) => Ok(CompletionKind::Accept {
@@ -293,14 +444,11 @@ impl IoProvider for KqueueBackend {
                         data.recycle();
                         res.map_err(IoError::Io)
                     }
-                    Op::UdpRecv { fd, .. } => {
+                    Op::UdpRecv { fd, flags, .. } => {
                         // Matched by `event.token()`
                         let udp_recv_buffers = unsafe { &mut *self.udp_recv_buffers.get() };
                         if let Some(mut buffer) = udp_recv_buffers.remove(&mio_token) {
-                            use std::os::unix::io::FromRawFd;
-                            let socket = unsafe { std::net::UdpSocket::from_raw_fd(*fd) };
-                            let res = socket
-                                .recv_from(&mut buffer)
+                            let res = syscall_recvfrom(*fd, &mut buffer, *flags)
                                 .map(|(bytes_read, addr)| {
                                     unsafe {
                                         buffer.set_len(bytes_read);
@@ -336,7 +484,6 @@ impl IoProvider for KqueueBackend {
                                         Err(e)
                                     }
                                 });
-                            // Socket will be automatically dropped here
 
                             if res.is_err()
                                 && res.as_ref().unwrap_err().kind() == io::ErrorKind::WouldBlock
@@ -352,17 +499,44 @@ impl IoProvider for KqueueBackend {
                     }
                     Op::UdpSend { fd, data, addr } => {
                         // Matched by `event.token()`
-                        use std::os::unix::io::FromRawFd;
-                        let socket = unsafe { std::net::UdpSocket::from_raw_fd(*fd) };
-                        let res = socket
-                            .send_to(&data, *addr) // Use &data as data is Buffer
+                        syscall_sendto(*fd, &data, addr.as_ref())
                             .map(|bytes_written| CompletionKind::UdpSend {
                                 bytes_written,
                                 data: data,
-                            }); // Move data for recycling
-                        std::mem::forget(socket);
-                        res.map_err(IoError::Io)
+                            }) // Move data for recycling
+                            .map_err(IoError::Io)
                     }
+                    Op::UdpRecvBatch {
+                        fd,
+                        max_datagrams,
+                        buf_size,
+                    } => match syscall_recv_batch(*fd, *max_datagrams, *buf_size) {
+                        Ok(datagrams) => Ok(CompletionKind::UdpRecvBatch { datagrams }),
+                        Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                            let mut source = SourceFd(fd);
+                            if let Err(reg_err) = poll.registry().register(
+                                &mut source,
+                                mio_token,
+                                mio::Interest::READABLE,
+                            ) {
+                                eprintln!(
+                                    "Failed to re-register UDP socket with kqueue: {}",
+                                    reg_err
+                                );
+                            }
+                            pending_ops.insert(mio_token, (io_token, op.clone()));
+                            continue; // Don't add to completions if WouldBlock
+                        }
+                        Err(e) => Err(IoError::Io(e)),
+                    },
+                    Op::UdpSendBatch { fd, messages } => syscall_send_batch(*fd, messages)
+                        .map(
+                            |(messages_sent, bytes_written)| CompletionKind::UdpSendBatch {
+                                messages_sent,
+                                bytes_written,
+                            },
+                        )
+                        .map_err(IoError::Io),
                     _ => continue,
                 };
                 completions.push((io_token, op, result));
@@ -372,8 +546,25 @@ impl IoProvider for KqueueBackend {
         // Handle any pending synchronous operations (ReadFile, WriteFile, Fsync, Close)
         let mut sync_completions_to_add = Vec::new();
         let pending_ops_snapshot: Vec<_> = pending_ops.drain().collect(); // Drain and re-insert if not completed
-        for (mio_token, (io_token, op)) in pending_ops_snapshot {
+        for (mio_token, (io_token, mut op)) in pending_ops_snapshot {
             match op {
+                Op::Readv { .. } | Op::Writev { .. } => {
+                    let result = vectored_syscall(&mut op).map_err(IoError::Io);
+                    sync_completions_to_add.push((io_token, op, result));
+                }
+                ref fs_op if fs_op.is_fs() => {
+                    let result = fs_syscall(&op).map_err(IoError::Io);
+                    sync_completions_to_add.push((io_token, op, result));
+                }
+                Op::Connect { .. } => {
+                    let result = connect_syscall(&op).map_err(IoError::Io);
+                    sync_completions_to_add.push((io_token, op, result));
+                }
+                // Fails: splice(2) is Linux only
+                Op::Splice { .. } => {
+                    let result = transfer_syscall(&op).map_err(IoError::Io);
+                    sync_completions_to_add.push((io_token, op, result));
+                }
                 Op::ReadFile { fd, offset, len } => {
                     let result = {
                         use std::os::unix::io::FromRawFd;
@@ -444,6 +635,30 @@ impl IoProvider for KqueueBackend {
             TaskPoll::Ready(completions)
         }
     }
+
+    fn wait(&self, timeout: Option<Duration>) {
+        // The kqueue fd polls readable once any registered fd, including the
+        // notifier, has an event to report
+        let poll = unsafe { &*self.poll.get() };
+        poll_readable(poll.as_raw_fd(), timeout);
+    }
+
+    fn cancel(&self, token: IoToken) {
+        let poll = unsafe { &mut *self.poll.get() };
+        let pending_ops = unsafe { &mut *self.pending_ops.get() };
+        let mio_token = pending_ops
+            .iter()
+            .find(|(_, (io_token, _))| *io_token == token)
+            .map(|(mio_token, _)| *mio_token);
+        if let Some((_, op)) = mio_token.and_then(|mio_token| pending_ops.remove(&mio_token)) {
+            let mut source = mio::unix::SourceFd(&op.as_raw_fd());
+            let _ = poll.registry().deregister(&mut source);
+        }
+    }
+
+    fn notifier(&self) -> Option<Arc<Notifier>> {
+        Some(self.notifier.clone())
+    }
 }
 
 fn syscall_accept(fd: RawFd) -> io::Result<(RawFd, std::net::SocketAddr)> {
@@ -486,18 +701,191 @@ fn syscall_accept(fd: RawFd) -> io::Result<(RawFd, std::net::SocketAddr)> {
     Ok((new_fd, addr))
 }
 
+/// `read(2)` into the spare capacity of `buffer` on a borrowed fd, setting its
+/// length to the number of bytes read.
+/// Runs a multishot operation until its socket would block, pushing a
+/// completion per connection or chunk. Returns whether it is still running.
+fn drain_multishot(
+    io_token: IoToken,
+    op: &Op,
+    completions: &mut Vec<(IoToken, Op, Result<CompletionKind, IoError>)>,
+) -> bool {
+    loop {
+        let result = match *op {
+            Op::AcceptMulti { fd } => match syscall_accept(fd) {
+                Ok((fd, addr)) => Ok(CompletionKind::Accept {
+                    fd,
+                    addr: Some(addr),
+                }),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return true,
+                Err(e) => Err(e),
+            },
+            Op::RecvMulti { fd } => {
+                let mut buffer = crate::buffer::BufferPool::get(crate::buffer::BUFFER_SIZE);
+                match syscall_read_into(fd, &mut buffer) {
+                    Ok(bytes_read) => Ok(CompletionKind::Read {
+                        bytes_read,
+                        data: buffer,
+                    }),
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => return true,
+                    Err(e) => Err(e),
+                }
+            }
+            _ => return false,
+        };
+        let ended = matches!(
+            result,
+            Err(_) | Ok(CompletionKind::Read { bytes_read: 0, .. })
+        );
+        completions.push((io_token, op.clone(), result.map_err(IoError::Io)));
+        if ended {
+            return false;
+        }
+    }
+}
+
+fn syscall_read_into(fd: RawFd, buffer: &mut Buffer) -> io::Result<usize> {
+    let n = unsafe {
+        libc::read(
+            fd,
+            buffer.as_mut_ptr() as *mut libc::c_void,
+            buffer.capacity(),
+        )
+    };
+    if n < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: the kernel initialised the first `n` bytes, and `n <= capacity`.
+    unsafe { buffer.set_len(n as usize) };
+    Ok(n as usize)
+}
+
+/// `recvfrom(2)` on a borrowed fd. Unlike going through `std::net::UdpSocket`,
+/// this never takes ownership of (and closes) the descriptor.
+fn syscall_recvfrom(
+    fd: RawFd,
+    buf: &mut [u8],
+    flags: i32,
+) -> io::Result<(usize, std::net::SocketAddr)> {
+    let mut storage: libc::sockaddr_storage = unsafe { std::mem::zeroed() };
+    let mut len = std::mem::size_of_val(&storage) as libc::socklen_t;
+    let n = unsafe {
+        libc::recvfrom(
+            fd,
+            buf.as_mut_ptr() as *mut libc::c_void,
+            buf.len(),
+            flags,
+            &mut storage as *mut _ as *mut libc::sockaddr,
+            &mut len,
+        )
+    };
+    if n < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok((n as usize, raw_to_socket_addr(&storage, len)?))
+}
+
+/// `sendto(2)` on a borrowed fd, or `send(2)` to the connected peer when `addr`
+/// is `None`.
+fn syscall_sendto(fd: RawFd, buf: &[u8], addr: Option<&std::net::SocketAddr>) -> io::Result<usize> {
+    let n = match addr {
+        Some(addr) => {
+            let (storage, len) = socket_addr_to_raw(addr);
+            unsafe {
+                libc::sendto(
+                    fd,
+                    buf.as_ptr() as *const libc::c_void,
+                    buf.len(),
+                    0,
+                    &storage as *const _ as *const libc::sockaddr,
+                    len,
+                )
+            }
+        }
+        None => unsafe { libc::send(fd, buf.as_ptr() as *const libc::c_void, buf.len(), 0) },
+    };
+    if n < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(n as usize)
+}
+
+/// Receives up to `max_datagrams` datagrams, one `recvfrom(2)` each; macOS has
+/// no `recvmmsg`.
+fn syscall_recv_batch(
+    fd: RawFd,
+    max_datagrams: usize,
+    buf_size: usize,
+) -> io::Result<Vec<Datagram>> {
+    let mut datagrams = Vec::new();
+    let mut buf = vec![0u8; buf_size];
+    while datagrams.len() < max_datagrams.max(1) {
+        match syscall_recvfrom(fd, &mut buf, 0) {
+            Ok((len, addr)) => push_datagrams(&mut datagrams, &buf[..len], addr, None),
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock && !datagrams.is_empty() => break,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(datagrams)
+}
+
+/// Sends `messages` in order, one `sendto(2)` each, returning how many messages
+/// and bytes went out.
+fn syscall_send_batch(fd: RawFd, messages: &[UdpMessage]) -> io::Result<(usize, usize)> {
+    let mut sent = 0;
+    let mut bytes_written = 0;
+    for message in messages {
+        if message.segment_size.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "UDP segmentation offload is only supported on Linux",
+            ));
+        }
+        match syscall_sendto(fd, &message.data, message.addr.as_ref()) {
+            Ok(n) => {
+                sent += 1;
+                bytes_written += n;
+            }
+            Err(_) if sent > 0 => break,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok((sent, bytes_written))
+}
+
 impl AsRawFd for Op {
     fn as_raw_fd(&self) -> RawFd {
         match *self {
             Op::Accept { fd } => fd,
+            Op::Connect { fd, .. } => fd,
+            Op::AcceptMulti { fd } => fd,
+            Op::RecvMulti { fd } => fd,
             Op::Read { fd, .. } => fd,
+            Op::ReadInto { fd, .. } => fd,
             Op::Write { fd, .. } => fd,
+            Op::Readv { fd, .. } => fd,
+            Op::Writev { fd, .. } => fd,
+            Op::RecvMsg { fd, .. } => fd,
+            Op::SendMsg { fd, .. } => fd,
+            Op::Splice { fd_out, .. } => fd_out,
+            Op::Sendfile { fd_out, .. } => fd_out,
+            Op::SendZc { fd, .. } => fd,
+            Op::SyncData { fd } => fd,
+            Op::Fallocate { fd, .. } => fd,
+            Op::Ftruncate { fd, .. } => fd,
+            Op::Statx { dir_fd, .. } => dir_fd,
+            // Path operations have no descriptor of their own
+            Op::OpenAt { .. } | Op::RenameAt { .. } | Op::UnlinkAt { .. } | Op::MkdirAt { .. } => {
+                libc::AT_FDCWD
+            }
             Op::Fsync { fd, .. } => fd,
             Op::Close { fd, .. } => fd,
             Op::ReadFile { fd, .. } => fd,
             Op::WriteFile { fd, .. } => fd,
             Op::UdpRecv { fd, .. } => fd, // Add UdpRecv fd
             Op::UdpSend { fd, .. } => fd, // Add UdpSend fd
+            Op::UdpRecvBatch { fd, .. } => fd,
+            Op::UdpSendBatch { fd, .. } => fd,
         }
     }
 }
@@ -18,9 +18,12 @@
 //! target platform and kernel version, and sets the appropriate `io_backend`
 //! configuration flag.
 
+use std::ffi::CString;
 use std::os::unix::io::RawFd;
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 use crate::buffer::Buffer; // Add Buffer import
                            // Remove BufferPool import as it's not directly used here and causes a warning.
@@ -51,6 +54,156 @@ pub trait IoProvider: Send + Sync + 'static {
     /// This method is non-blocking. If no completions are ready, it may
     /// register the waker to be notified when completions are available.
     fn poll_complete(&self, cx: &mut Context<'_>) -> Poll<Vec<Self::Completion>>;
+
+    /// Submits several operations at once, returning their tokens in order.
+    ///
+    /// Backends with a submission queue push them all and hand them to the
+    /// kernel together.
+    fn submit_batch(&self, ops: Vec<Op>) -> Vec<IoToken> {
+        ops.into_iter().map(|op| self.submit(op)).collect()
+    }
+
+    /// Submits `ops` as a chain: each operation starts only once the previous
+    /// one has completed successfully, and if one fails the rest complete with
+    /// `ECANCELED` (e.g. a write followed by an fsync). A chain is never
+    /// split: if it does not fit in the submission queue, every operation in
+    /// it completes with `EBUSY`.
+    ///
+    /// Backends without linking submit the operations independently.
+    fn submit_linked(&self, ops: Vec<Op>) -> Vec<IoToken> {
+        self.submit_batch(ops)
+    }
+
+    /// Hands operations queued by `submit` to the kernel.
+    ///
+    /// Backends that defer submission flush at the latest in their next
+    /// `poll_complete` or `wait`, so run loops get one submission per iteration.
+    fn flush(&self) {}
+
+    /// Moves completed operations into `out` and returns how many were added.
+    ///
+    /// Lets run loops reuse one buffer instead of receiving a new `Vec` from
+    /// every `poll_complete`.
+    fn drain_completions(&self, out: &mut Vec<Self::Completion>) -> usize {
+        let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+        match self.poll_complete(&mut cx) {
+            Poll::Ready(completions) => {
+                let count = completions.len();
+                out.extend(completions);
+                count
+            }
+            Poll::Pending => 0,
+        }
+    }
+
+    /// Takes an empty buffer of at least `capacity` bytes from memory the
+    /// backend has registered with the kernel, if it has one free.
+    ///
+    /// Operations submitted with such a buffer can skip per-operation page
+    /// mapping. Backends without registered memory return `None`.
+    fn fixed_buffer(&self, _capacity: usize) -> Option<Buffer> {
+        None
+    }
+
+    /// Blocks until a completion is ready for `poll_complete`, `timeout`
+    /// elapses (`None` waits indefinitely), or the backend's [`Notifier`] is
+    /// signalled.
+    ///
+    /// Backends without a notifier cannot be woken early, so they sleep for at
+    /// most a millisecond.
+    fn wait(&self, timeout: Option<Duration>) {
+        match self.notifier() {
+            Some(notifier) => notifier.wait(timeout),
+            None => {
+                let max = Duration::from_millis(1);
+                std::thread::sleep(timeout.map_or(max, |timeout| timeout.min(max)));
+            }
+        }
+    }
+
+    /// The notifier that interrupts [`wait`](Self::wait) from other threads,
+    /// if the backend has one.
+    fn notifier(&self) -> Option<Arc<Notifier>> {
+        None
+    }
+
+    /// The setup the backend's io_uring instance ended up with, for backends
+    /// that have one.
+    fn ring_setup(&self) -> Option<RingSetup> {
+        None
+    }
+
+    /// Stops the operation behind `token`. Used to end multishot operations,
+    /// which otherwise keep completing until they fail.
+    ///
+    /// Completions already produced may still be delivered.
+    fn cancel(&self, _token: IoToken) {}
+}
+
+/// Setup options for io_uring instances. Other backends ignore them.
+///
+/// Flags the running kernel rejects are dropped; [`RingSetup`] reports the
+/// ones that were applied.
+#[derive(Debug, Clone)]
+pub struct RingOptions {
+    /// Number of submission queue entries.
+    pub entries: u32,
+    /// Number of completion queue entries (`IORING_SETUP_CQSIZE`). The kernel
+    /// defaults to twice `entries`.
+    pub cq_entries: Option<u32>,
+    /// Poll the submission queue from a kernel thread (`IORING_SETUP_SQPOLL`),
+    /// so submitting needs no system call while the thread is awake.
+    pub sqpoll: Option<SqPollOptions>,
+    /// Promise that only the thread that created the ring submits to it
+    /// (`IORING_SETUP_SINGLE_ISSUER`). Linux 6.0+.
+    pub single_issuer: bool,
+    /// Run completion work when the thread next enters the kernel instead of
+    /// interrupting it (`IORING_SETUP_COOP_TASKRUN`). Linux 5.19+.
+    pub coop_taskrun: bool,
+    /// Defer completion work until the thread asks for completions
+    /// (`IORING_SETUP_DEFER_TASKRUN`). Requires `single_issuer` and cannot be
+    /// combined with `sqpoll`. Linux 6.1+.
+    pub defer_taskrun: bool,
+}
+
+impl Default for RingOptions {
+    fn default() -> Self {
+        Self {
+            entries: 1024,
+            cq_entries: None,
+            sqpoll: None,
+            single_issuer: false,
+            coop_taskrun: false,
+            defer_taskrun: false,
+        }
+    }
+}
+
+/// Submission queue polling options for [`RingOptions::sqpoll`].
+#[derive(Debug, Clone, Default)]
+pub struct SqPollOptions {
+    /// How long the polling thread spins without work before it sleeps.
+    pub idle: Duration,
+    /// CPU to pin the polling thread to. A `MultiCoreRuntime` pins the thread
+    /// of core `n` to `cpu + n`.
+    pub cpu: Option<u32>,
+}
+
+/// The setup of an io_uring instance after falling back from rejected flags.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RingSetup {
+    /// Number of submission queue entries.
+    pub sq_entries: u32,
+    /// Number of completion queue entries.
+    pub cq_entries: u32,
+    /// `IORING_SETUP_SQPOLL` is in effect.
+    pub sqpoll: bool,
+    /// `IORING_SETUP_SINGLE_ISSUER` is in effect.
+    pub single_issuer: bool,
+    /// `IORING_SETUP_COOP_TASKRUN` is in effect.
+    pub coop_taskrun: bool,
+    /// `IORING_SETUP_DEFER_TASKRUN` is in effect.
+    pub defer_taskrun: bool,
 }
 
 /// Represents a specific I/O operation to be performed.
@@ -59,22 +212,153 @@ pub enum Op {
     Accept {
         fd: i32,
     },
+    /// Connects the stream socket `fd` to `addr` (`connect(2)`). Completes
+    /// with `CompletionKind::Done` once the connection is established.
+    Connect {
+        fd: i32,
+        addr: SocketAddr,
+    },
+    /// Accepts connections until cancelled, completing once per connection
+    /// with `CompletionKind::Accept`.
+    AcceptMulti {
+        fd: i32,
+    },
     Read {
         fd: i32,
         offset: u64,
         len: usize,
     },
+    /// Reads into a caller-supplied buffer, filling it up to its capacity.
+    /// Completes with `CompletionKind::Read`.
+    ReadInto {
+        fd: i32,
+        offset: u64,
+        buffer: Buffer,
+    },
+    /// Receives from a stream socket until cancelled, completing once per
+    /// chunk with `CompletionKind::Read`. A zero-length read (end of file) or
+    /// an error is the last completion.
+    RecvMulti {
+        fd: i32,
+    },
     Write {
         fd: i32,
         offset: u64,
         data: Buffer, // Use Buffer for consistency
     },
+    /// Reads at `offset` into `buffers` in order, each up to its capacity
+    /// (`preadv(2)`). Completes with `CompletionKind::Readv`.
+    Readv {
+        fd: i32,
+        offset: u64,
+        buffers: Vec<Buffer>,
+    },
+    /// Writes `buffers` in order at `offset` (`pwritev(2)`). Completes with
+    /// `CompletionKind::Write`.
+    Writev {
+        fd: i32,
+        offset: u64,
+        buffers: Vec<Buffer>,
+    },
+    /// Receives from a socket into `buffers` in order, each up to its capacity
+    /// (`recvmsg(2)`). Completes with `CompletionKind::Readv`.
+    RecvMsg {
+        fd: i32,
+        buffers: Vec<Buffer>,
+    },
+    /// Sends `buffers` in order on a connected socket (`sendmsg(2)`).
+    /// Completes with `CompletionKind::Write`.
+    SendMsg {
+        fd: i32,
+        buffers: Vec<Buffer>,
+    },
+    /// Moves up to `len` bytes from `fd_in` to `fd_out` without copying them
+    /// through user space (`splice(2)`, Linux only); one of the two must be a
+    /// pipe. An offset of `None` uses the descriptor's own position, as pipes
+    /// and sockets require. Completes with `CompletionKind::Write`.
+    Splice {
+        fd_in: i32,
+        off_in: Option<u64>,
+        fd_out: i32,
+        off_out: Option<u64>,
+        len: usize,
+    },
+    /// Sends up to `len` bytes of the file `fd_in`, starting at `offset`, on
+    /// the socket `fd_out` (`sendfile(2)`). Completes with
+    /// `CompletionKind::Write`; 0 bytes are sent only at the end of the file.
+    Sendfile {
+        fd_out: i32,
+        fd_in: i32,
+        offset: u64,
+        len: usize,
+    },
+    /// Sends `data` on a connected socket, zero-copy (`IORING_OP_SEND_ZC`)
+    /// where supported: the backend holds the buffer until the kernel is done
+    /// with it. Completes with `CompletionKind::Write`.
+    SendZc {
+        fd: i32,
+        data: Buffer,
+    },
     Fsync {
         fd: i32,
     },
+    /// Flushes the file's data, but only the metadata needed to read it back
+    /// (`fdatasync(2)`). Completes with `CompletionKind::Fsync`.
+    SyncData {
+        fd: i32,
+    },
     Close {
         fd: i32,
     },
+    /// Opens `path`, relative to the working directory, with `open(2)` flags
+    /// and, for new files, permission bits `mode`. Completes with
+    /// `CompletionKind::Open`.
+    OpenAt {
+        path: CString,
+        flags: i32,
+        mode: u32,
+    },
+    /// Gets the metadata of the file `path` names relative to `dir_fd`
+    /// (`statx(2)`). `flags` takes `AT_SYMLINK_NOFOLLOW`, or `AT_EMPTY_PATH`
+    /// with an empty path for the file `dir_fd` itself. Completes with
+    /// `CompletionKind::Metadata`.
+    Statx {
+        dir_fd: i32,
+        path: CString,
+        flags: i32,
+    },
+    /// Allocates or manipulates the space of `len` bytes at `offset`
+    /// (`fallocate(2)`, Linux only). Completes with `CompletionKind::Done`.
+    Fallocate {
+        fd: i32,
+        mode: i32,
+        offset: u64,
+        len: u64,
+    },
+    /// Truncates or extends the file to `len` bytes (`ftruncate(2)`).
+    /// Completes with `CompletionKind::Done`.
+    Ftruncate {
+        fd: i32,
+        len: u64,
+    },
+    /// Renames `from` to `to`, replacing `to` if it exists (`rename(2)`).
+    /// Completes with `CompletionKind::Done`.
+    RenameAt {
+        from: CString,
+        to: CString,
+    },
+    /// Removes a file, or with `AT_REMOVEDIR` an empty directory
+    /// (`unlinkat(2)`). Completes with `CompletionKind::Done`.
+    UnlinkAt {
+        path: CString,
+        flags: i32,
+    },
+    /// Creates a directory with permission bits `mode` (`mkdir(2)`).
+    /// Completes with `CompletionKind::Done`.
+    MkdirAt {
+        path: CString,
+        mode: u32,
+    },
     ReadFile {
         fd: i32,
         offset: u64,
@@ -88,12 +372,70 @@ pub enum Op {
     UdpRecv {
         fd: RawFd,
         buffer: Buffer,
+        /// `recvmsg(2)` flags, e.g. `MSG_PEEK`.
+        flags: i32,
     },
     UdpSend {
         fd: RawFd,
         data: Buffer,
-        addr: SocketAddr,
+        /// Destination address, or `None` to send to the connected peer.
+        addr: Option<SocketAddr>,
     },
+    /// Receives up to `max_datagrams` datagrams of at most `buf_size` bytes each.
+    UdpRecvBatch {
+        fd: RawFd,
+        max_datagrams: usize,
+        buf_size: usize,
+    },
+    /// Sends every message in order, stopping at the first failure.
+    UdpSendBatch {
+        fd: RawFd,
+        messages: Vec<UdpMessage>,
+    },
+}
+
+impl Op {
+    /// Whether the operation completes more than once under its token.
+    pub fn is_multishot(&self) -> bool {
+        matches!(self, Op::AcceptMulti { .. } | Op::RecvMulti { .. })
+    }
+
+    /// Whether the operation is one [`fs_syscall`] runs.
+    #[cfg_attr(all(target_os = "linux", io_backend = "epoll"), allow(dead_code))]
+    pub(crate) fn is_fs(&self) -> bool {
+        matches!(
+            self,
+            Op::OpenAt { .. }
+                | Op::Statx { .. }
+                | Op::SyncData { .. }
+                | Op::Fallocate { .. }
+                | Op::Ftruncate { .. }
+                | Op::RenameAt { .. }
+                | Op::UnlinkAt { .. }
+                | Op::MkdirAt { .. }
+        )
+    }
+}
+
+/// A datagram returned by a batched receive.
+#[derive(Debug, Clone)]
+pub struct Datagram {
+    /// The payload.
+    pub data: Buffer,
+    /// The address the datagram came from.
+    pub addr: SocketAddr,
+}
+
+/// A single `sendmsg(2)` of a batched send.
+#[derive(Debug, Clone)]
+pub struct UdpMessage {
+    /// The payload.
+    pub data: Buffer,
+    /// Destination address, or `None` to send to the connected peer.
+    pub addr: Option<SocketAddr>,
+    /// With `Some`, the kernel splits `data` into datagrams of this size
+    /// (`UDP_SEGMENT`, Linux only).
+    pub segment_size: Option<u16>,
 }
 
 /// A unique identifier for a submitted I/O operation.
@@ -140,8 +482,21 @@ pub enum CompletionKind {
     Write {
         bytes_written: usize,
     },
+    /// The buffers of a `Readv` or `RecvMsg`, filled in order: each buffer's
+    /// length is set to the bytes it received.
+    Readv {
+        bytes_read: usize,
+        buffers: Vec<Buffer>,
+    },
     Fsync,
     Close,
+    /// The descriptor of a file opened by `OpenAt`.
+    Open {
+        fd: i32,
+    },
+    Metadata(crate::fs::Metadata),
+    /// An operation without a result succeeded.
+    Done,
     ReadFile {
         bytes_read: usize,
         data: Buffer,
@@ -158,6 +513,13 @@ pub enum CompletionKind {
         bytes_written: usize,
         data: Buffer,
     },
+    UdpRecvBatch {
+        datagrams: Vec<Datagram>,
+    },
+    UdpSendBatch {
+        messages_sent: usize,
+        bytes_written: usize,
+    },
 }
 
 /// Represents an error that can occur during an I/O operation.
@@ -189,6 +551,547 @@ impl From<IoError> for std::io::Error {
     }
 }
 
+/// Converts a `SocketAddr` into the raw `sockaddr_storage` and length expected by
+/// `sendmsg(2)` and friends.
+pub(crate) fn socket_addr_to_raw(addr: &SocketAddr) -> (libc::sockaddr_storage, libc::socklen_t) {
+    let sock_addr = socket2::SockAddr::from(*addr);
+    let len = sock_addr.len();
+    (sock_addr.as_storage(), len)
+}
+
+/// Converts a `sockaddr_storage` filled in by the kernel back into a `SocketAddr`.
+pub(crate) fn raw_to_socket_addr(
+    storage: &libc::sockaddr_storage,
+    len: libc::socklen_t,
+) -> std::io::Result<SocketAddr> {
+    // SAFETY: `storage` is a fully initialised `sockaddr_storage` and `len` does
+    // not exceed its size.
+    let sock_addr = unsafe { socket2::SockAddr::new(*storage, len) };
+    sock_addr.as_socket().ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "Unsupported address family",
+        )
+    })
+}
+
+/// The peer address of a connected socket, for accepts that do not report it.
+#[cfg_attr(not(io_backend = "io_uring"), allow(dead_code))]
+pub(crate) fn peer_addr(fd: RawFd) -> Option<SocketAddr> {
+    // SAFETY: zeroed storage is a valid `sockaddr_storage`.
+    let mut storage: libc::sockaddr_storage = unsafe { std::mem::zeroed() };
+    let mut len = std::mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+    // SAFETY: `storage` and `len` describe a writable buffer of `len` bytes.
+    let result = unsafe { libc::getpeername(fd, &mut storage as *mut _ as *mut _, &mut len) };
+    if result != 0 {
+        return None;
+    }
+    raw_to_socket_addr(&storage, len).ok()
+}
+
+/// `iovec`s covering the spare capacity of `buffers`, for vectored reads.
+pub(crate) fn read_iovecs(buffers: &mut [Buffer]) -> Vec<libc::iovec> {
+    buffers
+        .iter_mut()
+        .map(|buffer| libc::iovec {
+            iov_base: buffer.as_mut_ptr() as *mut libc::c_void,
+            iov_len: buffer.capacity(),
+        })
+        .collect()
+}
+
+/// `iovec`s covering the contents of `buffers`, for vectored writes.
+pub(crate) fn write_iovecs(buffers: &[Buffer]) -> Vec<libc::iovec> {
+    buffers
+        .iter()
+        .map(|buffer| libc::iovec {
+            iov_base: buffer.as_ptr() as *mut libc::c_void,
+            iov_len: buffer.len(),
+        })
+        .collect()
+}
+
+/// Sets the lengths of `buffers` after a vectored read of `bytes_read` bytes,
+/// which fills them in order.
+pub(crate) fn readv_completion(bytes_read: usize, mut buffers: Vec<Buffer>) -> CompletionKind {
+    let mut remaining = bytes_read;
+    for buffer in &mut buffers {
+        let filled = remaining.min(buffer.capacity());
+        // SAFETY: the kernel initialised the first `filled` bytes.
+        unsafe { buffer.set_len(filled) };
+        remaining -= filled;
+    }
+    CompletionKind::Readv {
+        bytes_read,
+        buffers,
+    }
+}
+
+/// Performs a vectored operation with a blocking or non-blocking system call,
+/// for backends that do not submit it to the kernel asynchronously.
+///
+/// Reads take the op's buffers and return them in the completion.
+#[cfg_attr(all(target_os = "linux", io_backend = "io_uring"), allow(dead_code))]
+pub(crate) fn vectored_syscall(op: &mut Op) -> std::io::Result<CompletionKind> {
+    let result = match op {
+        Op::Readv {
+            fd,
+            offset,
+            buffers,
+        } => {
+            let iovecs = read_iovecs(buffers);
+            // SAFETY: every iovec covers the spare capacity of a live buffer.
+            unsafe {
+                libc::preadv(
+                    *fd,
+                    iovecs.as_ptr(),
+                    iovecs.len() as libc::c_int,
+                    *offset as libc::off_t,
+                )
+            }
+        }
+        Op::Writev {
+            fd,
+            offset,
+            buffers,
+        } => {
+            let iovecs = write_iovecs(buffers);
+            // SAFETY: every iovec covers the contents of a live buffer.
+            unsafe {
+                libc::pwritev(
+                    *fd,
+                    iovecs.as_ptr(),
+                    iovecs.len() as libc::c_int,
+                    *offset as libc::off_t,
+                )
+            }
+        }
+        Op::RecvMsg { fd, buffers } => {
+            let mut iovecs = read_iovecs(buffers);
+            // SAFETY: zeroed is a valid empty `msghdr`.
+            let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+            msg.msg_iov = iovecs.as_mut_ptr();
+            msg.msg_iovlen = iovecs.len() as _;
+            // SAFETY: `msg` references the iovecs, which outlive the call.
+            unsafe { libc::recvmsg(*fd, &mut msg, 0) }
+        }
+        Op::SendMsg { fd, buffers } => {
+            let mut iovecs = write_iovecs(buffers);
+            // SAFETY: zeroed is a valid empty `msghdr`.
+            let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+            msg.msg_iov = iovecs.as_mut_ptr();
+            msg.msg_iovlen = iovecs.len() as _;
+            // SAFETY: `msg` references the iovecs, which outlive the call.
+            unsafe { libc::sendmsg(*fd, &msg, SEND_FLAGS) }
+        }
+        _ => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Not a vectored operation",
+            ))
+        }
+    };
+    if result < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let transferred = result as usize;
+    Ok(match op {
+        Op::Readv { buffers, .. } | Op::RecvMsg { buffers, .. } => {
+            readv_completion(transferred, std::mem::take(buffers))
+        }
+        _ => CompletionKind::Write {
+            bytes_written: transferred,
+        },
+    })
+}
+
+/// Runs a `ReadFile`, `WriteFile` or `Fsync`, or a `Read`, `ReadInto` or
+/// `Write` on a regular file, with a blocking, positional system call on the
+/// op's descriptor, which stays open and keeps its file offset. The
+/// readiness backends run it in their thread pool.
+///
+/// `ReadInto` takes the op's buffer and returns it in the completion.
+#[cfg_attr(all(target_os = "linux", io_backend = "io_uring"), allow(dead_code))]
+pub(crate) fn file_syscall(op: &mut Op) -> std::io::Result<CompletionKind> {
+    match op {
+        Op::ReadFile { fd, offset, len } | Op::Read { fd, offset, len } => {
+            let mut data = crate::buffer::BufferPool::get(*len);
+            let bytes_read = pread_into(*fd, &mut data, *len, *offset)?;
+            Ok(match op {
+                Op::ReadFile { .. } => CompletionKind::ReadFile { bytes_read, data },
+                _ => CompletionKind::Read { bytes_read, data },
+            })
+        }
+        Op::ReadInto { fd, offset, buffer } => {
+            let len = buffer.capacity();
+            let bytes_read = pread_into(*fd, buffer, len, *offset)?;
+            Ok(CompletionKind::Read {
+                bytes_read,
+                data: std::mem::replace(buffer, Buffer::new_zeroed(0)),
+            })
+        }
+        Op::WriteFile { fd, offset, data } | Op::Write { fd, offset, data } => {
+            // SAFETY: the pointer and length describe the live buffer.
+            let result = unsafe {
+                libc::pwrite(
+                    *fd,
+                    data.as_ptr() as *const libc::c_void,
+                    data.len(),
+                    *offset as libc::off_t,
+                )
+            };
+            if result < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            let bytes_written = result as usize;
+            Ok(match op {
+                Op::WriteFile { .. } => CompletionKind::WriteFile { bytes_written },
+                _ => CompletionKind::Write { bytes_written },
+            })
+        }
+        Op::Fsync { fd } => {
+            if unsafe { libc::fsync(*fd) } < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(CompletionKind::Fsync)
+        }
+        _ => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "Not a file operation",
+        )),
+    }
+}
+
+/// `pread(2)` of up to `len` bytes at `offset` into `buffer`, whose capacity
+/// is at least `len`, setting its length to the bytes read.
+#[cfg_attr(all(target_os = "linux", io_backend = "io_uring"), allow(dead_code))]
+fn pread_into(fd: RawFd, buffer: &mut Buffer, len: usize, offset: u64) -> std::io::Result<usize> {
+    // SAFETY: the kernel writes at most `len` bytes, which fit the buffer.
+    let result = unsafe {
+        libc::pread(
+            fd,
+            buffer.as_mut_ptr() as *mut libc::c_void,
+            len,
+            offset as libc::off_t,
+        )
+    };
+    if result < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    // SAFETY: the kernel initialised the first `result` bytes.
+    unsafe { buffer.set_len(result as usize) };
+    Ok(result as usize)
+}
+
+/// Runs a `Splice`, `Sendfile` or `SendZc` synchronously. Sockets are expected
+/// to be non-blocking, so the operation fails with `WouldBlock` rather than
+/// waiting; `Splice` doesn't block on its pipe either.
+///
+/// Without `sendfile(2)` with Linux semantics, `Sendfile` reads a chunk of the
+/// file and sends what the socket takes of it.
+#[cfg_attr(all(target_os = "linux", io_backend = "io_uring"), allow(dead_code))]
+pub(crate) fn transfer_syscall(op: &Op) -> std::io::Result<CompletionKind> {
+    let result = match *op {
+        #[cfg(target_os = "linux")]
+        Op::Splice {
+            fd_in,
+            off_in,
+            fd_out,
+            off_out,
+            len,
+        } => {
+            let mut off_in = off_in.map(|offset| offset as libc::loff_t);
+            let mut off_out = off_out.map(|offset| offset as libc::loff_t);
+            let offset_ptr = |offset: &mut Option<libc::loff_t>| {
+                offset
+                    .as_mut()
+                    .map_or(std::ptr::null_mut(), |offset| offset as *mut _)
+            };
+            // SAFETY: the offsets are either null or point to live locals.
+            unsafe {
+                libc::splice(
+                    fd_in,
+                    offset_ptr(&mut off_in),
+                    fd_out,
+                    offset_ptr(&mut off_out),
+                    len,
+                    libc::SPLICE_F_NONBLOCK | libc::SPLICE_F_MOVE,
+                )
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        Op::Splice { .. } => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "splice is only available on Linux",
+            ))
+        }
+        #[cfg(target_os = "linux")]
+        Op::Sendfile {
+            fd_out,
+            fd_in,
+            offset,
+            len,
+        } => {
+            let mut offset = offset as libc::off_t;
+            // SAFETY: `offset` is a live local.
+            unsafe { libc::sendfile(fd_out, fd_in, &mut offset, len) }
+        }
+        #[cfg(not(target_os = "linux"))]
+        Op::Sendfile {
+            fd_out,
+            fd_in,
+            offset,
+            len,
+        } => {
+            let mut chunk = vec![0u8; len.min(crate::buffer::BUFFER_SIZE)];
+            // SAFETY: `chunk` is valid for writes of its length.
+            let read = unsafe {
+                libc::pread(
+                    fd_in,
+                    chunk.as_mut_ptr() as *mut libc::c_void,
+                    chunk.len(),
+                    offset as libc::off_t,
+                )
+            };
+            if read <= 0 {
+                read
+            } else {
+                // SAFETY: `chunk` holds `read` initialized bytes.
+                unsafe {
+                    libc::send(
+                        fd_out,
+                        chunk.as_ptr() as *const libc::c_void,
+                        read as usize,
+                        SEND_FLAGS,
+                    )
+                }
+            }
+        }
+        Op::SendZc { fd, ref data } => {
+            // SAFETY: `data` is valid for reads of its length.
+            unsafe {
+                libc::send(
+                    fd,
+                    data.as_ptr() as *const libc::c_void,
+                    data.len(),
+                    SEND_FLAGS,
+                )
+            }
+        }
+        _ => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Not a transfer operation",
+            ))
+        }
+    };
+    if result < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(CompletionKind::Write {
+        bytes_written: result as usize,
+    })
+}
+
+/// Runs a filesystem operation (`OpenAt`, `Statx`, `SyncData`, `Fallocate`,
+/// `Ftruncate`, `RenameAt`, `UnlinkAt` or `MkdirAt`) synchronously. The
+/// readiness backends run it in their thread pool; io_uring only on kernels
+/// without the operation's opcode.
+pub(crate) fn fs_syscall(op: &Op) -> std::io::Result<CompletionKind> {
+    // SAFETY (all calls below): every path is a NUL-terminated string owned by
+    // `op`, and every out-parameter is a live local.
+    let result = match op {
+        Op::OpenAt { path, flags, mode } => {
+            let fd = unsafe {
+                libc::openat(
+                    libc::AT_FDCWD,
+                    path.as_ptr(),
+                    flags | libc::O_CLOEXEC,
+                    *mode as libc::c_uint,
+                )
+            };
+            if fd < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            return Ok(CompletionKind::Open { fd });
+        }
+        #[cfg(target_os = "linux")]
+        Op::Statx {
+            dir_fd,
+            path,
+            flags,
+        } => {
+            let mut statx: libc::statx = unsafe { std::mem::zeroed() };
+            let result = unsafe {
+                libc::statx(
+                    *dir_fd,
+                    path.as_ptr(),
+                    *flags,
+                    crate::fs::STATX_MASK,
+                    &mut statx,
+                )
+            };
+            if result < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            return Ok(CompletionKind::Metadata(crate::fs::Metadata::from_statx(
+                &statx,
+            )));
+        }
+        #[cfg(not(target_os = "linux"))]
+        Op::Statx {
+            dir_fd,
+            path,
+            flags,
+        } => {
+            let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+            let result = if path.as_bytes().is_empty() {
+                unsafe { libc::fstat(*dir_fd, &mut stat) }
+            } else {
+                unsafe { libc::fstatat(*dir_fd, path.as_ptr(), &mut stat, *flags) }
+            };
+            if result < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            return Ok(CompletionKind::Metadata(crate::fs::Metadata::from_stat(
+                &stat,
+            )));
+        }
+        #[cfg(target_os = "linux")]
+        Op::SyncData { fd } => unsafe { libc::fdatasync(*fd) },
+        #[cfg(not(target_os = "linux"))]
+        Op::SyncData { fd } => unsafe { libc::fsync(*fd) },
+        #[cfg(target_os = "linux")]
+        Op::Fallocate {
+            fd,
+            mode,
+            offset,
+            len,
+        } => unsafe { libc::fallocate(*fd, *mode, *offset as libc::off_t, *len as libc::off_t) },
+        #[cfg(not(target_os = "linux"))]
+        Op::Fallocate { .. } => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "fallocate is only available on Linux",
+            ))
+        }
+        Op::Ftruncate { fd, len } => unsafe { libc::ftruncate(*fd, *len as libc::off_t) },
+        Op::RenameAt { from, to } => unsafe { libc::rename(from.as_ptr(), to.as_ptr()) },
+        Op::UnlinkAt { path, flags } => unsafe {
+            libc::unlinkat(libc::AT_FDCWD, path.as_ptr(), *flags)
+        },
+        Op::MkdirAt { path, mode } => unsafe { libc::mkdir(path.as_ptr(), *mode as libc::mode_t) },
+        _ => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Not a filesystem operation",
+            ))
+        }
+    };
+    if result < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(match op {
+        Op::SyncData { .. } => CompletionKind::Fsync,
+        _ => CompletionKind::Done,
+    })
+}
+
+/// Runs a `Connect` synchronously, blocking until the connection is
+/// established or fails. The readiness backends run it in their thread pool.
+#[cfg_attr(all(target_os = "linux", io_backend = "io_uring"), allow(dead_code))]
+pub(crate) fn connect_syscall(op: &Op) -> std::io::Result<CompletionKind> {
+    let Op::Connect { fd, addr } = op else {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "Not a connect operation",
+        ));
+    };
+    let (storage, len) = socket_addr_to_raw(addr);
+    // SAFETY: `storage` holds a socket address of `len` bytes.
+    let result = unsafe { libc::connect(*fd, &storage as *const _ as *const libc::sockaddr, len) };
+    if result < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(CompletionKind::Done)
+}
+
+/// Flags for sends on stream sockets: a closed peer is reported as `EPIPE`
+/// rather than by `SIGPIPE`.
+#[cfg(target_os = "linux")]
+pub(crate) const SEND_FLAGS: libc::c_int = libc::MSG_NOSIGNAL;
+#[cfg(not(target_os = "linux"))]
+pub(crate) const SEND_FLAGS: libc::c_int = 0;
+
+/// Control buffer large enough for the `UDP_SEGMENT` and `UDP_GRO` control
+/// messages. Stored as `u64`s so it is suitably aligned for `cmsghdr`.
+#[cfg(target_os = "linux")]
+pub(crate) type UdpControlBuffer = [u64; 8];
+
+/// Attaches a `UDP_SEGMENT` control message to `msg`, using `control` as the
+/// backing storage. `control` must outlive every use of `msg`.
+#[cfg(target_os = "linux")]
+pub(crate) fn set_udp_segment_cmsg(
+    msg: &mut libc::msghdr,
+    control: &mut UdpControlBuffer,
+    segment_size: u16,
+) {
+    // SAFETY: `control` is large and aligned enough for one `u16` control message.
+    unsafe {
+        msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+        msg.msg_controllen = libc::CMSG_SPACE(std::mem::size_of::<u16>() as u32) as _;
+        let cmsg = libc::CMSG_FIRSTHDR(msg);
+        (*cmsg).cmsg_level = libc::SOL_UDP;
+        (*cmsg).cmsg_type = libc::UDP_SEGMENT;
+        (*cmsg).cmsg_len = libc::CMSG_LEN(std::mem::size_of::<u16>() as u32) as _;
+        std::ptr::write_unaligned(libc::CMSG_DATA(cmsg) as *mut u16, segment_size);
+    }
+}
+
+/// Returns the segment size from a `UDP_GRO` control message, if `msg`
+/// carries one.
+#[cfg(target_os = "linux")]
+pub(crate) fn udp_gro_segment_size(msg: &libc::msghdr) -> Option<usize> {
+    if msg.msg_control.is_null() || msg.msg_controllen == 0 {
+        return None;
+    }
+    // SAFETY: the kernel filled in `msg_control`/`msg_controllen`.
+    unsafe {
+        let mut cmsg = libc::CMSG_FIRSTHDR(msg);
+        while !cmsg.is_null() {
+            if (*cmsg).cmsg_level == libc::SOL_UDP && (*cmsg).cmsg_type == libc::UDP_GRO {
+                let size = std::ptr::read_unaligned(libc::CMSG_DATA(cmsg) as *const libc::c_int);
+                return usize::try_from(size).ok().filter(|&size| size > 0);
+            }
+            cmsg = libc::CMSG_NXTHDR(msg, cmsg);
+        }
+    }
+    None
+}
+
+/// Appends `payload` to `out`, split into `segment_size` datagrams when it is a
+/// coalesced `UDP_GRO` receive.
+pub(crate) fn push_datagrams(
+    out: &mut Vec<Datagram>,
+    payload: &[u8],
+    addr: SocketAddr,
+    segment_size: Option<usize>,
+) {
+    match segment_size {
+        Some(size) if size < payload.len() => {
+            out.extend(payload.chunks(size).map(|chunk| Datagram {
+                data: Buffer::from_slice(chunk),
+                addr,
+            }))
+        }
+        _ => out.push(Datagram {
+            data: Buffer::from_slice(payload),
+            addr,
+        }),
+    }
+}
+
 // Conditional compilation for different I/O backend implementations.
 // These modules will contain the concrete implementations of the `IoBackend` trait.
 
@@ -205,18 +1108,31 @@ pub mod epoll;
 pub mod uring;
 
 pub mod future;
+pub mod notifier;
+
+pub use notifier::Notifier;
 
 // --- Dummy Backend for testing and fallback ---
 
 /// A minimal I/O backend for testing.
 /// This backend completes operations immediately with dummy results.
 /// It's used for testing the runtime without actual I/O operations.
-#[derive(Debug, Default)]
-pub struct DummyIoBackend;
+#[derive(Debug)]
+pub struct DummyIoBackend {
+    notifier: Option<Arc<Notifier>>,
+}
 
 impl DummyIoBackend {
     pub fn new() -> Self {
-        Self
+        Self {
+            notifier: Notifier::new().ok().map(Arc::new),
+        }
+    }
+}
+
+impl Default for DummyIoBackend {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -234,4 +1150,8 @@ impl IoProvider for DummyIoBackend {
         // This matches the test expectation that no actual operations are processed
         Poll::Ready(Vec::new())
     }
+
+    fn notifier(&self) -> Option<Arc<Notifier>> {
+        self.notifier.clone()
+    }
 }
@@ -0,0 +1,185 @@
+//! Cross-thread wakeups for a thread blocked in [`IoProvider::wait`].
+//!
+//! Every backend owns a [`Notifier`] whose file descriptor it watches alongside
+//! its I/O. Wakers and remote message senders call [`Notifier::notify`] so an
+//! idle core blocked in the kernel returns as soon as there is work for it.
+//!
+//! [`IoProvider::wait`]: crate::io::IoProvider::wait
+
+use std::io;
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// A wakeup signal backed by an `eventfd` (a pipe on non-Linux systems).
+///
+/// Signals coalesce: once notified, further calls are free until the owning
+/// thread calls [`clear`](Self::clear).
+#[derive(Debug)]
+pub struct Notifier {
+    /// Becomes readable when notified.
+    fd: OwnedFd,
+    /// Write end of the pipe; the eventfd is written through `fd` itself.
+    #[cfg(not(target_os = "linux"))]
+    write_fd: OwnedFd,
+    /// Set while a signal is outstanding, so repeated notifies skip the syscall.
+    pending: AtomicBool,
+}
+
+impl Notifier {
+    /// Creates a new, unsignalled notifier.
+    #[cfg(target_os = "linux")]
+    pub fn new() -> io::Result<Self> {
+        let fd = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC | libc::EFD_NONBLOCK) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self {
+            fd: unsafe { OwnedFd::from_raw_fd(fd) },
+            pending: AtomicBool::new(false),
+        })
+    }
+
+    /// Creates a new, unsignalled notifier.
+    #[cfg(not(target_os = "linux"))]
+    pub fn new() -> io::Result<Self> {
+        let mut fds = [0; 2];
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let (read_fd, write_fd) =
+            unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) };
+        for fd in fds {
+            unsafe {
+                libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+                libc::fcntl(fd, libc::F_SETFL, libc::O_NONBLOCK);
+            }
+        }
+        Ok(Self {
+            fd: read_fd,
+            write_fd,
+            pending: AtomicBool::new(false),
+        })
+    }
+
+    /// Wakes the thread waiting on this notifier, or makes its next wait
+    /// return immediately. Safe to call from any thread.
+    pub fn notify(&self) {
+        if self.pending.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let value: u64 = 1;
+        #[cfg(target_os = "linux")]
+        let fd = self.fd.as_raw_fd();
+        #[cfg(not(target_os = "linux"))]
+        let fd = self.write_fd.as_raw_fd();
+        // A full pipe or saturated counter already means "signalled".
+        unsafe {
+            libc::write(fd, &value as *const u64 as *const libc::c_void, 8);
+        }
+    }
+
+    /// Consumes any outstanding signal.
+    ///
+    /// The owning thread calls this before it looks for work, so a notify that
+    /// races with the check still wakes the following wait.
+    pub fn clear(&self) {
+        let mut buf = [0u8; 64];
+        while unsafe {
+            libc::read(
+                self.fd.as_raw_fd(),
+                buf.as_mut_ptr() as *mut libc::c_void,
+                buf.len(),
+            )
+        } > 0
+        {}
+        self.pending.store(false, Ordering::Release);
+    }
+
+    /// Returns true if a signal is outstanding.
+    pub fn is_notified(&self) -> bool {
+        self.pending.load(Ordering::Acquire)
+    }
+
+    /// Blocks until notified or `timeout` elapses (`None` waits indefinitely).
+    ///
+    /// Used by backends that have no kernel completion queue to block on.
+    pub fn wait(&self, timeout: Option<Duration>) {
+        poll_readable(self.fd.as_raw_fd(), timeout);
+    }
+}
+
+/// Lets a notifier serve as a [`Waker`](std::task::Waker) for work that
+/// completes off the owning thread.
+impl futures::task::ArcWake for Notifier {
+    fn wake_by_ref(arc_self: &std::sync::Arc<Self>) {
+        arc_self.notify();
+    }
+}
+
+impl AsRawFd for Notifier {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+/// Blocks until `fd` is readable or `timeout` elapses. Interruptions count as
+/// a wakeup.
+pub(crate) fn poll_readable(fd: RawFd, timeout: Option<Duration>) {
+    let mut pollfd = libc::pollfd {
+        fd,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let timeout_ms = match timeout {
+        // Round up so a short timeout doesn't turn into a busy loop.
+        Some(timeout) => timeout
+            .as_nanos()
+            .div_ceil(1_000_000)
+            .min(libc::c_int::MAX as u128) as libc::c_int,
+        None => -1,
+    };
+    unsafe {
+        libc::poll(&mut pollfd, 1, timeout_ms);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    #[test]
+    fn test_notify_wakes_waiter() {
+        let notifier = Arc::new(Notifier::new().unwrap());
+        let remote = notifier.clone();
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            remote.notify();
+        });
+
+        let start = Instant::now();
+        notifier.wait(Some(Duration::from_secs(10)));
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(notifier.is_notified());
+        handle.join().unwrap();
+
+        // Once cleared, waits time out again
+        notifier.clear();
+        assert!(!notifier.is_notified());
+        let start = Instant::now();
+        notifier.wait(Some(Duration::from_millis(10)));
+        assert!(start.elapsed() >= Duration::from_millis(10));
+    }
+
+    #[test]
+    fn test_notify_before_wait_is_not_lost() {
+        let notifier = Notifier::new().unwrap();
+        notifier.notify();
+        notifier.notify();
+        let start = Instant::now();
+        notifier.wait(None);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -2,14 +2,28 @@
 
 //! A `io-uring` backend for the I/O subsystem.
 
-use crate::buffer::{Buffer, BufferPool}; // Explicitly import Buffer and BufferPool
-use crate::io::{CompletionKind, IoError, IoProvider, IoToken, Op};
-use io_uring::{opcode, types, IoUring}; // Import opcode, types and IoUring directly
+use crate::buffer::{Buffer, BufferPool, RegisteredRegion, BUFFER_SIZE}; // Explicitly import Buffer and BufferPool
+use crate::config::{FIXED_BUFFER_COUNT, PROVIDED_BUFFER_COUNT};
+use crate::fs::Metadata;
+use crate::io::{
+    fs_syscall, peer_addr, push_datagrams, raw_to_socket_addr, read_iovecs, readv_completion,
+    set_udp_segment_cmsg, socket_addr_to_raw, udp_gro_segment_size, write_iovecs, CompletionKind,
+    Datagram, IoError, IoProvider, IoToken, Notifier, Op, RingOptions, RingSetup, UdpControlBuffer,
+    SEND_FLAGS,
+};
+use io_uring::{cqueue, opcode, register::Probe, squeue, types, IoUring}; // Import opcode, types and IoUring directly
 use libc::{iovec, msghdr, sockaddr_storage, socklen_t};
+use std::alloc::{self, Layout};
 use std::cell::UnsafeCell;
 use std::collections::HashMap;
 use std::io;
+use std::os::fd::{FromRawFd, OwnedFd};
+use std::os::unix::io::AsRawFd;
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 /// A struct to hold a pending operation and any associated data, like buffers.
 enum PendingOp {
@@ -17,6 +31,12 @@ enum PendingOp {
         op: Op,
         buf: Buffer,
     },
+    /// A read whose buffer the kernel picks from the provided buffer ring.
+    ReadSelect {
+        fd: i32,
+        offset: u64,
+        len: usize,
+    },
     Write {
         op: Op,
         data: Buffer,
@@ -26,6 +46,58 @@ enum PendingOp {
         addr_storage: Box<sockaddr_storage>,
         addr_len: Box<socklen_t>,
     }, // Store addr_storage and length for Accept
+    /// The kernel reads `_addr` until the connect completes.
+    Connect {
+        op: Op,
+        _addr: Box<sockaddr_storage>,
+    },
+    /// Multishot accept. Re-armed under the same user_data when the kernel
+    /// ends it without an error, unless `cancelled`.
+    AcceptMulti {
+        fd: i32,
+        cancelled: bool,
+    },
+    /// A receive stream: a multishot `RECV` into the provided buffer ring, or
+    /// a single receive into `buf` when the ring has no buffer to spare.
+    /// Re-armed after every data-carrying completion that ends it.
+    RecvMulti {
+        fd: i32,
+        buf: Option<Buffer>,
+        cancelled: bool,
+    },
+    /// `Readv`, `Writev`, `RecvMsg` or `SendMsg`. The buffers stay in `op`;
+    /// the kernel uses `_iovecs` and `_msg` until completion.
+    Vectored {
+        op: Op,
+        _iovecs: Box<[iovec]>,
+        _msg: Option<Box<msghdr>>,
+    },
+    Splice {
+        op: Op,
+    },
+    /// A `Sendfile`, run as splices through a pipe.
+    Sendfile(SendfileState),
+    /// A zero-copy send. `result` holds the first CQE's result while the
+    /// kernel still references `data`; the notification CQE that follows
+    /// releases it.
+    SendZc {
+        fd: i32,
+        data: Buffer,
+        result: Option<i32>,
+    },
+    /// An operation finished while it was prepared, reported by a `NOP`.
+    Ready {
+        op: Op,
+        result: io::Result<CompletionKind>,
+    },
+    /// A filesystem operation; paths stay in `op` until completion.
+    Fs {
+        op: Op,
+    },
+    Statx {
+        op: Op,
+        statx: Box<libc::statx>,
+    },
     Fsync {
         op: Op,
     },
@@ -44,20 +116,413 @@ enum PendingOp {
         op: Op,
         buffer: Buffer,
         addr_storage: Box<sockaddr_storage>,
-        addr_len: Box<socklen_t>,
-    },
+        msg: Box<msghdr>,
+        _iov: Box<iovec>,
+    }, // The kernel uses msg/iov until completion, so they live here
     UdpSend {
         op: Op,
         data: Buffer,
-        addr_storage: Box<sockaddr_storage>,
-        addr_len: Box<socklen_t>,
+        _addr_storage: Option<Box<sockaddr_storage>>,
+        _msg: Box<msghdr>,
+        _iov: Box<iovec>,
+    },
+    UdpRecvBatch {
+        op: Op,
+        bgid: u16,
+        nbufs: u16,
+        entry_size: usize,
+        storage: Box<[u64]>,
+        msg: Box<msghdr>,
+        datagrams: Vec<Datagram>,
+        error: Option<io::Error>,
+        cancel_requested: bool,
+    }, // Multishot: stays pending until a CQE arrives without IORING_CQE_F_MORE
+    UdpSendBatch {
+        op: Op,
+        remaining: usize,
+        messages_sent: usize,
+        bytes_written: usize,
+        error: Option<io::Error>,
+        _slots: Box<[SendSlot]>,
+    }, // One linked SendMsg per message, all sharing the same user_data
+    /// Bookkeeping submitted by the backend itself (buffer provision and removal,
+    /// cancellation). Its completion is not reported; `_storage` is kept alive
+    /// until the kernel is done with it.
+    Internal {
+        _storage: Option<Box<[u64]>>,
     },
+    /// The poll on the notifier's eventfd that ends a blocking `wait`. Re-armed
+    /// every time it fires.
+    Wakeup,
+}
+
+/// Pipe size asked for by a `Sendfile`, bounding the bytes moved per splice.
+const SENDFILE_PIPE_SIZE: libc::c_int = 1 << 20;
+
+/// Progress of a `Sendfile`: splices alternate between moving the next chunk
+/// of the file into `pipe` and moving what the pipe holds to the socket, until
+/// `len` bytes are sent or the file ends.
+struct SendfileState {
+    fd_out: i32,
+    fd_in: i32,
+    offset: u64,
+    len: usize,
+    /// The read and write ends.
+    pipe: (OwnedFd, OwnedFd),
+    pipe_size: usize,
+    sent: usize,
+    /// Bytes spliced into the pipe but not yet to the socket.
+    buffered: usize,
+}
+
+impl SendfileState {
+    fn new(fd_out: i32, fd_in: i32, offset: u64, len: usize) -> io::Result<Self> {
+        let mut fds = [0; 2];
+        // SAFETY: `fds` has room for both ends.
+        if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: both ends were just created and are owned by nothing else.
+        let pipe = unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) };
+        // A larger pipe means fewer round trips; the default size still works.
+        // SAFETY: plain fcntl calls on a valid descriptor.
+        let pipe_size = unsafe {
+            libc::fcntl(fds[1], libc::F_SETPIPE_SZ, SENDFILE_PIPE_SIZE);
+            libc::fcntl(fds[1], libc::F_GETPIPE_SZ)
+        };
+        Ok(Self {
+            fd_out,
+            fd_in,
+            offset,
+            len,
+            pipe,
+            pipe_size: pipe_size.max(libc::PIPE_BUF as libc::c_int) as usize,
+            sent: 0,
+            buffered: 0,
+        })
+    }
+
+    fn op(&self) -> Op {
+        Op::Sendfile {
+            fd_out: self.fd_out,
+            fd_in: self.fd_in,
+            offset: self.offset,
+            len: self.len,
+        }
+    }
+
+    /// The next splice: draining the pipe if it holds data, filling it
+    /// otherwise.
+    fn next_entry(&self) -> squeue::Entry {
+        if self.buffered > 0 {
+            opcode::Splice::new(
+                types::Fd(self.pipe.0.as_raw_fd()),
+                -1,
+                types::Fd(self.fd_out),
+                -1,
+                self.buffered as u32,
+            )
+            .build()
+        } else {
+            let chunk = (self.len - self.sent).min(self.pipe_size);
+            opcode::Splice::new(
+                types::Fd(self.fd_in),
+                (self.offset + self.sent as u64) as i64,
+                types::Fd(self.pipe.1.as_raw_fd()),
+                -1,
+                chunk as u32,
+            )
+            .build()
+        }
+    }
+
+    /// Accounts for the result of the splice in flight, returning the
+    /// operation's result once it is finished.
+    fn advance(&mut self, result: i32) -> Option<io::Result<usize>> {
+        if self.buffered == 0 {
+            // Filling the pipe: an error after some progress only ends the transfer
+            match result {
+                0 => return Some(Ok(self.sent)),
+                _ if result < 0 && self.sent > 0 => return Some(Ok(self.sent)),
+                _ if result < 0 => return Some(Err(io::Error::from_raw_os_error(-result))),
+                _ => self.buffered = result as usize,
+            }
+        } else {
+            // Bytes left in the pipe would be lost, so failing to drain it is an error
+            match result {
+                0 => return Some(Err(io::ErrorKind::WriteZero.into())),
+                _ if result < 0 => return Some(Err(io::Error::from_raw_os_error(-result))),
+                _ => {
+                    self.sent += result as usize;
+                    self.buffered -= result as usize;
+                }
+            }
+        }
+        (self.buffered == 0 && self.sent == self.len).then_some(Ok(self.sent))
+    }
+}
+
+/// The opcode a filesystem operation is submitted with.
+fn fs_opcode(op: &Op) -> u8 {
+    match op {
+        Op::OpenAt { .. } => opcode::OpenAt::CODE,
+        Op::Statx { .. } => opcode::Statx::CODE,
+        Op::SyncData { .. } => opcode::Fsync::CODE,
+        Op::Fallocate { .. } => opcode::Fallocate::CODE,
+        Op::Ftruncate { .. } => opcode::Ftruncate::CODE,
+        Op::RenameAt { .. } => opcode::RenameAt::CODE,
+        Op::UnlinkAt { .. } => opcode::UnlinkAt::CODE,
+        Op::MkdirAt { .. } => opcode::MkDirAt::CODE,
+        _ => opcode::Nop::CODE,
+    }
+}
+
+/// Kernel-visible state for one message of a batched send.
+struct SendSlot {
+    msg: msghdr,
+    iov: iovec,
+    addr: sockaddr_storage,
+    control: UdpControlBuffer,
+}
+
+/// The submission queue entries of one operation: `leading` entries are
+/// linked to `entry`, whose completion completes the operation.
+struct Prepared {
+    leading: Vec<squeue::Entry>,
+    entry: squeue::Entry,
+    pending: PendingOp,
+}
+
+/// Size of the `io_uring_recvmsg_out` header the kernel writes at the start of
+/// every buffer used by a multishot `RECVMSG`.
+const RECVMSG_OUT_HEADER_LEN: usize = 4 * std::mem::size_of::<u32>();
+
+/// `IORING_ENTER_GETEVENTS`: makes `io_uring_enter` run deferred completion work.
+const IORING_ENTER_GETEVENTS: u32 = 1;
+
+/// Buffer group id of the backend's provided buffer ring. Groups for multishot
+/// receives are allocated from the remaining ids.
+const PROVIDED_RING_BGID: u16 = u16::MAX;
+
+/// A provided buffer ring (`IORING_REGISTER_PBUF_RING`): the kernel picks a
+/// slot of `region` when a read completes, so waiting reads hold no memory.
+///
+/// Slots come back to the region's free list when the returned buffers are
+/// dropped and are put back in the ring by [`ProvidedBufRing::replenish`].
+struct ProvidedBufRing {
+    entries: NonNull<types::BufRingEntry>,
+    layout: Layout,
+    mask: u16,
+    tail: UnsafeCell<u16>,
+    region: Arc<RegisteredRegion>,
+}
+
+impl ProvidedBufRing {
+    /// Allocates the ring and registers it with `ring` under `bgid`.
+    fn register(ring: &IoUring, bgid: u16, buf_size: usize, count: u16) -> io::Result<Self> {
+        assert!(count.is_power_of_two(), "ring size must be a power of two");
+        let layout = Layout::array::<types::BufRingEntry>(count as usize)
+            .and_then(|layout| layout.align_to(4096))
+            .map_err(io::Error::other)?;
+        // SAFETY: the layout has a non-zero size.
+        let entries = unsafe { alloc::alloc_zeroed(layout) } as *mut types::BufRingEntry;
+        let entries = NonNull::new(entries).unwrap_or_else(|| alloc::handle_alloc_error(layout));
+        let buf_ring = Self {
+            entries,
+            layout,
+            mask: count - 1,
+            tail: UnsafeCell::new(0),
+            // Every slot starts out in the ring, i.e. lent to the kernel.
+            region: RegisteredRegion::new(buf_size, count, false),
+        };
+        // SAFETY: the entries stay allocated until after the ring is dropped.
+        unsafe {
+            ring.submitter().register_buf_ring_with_flags(
+                entries.as_ptr() as u64,
+                count,
+                bgid,
+                0,
+            )?;
+        }
+        for index in 0..count {
+            buf_ring.push(index);
+        }
+        buf_ring.publish();
+        Ok(buf_ring)
+    }
+
+    fn buf_size(&self) -> usize {
+        self.region.buf_size()
+    }
+
+    /// Writes slot `index` into the next ring entry without publishing it.
+    fn push(&self, index: u16) {
+        // SAFETY: only this thread touches the tail and unpublished entries.
+        unsafe {
+            let tail = &mut *self.tail.get();
+            let entry = &mut *self.entries.as_ptr().add((*tail & self.mask) as usize);
+            entry.set_addr(self.region.slot_ptr(index) as u64);
+            entry.set_len(self.region.buf_size() as u32);
+            entry.set_bid(index);
+            *tail = tail.wrapping_add(1);
+        }
+    }
+
+    /// Makes pushed entries visible to the kernel.
+    fn publish(&self) {
+        // SAFETY: the tail field lives inside the entry array, and the kernel
+        // reads it with acquire semantics.
+        unsafe {
+            let tail = types::BufRingEntry::tail(self.entries.as_ptr()) as *const AtomicU16;
+            (*tail).store(*self.tail.get(), Ordering::Release);
+        }
+    }
+
+    /// Gives slots released by dropped buffers back to the kernel.
+    fn replenish(&self) {
+        let mut pushed = false;
+        while let Some(index) = self.region.pop_free() {
+            self.push(index);
+            pushed = true;
+        }
+        if pushed {
+            self.publish();
+        }
+    }
+
+    /// Wraps the slot the kernel picked for a completed read.
+    fn take(&self, index: u16, len: usize) -> Buffer {
+        // SAFETY: the kernel has finished with the slot and handed it to us.
+        unsafe { self.region.buffer(index, len) }
+    }
+}
+
+impl Drop for ProvidedBufRing {
+    fn drop(&mut self) {
+        // SAFETY: allocated in `register` with the same layout; the io_uring
+        // instance, and with it the registration, is dropped first.
+        unsafe { alloc::dealloc(self.entries.as_ptr() as *mut u8, self.layout) }
+    }
+}
+
+/// Pushes `entries` to the submission queue all at once, flushing it first if
+/// they do not fit; `false` if they still do not.
+fn push_entries(ring: &mut IoUring, entries: &[squeue::Entry]) -> bool {
+    let free = {
+        let sq = ring.submission();
+        sq.capacity() - sq.len()
+    };
+    if free < entries.len() && ring.submit().is_err() {
+        return false;
+    }
+    // SAFETY: callers keep every buffer referenced by `entries` alive in `pending_ops`.
+    unsafe { ring.submission().push_multiple(entries) }.is_ok()
+}
+
+/// Pushes `entry`, flushing the submission queue to the kernel first if it is full.
+fn push_entry(ring: &mut IoUring, entry: &squeue::Entry) -> io::Result<()> {
+    // SAFETY: callers keep every buffer referenced by `entry` alive in `pending_ops`.
+    if unsafe { ring.submission().push(entry) }.is_ok() {
+        return Ok(());
+    }
+    ring.submit()?;
+    unsafe { ring.submission().push(entry) }.map_err(io::Error::other)
+}
+
+/// Folds one CQE of a multishot `RECVMSG` into its pending batch.
+fn fold_recv_batch_cqe(pending_op: &mut PendingOp, result: i32, flags: u32) {
+    let PendingOp::UdpRecvBatch {
+        entry_size,
+        storage,
+        msg,
+        datagrams,
+        error,
+        ..
+    } = pending_op
+    else {
+        return;
+    };
+
+    if result < 0 {
+        error.get_or_insert_with(|| io::Error::from_raw_os_error(-result));
+        return;
+    }
+    let Some(bid) = cqueue::buffer_select(flags) else {
+        return;
+    };
+
+    // SAFETY: `storage` is a plain byte arena; viewing it as `u8` is always valid.
+    let arena = unsafe {
+        std::slice::from_raw_parts(
+            storage.as_ptr() as *const u8,
+            std::mem::size_of_val(&storage[..]),
+        )
+    };
+    let start = bid as usize * *entry_size;
+    let buf = &arena[start..start + (result as usize).min(*entry_size)];
+    let Ok(out) = types::RecvMsgOut::parse(buf, msg) else {
+        error.get_or_insert_with(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Malformed multishot recvmsg buffer",
+            )
+        });
+        return;
+    };
+
+    let name = out.name_data();
+    let mut addr_storage = unsafe { std::mem::zeroed::<sockaddr_storage>() };
+    // SAFETY: `name` is at most `msg_namelen` bytes, the size of `sockaddr_storage`.
+    unsafe {
+        std::ptr::copy_nonoverlapping(
+            name.as_ptr(),
+            &mut addr_storage as *mut _ as *mut u8,
+            name.len(),
+        );
+    }
+    let addr = match raw_to_socket_addr(&addr_storage, name.len() as socklen_t) {
+        Ok(addr) => addr,
+        Err(e) => {
+            error.get_or_insert(e);
+            return;
+        }
+    };
+
+    let control = out.control_data();
+    let mut control_msg = unsafe { std::mem::zeroed::<msghdr>() };
+    control_msg.msg_control = control.as_ptr() as *mut _;
+    control_msg.msg_controllen = control.len() as _;
+    push_datagrams(
+        datagrams,
+        out.payload_data(),
+        addr,
+        udp_gro_segment_size(&control_msg),
+    );
 }
 
 /// A `io-uring` based `IoProvider`.
+///
+/// Where the kernel supports it, the backend registers a region of fixed
+/// buffers (used for reads and writes through `READ_FIXED`/`WRITE_FIXED`
+/// whenever an operation's buffer comes from it, e.g. via `BufferPool::get`)
+/// and a provided buffer ring from which socket reads pick their buffer at
+/// completion time.
 pub struct UringBackend {
+    // Dropped first: the kernel may reference the memory owned by the fields below.
     ring: UnsafeCell<IoUring>,
     pending_ops: UnsafeCell<HashMap<u64, PendingOp>>,
+    /// Operations that did not fit in the submission queue, completed with
+    /// `EBUSY` by the next drain
+    rejected: UnsafeCell<Vec<u64>>,
+    next_bgid: UnsafeCell<u16>,
+    fixed: Option<Arc<RegisteredRegion>>,
+    buf_ring: Option<ProvidedBufRing>,
+    notifier: Arc<Notifier>,
+    setup: RingSetup,
+    /// The opcodes the kernel supports. `SendZc` is an ordinary send without
+    /// `IORING_OP_SEND_ZC`; filesystem operations without their opcode run
+    /// inline.
+    probe: Probe,
 }
 
 // SAFETY: This is safe in our thread-per-core model.
@@ -66,26 +531,277 @@ unsafe impl Sync for UringBackend {}
 
 impl UringBackend {
     pub fn new(entries: u32) -> io::Result<Self> {
-        let ring = IoUring::new(entries)?;
-        Ok(Self {
+        Self::with_options(&RingOptions {
+            entries,
+            ..Default::default()
+        })
+    }
+
+    /// Creates a backend whose ring is set up according to `options`, minus
+    /// the flags the running kernel rejects.
+    ///
+    /// With `single_issuer`, the backend must be created on the thread that
+    /// will use it.
+    pub fn with_options(options: &RingOptions) -> io::Result<Self> {
+        let (ring, setup) = Self::build_ring(options)?;
+
+        // Both registrations are optimisations: without them every operation
+        // falls back to ordinary buffers.
+        let fixed = RegisteredRegion::new(BUFFER_SIZE, FIXED_BUFFER_COUNT, true);
+        // SAFETY: the region outlives the ring, which is dropped first.
+        let fixed = unsafe { ring.submitter().register_buffers(&fixed.iovecs()) }
+            .ok()
+            .map(|()| fixed);
+        let buf_ring = ProvidedBufRing::register(
+            &ring,
+            PROVIDED_RING_BGID,
+            BUFFER_SIZE,
+            PROVIDED_BUFFER_COUNT,
+        )
+        .ok();
+        // Kernels too old to probe support none of the optional opcodes
+        let mut probe = Probe::new();
+        let _ = ring.submitter().register_probe(&mut probe);
+
+        let mut backend = Self {
             ring: UnsafeCell::new(ring),
             pending_ops: UnsafeCell::new(HashMap::new()),
-        })
+            rejected: UnsafeCell::new(Vec::new()),
+            next_bgid: UnsafeCell::new(0),
+            fixed,
+            buf_ring,
+            notifier: Arc::new(Notifier::new()?),
+            setup,
+            probe,
+        };
+        Self::arm_wakeup(
+            backend.ring.get_mut(),
+            backend.pending_ops.get_mut(),
+            &backend.notifier,
+        );
+        Ok(backend)
     }
-}
 
-impl IoProvider for UringBackend {
-    type Completion = (IoToken, Op, Result<CompletionKind, IoError>);
+    /// Builds a ring with every flag of `options` if the kernel accepts them
+    /// all, and otherwise with each flag it accepts on its own.
+    fn build_ring(options: &RingOptions) -> io::Result<(IoUring, RingSetup)> {
+        let requested = RingSetup {
+            sqpoll: options.sqpoll.is_some(),
+            single_issuer: options.single_issuer,
+            coop_taskrun: options.coop_taskrun,
+            defer_taskrun: options.defer_taskrun,
+            ..Default::default()
+        };
+        if let Ok(ring) = Self::try_build(options, &requested) {
+            return Ok(Self::with_queue_sizes(ring, requested));
+        }
 
-    fn submit(&self, op: Op) -> IoToken {
-        let token = IoToken::new();
-        let user_data = token.id();
+        let mut setup = RingSetup::default();
+        let mut ring = Self::try_build(options, &setup)?;
+        for flag in ["SQPOLL", "COOP_TASKRUN", "SINGLE_ISSUER", "DEFER_TASKRUN"] {
+            let mut candidate = setup;
+            match flag {
+                "SQPOLL" => candidate.sqpoll = requested.sqpoll,
+                "COOP_TASKRUN" => candidate.coop_taskrun = requested.coop_taskrun,
+                "SINGLE_ISSUER" => candidate.single_issuer = requested.single_issuer,
+                _ => candidate.defer_taskrun = requested.defer_taskrun,
+            }
+            if candidate == setup {
+                continue;
+            }
+            match Self::try_build(options, &candidate) {
+                Ok(accepted) => {
+                    ring = accepted;
+                    setup = candidate;
+                }
+                Err(e) => tracing::warn!("io_uring setup flag {} rejected: {}", flag, e),
+            }
+        }
+        Ok(Self::with_queue_sizes(ring, setup))
+    }
 
+    fn try_build(options: &RingOptions, setup: &RingSetup) -> io::Result<IoUring> {
+        let mut builder = IoUring::builder();
+        if let Some(cq_entries) = options.cq_entries {
+            builder.setup_cqsize(cq_entries);
+        }
+        if let (true, Some(sqpoll)) = (setup.sqpoll, &options.sqpoll) {
+            builder.setup_sqpoll(sqpoll.idle.as_millis().min(u32::MAX as u128) as u32);
+            if let Some(cpu) = sqpoll.cpu {
+                builder.setup_sqpoll_cpu(cpu);
+            }
+        }
+        if setup.single_issuer {
+            builder.setup_single_issuer();
+        }
+        if setup.coop_taskrun {
+            builder.setup_coop_taskrun();
+        }
+        if setup.defer_taskrun {
+            builder.setup_defer_taskrun();
+        }
+        if setup.coop_taskrun || setup.defer_taskrun {
+            // Lets the drain skip entering the kernel when no work is pending
+            builder.setup_taskrun_flag();
+        }
+        builder.build(options.entries)
+    }
+
+    fn with_queue_sizes(ring: IoUring, mut setup: RingSetup) -> (IoUring, RingSetup) {
+        setup.sq_entries = ring.params().sq_entries();
+        setup.cq_entries = ring.params().cq_entries();
+        (ring, setup)
+    }
+
+    /// Polls the notifier's eventfd so that signalling it completes `wait`.
+    fn arm_wakeup(
+        ring: &mut IoUring,
+        pending_ops: &mut HashMap<u64, PendingOp>,
+        notifier: &Notifier,
+    ) {
+        let user_data = IoToken::new().id();
+        let entry = opcode::PollAdd::new(types::Fd(notifier.as_raw_fd()), libc::POLLIN as u32)
+            .build()
+            .user_data(user_data);
+        match push_entry(ring, &entry) {
+            Ok(()) => {
+                pending_ops.insert(user_data, PendingOp::Wakeup);
+            }
+            Err(e) => eprintln!("Failed to submit io-uring operation: {}", e),
+        }
+    }
+
+    /// Allocates a provided-buffer group id for a multishot receive.
+    fn next_buffer_group(&self) -> u16 {
+        let next_bgid = unsafe { &mut *self.next_bgid.get() };
+        let bgid = *next_bgid;
+        *next_bgid = next_bgid.wrapping_add(1);
+        if *next_bgid == PROVIDED_RING_BGID {
+            *next_bgid = 0;
+        }
+        bgid
+    }
+
+    /// The fixed-buffer index of `buf`, if it is one of this backend's
+    /// registered buffers.
+    fn fixed_index(&self, buf: &Buffer) -> Option<u16> {
+        let (region, index) = buf.registered_slot()?;
+        let fixed = self.fixed.as_ref()?;
+        Arc::ptr_eq(region, fixed).then_some(index)
+    }
+
+    /// Builds a read into `buf`, using `READ_FIXED` for registered buffers.
+    fn read_entry(&self, fd: i32, offset: u64, buf: &mut Buffer, len: usize) -> squeue::Entry {
+        let ptr = buf.as_mut_ptr();
+        match self.fixed_index(buf) {
+            Some(index) => opcode::ReadFixed::new(types::Fd(fd), ptr, len as u32, index)
+                .offset(offset)
+                .build(),
+            None => opcode::Read::new(types::Fd(fd), ptr, len as u32)
+                .offset(offset)
+                .build(),
+        }
+    }
+
+    /// Builds a write of `data`, using `WRITE_FIXED` for registered buffers.
+    fn write_entry(&self, fd: i32, offset: u64, data: &Buffer) -> squeue::Entry {
+        match self.fixed_index(data) {
+            Some(index) => {
+                opcode::WriteFixed::new(types::Fd(fd), data.as_ptr(), data.len() as u32, index)
+                    .offset(offset)
+                    .build()
+            }
+            None => opcode::Write::new(types::Fd(fd), data.as_ptr(), data.len() as u32)
+                .offset(offset)
+                .build(),
+        }
+    }
+
+    /// A read of `len` bytes into a buffer taken from `BufferPool`.
+    fn pooled_read(&self, fd: i32, offset: u64, len: usize) -> (squeue::Entry, PendingOp) {
+        let mut buf = BufferPool::get(len);
+        let entry = self.read_entry(fd, offset, &mut buf, len);
+        (
+            entry,
+            PendingOp::Read {
+                op: Op::Read { fd, offset, len },
+                buf,
+            },
+        )
+    }
+
+    /// Builds the next receive of a receive stream on `fd`, from the provided
+    /// buffer ring if there is one and `select` is set.
+    fn recv_multi_entry(&self, fd: i32, select: bool) -> (squeue::Entry, PendingOp) {
+        match &self.buf_ring {
+            Some(buf_ring) if select => {
+                buf_ring.replenish();
+                let entry = opcode::RecvMulti::new(types::Fd(fd), PROVIDED_RING_BGID).build();
+                (
+                    entry,
+                    PendingOp::RecvMulti {
+                        fd,
+                        buf: None,
+                        cancelled: false,
+                    },
+                )
+            }
+            _ => {
+                let mut buf = BufferPool::get(BUFFER_SIZE);
+                let entry = self.read_entry(fd, 0, &mut buf, BUFFER_SIZE);
+                (
+                    entry,
+                    PendingOp::RecvMulti {
+                        fd,
+                        buf: Some(buf),
+                        cancelled: false,
+                    },
+                )
+            }
+        }
+    }
+
+    /// A `msghdr` carrying `iovecs` and nothing else.
+    fn iovec_msghdr(iovecs: &mut [iovec]) -> Box<msghdr> {
+        // SAFETY: zeroed is a valid empty `msghdr`.
+        let mut msg = Box::new(unsafe { std::mem::zeroed::<msghdr>() });
+        msg.msg_iov = iovecs.as_mut_ptr();
+        msg.msg_iovlen = iovecs.len() as _;
+        msg
+    }
+
+    fn vectored(op: Op, iovecs: Box<[iovec]>, msg: Option<Box<msghdr>>) -> PendingOp {
+        PendingOp::Vectored {
+            op,
+            _iovecs: iovecs,
+            _msg: msg,
+        }
+    }
+
+    /// Submits an operation whose completion is consumed by the backend itself.
+    fn submit_internal(
+        ring: &mut IoUring,
+        pending_ops: &mut HashMap<u64, PendingOp>,
+        entry: squeue::Entry,
+        storage: Option<Box<[u64]>>,
+    ) {
+        let user_data = IoToken::new().id();
+        match push_entry(ring, &entry.user_data(user_data)) {
+            Ok(()) => {
+                pending_ops.insert(user_data, PendingOp::Internal { _storage: storage });
+            }
+            Err(e) => eprintln!("Failed to submit io-uring operation: {}", e),
+        }
+    }
+
+    /// Builds the submission queue entries of `op`, completing under `user_data`.
+    fn prepare(&self, op: Op, user_data: u64) -> Prepared {
         // SAFETY: We have exclusive access on this thread.
         let ring = unsafe { &mut *self.ring.get() };
         let pending_ops = unsafe { &mut *self.pending_ops.get() };
 
-        let (entry, pending_op) = {
+        let mut leading = Vec::new();
+        let (entry, pending) = {
             match op {
                 Op::Accept { fd } => {
                     let mut addr_storage =
@@ -108,37 +824,293 @@ impl IoProvider for UringBackend {
                         },
                     )
                 }
-                Op::Read { fd, offset, len } => {
-                    let mut buf = BufferPool::get(len); // Get a buffer from the pool
-                    let entry = opcode::Read::new(types::Fd(fd), buf.as_mut_ptr(), len as u32)
-                        .offset(offset)
+                Op::Connect { fd, ref addr } => {
+                    let (storage, len) = socket_addr_to_raw(addr);
+                    let storage = Box::new(storage);
+                    let entry = opcode::Connect::new(
+                        types::Fd(fd),
+                        &*storage as *const sockaddr_storage as *const _,
+                        len,
+                    )
+                    .build()
+                    .user_data(user_data);
+                    (entry, PendingOp::Connect { op, _addr: storage })
+                }
+                Op::AcceptMulti { fd } => (
+                    opcode::AcceptMulti::new(types::Fd(fd))
+                        .build()
+                        .user_data(user_data),
+                    PendingOp::AcceptMulti {
+                        fd,
+                        cancelled: false,
+                    },
+                ),
+                Op::RecvMulti { fd } => {
+                    let (entry, pending_op) = self.recv_multi_entry(fd, true);
+                    (entry.user_data(user_data), pending_op)
+                }
+                Op::Readv {
+                    fd,
+                    offset,
+                    mut buffers,
+                } => {
+                    let iovecs = read_iovecs(&mut buffers).into_boxed_slice();
+                    let entry =
+                        opcode::Readv::new(types::Fd(fd), iovecs.as_ptr(), iovecs.len() as u32)
+                            .offset(offset)
+                            .build()
+                            .user_data(user_data);
+                    let op = Op::Readv {
+                        fd,
+                        offset,
+                        buffers,
+                    };
+                    (entry, Self::vectored(op, iovecs, None))
+                }
+                Op::Writev {
+                    fd,
+                    offset,
+                    buffers,
+                } => {
+                    let iovecs = write_iovecs(&buffers).into_boxed_slice();
+                    let entry =
+                        opcode::Writev::new(types::Fd(fd), iovecs.as_ptr(), iovecs.len() as u32)
+                            .offset(offset)
+                            .build()
+                            .user_data(user_data);
+                    let op = Op::Writev {
+                        fd,
+                        offset,
+                        buffers,
+                    };
+                    (entry, Self::vectored(op, iovecs, None))
+                }
+                Op::RecvMsg { fd, mut buffers } => {
+                    let mut iovecs = read_iovecs(&mut buffers).into_boxed_slice();
+                    let msg = Self::iovec_msghdr(&mut iovecs);
+                    let entry = opcode::RecvMsg::new(types::Fd(fd), &*msg as *const _ as *mut _)
                         .build()
                         .user_data(user_data);
+                    let op = Op::RecvMsg { fd, buffers };
+                    (entry, Self::vectored(op, iovecs, Some(msg)))
+                }
+                Op::SendMsg { fd, buffers } => {
+                    let mut iovecs = write_iovecs(&buffers).into_boxed_slice();
+                    let msg = Self::iovec_msghdr(&mut iovecs);
+                    let entry = opcode::SendMsg::new(types::Fd(fd), &*msg)
+                        .flags(SEND_FLAGS as u32)
+                        .build()
+                        .user_data(user_data);
+                    let op = Op::SendMsg { fd, buffers };
+                    (entry, Self::vectored(op, iovecs, Some(msg)))
+                }
+                Op::Read { fd, offset, len } => match &self.buf_ring {
+                    // Reads larger than a ring buffer take the pooled path, which
+                    // sizes the buffer to the request
+                    Some(buf_ring) if len > 0 && len <= buf_ring.buf_size() => {
+                        // No memory is tied up while the read waits for data; the
+                        // kernel picks a ring buffer when it completes.
+                        buf_ring.replenish();
+                        let entry =
+                            opcode::Read::new(types::Fd(fd), std::ptr::null_mut(), len as u32)
+                                .offset(offset)
+                                .buf_group(PROVIDED_RING_BGID)
+                                .build()
+                                .flags(squeue::Flags::BUFFER_SELECT)
+                                .user_data(user_data);
+                        (entry, PendingOp::ReadSelect { fd, offset, len })
+                    }
+                    _ => {
+                        let (entry, pending_op) = self.pooled_read(fd, offset, len);
+                        (entry.user_data(user_data), pending_op)
+                    }
+                },
+                Op::ReadInto {
+                    fd,
+                    offset,
+                    mut buffer,
+                } => {
+                    let len = buffer.capacity();
+                    let entry = self
+                        .read_entry(fd, offset, &mut buffer, len)
+                        .user_data(user_data);
                     (
                         entry,
                         PendingOp::Read {
-                            op: Op::Read { fd, offset, len },
-                            buf,
+                            op: Op::ReadInto {
+                                fd,
+                                offset,
+                                buffer: Buffer::new_zeroed(0), // The buffer itself lives in PendingOp
+                            },
+                            buf: buffer,
                         },
                     )
                 }
                 Op::Write { fd, offset, data } => {
-                    let entry = opcode::Write::new(types::Fd(fd), data.as_ptr(), data.len() as u32)
-                        .offset(offset)
-                        .build()
-                        .user_data(user_data);
+                    let entry = self.write_entry(fd, offset, &data).user_data(user_data);
                     (
                         entry,
                         PendingOp::Write {
                             op: Op::Write {
                                 fd,
                                 offset,
-                                data: data.clone(), // Clone data for op, move original into PendingOp
+                                data: Buffer::new_zeroed(0), // The data itself lives in PendingOp
                             },
                             data, // Store the original Buffer here
                         },
                     )
                 }
+                Op::Splice {
+                    fd_in,
+                    off_in,
+                    fd_out,
+                    off_out,
+                    len,
+                } => {
+                    let offset = |offset: Option<u64>| offset.map_or(-1, |offset| offset as i64);
+                    let entry = opcode::Splice::new(
+                        types::Fd(fd_in),
+                        offset(off_in),
+                        types::Fd(fd_out),
+                        offset(off_out),
+                        len as u32,
+                    )
+                    .build()
+                    .user_data(user_data);
+                    (entry, PendingOp::Splice { op })
+                }
+                Op::Sendfile {
+                    fd_out,
+                    fd_in,
+                    offset,
+                    len,
+                } => match SendfileState::new(fd_out, fd_in, offset, len) {
+                    Ok(state) if len > 0 => (
+                        state.next_entry().user_data(user_data),
+                        PendingOp::Sendfile(state),
+                    ),
+                    Ok(_) => (
+                        opcode::Nop::new().build().user_data(user_data),
+                        PendingOp::Splice { op },
+                    ),
+                    Err(error) => (
+                        opcode::Nop::new().build().user_data(user_data),
+                        PendingOp::Ready {
+                            op,
+                            result: Err(error),
+                        },
+                    ),
+                },
+                op if op.is_fs() && !self.probe.is_supported(fs_opcode(&op)) => {
+                    // Kernels without the opcode get the system call, inline
+                    let result = fs_syscall(&op);
+                    (
+                        opcode::Nop::new().build().user_data(user_data),
+                        PendingOp::Ready { op, result },
+                    )
+                }
+                Op::OpenAt {
+                    ref path,
+                    flags,
+                    mode,
+                } => {
+                    let entry = opcode::OpenAt::new(types::Fd(libc::AT_FDCWD), path.as_ptr())
+                        .flags(flags | libc::O_CLOEXEC)
+                        .mode(mode)
+                        .build()
+                        .user_data(user_data);
+                    (entry, PendingOp::Fs { op })
+                }
+                Op::Statx {
+                    dir_fd,
+                    ref path,
+                    flags,
+                } => {
+                    // SAFETY: zeroed is a valid `statx`.
+                    let mut statx: Box<libc::statx> = Box::new(unsafe { std::mem::zeroed() });
+                    let entry = opcode::Statx::new(
+                        types::Fd(dir_fd),
+                        path.as_ptr(),
+                        &mut *statx as *mut libc::statx as *mut types::statx,
+                    )
+                    .flags(flags)
+                    .mask(crate::fs::STATX_MASK)
+                    .build()
+                    .user_data(user_data);
+                    (entry, PendingOp::Statx { op, statx })
+                }
+                Op::SyncData { fd } => {
+                    let entry = opcode::Fsync::new(types::Fd(fd))
+                        .flags(types::FsyncFlags::DATASYNC)
+                        .build()
+                        .user_data(user_data);
+                    (entry, PendingOp::Fs { op })
+                }
+                Op::Fallocate {
+                    fd,
+                    mode,
+                    offset,
+                    len,
+                } => {
+                    let entry = opcode::Fallocate::new(types::Fd(fd), len)
+                        .offset(offset)
+                        .mode(mode)
+                        .build()
+                        .user_data(user_data);
+                    (entry, PendingOp::Fs { op })
+                }
+                Op::Ftruncate { fd, len } => {
+                    let entry = opcode::Ftruncate::new(types::Fd(fd), len)
+                        .build()
+                        .user_data(user_data);
+                    (entry, PendingOp::Fs { op })
+                }
+                Op::RenameAt { ref from, ref to } => {
+                    let entry = opcode::RenameAt::new(
+                        types::Fd(libc::AT_FDCWD),
+                        from.as_ptr(),
+                        types::Fd(libc::AT_FDCWD),
+                        to.as_ptr(),
+                    )
+                    .build()
+                    .user_data(user_data);
+                    (entry, PendingOp::Fs { op })
+                }
+                Op::UnlinkAt { ref path, flags } => {
+                    let entry = opcode::UnlinkAt::new(types::Fd(libc::AT_FDCWD), path.as_ptr())
+                        .flags(flags)
+                        .build()
+                        .user_data(user_data);
+                    (entry, PendingOp::Fs { op })
+                }
+                Op::MkdirAt { ref path, mode } => {
+                    let entry = opcode::MkDirAt::new(types::Fd(libc::AT_FDCWD), path.as_ptr())
+                        .mode(mode)
+                        .build()
+                        .user_data(user_data);
+                    (entry, PendingOp::Fs { op })
+                }
+                Op::SendZc { fd, data } => {
+                    let (ptr, len) = (data.as_ptr(), data.len() as u32);
+                    let entry = if self.probe.is_supported(opcode::SendZc::CODE) {
+                        opcode::SendZc::new(types::Fd(fd), ptr, len)
+                            .buf_index(self.fixed_index(&data))
+                            .flags(SEND_FLAGS)
+                            .build()
+                    } else {
+                        opcode::Send::new(types::Fd(fd), ptr, len)
+                            .flags(SEND_FLAGS)
+                            .build()
+                    };
+                    (
+                        entry.user_data(user_data),
+                        PendingOp::SendZc {
+                            fd,
+                            data,
+                            result: None,
+                        },
+                    )
+                }
                 Op::Fsync { fd } => {
                     let entry = opcode::Fsync::new(types::Fd(fd))
                         .build()
@@ -163,9 +1135,8 @@ impl IoProvider for UringBackend {
                 }
                 Op::ReadFile { fd, offset, len } => {
                     let mut buf = BufferPool::get(len); // Get a buffer from the pool
-                    let entry = opcode::Read::new(types::Fd(fd), buf.as_mut_ptr(), len as u32)
-                        .offset(offset)
-                        .build()
+                    let entry = self
+                        .read_entry(fd, offset, &mut buf, len)
                         .user_data(user_data);
                     (
                         entry,
@@ -177,43 +1148,42 @@ impl IoProvider for UringBackend {
                 }
                 Op::WriteFile { fd, offset, data } => {
                     // The data Buffer is moved into PendingOp to ensure it lives until completion.
-                    let entry = opcode::Write::new(types::Fd(fd), data.as_ptr(), data.len() as u32)
-                        .offset(offset)
-                        .build()
-                        .user_data(user_data);
+                    let entry = self.write_entry(fd, offset, &data).user_data(user_data);
                     (
                         entry,
                         PendingOp::WriteFile {
                             op: Op::WriteFile {
                                 fd,
                                 offset,
-                                data: data.clone(), // Clone data for op, move original into PendingOp
+                                data: Buffer::new_zeroed(0), // The data itself lives in PendingOp
                             },
                             data, // Store the original Buffer here
                         },
                     )
                 }
-                Op::UdpRecv { fd, mut buffer } => {
-                    // Build a libc iovec and msghdr for recvmsg
+                Op::UdpRecv {
+                    fd,
+                    mut buffer,
+                    flags,
+                } => {
+                    // The iovec, msghdr and address storage are boxed and kept in
+                    // the pending op: the kernel may read and write them until the
+                    // completion arrives.
                     let mut iov = Box::new(iovec {
                         iov_base: buffer.as_mut_ptr() as *mut _,
                         iov_len: buffer.len(),
                     });
                     let mut addr_storage =
                         Box::new(unsafe { std::mem::zeroed::<sockaddr_storage>() });
-                    let addr_len = Box::new(std::mem::size_of::<sockaddr_storage>() as socklen_t);
-
-                    let mut msg = Box::new(msghdr {
-                        msg_name: addr_storage.as_mut() as *mut _ as *mut _,
-                        msg_namelen: *addr_len,
-                        msg_iov: &mut *iov as *mut _,
-                        msg_iovlen: 1,
-                        msg_control: std::ptr::null_mut(),
-                        msg_controllen: 0,
-                        msg_flags: 0,
-                    });
+
+                    let mut msg = Box::new(unsafe { std::mem::zeroed::<msghdr>() });
+                    msg.msg_name = addr_storage.as_mut() as *mut _ as *mut _;
+                    msg.msg_namelen = std::mem::size_of::<sockaddr_storage>() as socklen_t;
+                    msg.msg_iov = iov.as_mut() as *mut _;
+                    msg.msg_iovlen = 1;
 
                     let entry = opcode::RecvMsg::new(types::Fd(fd), msg.as_mut() as *mut _)
+                        .flags(flags as u32)
                         .build()
                         .user_data(user_data);
 
@@ -222,75 +1192,36 @@ impl IoProvider for UringBackend {
                         PendingOp::UdpRecv {
                             op: Op::UdpRecv {
                                 fd,
-                                buffer: buffer.clone(),
+                                buffer: Buffer::new_zeroed(0), // The buffer itself lives in PendingOp
+                                flags,
                             },
                             buffer,
                             addr_storage,
-                            addr_len,
+                            msg,
+                            _iov: iov,
                         },
                     )
                 }
                 Op::UdpSend { fd, data, addr } => {
-                    // Build iovec and msghdr for sendmsg
                     let mut iov = Box::new(iovec {
                         iov_base: data.as_ptr() as *mut _,
                         iov_len: data.len(),
                     });
 
-                    // Convert SocketAddr into sockaddr_storage
-                    let mut addr_storage: Box<sockaddr_storage> =
-                        Box::new(unsafe { std::mem::zeroed() });
-                    let mut addr_len: Box<socklen_t> = Box::new(0);
-                    // fill in addr_storage using libc helpers via socket2 or manual match
-                    match addr {
-                        std::net::SocketAddr::V4(sa_v4) => {
-                            let in_addr = libc::sockaddr_in {
-                                sin_family: libc::AF_INET as u16,
-                                sin_port: sa_v4.port().to_be(),
-                                sin_addr: libc::in_addr {
-                                    s_addr: u32::from(*sa_v4.ip()).to_be(),
-                                },
-                                sin_zero: [0; 8],
-                            };
-                            unsafe {
-                                std::ptr::write(
-                                    addr_storage.as_mut() as *mut _ as *mut libc::sockaddr_in,
-                                    in_addr,
-                                );
-                            }
-                            *addr_len = std::mem::size_of::<libc::sockaddr_in>() as socklen_t;
-                        }
-                        std::net::SocketAddr::V6(sa_v6) => {
-                            let sin6 = libc::sockaddr_in6 {
-                                sin6_family: libc::AF_INET6 as u16,
-                                sin6_port: sa_v6.port().to_be(),
-                                sin6_flowinfo: sa_v6.flowinfo(),
-                                sin6_addr: libc::in6_addr {
-                                    s6_addr: sa_v6.ip().octets(),
-                                },
-                                sin6_scope_id: sa_v6.scope_id(),
-                            };
-                            unsafe {
-                                std::ptr::write(
-                                    addr_storage.as_mut() as *mut _ as *mut libc::sockaddr_in6,
-                                    sin6,
-                                );
-                            }
-                            *addr_len = std::mem::size_of::<libc::sockaddr_in6>() as socklen_t;
-                        }
-                    }
+                    let mut msg = Box::new(unsafe { std::mem::zeroed::<msghdr>() });
+                    msg.msg_iov = iov.as_mut() as *mut _;
+                    msg.msg_iovlen = 1;
 
-                    let mut msg = Box::new(msghdr {
-                        msg_name: addr_storage.as_mut() as *mut _ as *mut _,
-                        msg_namelen: *addr_len,
-                        msg_iov: &mut *iov as *mut _,
-                        msg_iovlen: 1,
-                        msg_control: std::ptr::null_mut(),
-                        msg_controllen: 0,
-                        msg_flags: 0,
+                    // Without a destination the socket must be connected.
+                    let addr_storage = addr.map(|addr| {
+                        let (storage, len) = socket_addr_to_raw(&addr);
+                        let mut storage = Box::new(storage);
+                        msg.msg_name = storage.as_mut() as *mut _ as *mut _;
+                        msg.msg_namelen = len;
+                        storage
                     });
 
-                    let entry = opcode::SendMsg::new(types::Fd(fd), msg.as_mut() as *mut _)
+                    let entry = opcode::SendMsg::new(types::Fd(fd), msg.as_ref() as *const _)
                         .build()
                         .user_data(user_data);
                     (
@@ -298,42 +1229,471 @@ impl IoProvider for UringBackend {
                         PendingOp::UdpSend {
                             op: Op::UdpSend {
                                 fd,
-                                data: data.clone(),
+                                data: Buffer::new_zeroed(0), // The data itself lives in PendingOp
                                 addr,
                             },
                             data,
-                            addr_storage,
-                            addr_len,
+                            _addr_storage: addr_storage,
+                            _msg: msg,
+                            _iov: iov,
+                        },
+                    )
+                }
+                Op::UdpRecvBatch {
+                    fd,
+                    max_datagrams,
+                    buf_size,
+                } => {
+                    // Multishot RECVMSG into a freshly provided buffer group: one
+                    // buffer per datagram, each laid out as the recvmsg_out header,
+                    // the source address, the control data and the payload.
+                    let nbufs = max_datagrams.clamp(1, u16::MAX as usize) as u16;
+                    let bgid = self.next_buffer_group();
+
+                    let mut msg = Box::new(unsafe { std::mem::zeroed::<msghdr>() });
+                    msg.msg_namelen = std::mem::size_of::<sockaddr_storage>() as socklen_t;
+                    msg.msg_controllen = std::mem::size_of::<UdpControlBuffer>() as _;
+
+                    let entry_size = (RECVMSG_OUT_HEADER_LEN
+                        + msg.msg_namelen as usize
+                        + msg.msg_controllen as usize
+                        + buf_size)
+                        .next_multiple_of(std::mem::size_of::<u64>());
+                    let mut storage =
+                        vec![0u64; entry_size / 8 * nbufs as usize].into_boxed_slice();
+
+                    let provide = opcode::ProvideBuffers::new(
+                        storage.as_mut_ptr() as *mut u8,
+                        entry_size as i32,
+                        nbufs,
+                        bgid,
+                        0,
+                    )
+                    .build()
+                    .flags(squeue::Flags::IO_LINK);
+                    let provide_data = IoToken::new().id();
+                    pending_ops.insert(provide_data, PendingOp::Internal { _storage: None });
+                    leading.push(provide.user_data(provide_data));
+
+                    let entry =
+                        opcode::RecvMsgMulti::new(types::Fd(fd), msg.as_ref() as *const _, bgid)
+                            .build()
+                            .user_data(user_data);
+                    (
+                        entry,
+                        PendingOp::UdpRecvBatch {
+                            op: Op::UdpRecvBatch {
+                                fd,
+                                max_datagrams,
+                                buf_size,
+                            },
+                            bgid,
+                            nbufs,
+                            entry_size,
+                            storage,
+                            msg,
+                            datagrams: Vec::new(),
+                            error: None,
+                            cancel_requested: false,
+                        },
+                    )
+                }
+                Op::UdpSendBatch { fd, messages } => {
+                    // io_uring has no sendmmsg: every message gets its own SendMsg,
+                    // linked so they go out in order and a failure cancels the rest.
+                    // The payload buffers stay in `op`, whose heap data never moves.
+                    //
+                    // A chain is pushed whole or not at all, so only what fits in
+                    // the submission queue is sent and the caller resubmits the rest.
+                    let chunk = messages.len().min(ring.submission().capacity());
+                    let mut slots: Box<[SendSlot]> = (0..chunk)
+                        .map(|_| unsafe { std::mem::zeroed::<SendSlot>() })
+                        .collect();
+                    let mut entries = Vec::with_capacity(messages.len());
+                    for (slot, message) in slots.iter_mut().zip(&messages) {
+                        slot.iov = iovec {
+                            iov_base: message.data.as_ptr() as *mut _,
+                            iov_len: message.data.len(),
+                        };
+                        slot.msg.msg_iov = &mut slot.iov;
+                        slot.msg.msg_iovlen = 1;
+                        if let Some(addr) = message.addr {
+                            let (storage, len) = socket_addr_to_raw(&addr);
+                            slot.addr = storage;
+                            slot.msg.msg_name = &mut slot.addr as *mut _ as *mut _;
+                            slot.msg.msg_namelen = len;
+                        }
+                        if let Some(segment_size) = message.segment_size {
+                            set_udp_segment_cmsg(&mut slot.msg, &mut slot.control, segment_size);
+                        }
+                        entries.push(
+                            opcode::SendMsg::new(types::Fd(fd), &slot.msg as *const _)
+                                .build()
+                                .user_data(user_data),
+                        );
+                    }
+
+                    // The chain ends at the last entry; an empty batch completes
+                    // through a single no-op.
+                    let entry = entries
+                        .pop()
+                        .unwrap_or_else(|| opcode::Nop::new().build().user_data(user_data));
+                    let remaining = entries.len() + 1;
+                    leading.extend(
+                        entries
+                            .into_iter()
+                            .map(|entry| entry.flags(squeue::Flags::IO_LINK)),
+                    );
+                    (
+                        entry,
+                        PendingOp::UdpSendBatch {
+                            op: Op::UdpSendBatch { fd, messages },
+                            remaining,
+                            messages_sent: 0,
+                            bytes_written: 0,
+                            error: None,
+                            _slots: slots,
                         },
                     )
                 }
             }
         };
 
-        match unsafe { ring.submission().push(&entry) } {
-            Ok(_) => {
-                pending_ops.insert(user_data, pending_op);
+        Prepared {
+            leading,
+            entry,
+            pending,
+        }
+    }
+
+    /// Pushes prepared operations to the submission queue, linking each to the
+    /// next if `link` is set. They reach the kernel with the next flush.
+    ///
+    /// The entries of one operation, or of the whole chain with `link`, are
+    /// pushed together or not at all: the queue is flushed first if they do
+    /// not fit behind what is already queued. Operations that still do not
+    /// fit complete with `EBUSY` on the next drain.
+    fn enqueue(&self, ops: Vec<Prepared>, link: bool) {
+        if link {
+            self.enqueue_group(ops, true);
+        } else {
+            for prepared in ops {
+                self.enqueue_group(vec![prepared], false);
+            }
+        }
+    }
+
+    fn enqueue_group(&self, group: Vec<Prepared>, link: bool) {
+        // SAFETY: We have exclusive access on this thread.
+        let ring = unsafe { &mut *self.ring.get() };
+        let pending_ops = unsafe { &mut *self.pending_ops.get() };
+        let rejected = unsafe { &mut *self.rejected.get() };
+
+        let mut entries = Vec::new();
+        for (i, prepared) in group.iter().enumerate() {
+            entries.extend(prepared.leading.iter().cloned());
+            let entry = prepared.entry.clone();
+            entries.push(if link && i + 1 < group.len() {
+                entry.flags(squeue::Flags::IO_LINK)
+            } else {
+                entry
+            });
+        }
+        let pushed = push_entries(ring, &entries);
+
+        for mut prepared in group {
+            let user_data = prepared.entry.get_user_data();
+            if !pushed {
+                // Leading entries that complete under their own token never will
+                for entry in &prepared.leading {
+                    if entry.get_user_data() != user_data {
+                        pending_ops.remove(&entry.get_user_data());
+                    }
+                }
+                // The rejection is the only completion the batch gets
+                if let PendingOp::UdpSendBatch { remaining, .. } = &mut prepared.pending {
+                    *remaining = 1;
+                }
+                rejected.push(user_data);
+            }
+            pending_ops.insert(user_data, prepared.pending);
+        }
+    }
+
+    /// Prepares and enqueues `ops`, returning their tokens in order.
+    fn submit_all(&self, ops: Vec<Op>, link: bool) -> Vec<IoToken> {
+        let mut tokens = Vec::with_capacity(ops.len());
+        let prepared: Vec<Prepared> = ops
+            .into_iter()
+            .map(|op| {
+                let token = IoToken::new();
+                tokens.push(token);
+                self.prepare(op, token.id())
+            })
+            .collect();
+        self.enqueue(prepared, link);
+        tokens
+    }
+}
+
+impl IoProvider for UringBackend {
+    type Completion = (IoToken, Op, Result<CompletionKind, IoError>);
+
+    fn wait(&self, timeout: Option<Duration>) {
+        // Rejected operations complete without the kernel
+        if !unsafe { &*self.rejected.get() }.is_empty() {
+            return;
+        }
+        let ring = unsafe { &mut *self.ring.get() };
+        let result = match timeout {
+            None => ring.submit_and_wait(1),
+            Some(timeout) if ring.params().is_feature_ext_arg() => {
+                let timespec = types::Timespec::from(timeout);
+                let args = types::SubmitArgs::new().timespec(&timespec);
+                ring.submitter().submit_with_args(1, &args)
             }
-            Err(e) => {
-                eprintln!("Failed to submit io-uring operation: {}", e);
+            Some(timeout) => {
+                // Kernels before 5.11 cannot bound the wait; nap briefly instead.
+                let _ = ring.submit();
+                std::thread::sleep(timeout.min(Duration::from_millis(1)));
+                return;
             }
+        };
+        match result {
+            Ok(_) => {}
+            Err(e)
+                if matches!(
+                    e.raw_os_error(),
+                    Some(libc::ETIME | libc::EINTR | libc::EBUSY | libc::EAGAIN)
+                ) => {}
+            Err(e) => eprintln!("Failed to wait for io-uring completions: {}", e),
         }
+    }
+
+    fn notifier(&self) -> Option<Arc<Notifier>> {
+        Some(self.notifier.clone())
+    }
+
+    fn ring_setup(&self) -> Option<RingSetup> {
+        Some(self.setup)
+    }
+
+    fn cancel(&self, token: IoToken) {
+        // SAFETY: We have exclusive access on this thread.
+        let ring = unsafe { &mut *self.ring.get() };
+        let pending_ops = unsafe { &mut *self.pending_ops.get() };
+        match pending_ops.get_mut(&token.id()) {
+            // Keeps a multishot operation the kernel ends anyway from being re-armed
+            Some(
+                PendingOp::AcceptMulti { cancelled, .. } | PendingOp::RecvMulti { cancelled, .. },
+            ) => *cancelled = true,
+            Some(_) => {}
+            None => return,
+        }
+        let entry = opcode::AsyncCancel::new(token.id()).build();
+        Self::submit_internal(ring, pending_ops, entry, None);
+    }
+
+    fn fixed_buffer(&self, capacity: usize) -> Option<Buffer> {
+        let fixed = self.fixed.as_ref()?;
+        if capacity > fixed.buf_size() {
+            return None;
+        }
+        fixed.take()
+    }
 
-        let _ = ring.submit();
+    fn submit(&self, op: Op) -> IoToken {
+        let token = IoToken::new();
+        let prepared = self.prepare(op, token.id());
+        self.enqueue(vec![prepared], false);
         token
     }
 
+    fn submit_batch(&self, ops: Vec<Op>) -> Vec<IoToken> {
+        self.submit_all(ops, false)
+    }
+
+    fn submit_linked(&self, ops: Vec<Op>) -> Vec<IoToken> {
+        self.submit_all(ops, true)
+    }
+
+    fn flush(&self) {
+        let ring = unsafe { &mut *self.ring.get() };
+        if !ring.submission().is_empty() {
+            if let Err(e) = ring.submit() {
+                eprintln!("Failed to submit io-uring operations: {}", e);
+            }
+        }
+    }
+
     fn poll_complete(&self, _cx: &mut Context<'_>) -> Poll<Vec<Self::Completion>> {
+        let mut completions = Vec::new();
+        if self.drain_completions(&mut completions) == 0 {
+            Poll::Pending
+        } else {
+            Poll::Ready(completions)
+        }
+    }
+
+    fn drain_completions(&self, completions: &mut Vec<Self::Completion>) -> usize {
+        // Operations queued since the last iteration go to the kernel first;
+        // those that complete inline are reaped right away.
+        self.flush();
+
         let ring = unsafe { &mut *self.ring.get() };
         let pending_ops = unsafe { &mut *self.pending_ops.get() };
 
-        let mut completions = Vec::new();
+        // Deferred completion work only runs when asked for
+        if self.setup.defer_taskrun && ring.submission().taskrun() {
+            // SAFETY: no arguments are passed.
+            if let Err(e) = unsafe {
+                ring.submitter()
+                    .enter::<libc::sigset_t>(0, 0, IORING_ENTER_GETEVENTS, None)
+            } {
+                eprintln!("Failed to run io-uring task work: {}", e);
+            }
+        }
+
+        if let Some(buf_ring) = &self.buf_ring {
+            buf_ring.replenish();
+        }
+
+        let start = completions.len();
+        let mut exhausted_groups = Vec::new();
+        let mut unselected_reads = Vec::new();
+        let mut rearmed_accepts = Vec::new();
+        let mut rearmed_recvs = Vec::new();
+        let mut sendfile_steps = Vec::new();
+        let mut rearm_wakeup = false;
+        let rejected = std::mem::take(unsafe { &mut *self.rejected.get() });
         let mut cq = ring.completion();
         cq.sync();
+        let cqes = cq.map(|cqe| (cqe.user_data(), cqe.result(), cqe.flags()));
 
-        for cqe in cq {
-            let token_id = cqe.user_data();
-            let result = cqe.result();
+        for (token_id, result, flags) in rejected
+            .into_iter()
+            .map(|token_id| (token_id, -libc::EBUSY, 0))
+            .chain(cqes)
+        {
+            // Operations that produce several CQEs are only reported once the
+            // last one has arrived.
+            match pending_ops.get_mut(&token_id) {
+                Some(PendingOp::Internal { .. }) => {
+                    pending_ops.remove(&token_id);
+                    continue;
+                }
+                Some(PendingOp::Wakeup) => {
+                    // The signal itself is consumed by the run loop's `clear`.
+                    pending_ops.remove(&token_id);
+                    rearm_wakeup = true;
+                    continue;
+                }
+                Some(PendingOp::AcceptMulti { fd, cancelled }) => {
+                    let (fd, cancelled) = (*fd, *cancelled);
+                    let res = if result < 0 {
+                        Err(IoError::Io(io::Error::from_raw_os_error(-result)))
+                    } else {
+                        Ok(CompletionKind::Accept {
+                            fd: result,
+                            addr: peer_addr(result),
+                        })
+                    };
+                    if !cqueue::more(flags) {
+                        pending_ops.remove(&token_id);
+                        if result >= 0 && !cancelled {
+                            rearmed_accepts.push((token_id, fd));
+                        }
+                    }
+                    completions.push((IoToken { id: token_id }, Op::AcceptMulti { fd }, res));
+                    continue;
+                }
+                Some(PendingOp::RecvMulti { fd, buf, cancelled }) => {
+                    let (fd, cancelled) = (*fd, *cancelled);
+                    let res = if result == -libc::ENOBUFS {
+                        // Every ring buffer is still held by the application:
+                        // continue with an ordinary buffer.
+                        pending_ops.remove(&token_id);
+                        if !cancelled {
+                            rearmed_recvs.push((token_id, fd, false));
+                        }
+                        continue;
+                    } else if result < 0 {
+                        if let Some(buf) = buf.take() {
+                            buf.recycle();
+                        }
+                        Err(IoError::Io(io::Error::from_raw_os_error(-result)))
+                    } else {
+                        let bytes_read = result as usize;
+                        let data = match (buf.take(), &self.buf_ring, cqueue::buffer_select(flags))
+                        {
+                            (Some(mut buf), _, _) => {
+                                unsafe {
+                                    buf.set_len(bytes_read);
+                                }
+                                buf
+                            }
+                            (None, Some(buf_ring), Some(index)) => buf_ring.take(index, bytes_read),
+                            _ => Buffer::new_zeroed(0),
+                        };
+                        Ok(CompletionKind::Read { bytes_read, data })
+                    };
+                    if !cqueue::more(flags) {
+                        pending_ops.remove(&token_id);
+                        if result > 0 && !cancelled {
+                            rearmed_recvs.push((token_id, fd, true));
+                        }
+                    }
+                    completions.push((IoToken { id: token_id }, Op::RecvMulti { fd }, res));
+                    continue;
+                }
+                Some(PendingOp::Sendfile(state)) => {
+                    match state.advance(result) {
+                        Some(res) => {
+                            let op = state.op();
+                            pending_ops.remove(&token_id);
+                            let res = res
+                                .map(|bytes_written| CompletionKind::Write { bytes_written })
+                                .map_err(IoError::Io);
+                            completions.push((IoToken { id: token_id }, op, res));
+                        }
+                        None => sendfile_steps.push(token_id),
+                    }
+                    continue;
+                }
+                // With IORING_CQE_F_MORE the buffer is in use until the
+                // notification arrives.
+                Some(PendingOp::SendZc { result: sent, .. }) if !cqueue::notif(flags) => {
+                    *sent = Some(result);
+                    if cqueue::more(flags) {
+                        continue;
+                    }
+                }
+                Some(pending_op @ PendingOp::UdpRecvBatch { .. }) => {
+                    fold_recv_batch_cqe(pending_op, result, flags);
+                    if cqueue::more(flags) {
+                        continue;
+                    }
+                }
+                Some(PendingOp::UdpSendBatch {
+                    remaining,
+                    messages_sent,
+                    bytes_written,
+                    error,
+                    ..
+                }) => {
+                    if result < 0 {
+                        error.get_or_insert_with(|| io::Error::from_raw_os_error(-result));
+                    } else if error.is_none() {
+                        *messages_sent += 1;
+                        *bytes_written += result as usize;
+                    }
+                    *remaining -= 1;
+                    if *remaining > 0 {
+                        continue;
+                    }
+                }
+                _ => {}
+            }
 
             if let Some(pending_op) = pending_ops.remove(&token_id) {
                 let token = IoToken { id: token_id };
@@ -415,6 +1775,24 @@ impl IoProvider for UringBackend {
                         };
                         (op, res)
                     }
+                    PendingOp::ReadSelect { fd, offset, len } => {
+                        let res = if result == -libc::ENOBUFS {
+                            // Every ring buffer is still held by the application:
+                            // retry with an ordinary buffer.
+                            unselected_reads.push((token_id, fd, offset, len));
+                            continue;
+                        } else if result < 0 {
+                            Err(IoError::Io(io::Error::from_raw_os_error(-result)))
+                        } else {
+                            let bytes_read = result as usize;
+                            let data = match (&self.buf_ring, cqueue::buffer_select(flags)) {
+                                (Some(buf_ring), Some(index)) => buf_ring.take(index, bytes_read),
+                                _ => Buffer::new_zeroed(0),
+                            };
+                            Ok(CompletionKind::Read { bytes_read, data })
+                        };
+                        (Op::Read { fd, offset, len }, res)
+                    }
                     PendingOp::Write { op, data } => {
                         let res = {
                             data.recycle();
@@ -428,6 +1806,81 @@ impl IoProvider for UringBackend {
                         };
                         (op, res)
                     }
+                    PendingOp::Vectored { mut op, .. } => {
+                        let res = if result < 0 {
+                            Err(IoError::Io(io::Error::from_raw_os_error(-result)))
+                        } else {
+                            let transferred = result as usize;
+                            match &mut op {
+                                Op::Readv { buffers, .. } | Op::RecvMsg { buffers, .. } => {
+                                    Ok(readv_completion(transferred, std::mem::take(buffers)))
+                                }
+                                _ => Ok(CompletionKind::Write {
+                                    bytes_written: transferred,
+                                }),
+                            }
+                        };
+                        (op, res)
+                    }
+                    PendingOp::Splice { op } => {
+                        let res = if result < 0 {
+                            Err(IoError::Io(io::Error::from_raw_os_error(-result)))
+                        } else {
+                            Ok(CompletionKind::Write {
+                                bytes_written: result as usize,
+                            })
+                        };
+                        (op, res)
+                    }
+                    PendingOp::SendZc {
+                        fd,
+                        data,
+                        result: sent,
+                    } => {
+                        drop(data);
+                        let result = sent.unwrap_or(result);
+                        let res = if result < 0 {
+                            Err(IoError::Io(io::Error::from_raw_os_error(-result)))
+                        } else {
+                            Ok(CompletionKind::Write {
+                                bytes_written: result as usize,
+                            })
+                        };
+                        let op = Op::SendZc {
+                            fd,
+                            data: Buffer::new_zeroed(0), // Released by the kernel
+                        };
+                        (op, res)
+                    }
+                    PendingOp::Ready { op, result } => (op, result.map_err(IoError::Io)),
+                    PendingOp::Fs { op } => {
+                        let res = if result < 0 {
+                            Err(IoError::Io(io::Error::from_raw_os_error(-result)))
+                        } else {
+                            Ok(match op {
+                                Op::OpenAt { .. } => CompletionKind::Open { fd: result },
+                                Op::SyncData { .. } => CompletionKind::Fsync,
+                                _ => CompletionKind::Done,
+                            })
+                        };
+                        (op, res)
+                    }
+                    PendingOp::Statx { op, statx } => {
+                        let res = if result < 0 {
+                            Err(IoError::Io(io::Error::from_raw_os_error(-result)))
+                        } else {
+                            Ok(CompletionKind::Metadata(Metadata::from_statx(&statx)))
+                        };
+                        (op, res)
+                    }
+                    PendingOp::Connect { op, .. } => {
+                        let res = if result < 0 {
+                            Err(IoError::Io(io::Error::from_raw_os_error(-result)))
+                        } else {
+                            Ok(CompletionKind::Done)
+                        };
+                        (op, res)
+                    }
                     PendingOp::Fsync { op } => {
                         let res = if result < 0 {
                             Err(IoError::Io(io::Error::from_raw_os_error(-result)))
@@ -477,7 +1930,8 @@ impl IoProvider for UringBackend {
                         op,
                         mut buffer,
                         addr_storage,
-                        addr_len,
+                        msg,
+                        _iov,
                     } => {
                         let res = if result < 0 {
                             buffer.recycle();
@@ -487,68 +1941,17 @@ impl IoProvider for UringBackend {
                             unsafe {
                                 buffer.set_len(bytes_read);
                             }
-                            // convert sockaddr_storage to SocketAddr
-                            let socket_addr = {
-                                let storage: *const libc::sockaddr_storage =
-                                    &*addr_storage as *const _;
-                                unsafe {
-                                    match (*storage).ss_family as libc::c_int {
-                                        libc::AF_INET => {
-                                            let sockaddr_in =
-                                                &*(storage as *const _ as *const libc::sockaddr_in);
-                                            let ip = std::net::Ipv4Addr::from(u32::from_be(
-                                                sockaddr_in.sin_addr.s_addr,
-                                            ));
-                                            let port = u16::from_be(sockaddr_in.sin_port);
-                                            Ok(std::net::SocketAddr::V4(
-                                                std::net::SocketAddrV4::new(ip, port),
-                                            ))
-                                        }
-                                        libc::AF_INET6 => {
-                                            let sockaddr_in6 = &*(storage as *const _
-                                                as *const libc::sockaddr_in6);
-                                            let ip = std::net::Ipv6Addr::from(
-                                                sockaddr_in6.sin6_addr.s6_addr,
-                                            );
-                                            let port = u16::from_be(sockaddr_in6.sin6_port);
-                                            Ok(std::net::SocketAddr::V6(
-                                                std::net::SocketAddrV6::new(
-                                                    ip,
-                                                    port,
-                                                    sockaddr_in6.sin6_flowinfo,
-                                                    sockaddr_in6.sin6_scope_id,
-                                                ),
-                                            ))
-                                        }
-                                        _ => Err(IoError::Io(io::Error::new(
-                                            io::ErrorKind::InvalidData,
-                                            "Unsupported address family",
-                                        ))),
-                                    }
-                                }
-                            };
-                            // read addr_len to avoid unused-field warning
-                            let _ = *addr_len;
-                            match socket_addr {
-                                Ok(sa) => Ok(CompletionKind::UdpRecv {
+                            raw_to_socket_addr(&addr_storage, msg.msg_namelen)
+                                .map(|addr| CompletionKind::UdpRecv {
                                     bytes_read,
                                     buffer,
-                                    addr: sa,
-                                }),
-                                Err(e) => Err(e),
-                            }
+                                    addr,
+                                })
+                                .map_err(IoError::Io)
                         };
                         (op, res)
                     }
-                    PendingOp::UdpSend {
-                        op,
-                        data,
-                        addr_storage,
-                        addr_len,
-                    } => {
-                        // touch addr_storage/addr_len so they're considered read (keeps them alive)
-                        let _ = &*addr_storage as *const _;
-                        let _ = *addr_len;
+                    PendingOp::UdpSend { op, data, .. } => {
                         let res = if result < 0 {
                             Err(IoError::Io(io::Error::from_raw_os_error(-result)))
                         } else {
@@ -559,15 +1962,152 @@ impl IoProvider for UringBackend {
                         };
                         (op, res)
                     }
+                    PendingOp::UdpRecvBatch {
+                        op,
+                        bgid,
+                        nbufs,
+                        storage,
+                        datagrams,
+                        error,
+                        ..
+                    } => {
+                        // Buffers the kernel never used are still registered under
+                        // `bgid` and must be removed before the arena is freed.
+                        exhausted_groups.push((bgid, nbufs, storage));
+                        let res = match error {
+                            Some(e) if datagrams.is_empty() => Err(IoError::Io(e)),
+                            _ => Ok(CompletionKind::UdpRecvBatch { datagrams }),
+                        };
+                        (op, res)
+                    }
+                    PendingOp::UdpSendBatch {
+                        op,
+                        messages_sent,
+                        bytes_written,
+                        error,
+                        ..
+                    } => {
+                        let res = match error {
+                            Some(e) if messages_sent == 0 => Err(IoError::Io(e)),
+                            _ => {
+                                let total = match &op {
+                                    Op::UdpSendBatch { messages, .. } => messages.len(),
+                                    _ => 0,
+                                };
+                                Ok(CompletionKind::UdpSendBatch {
+                                    // An empty batch still completes one no-op
+                                    messages_sent: messages_sent.min(total),
+                                    bytes_written,
+                                })
+                            }
+                        };
+                        (op, res)
+                    }
+                    PendingOp::AcceptMulti { .. }
+                    | PendingOp::RecvMulti { .. }
+                    | PendingOp::Sendfile(_)
+                    | PendingOp::Internal { .. }
+                    | PendingOp::Wakeup => continue,
                 };
                 completions.push((token, op, completion_result));
             }
         }
 
-        if completions.is_empty() {
-            Poll::Pending
-        } else {
-            Poll::Ready(completions)
+        // Stop multishot receives that already hold datagrams so the batch gets
+        // delivered. Datagrams landing before the cancellation still join it.
+        let mut cancels = Vec::new();
+        for (&user_data, pending_op) in pending_ops.iter_mut() {
+            if let PendingOp::UdpRecvBatch {
+                datagrams,
+                cancel_requested,
+                ..
+            } = pending_op
+            {
+                if !datagrams.is_empty() && !*cancel_requested {
+                    *cancel_requested = true;
+                    cancels.push(user_data);
+                }
+            }
+        }
+        let resubmit = !cancels.is_empty()
+            || !exhausted_groups.is_empty()
+            || !unselected_reads.is_empty()
+            || !rearmed_accepts.is_empty()
+            || !rearmed_recvs.is_empty()
+            || !sendfile_steps.is_empty()
+            || rearm_wakeup;
+        if rearm_wakeup {
+            Self::arm_wakeup(ring, pending_ops, &self.notifier);
         }
+        for (user_data, fd, offset, len) in unselected_reads {
+            let (entry, pending_op) = self.pooled_read(fd, offset, len);
+            match push_entry(ring, &entry.user_data(user_data)) {
+                Ok(()) => {
+                    pending_ops.insert(user_data, pending_op);
+                }
+                Err(e) => completions.push((
+                    IoToken { id: user_data },
+                    Op::Read { fd, offset, len },
+                    Err(IoError::Io(e)),
+                )),
+            }
+        }
+        // Multishot operations the kernel ended while they were still wanted
+        for (user_data, fd) in rearmed_accepts {
+            let entry = opcode::AcceptMulti::new(types::Fd(fd)).build();
+            match push_entry(ring, &entry.user_data(user_data)) {
+                Ok(()) => {
+                    pending_ops.insert(
+                        user_data,
+                        PendingOp::AcceptMulti {
+                            fd,
+                            cancelled: false,
+                        },
+                    );
+                }
+                Err(e) => completions.push((
+                    IoToken { id: user_data },
+                    Op::AcceptMulti { fd },
+                    Err(IoError::Io(e)),
+                )),
+            }
+        }
+        for (user_data, fd, select) in rearmed_recvs {
+            let (entry, pending_op) = self.recv_multi_entry(fd, select);
+            match push_entry(ring, &entry.user_data(user_data)) {
+                Ok(()) => {
+                    pending_ops.insert(user_data, pending_op);
+                }
+                Err(e) => completions.push((
+                    IoToken { id: user_data },
+                    Op::RecvMulti { fd },
+                    Err(IoError::Io(e)),
+                )),
+            }
+        }
+        for user_data in sendfile_steps {
+            let Some(PendingOp::Sendfile(state)) = pending_ops.get(&user_data) else {
+                continue;
+            };
+            let entry = state.next_entry().user_data(user_data);
+            if let Err(e) = push_entry(ring, &entry) {
+                if let Some(PendingOp::Sendfile(state)) = pending_ops.remove(&user_data) {
+                    completions.push((IoToken { id: user_data }, state.op(), Err(IoError::Io(e))));
+                }
+            }
+        }
+        for user_data in cancels {
+            let entry = opcode::AsyncCancel::new(user_data).build();
+            Self::submit_internal(ring, pending_ops, entry, None);
+        }
+        for (bgid, nbufs, storage) in exhausted_groups {
+            let entry = opcode::RemoveBuffers::new(nbufs, bgid).build();
+            Self::submit_internal(ring, pending_ops, entry, Some(storage));
+        }
+        if resubmit {
+            let _ = ring.submit();
+        }
+
+        completions.len() - start
     }
 }
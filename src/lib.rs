@@ -94,6 +94,7 @@
 
 #![deny(warnings)]
 
+pub mod blocking;
 pub mod buffer;
 pub mod cancellation;
 pub mod config;
@@ -110,16 +111,21 @@ pub mod timer;
 pub mod waker;
 
 // Re-export core types
-pub use buffer::{Buffer, BufferPool};
+pub use blocking::{spawn_blocking, BlockingPool, BlockingPoolOptions, BlockingTask};
+pub use buffer::{AlignedBuffer, Buffer, BufferPool};
 pub use cpu::Cpu;
 pub use executor::{Executor, Runtime};
 pub use fs::AsyncFile;
 pub use http::{
-    EchoHandler, HttpConnection, HttpHandler, Method, Request, Response, StaticHandler, StatusCode,
+    ConnectionOptions, EchoHandler, HttpConnection, HttpHandler, Method, Request, Response,
+    StaticHandler, StatusCode,
 };
-pub use io::{CompletionKind, DummyIoBackend, IoError, IoProvider, IoToken, Op};
-pub use multicore::MultiCoreRuntime;
-pub use net::{AsyncTcpListener, AsyncTcpStream, AsyncUdpSocket};
+pub use io::{
+    CompletionKind, Datagram, DummyIoBackend, IoError, IoProvider, IoToken, Notifier, Op,
+    RingOptions, RingSetup, SqPollOptions,
+};
+pub use multicore::{MultiCoreRuntime, TcpServeOptions};
+pub use net::{AsyncTcpListener, AsyncTcpStream, AsyncUdpSocket, UdpBindOptions};
 pub use task::{spawn, Task, TaskBuilder, TaskError, TaskResult};
 pub use timer::{sleep, timeout, Entry, Interval, TimeoutError, TimerId, TimerWheel};
 
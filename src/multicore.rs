@@ -10,19 +10,26 @@
 //! 4. **Independent IO**: Each core has its own io_uring/epoll instance
 
 use crossbeam_queue::SegQueue;
-use std::collections::VecDeque;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::future::Future;
+use std::net::SocketAddr;
 use std::pin::Pin;
 use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use crate::blocking::{BlockingPool, BlockingPoolOptions};
+use crate::cpu::{clear_current_io_state, set_current_io_state, CpuIoState};
 use crate::error::{Result, RuntimeError};
-use crate::io::{CompletionKind, IoError, IoProvider, IoToken, Op};
+use crate::io::{
+    CompletionKind, IoError, IoProvider, IoToken, Notifier, Op, RingOptions, RingSetup,
+};
+use crate::net::{AsyncTcpListener, AsyncTcpStream};
 use crate::task::Task;
 use crate::timer::TimerWheel;
-use crate::waker::TaskId;
+use crate::waker::{MinissWaker, TaskId};
 
 /// Type alias for the IO backend completion type
 type IoCompletion = (IoToken, Op, std::result::Result<CompletionKind, IoError>);
@@ -30,11 +37,48 @@ type IoCompletion = (IoToken, Op, std::result::Result<CompletionKind, IoError>);
 /// Global task ID generator
 static NEXT_TASK_ID: AtomicU64 = AtomicU64::new(1);
 
+/// How long an accept loop pauses after an error other than an aborted
+/// connection, such as running out of file descriptors
+const ACCEPT_ERROR_BACKOFF: Duration = Duration::from_millis(100);
+
 /// Generate unique task ID
 fn next_task_id() -> TaskId {
     TaskId(NEXT_TASK_ID.fetch_add(1, Ordering::Relaxed))
 }
 
+thread_local! {
+    /// Id and inbox of the core running on this thread, used by [`spawn_local`].
+    static CURRENT_CORE: RefCell<Option<(usize, Arc<SegQueue<CoreMessage>>)>> =
+        const { RefCell::new(None) };
+}
+
+/// Spawn a task on the core the caller is currently running on.
+///
+/// Must be called from a task running on a `MultiCoreRuntime` core. The task
+/// never leaves that core, so state created by the caller (e.g. an accepted
+/// connection) stays core-local.
+pub fn spawn_local<F>(future: F) -> Result<TaskId>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    CURRENT_CORE.with(|cell| match cell.borrow().as_ref() {
+        Some((_, inbox)) => {
+            let task_id = next_task_id();
+            inbox.push(CoreMessage::Task {
+                id: task_id,
+                future: Box::pin(future),
+            });
+            Ok(task_id)
+        }
+        None => Err(RuntimeError::NotInitialized),
+    })
+}
+
+/// Id of the core the caller is running on, or `None` outside the runtime.
+pub fn current_core() -> Option<usize> {
+    CURRENT_CORE.with(|cell| cell.borrow().as_ref().map(|(id, _)| *id))
+}
+
 /// Message types for inter-core communication
 pub enum CoreMessage {
     /// Execute a task on this core
@@ -67,19 +111,48 @@ impl std::fmt::Debug for CoreMessage {
     }
 }
 
+/// Sending side of a core's inbox
+///
+/// Signals the core's notifier after each message so a core blocked in its
+/// IO backend wakes up to process it.
+#[derive(Clone)]
+struct CoreSender {
+    inbox: Arc<SegQueue<CoreMessage>>,
+    notifier: Option<Arc<Notifier>>,
+}
+
+impl CoreSender {
+    fn send(&self, message: CoreMessage) {
+        self.inbox.push(message);
+        if let Some(notifier) = &self.notifier {
+            notifier.notify();
+        }
+    }
+}
+
 /// Individual CPU core executor - completely independent
 pub struct CpuCore {
     /// Core ID (matches physical CPU)
     id: usize,
-    /// Local task queue - no synchronization needed
+    /// Tasks ready to be polled - no synchronization needed
     task_queue: VecDeque<Task>,
+    /// Pending tasks waiting for their waker
+    parked: HashMap<TaskId, Task>,
+    /// Ids of parked tasks that have been woken, pushed by their wakers
+    woken: Arc<SegQueue<TaskId>>,
     /// Message inbox from other cores
     message_inbox: Arc<SegQueue<CoreMessage>>,
     /// Local timer wheel
     #[allow(dead_code)]
     timer_wheel: TimerWheel,
     /// IO backend (io_uring/epoll/kqueue)
-    io_backend: Box<dyn IoProvider<Completion = IoCompletion>>,
+    io_backend: Arc<dyn IoProvider<Completion = IoCompletion>>,
+    /// Interrupts the backend's blocking wait when work arrives from elsewhere
+    notifier: Option<Arc<Notifier>>,
+    /// I/O state shared with the `IoFuture`s running on this core
+    io_state: Arc<CpuIoState>,
+    /// Reused for every drain of the IO backend's completions
+    completions: Vec<IoCompletion>,
     /// Shutdown flag
     shutdown: Arc<AtomicBool>,
     /// Task counter for this core
@@ -90,15 +163,30 @@ impl CpuCore {
     /// Create new CPU core
     fn new(
         id: usize,
-        io_backend: Box<dyn IoProvider<Completion = IoCompletion>>,
+        message_inbox: Arc<SegQueue<CoreMessage>>,
+        io_backend: Arc<dyn IoProvider<Completion = IoCompletion>>,
+        blocking_pool: Arc<BlockingPool>,
         shutdown: Arc<AtomicBool>,
     ) -> Self {
+        let io_state = Arc::new(CpuIoState {
+            io_backend: io_backend.clone(),
+            io_wakers: Mutex::new(HashMap::new()),
+            completed_io: Mutex::new(HashMap::new()),
+            awaited_io: Mutex::new(HashSet::new()),
+            streams: Mutex::new(HashMap::new()),
+            blocking_pool,
+        });
         Self {
             id,
             task_queue: VecDeque::new(),
-            message_inbox: Arc::new(SegQueue::new()),
+            parked: HashMap::new(),
+            woken: Arc::new(SegQueue::new()),
+            message_inbox,
             timer_wheel: TimerWheel::new(1024, 1),
+            notifier: io_backend.notifier(),
             io_backend,
+            io_state,
+            completions: Vec::new(),
             shutdown,
             local_task_count: 0,
         }
@@ -109,6 +197,12 @@ impl CpuCore {
         // Bind to CPU core for optimal cache locality
         self.bind_to_cpu()?;
 
+        // Make this core's I/O state and inbox visible to the tasks it runs
+        set_current_io_state(self.io_state.clone());
+        CURRENT_CORE.with(|cell| {
+            *cell.borrow_mut() = Some((self.id, self.message_inbox.clone()));
+        });
+
         tracing::info!("CPU core {} started", self.id);
 
         while !self.shutdown.load(Ordering::Relaxed) {
@@ -126,13 +220,21 @@ impl CpuCore {
             // 4. Process timers
             work_done |= self.process_timers();
 
-            // Use proper waiting mechanism instead of busy-waiting with yield
+            // Nothing to do: sleep in the IO backend until there is
             if !work_done {
-                // Consider using condition variable or park/unpark for better CPU efficiency
-                std::thread::park_timeout(Duration::from_micros(100));
+                self.wait_for_work();
             }
         }
 
+        // Drop the remaining tasks while the I/O state is still installed:
+        // dropping an `IoFuture` deregisters its token from it.
+        self.task_queue.clear();
+        self.parked.clear();
+        clear_current_io_state();
+        CURRENT_CORE.with(|cell| {
+            *cell.borrow_mut() = None;
+        });
+
         tracing::info!("CPU core {} shutting down", self.id);
         Ok(())
     }
@@ -191,6 +293,7 @@ impl CpuCore {
                 Some(CoreMessage::CancelTask(task_id)) => {
                     // Remove task from queue if it exists
                     self.task_queue.retain(|task| task.id() != task_id);
+                    self.parked.remove(&task_id);
                     tracing::trace!("Cancelled task {:?}", task_id);
                     processed += 1;
                 }
@@ -205,11 +308,22 @@ impl CpuCore {
     fn execute_tasks(&mut self) -> bool {
         let mut executed = 0;
 
+        // Move woken tasks back to the ready queue
+        while let Some(task_id) = self.woken.pop() {
+            if let Some(task) = self.parked.remove(&task_id) {
+                self.task_queue.push_back(task);
+            }
+        }
+
         // Execute up to 16 tasks per iteration
         while executed < 16 && !self.task_queue.is_empty() {
             if let Some(mut task) = self.task_queue.pop_front() {
                 // Create waker for this task
-                let waker = crate::waker::dummy_waker();
+                let waker = MinissWaker::create_waker_with_notifier(
+                    task.id(),
+                    self.woken.clone(),
+                    self.notifier.clone(),
+                );
                 let mut context = std::task::Context::from_waker(&waker);
 
                 match task.poll(&mut context) {
@@ -219,8 +333,8 @@ impl CpuCore {
                         tracing::trace!("Task {:?} completed on core {}", task.id(), self.id);
                     }
                     std::task::Poll::Pending => {
-                        // Task not ready, put it back
-                        self.task_queue.push_back(task);
+                        // Task not ready, wait for its waker
+                        self.parked.insert(task.id(), task);
                     }
                 }
                 executed += 1;
@@ -232,22 +346,32 @@ impl CpuCore {
 
     /// Process IO completions
     fn process_io(&mut self) -> bool {
-        // Create a dummy waker for polling
-        let waker = crate::waker::dummy_waker();
-        let mut context = std::task::Context::from_waker(&waker);
-
-        // Poll IO backend for completions
-        match self.io_backend.poll_complete(&mut context) {
-            std::task::Poll::Ready(completions) => !completions.is_empty(),
-            std::task::Poll::Pending => false,
+        // Flush this iteration's submissions, then hand completions to the
+        // waiting futures
+        if self.io_backend.drain_completions(&mut self.completions) == 0 {
+            return false;
         }
+        self.io_state.complete(&mut self.completions);
+        true
     }
 
     /// Process timer events
     fn process_timers(&mut self) -> bool {
-        // Simple timer processing - check if any timers need processing
-        // For now, just return false as we don't have expired timer processing
-        false
+        crate::timer::wake_expired(Instant::now())
+    }
+
+    /// Block in the IO backend until an IO completion, a message or wakeup from
+    /// another thread, or the next timer deadline.
+    fn wait_for_work(&self) {
+        if let Some(notifier) = &self.notifier {
+            notifier.clear();
+        }
+        // Work that arrived before the notifier was cleared has no pending signal
+        if !self.message_inbox.is_empty() || !self.woken.is_empty() {
+            return;
+        }
+        self.io_backend
+            .wait(crate::timer::time_until_next_deadline());
     }
 }
 
@@ -266,9 +390,11 @@ pub struct MultiCoreRuntime {
     /// Number of CPU cores
     num_cores: usize,
     /// Message senders for each core
-    core_senders: Vec<Arc<SegQueue<CoreMessage>>>,
+    core_senders: Vec<CoreSender>,
     /// Thread join handles
     join_handles: Vec<thread::JoinHandle<Result<()>>>,
+    /// Ring setup each core's IO backend ended up with, if it has a ring
+    ring_setups: Vec<Option<RingSetup>>,
     /// Runtime state
     state: AtomicU8,
     /// Next core for round-robin task distribution
@@ -298,6 +424,29 @@ impl MultiCoreRuntime {
 
     /// Create new multi-core runtime
     pub fn new(num_cores: Option<usize>) -> Result<Arc<Self>> {
+        Self::with_ring_options(num_cores, RingOptions::default())
+    }
+
+    /// Create new multi-core runtime whose cores set up their io_uring rings
+    /// according to `options`
+    ///
+    /// Flags the kernel rejects are dropped; [`RuntimeStats::ring_setups`]
+    /// reports what each core ended up with. Other backends ignore the options.
+    pub fn with_ring_options(num_cores: Option<usize>, options: RingOptions) -> Result<Arc<Self>> {
+        Self::with_options(num_cores, options, BlockingPoolOptions::default())
+    }
+
+    /// Create new multi-core runtime with io_uring `ring_options`, as in
+    /// [`with_ring_options`](Self::with_ring_options), whose cores share one
+    /// blocking pool sized by `blocking_options`
+    ///
+    /// The pool runs [`spawn_blocking`](crate::spawn_blocking) work and, on
+    /// the epoll backend, file operations.
+    pub fn with_options(
+        num_cores: Option<usize>,
+        options: RingOptions,
+        blocking_options: BlockingPoolOptions,
+    ) -> Result<Arc<Self>> {
         let num_cores = num_cores.unwrap_or_else(num_cpus::get);
 
         if num_cores == 0 {
@@ -308,27 +457,46 @@ impl MultiCoreRuntime {
 
         tracing::info!("Creating thread-per-core runtime with {} cores", num_cores);
 
-        let mut core_senders = Vec::with_capacity(num_cores);
-        let mut join_handles = Vec::with_capacity(num_cores);
+        let mut core_senders: Vec<CoreSender> = Vec::with_capacity(num_cores);
+        let mut join_handles: Vec<thread::JoinHandle<Result<()>>> = Vec::with_capacity(num_cores);
+        let mut ring_setups = Vec::with_capacity(num_cores);
+        let blocking_pool = Arc::new(BlockingPool::new(blocking_options));
 
         // Create cores and start threads
         for core_id in 0..num_cores {
-            // Create IO backend for this core
-            let io_backend = Self::create_io_backend(core_id)?;
-
-            // Create core with shutdown flag
-            let core_shutdown = Arc::new(AtomicBool::new(false));
-            let mut core = CpuCore::new(core_id, io_backend, core_shutdown.clone());
-
-            // Store reference to core's message queue
-            let sender = Arc::new(SegQueue::new());
-            core_senders.push(sender.clone());
-            core.message_inbox = sender;
-
-            // Spawn thread for this core
-            let handle = thread::Builder::new()
+            let inbox = Arc::new(SegQueue::new());
+            let core_inbox = inbox.clone();
+            let core_options = options.clone();
+            let core_blocking_pool = blocking_pool.clone();
+            let (ready_tx, ready_rx) = std::sync::mpsc::channel();
+
+            // Spawn thread for this core. The IO backend is created on it, as a
+            // SINGLE_ISSUER ring only accepts submissions from its creator.
+            let spawned = thread::Builder::new()
                 .name(format!("miniss-core-{}", core_id))
                 .spawn(move || {
+                    let io_backend = match Self::create_io_backend(
+                        core_id,
+                        &core_options,
+                        &core_blocking_pool,
+                    ) {
+                        Ok(io_backend) => io_backend,
+                        Err(e) => {
+                            let _ = ready_tx.send(Err(e));
+                            return Ok(());
+                        }
+                    };
+                    let _ = ready_tx.send(Ok((io_backend.notifier(), io_backend.ring_setup())));
+
+                    // Create core with shutdown flag
+                    let core_shutdown = Arc::new(AtomicBool::new(false));
+                    let mut core = CpuCore::new(
+                        core_id,
+                        core_inbox,
+                        io_backend,
+                        core_blocking_pool,
+                        core_shutdown.clone(),
+                    );
                     let result = core.run();
                     // Set shutdown flag when core exits
                     core_shutdown.store(true, Ordering::Relaxed);
@@ -336,15 +504,41 @@ impl MultiCoreRuntime {
                 })
                 .map_err(|e| {
                     RuntimeError::TaskFailed(format!("Failed to spawn core thread: {}", e))
-                })?;
+                })
+                .and_then(|handle| match ready_rx.recv() {
+                    Ok(Ok(ready)) => Ok((handle, ready)),
+                    Ok(Err(e)) => Err(e),
+                    Err(_) => Err(RuntimeError::TaskFailed(format!(
+                        "Core {} exited during startup",
+                        core_id
+                    ))),
+                });
+
+            let (handle, (notifier, ring_setup)) = match spawned {
+                Ok(spawned) => spawned,
+                Err(e) => {
+                    // Stop the cores that did start
+                    for sender in &core_senders {
+                        sender.send(CoreMessage::Shutdown);
+                    }
+                    for handle in join_handles {
+                        let _ = handle.join();
+                    }
+                    return Err(e);
+                }
+            };
 
+            // Store reference to core's message queue
+            core_senders.push(CoreSender { inbox, notifier });
             join_handles.push(handle);
+            ring_setups.push(ring_setup);
         }
 
         let runtime = Arc::new(Self {
             num_cores,
             core_senders,
             join_handles,
+            ring_setups,
             state: AtomicU8::new(RuntimeState::Initializing as u8),
             next_core: AtomicUsize::new(0),
         });
@@ -358,13 +552,24 @@ impl MultiCoreRuntime {
     }
 
     /// Create IO backend for a specific core
-    fn create_io_backend(core_id: usize) -> Result<Box<dyn IoProvider<Completion = IoCompletion>>> {
+    // `options` only configures io_uring, and `blocking_pool` only epoll
+    #[allow(unused_variables)]
+    fn create_io_backend(
+        core_id: usize,
+        options: &RingOptions,
+        blocking_pool: &Arc<BlockingPool>,
+    ) -> Result<Arc<dyn IoProvider<Completion = IoCompletion>>> {
         #[cfg(all(target_os = "linux", io_backend = "io_uring"))]
         {
-            match crate::io::uring::UringBackend::new(1024) {
+            // Each core's SQ poll thread gets its own CPU
+            let mut options = options.clone();
+            if let Some(sqpoll) = &mut options.sqpoll {
+                sqpoll.cpu = sqpoll.cpu.map(|cpu| cpu + core_id as u32);
+            }
+            match crate::io::uring::UringBackend::with_options(&options) {
                 Ok(uring) => {
                     tracing::debug!("Core {} using io_uring backend", core_id);
-                    return Ok(Box::new(uring));
+                    return Ok(Arc::new(uring));
                 }
                 Err(e) => {
                     tracing::warn!(
@@ -381,7 +586,7 @@ impl MultiCoreRuntime {
             match crate::io::kqueue::KqueueBackend::new() {
                 Ok(kqueue) => {
                     tracing::debug!("Core {} using kqueue backend", core_id);
-                    return Ok(Box::new(kqueue));
+                    return Ok(Arc::new(kqueue));
                 }
                 Err(e) => {
                     tracing::warn!("Core {} failed to create kqueue: {}", core_id, e);
@@ -391,10 +596,10 @@ impl MultiCoreRuntime {
 
         #[cfg(all(target_os = "linux", io_backend = "epoll"))]
         {
-            match crate::io::epoll::EpollBackend::new() {
+            match crate::io::epoll::EpollBackend::new(blocking_pool.clone()) {
                 Ok(epoll) => {
                     tracing::debug!("Core {} using epoll backend", core_id);
-                    return Ok(Box::new(epoll));
+                    return Ok(Arc::new(epoll));
                 }
                 Err(e) => {
                     tracing::warn!("Core {} failed to create epoll: {}", core_id, e);
@@ -470,12 +675,93 @@ impl MultiCoreRuntime {
             future: Box::pin(future),
         };
 
-        self.core_senders[core_id].push(message);
+        self.core_senders[core_id].send(message);
 
         tracing::trace!("Task {:?} submitted to core {}", task_id, core_id);
         Ok(task_id)
     }
 
+    /// Serve TCP connections on `addr` with one `SO_REUSEPORT` listener per core.
+    ///
+    /// See [`serve_tcp_with`](Self::serve_tcp_with).
+    pub fn serve_tcp<H, Fut>(&self, addr: SocketAddr, handler: H) -> Result<SocketAddr>
+    where
+        H: Fn(AsyncTcpStream, Option<SocketAddr>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.serve_tcp_with(addr, TcpServeOptions::default(), handler)
+    }
+
+    /// Serve TCP connections on `addr` with one `SO_REUSEPORT` listener per core.
+    ///
+    /// Every core gets its own listener and accept queue and runs its own accept
+    /// loop; each accepted connection is handed to `handler` on the same core via
+    /// [`spawn_local`], so a connection never crosses cores.
+    ///
+    /// Returns the bound address, which is useful when `addr` uses port 0.
+    pub fn serve_tcp_with<H, Fut>(
+        &self,
+        addr: SocketAddr,
+        options: TcpServeOptions,
+        handler: H,
+    ) -> Result<SocketAddr>
+    where
+        H: Fn(AsyncTcpStream, Option<SocketAddr>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        // Bind all listeners here, in core order, instead of on each core: the
+        // reuseport group is indexed in bind order, and the CPU steering program
+        // relies on listener `n` belonging to core `n`. Binding the first one also
+        // resolves port 0 so the rest join the same group.
+        let first = AsyncTcpListener::bind_reuseport(addr)?;
+        let local_addr = first.local_addr()?;
+        let mut listeners = Vec::with_capacity(self.num_cores);
+        listeners.push(first);
+        for _ in 1..self.num_cores {
+            listeners.push(AsyncTcpListener::bind_reuseport(local_addr)?);
+        }
+
+        if options.cpu_steering {
+            listeners[0].attach_reuseport_cbpf(self.num_cores)?;
+        }
+
+        let handler = Arc::new(handler);
+        for (core_id, listener) in listeners.into_iter().enumerate() {
+            let handler = handler.clone();
+            self.spawn_on(core_id, async move {
+                loop {
+                    match listener.accept().await {
+                        Ok((stream, peer)) => {
+                            if let Err(e) = spawn_local(handler(stream, peer)) {
+                                tracing::warn!("Core {} failed to spawn handler: {}", core_id, e);
+                            }
+                        }
+                        // A connection reset before it was accepted, or a signal
+                        Err(e)
+                            if matches!(
+                                e.raw_os_error(),
+                                Some(libc::ECONNABORTED | libc::EINTR)
+                            ) => {}
+                        Err(e) => {
+                            // Out of file descriptors or memory: retrying at once
+                            // would only spin until a connection closes
+                            tracing::warn!("Core {} accept failed: {}", core_id, e);
+                            crate::timer::sleep(ACCEPT_ERROR_BACKOFF).await;
+                        }
+                    }
+                }
+            })?;
+        }
+
+        tracing::info!(
+            "Serving TCP on {} with {} listeners (cpu steering: {})",
+            local_addr,
+            self.num_cores,
+            options.cpu_steering
+        );
+        Ok(local_addr)
+    }
+
     /// Initiate graceful shutdown
     pub fn shutdown(&self) -> Result<()> {
         tracing::info!("Initiating runtime shutdown");
@@ -514,7 +800,7 @@ impl MultiCoreRuntime {
 
         // Send shutdown message to all cores
         for sender in &self.core_senders {
-            sender.push(CoreMessage::Shutdown);
+            sender.send(CoreMessage::Shutdown);
         }
 
         tracing::info!("Shutdown signal sent to all cores");
@@ -543,7 +829,7 @@ impl MultiCoreRuntime {
         for (from_core, sender) in self.core_senders.iter().enumerate() {
             for to_core in 0..self.num_cores {
                 if from_core != to_core {
-                    sender.push(CoreMessage::Ping { from_core });
+                    sender.send(CoreMessage::Ping { from_core });
                 }
             }
         }
@@ -646,6 +932,7 @@ impl MultiCoreRuntime {
             self.num_cores,
             self.state.load(Ordering::Acquire) == RuntimeState::ShuttingDown as u8
                 || self.state.load(Ordering::Acquire) == RuntimeState::Terminated as u8,
+            self.ring_setups.clone(),
         )
     }
 
@@ -655,19 +942,32 @@ impl MultiCoreRuntime {
     }
 }
 
+/// Options for [`MultiCoreRuntime::serve_tcp_with`]
+#[derive(Debug, Clone, Default)]
+pub struct TcpServeOptions {
+    /// Attach a `SO_ATTACH_REUSEPORT_CBPF` program that steers each connection
+    /// to the listener of the CPU that received it, instead of the kernel's
+    /// default hash-based selection. Linux only; works best when NIC RSS queues
+    /// are pinned to the runtime's cores.
+    pub cpu_steering: bool,
+}
+
 /// Runtime statistics
 #[derive(Debug, Clone)]
 pub struct RuntimeStats {
     pub num_cores: usize,
     pub is_shutdown: bool,
+    /// Ring setup of each core's IO backend, `None` for backends without one
+    pub ring_setups: Vec<Option<RingSetup>>,
 }
 
 impl RuntimeStats {
     /// Create new runtime statistics
-    fn new(num_cores: usize, is_shutdown: bool) -> Self {
+    fn new(num_cores: usize, is_shutdown: bool, ring_setups: Vec<Option<RingSetup>>) -> Self {
         Self {
             num_cores,
             is_shutdown,
+            ring_setups,
         }
     }
 }
@@ -1,10 +1,89 @@
 //! Async networking primitives for miniss.
 
 use crate::cpu::io_state;
-use crate::io::{future::IoFuture, CompletionKind, Op};
+use crate::io::future::{IoFuture, IoStream};
+use crate::io::{CompletionKind, Datagram, Op, UdpMessage};
+use futures::Stream;
 use std::io;
-use std::net::{SocketAddr, TcpListener, TcpStream, UdpSocket};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream, UdpSocket};
 use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Poll};
+
+/// Most segments the kernel accepts in one `UDP_SEGMENT` send.
+const UDP_MAX_GSO_SEGMENTS: usize = 64;
+
+/// Largest UDP payload that fits in a single IPv4 packet.
+const UDP_MAX_PAYLOAD: usize = 65_507;
+
+/// Smallest write [`AsyncTcpStream::write_all_buf`] sends zero-copy; below
+/// this, copying is cheaper than the kernel's page pinning and notification.
+const SEND_ZC_THRESHOLD: usize = 64 * 1024;
+
+/// Moves up to `len` bytes between two file descriptors without copying them
+/// through user space (`splice(2)`, Linux only).
+///
+/// One of the descriptors must be a pipe. An offset of `None` reads or writes
+/// at the descriptor's own position, which pipes and sockets require; a file
+/// offset given here leaves the file position unchanged.
+///
+/// # Arguments
+///
+/// * `fd_in` - The descriptor to move data from
+/// * `off_in` - Where to read `fd_in`, or `None`
+/// * `fd_out` - The descriptor to move data to
+/// * `off_out` - Where to write `fd_out`, or `None`
+/// * `len` - The most bytes to move
+///
+/// # Returns
+///
+/// * `Ok(usize)` - Number of bytes moved, 0 at the end of `fd_in`
+/// * `Err(io::Error)` - Failed to splice, e.g. because neither end is a pipe
+///
+/// # Examples
+///
+/// ```no_run
+/// use rust_miniss::{net, Runtime};
+/// use std::os::unix::io::AsRawFd;
+///
+/// # fn example(file: std::fs::File, pipe_writer: std::os::fd::OwnedFd) {
+/// let runtime = Runtime::new();
+/// runtime.block_on(async {
+///     let moved = net::splice(file.as_raw_fd(), Some(0), pipe_writer.as_raw_fd(), None, 4096)
+///         .await
+///         .expect("Failed to splice");
+///     println!("Moved {} bytes into the pipe", moved);
+/// });
+/// # }
+/// ```
+pub async fn splice(
+    fd_in: RawFd,
+    off_in: Option<u64>,
+    fd_out: RawFd,
+    off_out: Option<u64>,
+    len: usize,
+) -> io::Result<usize> {
+    let state = io_state();
+    let op = Op::Splice {
+        fd_in,
+        off_in,
+        fd_out,
+        off_out,
+        len,
+    };
+    let token = state.io_backend.submit(op);
+    let future = IoFuture::new(token);
+
+    match future.await {
+        Ok(CompletionKind::Write { bytes_written }) => Ok(bytes_written),
+        Ok(_) => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Unexpected completion kind",
+        )),
+        Err(e) => Err(e.into()),
+    }
+}
 
 /// An asynchronous TCP listener.
 #[derive(Debug)]
@@ -20,6 +99,97 @@ impl AsyncTcpListener {
         Ok(Self { inner: listener })
     }
 
+    /// Binds a listener with `SO_REUSEPORT` set, so several listeners (typically one
+    /// per core) can share the same address and each get their own accept queue.
+    ///
+    /// The kernel load-balances new connections across all listeners in the group.
+    /// Use [`attach_reuseport_cbpf`](Self::attach_reuseport_cbpf) to steer them by CPU
+    /// instead of by hash.
+    pub fn bind_reuseport<A: Into<SocketAddr>>(addr: A) -> io::Result<Self> {
+        use socket2::{Domain, Socket, Type};
+
+        let addr = addr.into();
+        let socket = Socket::new(Domain::for_address(addr), Type::STREAM, None)?;
+        socket.set_reuse_address(true)?;
+        set_reuse_port(&socket)?;
+        socket.bind(&addr.into())?;
+        socket.listen(crate::config::LISTEN_BACKLOG)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            inner: socket.into(),
+        })
+    }
+
+    /// Attaches a classic BPF program to this listener's `SO_REUSEPORT` group that
+    /// selects the listener whose index equals the CPU handling the incoming packet
+    /// (modulo `group_size`).
+    ///
+    /// Group indices follow the order in which the listeners were bound, so the
+    /// listener for CPU `n` must be the `n`-th one bound. The program applies to the
+    /// whole group and only needs to be attached to one of its members.
+    #[cfg(target_os = "linux")]
+    pub fn attach_reuseport_cbpf(&self, group_size: usize) -> io::Result<()> {
+        if group_size == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "reuseport group size must be non-zero",
+            ));
+        }
+
+        // A = cpu; A %= group_size; return A
+        let mut code = [
+            libc::sock_filter {
+                code: (libc::BPF_LD | libc::BPF_W | libc::BPF_ABS) as u16,
+                jt: 0,
+                jf: 0,
+                k: (libc::SKF_AD_OFF + libc::SKF_AD_CPU) as u32,
+            },
+            libc::sock_filter {
+                code: (libc::BPF_ALU | libc::BPF_MOD | libc::BPF_K) as u16,
+                jt: 0,
+                jf: 0,
+                k: group_size as u32,
+            },
+            libc::sock_filter {
+                code: (libc::BPF_RET | libc::BPF_A) as u16,
+                jt: 0,
+                jf: 0,
+                k: 0,
+            },
+        ];
+        let prog = libc::sock_fprog {
+            len: code.len() as libc::c_ushort,
+            filter: code.as_mut_ptr(),
+        };
+        let result = unsafe {
+            libc::setsockopt(
+                self.inner.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_ATTACH_REUSEPORT_CBPF,
+                &prog as *const _ as *const _,
+                std::mem::size_of::<libc::sock_fprog>() as libc::socklen_t,
+            )
+        };
+        if result != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// `SO_ATTACH_REUSEPORT_CBPF` is Linux-only.
+    #[cfg(not(target_os = "linux"))]
+    pub fn attach_reuseport_cbpf(&self, _group_size: usize) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "SO_ATTACH_REUSEPORT_CBPF is only available on Linux",
+        ))
+    }
+
+    /// Returns the local address that this listener is bound to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.local_addr()
+    }
+
     /// Accepts a new incoming connection from this listener.
     pub async fn accept(&self) -> io::Result<(AsyncTcpStream, Option<SocketAddr>)> {
         let state = io_state();
@@ -41,6 +211,84 @@ impl AsyncTcpListener {
             Err(e) => Err(e.into()),
         }
     }
+
+    /// Returns a stream of incoming connections.
+    ///
+    /// A single multishot accept on io_uring, or a readiness registration on
+    /// epoll, serves every connection instead of one operation per `accept`.
+    /// An error is yielded once and the next poll starts accepting again, so the
+    /// stream never ends. Dropping it stops accepting.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures::StreamExt;
+    /// use rust_miniss::{net::AsyncTcpListener, Runtime};
+    /// use std::net::SocketAddr;
+    ///
+    /// let runtime = Runtime::new();
+    /// runtime.block_on(async {
+    ///     let listener = AsyncTcpListener::bind("127.0.0.1:0".parse::<SocketAddr>().unwrap())
+    ///         .unwrap();
+    ///     let addr = listener.local_addr().unwrap();
+    ///     let clients: Vec<_> = (0..2)
+    ///         .map(|_| std::net::TcpStream::connect(addr).unwrap())
+    ///         .collect();
+    ///
+    ///     let mut incoming = listener.incoming();
+    ///     for _ in &clients {
+    ///         let (_stream, peer) = incoming.next().await.unwrap().unwrap();
+    ///         assert!(peer.is_some());
+    ///     }
+    /// });
+    /// ```
+    pub fn incoming(&self) -> Incoming<'_> {
+        Incoming {
+            listener: self,
+            accepts: None,
+        }
+    }
+}
+
+/// Stream of connections accepted by an [`AsyncTcpListener`], returned by
+/// [`AsyncTcpListener::incoming`].
+#[derive(Debug)]
+pub struct Incoming<'a> {
+    listener: &'a AsyncTcpListener,
+    /// The running multishot accept; `None` after it failed.
+    accepts: Option<IoStream>,
+}
+
+impl Stream for Incoming<'_> {
+    type Item = io::Result<(AsyncTcpStream, Option<SocketAddr>)>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let fd = self.listener.inner.as_raw_fd();
+        let accepts = self
+            .accepts
+            .get_or_insert_with(|| IoStream::submit(Op::AcceptMulti { fd }));
+        let result = match Pin::new(accepts).poll_next(cx) {
+            Poll::Ready(Some(result)) => result,
+            Poll::Ready(None) => return Poll::Ready(None),
+            Poll::Pending => return Poll::Pending,
+        };
+
+        Poll::Ready(Some(match result {
+            Ok(CompletionKind::Accept { fd, addr }) => {
+                let stream = unsafe { TcpStream::from_raw_fd(fd) };
+                Ok((AsyncTcpStream { inner: stream }, addr))
+            }
+            Ok(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Unexpected completion kind",
+            )),
+            Err(e) => {
+                // The multishot accept has ended; the next poll starts another
+                self.accepts = None;
+                Err(e.into())
+            }
+        }))
+    }
 }
 
 impl AsRawFd for AsyncTcpListener {
@@ -56,8 +304,66 @@ pub struct AsyncTcpStream {
 }
 
 impl AsyncTcpStream {
+    /// Opens a TCP connection to a remote address.
+    ///
+    /// The connect runs as one operation on io_uring, and in the thread pool
+    /// of the readiness backends. `TCP_NODELAY` is set, as suits
+    /// request/response traffic.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - The address to connect to
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(AsyncTcpStream)` - The connected stream
+    /// * `Err(io::Error)` - The connection was refused or could not be made
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_miniss::{net::AsyncTcpStream, AsyncTcpListener, Runtime};
+    /// use std::net::SocketAddr;
+    ///
+    /// let runtime = Runtime::new();
+    /// runtime.block_on(async {
+    ///     let listener = AsyncTcpListener::bind("127.0.0.1:0".parse::<SocketAddr>().unwrap())
+    ///         .unwrap();
+    ///     let stream = AsyncTcpStream::connect(listener.local_addr().unwrap())
+    ///         .await
+    ///         .expect("Failed to connect");
+    ///     stream.write_all(b"hello").await.unwrap();
+    /// });
+    /// ```
+    pub async fn connect<A: Into<SocketAddr>>(addr: A) -> io::Result<Self> {
+        use socket2::{Domain, Protocol, Socket, Type};
+
+        let addr = addr.into();
+        let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+        socket.set_nodelay(true)?;
+        let state = io_state();
+        let op = Op::Connect {
+            fd: socket.as_raw_fd(),
+            addr,
+        };
+        let token = state.io_backend.submit(op);
+        let future = IoFuture::new(token);
+
+        match future.await {
+            Ok(CompletionKind::Done) => Ok(TcpStream::from(socket).into()),
+            Ok(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Unexpected completion kind",
+            )),
+            Err(e) => Err(e.into()),
+        }
+    }
+
     /// Reads some bytes from the stream.
     /// Returns the number of bytes read and a buffer containing the data.
+    ///
+    /// The buffer is the one the backend read into, truncated to the bytes read;
+    /// nothing is copied.
     pub async fn read(&self) -> io::Result<(usize, crate::buffer::Buffer)> {
         let state = io_state();
         // Get a buffer from the pool to read into.
@@ -71,12 +377,7 @@ impl AsyncTcpStream {
         let future = IoFuture::new(token);
 
         match future.await {
-            Ok(CompletionKind::Read { bytes_read, data }) => {
-                // Resize buffer to actual bytes read for zero-copy
-                let mut result_buffer = crate::buffer::Buffer::new_zeroed(bytes_read);
-                result_buffer.copy_from_slice(&data.as_ref()[..bytes_read]);
-                Ok((bytes_read, result_buffer))
-            }
+            Ok(CompletionKind::Read { bytes_read, data }) => Ok((bytes_read, data)),
             Ok(_) => Err(io::Error::new(
                 io::ErrorKind::InvalidData,
                 "Unexpected completion kind",
@@ -85,17 +386,269 @@ impl AsyncTcpStream {
         }
     }
 
+    /// Reads some bytes into a caller-supplied buffer.
+    ///
+    /// Ownership of `buf` passes to the backend for the duration of the read and
+    /// is handed back afterwards, so one buffer can be reused across reads. Up to
+    /// `buf.capacity()` bytes are read, overwriting its previous contents, and
+    /// its length is set to the number of bytes read. On error the buffer is
+    /// dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use rust_miniss::{net::AsyncTcpStream, Buffer, Runtime};
+    ///
+    /// # fn example(stream: AsyncTcpStream) {
+    /// let runtime = Runtime::new();
+    /// runtime.block_on(async {
+    ///     let mut buf = Buffer::with_capacity(8192);
+    ///     loop {
+    ///         let (n, returned) = stream.read_into(buf).await.expect("Failed to read");
+    ///         if n == 0 {
+    ///             break;
+    ///         }
+    ///         stream.write_all(&returned).await.expect("Failed to write");
+    ///         buf = returned;
+    ///     }
+    /// });
+    /// # }
+    /// ```
+    pub async fn read_into(
+        &self,
+        buf: crate::buffer::Buffer,
+    ) -> io::Result<(usize, crate::buffer::Buffer)> {
+        let state = io_state();
+        let op = Op::ReadInto {
+            fd: self.inner.as_raw_fd(),
+            offset: 0,
+            buffer: buf,
+        };
+        let token = state.io_backend.submit(op);
+        let future = IoFuture::new(token);
+
+        match future.await {
+            Ok(CompletionKind::Read { bytes_read, data }) => Ok((bytes_read, data)),
+            Ok(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Unexpected completion kind",
+            )),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Returns a stream of the data received on this connection.
+    ///
+    /// Uses a multishot receive into the provided buffer ring on io_uring, so
+    /// the connection holds no buffer while it waits for data, and a readiness
+    /// registration on epoll. Each item is a non-empty buffer; the stream ends
+    /// after end of file or the first error. Dropping it stops receiving.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures::StreamExt;
+    /// use rust_miniss::{net::AsyncTcpListener, Runtime};
+    /// use std::io::Write;
+    /// use std::net::SocketAddr;
+    ///
+    /// let runtime = Runtime::new();
+    /// runtime.block_on(async {
+    ///     let listener = AsyncTcpListener::bind("127.0.0.1:0".parse::<SocketAddr>().unwrap())
+    ///         .unwrap();
+    ///     let mut client = std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    ///     let (stream, _) = listener.accept().await.unwrap();
+    ///
+    ///     client.write_all(b"hello").unwrap();
+    ///     drop(client);
+    ///
+    ///     let mut received = Vec::new();
+    ///     let mut chunks = stream.recv_stream();
+    ///     while let Some(chunk) = chunks.next().await {
+    ///         received.extend_from_slice(&chunk.unwrap());
+    ///     }
+    ///     assert_eq!(received, b"hello");
+    /// });
+    /// ```
+    pub fn recv_stream(&self) -> RecvStream<'_> {
+        RecvStream {
+            stream: self,
+            recvs: None,
+            done: false,
+        }
+    }
+
     /// Writes a buffer into this writer, returning how many bytes were written.
+    ///
+    /// `buf` is copied into a buffer owned by the backend; use
+    /// [`write_buf`](Self::write_buf) to hand over data without copying.
     pub async fn write(&self, buf: &[u8]) -> io::Result<usize> {
-        let state = io_state();
+        self.write_buf(crate::buffer::Buffer::from_slice(buf)).await
+    }
 
-        // Create a buffer that references the user's data without copying
-        // This is a zero-copy implementation
-        let buffer = crate::buffer::Buffer::from_slice(buf);
+    /// Writes an owned buffer into this writer, returning how many bytes were
+    /// written.
+    ///
+    /// Accepts a [`Buffer`](crate::buffer::Buffer), `Vec<u8>` or `bytes::Bytes`;
+    /// the data is passed to the backend as-is, without copying.
+    pub async fn write_buf(&self, buf: impl Into<crate::buffer::Buffer>) -> io::Result<usize> {
+        let state = io_state();
         let op = Op::Write {
             fd: self.inner.as_raw_fd(),
             offset: 0,
-            data: buffer,
+            data: buf.into(),
+        };
+        let token = state.io_backend.submit(op);
+        let future = IoFuture::new(token);
+
+        match future.await {
+            Ok(CompletionKind::Write { bytes_written }) => Ok(bytes_written),
+            Ok(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Unexpected completion kind",
+            )),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Sends an owned buffer zero-copy, returning how many bytes were written.
+    ///
+    /// With io_uring's `SEND_ZC` the kernel transmits straight from the buffer,
+    /// which the backend holds until the kernel reports it no longer needs it;
+    /// elsewhere this is an ordinary send. Worth it for large buffers only.
+    pub async fn send_zc(&self, buf: impl Into<crate::buffer::Buffer>) -> io::Result<usize> {
+        let state = io_state();
+        let op = Op::SendZc {
+            fd: self.inner.as_raw_fd(),
+            data: buf.into(),
+        };
+        let token = state.io_backend.submit(op);
+        let future = IoFuture::new(token);
+
+        match future.await {
+            Ok(CompletionKind::Write { bytes_written }) => Ok(bytes_written),
+            Ok(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Unexpected completion kind",
+            )),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Sends `len` bytes of `file`, starting at `offset`, without copying them
+    /// through user space.
+    ///
+    /// The epoll backend uses `sendfile(2)`; io_uring splices the file into a
+    /// pipe and the pipe into the socket. The file's own position is not used
+    /// or changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The file to send from
+    /// * `offset` - Where in the file to start
+    /// * `len` - The number of bytes to send
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(usize)` - Number of bytes sent: `len`, or fewer if the file ends first
+    /// * `Err(io::Error)` - Failed to read the file or write to the socket
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use rust_miniss::{fs::AsyncFile, net::AsyncTcpStream, Runtime};
+    ///
+    /// # fn example(stream: AsyncTcpStream) {
+    /// let runtime = Runtime::new();
+    /// runtime.block_on(async {
+    ///     let file = AsyncFile::open("index.html").expect("Failed to open file");
+    ///     let len = std::fs::metadata("index.html").unwrap().len() as usize;
+    ///     let head = format!("HTTP/1.1 200 OK\r\ncontent-length: {}\r\n\r\n", len);
+    ///     stream.write_all(head.as_bytes()).await.expect("Failed to write");
+    ///     stream.sendfile(&file, 0, len).await.expect("Failed to send file");
+    /// });
+    /// # }
+    /// ```
+    pub async fn sendfile(
+        &self,
+        file: &crate::fs::AsyncFile,
+        offset: u64,
+        len: usize,
+    ) -> io::Result<usize> {
+        let state = io_state();
+        let mut sent = 0;
+        while sent < len {
+            let op = Op::Sendfile {
+                fd_out: self.inner.as_raw_fd(),
+                fd_in: file.as_raw_fd(),
+                offset: offset + sent as u64,
+                len: len - sent,
+            };
+            let token = state.io_backend.submit(op);
+            let future = IoFuture::new(token);
+
+            match future.await {
+                Ok(CompletionKind::Write { bytes_written: 0 }) => break,
+                Ok(CompletionKind::Write { bytes_written }) => sent += bytes_written,
+                Ok(_) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "Unexpected completion kind",
+                    ))
+                }
+                Err(e) => {
+                    let e: io::Error = e.into();
+                    if e.kind() != io::ErrorKind::Interrupted {
+                        return Err(e);
+                    }
+                }
+            }
+        }
+        Ok(sent)
+    }
+
+    /// Reads some bytes into several buffers, filling them in order
+    /// (`recvmsg(2)`).
+    ///
+    /// Each buffer receives up to its capacity, overwriting its previous
+    /// contents, and its length is set to the bytes it received. Returns the
+    /// total number of bytes read, 0 at end of file, and the buffers. On error
+    /// the buffers are dropped.
+    pub async fn read_vectored(
+        &self,
+        bufs: Vec<crate::buffer::Buffer>,
+    ) -> io::Result<(usize, Vec<crate::buffer::Buffer>)> {
+        let state = io_state();
+        let op = Op::RecvMsg {
+            fd: self.inner.as_raw_fd(),
+            buffers: bufs,
+        };
+        let token = state.io_backend.submit(op);
+        let future = IoFuture::new(token);
+
+        match future.await {
+            Ok(CompletionKind::Readv {
+                bytes_read,
+                buffers,
+            }) => Ok((bytes_read, buffers)),
+            Ok(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Unexpected completion kind",
+            )),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes several buffers in order as one operation (`sendmsg(2)`),
+    /// returning how many bytes were written across all of them.
+    ///
+    /// The buffers are handed to the backend without copying, e.g. response
+    /// headers and a body that live in separate allocations.
+    pub async fn write_vectored(&self, bufs: Vec<crate::buffer::Buffer>) -> io::Result<usize> {
+        let state = io_state();
+        let op = Op::SendMsg {
+            fd: self.inner.as_raw_fd(),
+            buffers: bufs,
         };
         let token = state.io_backend.submit(op);
         let future = IoFuture::new(token);
@@ -110,6 +663,58 @@ impl AsyncTcpStream {
         }
     }
 
+    /// Attempts to write several entire buffers in order, without copying.
+    ///
+    /// After a short write, the buffers already sent are dropped and the
+    /// partially sent one is resubmitted from where the write stopped.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use rust_miniss::{net::AsyncTcpStream, Buffer, Runtime};
+    ///
+    /// # fn example(stream: AsyncTcpStream, body: Vec<u8>) {
+    /// let runtime = Runtime::new();
+    /// runtime.block_on(async {
+    ///     let head = format!("HTTP/1.1 200 OK\r\ncontent-length: {}\r\n\r\n", body.len());
+    ///     stream
+    ///         .write_all_vectored(vec![Buffer::from(head.into_bytes()), Buffer::from(body)])
+    ///         .await
+    ///         .expect("Failed to write");
+    /// });
+    /// # }
+    /// ```
+    pub async fn write_all_vectored(&self, bufs: Vec<crate::buffer::Buffer>) -> io::Result<()> {
+        // Shared slices can be resubmitted without copying
+        let mut remaining: Vec<bytes::Bytes> = bufs
+            .into_iter()
+            .map(crate::buffer::Buffer::into_bytes)
+            .filter(|buf| !buf.is_empty())
+            .collect();
+        while !remaining.is_empty() {
+            let bufs = remaining.iter().cloned().map(Into::into).collect();
+            match self.write_vectored(bufs).await {
+                Ok(0) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::WriteZero,
+                        "failed to write whole buffer",
+                    ));
+                }
+                Ok(mut n) => {
+                    while n > 0 && n >= remaining[0].len() {
+                        n -= remaining.remove(0).len();
+                    }
+                    if n > 0 {
+                        remaining[0] = remaining[0].slice(n..);
+                    }
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
     /// Attempts to write an entire buffer into this writer.
     pub async fn write_all(&self, mut buf: &[u8]) -> io::Result<()> {
         while !buf.is_empty() {
@@ -127,6 +732,89 @@ impl AsyncTcpStream {
         }
         Ok(())
     }
+
+    /// Attempts to write an entire owned buffer into this writer.
+    ///
+    /// Like [`write_buf`](Self::write_buf), the data is never copied: after a
+    /// short write the remainder is resubmitted as a slice of the same
+    /// allocation. Large remainders go out with [`send_zc`](Self::send_zc).
+    pub async fn write_all_buf(&self, buf: impl Into<crate::buffer::Buffer>) -> io::Result<()> {
+        let mut remaining = buf.into().into_bytes();
+        while !remaining.is_empty() {
+            let written = if remaining.len() >= SEND_ZC_THRESHOLD {
+                self.send_zc(remaining.clone()).await
+            } else {
+                self.write_buf(remaining.clone()).await
+            };
+            match written {
+                Ok(0) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::WriteZero,
+                        "failed to write whole buffer",
+                    ));
+                }
+                Ok(n) => remaining = remaining.slice(n..),
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Shuts down the read, write, or both halves of this connection.
+    ///
+    /// Unlike dropping the stream, this takes effect even while a read
+    /// submitted to the backend still holds the socket open.
+    ///
+    /// # Arguments
+    ///
+    /// * `how` - Which halves to shut down
+    pub fn shutdown(&self, how: std::net::Shutdown) -> io::Result<()> {
+        self.inner.shutdown(how)
+    }
+}
+
+/// Stream of the data received on an [`AsyncTcpStream`], returned by
+/// [`AsyncTcpStream::recv_stream`].
+#[derive(Debug)]
+pub struct RecvStream<'a> {
+    stream: &'a AsyncTcpStream,
+    /// The multishot receive, started on the first poll.
+    recvs: Option<IoStream>,
+    done: bool,
+}
+
+impl Stream for RecvStream<'_> {
+    type Item = io::Result<crate::buffer::Buffer>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+        let fd = self.stream.inner.as_raw_fd();
+        let recvs = self
+            .recvs
+            .get_or_insert_with(|| IoStream::submit(Op::RecvMulti { fd }));
+        let result = match Pin::new(recvs).poll_next(cx) {
+            Poll::Ready(Some(result)) => result,
+            Poll::Ready(None) => return Poll::Ready(None),
+            Poll::Pending => return Poll::Pending,
+        };
+
+        let item = match result {
+            Ok(CompletionKind::Read { bytes_read: 0, .. }) => None,
+            Ok(CompletionKind::Read { data, .. }) => return Poll::Ready(Some(Ok(data))),
+            Ok(_) => Some(Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Unexpected completion kind",
+            ))),
+            Err(e) => Some(Err(e.into())),
+        };
+        // End of file and errors end the multishot receive
+        self.done = true;
+        self.recvs = None;
+        Poll::Ready(item)
+    }
 }
 
 impl From<TcpStream> for AsyncTcpStream {
@@ -168,29 +856,50 @@ impl IntoRawFd for AsyncTcpStream {
 ///
 /// let runtime = Runtime::new();
 /// runtime.block_on(async {
-///     // Bind to a local address
-///     let addr: SocketAddr = "127.0.0.1:8080".parse().unwrap();
-///     let socket = AsyncUdpSocket::bind(addr).expect("Failed to bind socket");
-///     
-///     // Send data to a remote address
-///     let remote_addr: SocketAddr = "127.0.0.1:9090".parse().unwrap();
+///     // Bind two sockets to ephemeral local ports
+///     let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+///     let sender = AsyncUdpSocket::bind(addr).expect("Failed to bind socket");
+///     let receiver = AsyncUdpSocket::bind(addr).expect("Failed to bind socket");
+///
+///     // Send data to the receiver's address
 ///     let data = b"Hello, UDP!";
-///     let bytes_sent = socket.send_to(data, remote_addr).await.expect("Failed to send");
-///     
+///     let target = receiver.local_addr().unwrap();
+///     let bytes_sent = sender.send_to(data, target).await.expect("Failed to send");
+///     assert_eq!(bytes_sent, data.len());
+///
 ///     // Receive data from any address
 ///     let mut buf = [0; 1024];
-///     let (bytes_received, src_addr) = socket.recv_from(&mut buf).await.expect("Failed to receive");
+///     let (bytes_received, src_addr) = receiver.recv_from(&mut buf).await.expect("Failed to receive");
+///     assert_eq!(&buf[..bytes_received], data);
+///     assert_eq!(src_addr, sender.local_addr().unwrap());
 /// });
 /// ```
 #[derive(Debug)]
 pub struct AsyncUdpSocket {
     inner: UdpSocket,
+    gso: AtomicBool,
+}
+
+/// Socket options applied by [`AsyncUdpSocket::bind_with`] before or right after
+/// binding.
+///
+/// Everything is off by default, matching a plain `bind(2)`.
+#[derive(Debug, Clone, Default)]
+pub struct UdpBindOptions {
+    /// Set `SO_REUSEADDR` before binding.
+    pub reuse_address: bool,
+    /// Set `SO_REUSEPORT` before binding, so several sockets can share the address.
+    pub reuse_port: bool,
+    /// Set `SO_BROADCAST` so the socket may send to broadcast addresses.
+    pub broadcast: bool,
 }
 
 impl AsyncUdpSocket {
     /// Creates a UDP socket bound to the specified address.
     ///
     /// This function creates a new UDP socket and binds it to the specified address.
+    /// No socket options are set; use [`bind_with`](Self::bind_with) for
+    /// `SO_REUSEADDR`, `SO_REUSEPORT` or `SO_BROADCAST`.
     ///
     /// # Arguments
     ///
@@ -213,34 +922,62 @@ impl AsyncUdpSocket {
     pub fn bind<A: Into<SocketAddr>>(addr: A) -> io::Result<Self> {
         let socket = UdpSocket::bind(addr.into())?;
         socket.set_nonblocking(true)?;
+        Ok(Self {
+            inner: socket,
+            gso: AtomicBool::new(false),
+        })
+    }
 
-        // Set SO_REUSEADDR to allow rebinding to the same address
-        #[cfg(target_os = "linux")]
-        {
-            use std::os::unix::prelude::*;
-            unsafe {
-                let reuseaddr = libc::SO_REUSEADDR;
-                let result = libc::setsockopt(
-                    socket.as_raw_fd(),
-                    libc::SOL_SOCKET,
-                    reuseaddr,
-                    &1 as *const _ as *const _,
-                    std::mem::size_of::<i32>() as libc::socklen_t,
-                );
-                if result != 0 {
-                    // Don't fail if SO_REUSEADDR fails, just continue
-                    eprintln!(
-                        "Warning: Failed to set SO_REUSEADDR: {}",
-                        io::Error::last_os_error()
-                    );
-                }
-            }
-        }
+    /// Creates a UDP socket bound to the specified address with the given options.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - The address to bind the socket to
+    /// * `options` - Socket options to apply
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_miniss::net::{AsyncUdpSocket, UdpBindOptions};
+    /// use std::net::SocketAddr;
+    ///
+    /// let addr: SocketAddr = "0.0.0.0:0".parse().unwrap();
+    /// let options = UdpBindOptions {
+    ///     broadcast: true,
+    ///     ..Default::default()
+    /// };
+    /// let socket = AsyncUdpSocket::bind_with(addr, &options).expect("Failed to bind socket");
+    /// assert!(socket.broadcast().unwrap());
+    /// ```
+    pub fn bind_with<A: Into<SocketAddr>>(addr: A, options: &UdpBindOptions) -> io::Result<Self> {
+        use socket2::{Domain, Socket, Type};
 
-        // Set SO_BROADCAST for UDP sockets
-        socket.set_broadcast(true)?;
+        let addr = addr.into();
+        let socket = Socket::new(Domain::for_address(addr), Type::DGRAM, None)?;
+        if options.reuse_address {
+            socket.set_reuse_address(true)?;
+        }
+        if options.reuse_port {
+            set_reuse_port(&socket)?;
+        }
+        socket.bind(&addr.into())?;
+        socket.set_nonblocking(true)?;
+        if options.broadcast {
+            socket.set_broadcast(true)?;
+        }
+        Ok(Self {
+            inner: socket.into(),
+            gso: AtomicBool::new(false),
+        })
+    }
 
-        Ok(Self { inner: socket })
+    /// Connects this socket to a remote address.
+    ///
+    /// UDP is connectionless, so this only records the default destination for
+    /// [`send`](Self::send) and makes the kernel drop datagrams from any other
+    /// source. It does not block.
+    pub fn connect<A: Into<SocketAddr>>(&self, addr: A) -> io::Result<()> {
+        self.inner.connect(addr.into())
     }
 
     /// Sends data to the specified address.
@@ -271,24 +1008,38 @@ impl AsyncUdpSocket {
     ///     let remote_addr: SocketAddr = "127.0.0.1:9090".parse().unwrap();
     ///     let data = b"Hello, UDP!";
     ///     let bytes_sent = socket.send_to(data, remote_addr).await.expect("Failed to send");
-    ///
-    ///     // Receive data from any address
-    ///     let mut buf = [0; 1024];
-    ///     let (bytes_received, src_addr) = socket.recv_from(&mut buf).await.expect("Failed to receive");
+    ///     assert_eq!(bytes_sent, data.len());
     /// });
     /// ```
-    pub async fn send_to<A: Into<SocketAddr> + std::net::ToSocketAddrs>(
-        &self,
-        buf: &[u8],
-        target: A,
-    ) -> io::Result<usize> {
-        // Use synchronous send for now to avoid io_uring UDP compatibility issues
-        // This is a workaround until the io_uring UDP implementation is fully fixed
-        use std::os::unix::io::FromRawFd;
-        let socket = unsafe { std::net::UdpSocket::from_raw_fd(self.inner.as_raw_fd()) };
-        let result = socket.send_to(buf, target);
-        std::mem::forget(socket); // Don't drop the socket as we don't own the fd
-        result
+    pub async fn send_to<A: Into<SocketAddr>>(&self, buf: &[u8], target: A) -> io::Result<usize> {
+        self.send_inner(buf, Some(target.into())).await
+    }
+
+    /// Sends data to the connected peer.
+    ///
+    /// The socket must have been connected with [`connect`](Self::connect).
+    pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.send_inner(buf, None).await
+    }
+
+    async fn send_inner(&self, buf: &[u8], addr: Option<SocketAddr>) -> io::Result<usize> {
+        let state = io_state();
+        let op = Op::UdpSend {
+            fd: self.inner.as_raw_fd(),
+            data: crate::buffer::Buffer::from_slice(buf),
+            addr,
+        };
+        let token = state.io_backend.submit(op);
+        let future = IoFuture::new(token);
+
+        match future.await {
+            Ok(CompletionKind::UdpSend { bytes_written, .. }) => Ok(bytes_written),
+            Ok(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Unexpected completion kind for UdpSend",
+            )),
+            Err(e) => Err(e.into()),
+        }
     }
 
     /// Receives data from any address.
@@ -307,20 +1058,38 @@ impl AsyncUdpSocket {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
     /// use rust_miniss::{net::AsyncUdpSocket, Runtime};
     /// use std::net::SocketAddr;
     ///
     /// let runtime = Runtime::new();
     /// runtime.block_on(async {
-    ///     let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    ///     let addr: SocketAddr = "127.0.0.1:9090".parse().unwrap();
     ///     let socket = AsyncUdpSocket::bind(addr).expect("Failed to bind socket");
-    ///     
+    ///
     ///     let mut buf = [0; 1024];
     ///     let (bytes_received, src_addr) = socket.recv_from(&mut buf).await.expect("Failed to receive");
     /// });
     /// ```
     pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        self.recv_inner(buf, 0).await
+    }
+
+    /// Receives data from the connected peer.
+    ///
+    /// The socket must have been connected with [`connect`](Self::connect).
+    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.recv_inner(buf, 0).await.map(|(n, _)| n)
+    }
+
+    /// Receives data from any address without removing it from the queue.
+    ///
+    /// A subsequent `recv_from` returns the same datagram.
+    pub async fn peek_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        self.recv_inner(buf, libc::MSG_PEEK).await
+    }
+
+    async fn recv_inner(&self, buf: &mut [u8], flags: i32) -> io::Result<(usize, SocketAddr)> {
         let state = io_state();
         // Get a buffer from the pool to receive into.
         let buffer_for_recv = crate::buffer::BufferPool::get(buf.len());
@@ -328,6 +1097,7 @@ impl AsyncUdpSocket {
         let op = Op::UdpRecv {
             fd: self.inner.as_raw_fd(),
             buffer: buffer_for_recv,
+            flags,
         };
         let token = state.io_backend.submit(op);
         let future = IoFuture::new(token);
@@ -352,6 +1122,164 @@ impl AsyncUdpSocket {
         }
     }
 
+    /// Receives up to `max_datagrams` datagrams in a single operation.
+    ///
+    /// Waits until at least one datagram is available, then returns everything
+    /// queued up to the limit. Uses `recvmmsg(2)` on epoll and a multishot
+    /// `RECVMSG` on io_uring, so a burst costs one operation instead of one
+    /// per datagram.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_datagrams` - Upper bound on the datagrams read from the socket
+    /// * `buf_size` - Receive size per datagram; longer datagrams are truncated.
+    ///   With [`set_gro`](Self::set_gro) enabled this should be 65535, since a
+    ///   single coalesced receive can hold many datagrams.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<Datagram>)` - The received datagrams, in arrival order. Coalesced
+    ///   GRO receives are split back into the original datagrams, so this may
+    ///   hold more than `max_datagrams` entries.
+    /// * `Err(io::Error)` - Failed to receive data
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rust_miniss::{net::AsyncUdpSocket, Runtime};
+    /// use std::net::SocketAddr;
+    ///
+    /// let runtime = Runtime::new();
+    /// runtime.block_on(async {
+    ///     let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    ///     let sender = AsyncUdpSocket::bind(addr).unwrap();
+    ///     let receiver = AsyncUdpSocket::bind(addr).unwrap();
+    ///     let target = receiver.local_addr().unwrap();
+    ///
+    ///     let batch = [(&b"one"[..], target), (&b"two"[..], target)];
+    ///     assert_eq!(sender.send_batch(&batch).await.unwrap(), 2);
+    ///
+    ///     let mut received = Vec::new();
+    ///     while received.len() < 2 {
+    ///         received.extend(receiver.recv_batch(32, 1500).await.unwrap());
+    ///     }
+    ///     assert_eq!(&received[0].data[..], b"one");
+    ///     assert_eq!(&received[1].data[..], b"two");
+    /// });
+    /// ```
+    pub async fn recv_batch(
+        &self,
+        max_datagrams: usize,
+        buf_size: usize,
+    ) -> io::Result<Vec<Datagram>> {
+        let state = io_state();
+        let op = Op::UdpRecvBatch {
+            fd: self.inner.as_raw_fd(),
+            max_datagrams,
+            buf_size,
+        };
+        let token = state.io_backend.submit(op);
+        let future = IoFuture::new(token);
+
+        match future.await {
+            Ok(CompletionKind::UdpRecvBatch { datagrams }) => Ok(datagrams),
+            Ok(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Unexpected completion kind for UdpRecvBatch",
+            )),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Sends a batch of datagrams in a single operation.
+    ///
+    /// Uses `sendmmsg(2)` on epoll and linked `SENDMSG`s on io_uring. With
+    /// [`set_gso`](Self::set_gso) enabled, consecutive datagrams to the same
+    /// destination are coalesced into `UDP_SEGMENT` sends.
+    ///
+    /// # Arguments
+    ///
+    /// * `datagrams` - `(payload, destination)` pairs, sent in order
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(usize)` - Number of datagrams sent. Sending stops at the first
+    ///   failure, so this may be less than `datagrams.len()`.
+    /// * `Err(io::Error)` - Not even the first datagram could be sent
+    pub async fn send_batch(&self, datagrams: &[(&[u8], SocketAddr)]) -> io::Result<usize> {
+        // Backends may send only part of a batch, e.g. when it does not fit the
+        // io_uring submission queue; keep going until everything is out.
+        let mut sent = 0;
+        while sent < datagrams.len() {
+            match self.send_batch_once(&datagrams[sent..]).await {
+                Ok(0) => break,
+                Ok(n) => sent += n,
+                Err(e) if sent == 0 => return Err(e),
+                Err(_) => break,
+            }
+        }
+        Ok(sent)
+    }
+
+    async fn send_batch_once(&self, datagrams: &[(&[u8], SocketAddr)]) -> io::Result<usize> {
+        let (messages, counts) = build_udp_messages(datagrams, self.gso());
+
+        let state = io_state();
+        let op = Op::UdpSendBatch {
+            fd: self.inner.as_raw_fd(),
+            messages,
+        };
+        let token = state.io_backend.submit(op);
+        let future = IoFuture::new(token);
+
+        match future.await {
+            Ok(CompletionKind::UdpSendBatch { messages_sent, .. }) => {
+                Ok(counts.iter().take(messages_sent).sum())
+            }
+            Ok(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Unexpected completion kind for UdpSendBatch",
+            )),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Enables or disables UDP segmentation offload for
+    /// [`send_batch`](Self::send_batch).
+    ///
+    /// Coalesced datagrams are split by the kernel (or the NIC), so each of them
+    /// must fit the path MTU.
+    #[cfg(target_os = "linux")]
+    pub fn set_gso(&self, on: bool) {
+        self.gso.store(on, Ordering::Relaxed);
+    }
+
+    /// Returns whether [`send_batch`](Self::send_batch) coalesces datagrams with
+    /// UDP segmentation offload.
+    pub fn gso(&self) -> bool {
+        self.gso.load(Ordering::Relaxed)
+    }
+
+    /// Sets `UDP_GRO`, letting the kernel coalesce incoming datagrams of the same
+    /// flow. [`recv_batch`](Self::recv_batch) splits them up again.
+    #[cfg(target_os = "linux")]
+    pub fn set_gro(&self, on: bool) -> io::Result<()> {
+        let optval: libc::c_int = on.into();
+        let result = unsafe {
+            libc::setsockopt(
+                self.inner.as_raw_fd(),
+                libc::SOL_UDP,
+                libc::UDP_GRO,
+                &optval as *const _ as *const _,
+                std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+            )
+        };
+        if result != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
     /// Returns the socket address that this socket was bound to.
     ///
     /// # Returns
@@ -361,6 +1289,141 @@ impl AsyncUdpSocket {
     pub fn local_addr(&self) -> io::Result<SocketAddr> {
         self.inner.local_addr()
     }
+
+    /// Returns the address of the connected peer.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.peer_addr()
+    }
+
+    /// Sets `SO_BROADCAST`, allowing sends to broadcast addresses.
+    pub fn set_broadcast(&self, on: bool) -> io::Result<()> {
+        self.inner.set_broadcast(on)
+    }
+
+    /// Returns whether `SO_BROADCAST` is set.
+    pub fn broadcast(&self) -> io::Result<bool> {
+        self.inner.broadcast()
+    }
+
+    /// Sets the `IP_TTL` of outgoing unicast datagrams.
+    pub fn set_ttl(&self, ttl: u32) -> io::Result<()> {
+        self.inner.set_ttl(ttl)
+    }
+
+    /// Returns the `IP_TTL` of outgoing unicast datagrams.
+    pub fn ttl(&self) -> io::Result<u32> {
+        self.inner.ttl()
+    }
+
+    /// Sets the `IP_MULTICAST_TTL` of outgoing IPv4 multicast datagrams.
+    pub fn set_multicast_ttl_v4(&self, ttl: u32) -> io::Result<()> {
+        self.inner.set_multicast_ttl_v4(ttl)
+    }
+
+    /// Sets whether IPv4 multicast datagrams are looped back to local sockets.
+    pub fn set_multicast_loop_v4(&self, on: bool) -> io::Result<()> {
+        self.inner.set_multicast_loop_v4(on)
+    }
+
+    /// Sets whether IPv6 multicast datagrams are looped back to local sockets.
+    pub fn set_multicast_loop_v6(&self, on: bool) -> io::Result<()> {
+        self.inner.set_multicast_loop_v6(on)
+    }
+
+    /// Joins an IPv4 multicast group on the given local interface.
+    pub fn join_multicast_v4(&self, multiaddr: &Ipv4Addr, interface: &Ipv4Addr) -> io::Result<()> {
+        self.inner.join_multicast_v4(multiaddr, interface)
+    }
+
+    /// Leaves an IPv4 multicast group on the given local interface.
+    pub fn leave_multicast_v4(&self, multiaddr: &Ipv4Addr, interface: &Ipv4Addr) -> io::Result<()> {
+        self.inner.leave_multicast_v4(multiaddr, interface)
+    }
+
+    /// Joins an IPv6 multicast group on the interface with the given index
+    /// (0 lets the kernel choose).
+    pub fn join_multicast_v6(&self, multiaddr: &Ipv6Addr, interface: u32) -> io::Result<()> {
+        self.inner.join_multicast_v6(multiaddr, interface)
+    }
+
+    /// Leaves an IPv6 multicast group on the interface with the given index.
+    pub fn leave_multicast_v6(&self, multiaddr: &Ipv6Addr, interface: u32) -> io::Result<()> {
+        self.inner.leave_multicast_v6(multiaddr, interface)
+    }
+}
+
+/// Sets `SO_REUSEPORT`, which socket2 only exposes behind its `all` feature.
+fn set_reuse_port(socket: &socket2::Socket) -> io::Result<()> {
+    let optval: libc::c_int = 1;
+    let result = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_REUSEPORT,
+            &optval as *const _ as *const _,
+            std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Turns `(payload, destination)` pairs into the messages of a batched send,
+/// along with how many datagrams each message carries.
+///
+/// With `gso`, runs of datagrams to the same destination become one
+/// `UDP_SEGMENT` message: all but the last must be exactly the segment size, and
+/// the run is capped by the kernel's segment and payload limits.
+fn build_udp_messages(
+    datagrams: &[(&[u8], SocketAddr)],
+    gso: bool,
+) -> (Vec<UdpMessage>, Vec<usize>) {
+    let mut messages = Vec::new();
+    let mut counts = Vec::new();
+    let mut i = 0;
+    while i < datagrams.len() {
+        let (first, addr) = datagrams[i];
+        let mut end = i + 1;
+        if gso && !first.is_empty() {
+            let mut total = first.len();
+            while end < datagrams.len()
+                && end - i < UDP_MAX_GSO_SEGMENTS
+                && datagrams[end].1 == addr
+                && datagrams[end - 1].0.len() == first.len()
+                && !datagrams[end].0.is_empty()
+                && datagrams[end].0.len() <= first.len()
+                && total + datagrams[end].0.len() <= UDP_MAX_PAYLOAD
+            {
+                total += datagrams[end].0.len();
+                end += 1;
+            }
+        }
+
+        let run = &datagrams[i..end];
+        let message = if run.len() == 1 {
+            UdpMessage {
+                data: crate::buffer::Buffer::from_slice(first),
+                addr: Some(addr),
+                segment_size: None,
+            }
+        } else {
+            let payload: Vec<u8> = run
+                .iter()
+                .flat_map(|(data, _)| data.iter().copied())
+                .collect();
+            UdpMessage {
+                data: crate::buffer::Buffer::from_slice(&payload),
+                addr: Some(addr),
+                segment_size: Some(first.len() as u16),
+            }
+        };
+        messages.push(message);
+        counts.push(run.len());
+        i = end;
+    }
+    (messages, counts)
 }
 
 impl AsRawFd for AsyncUdpSocket {
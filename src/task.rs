@@ -97,6 +97,12 @@ impl<T> JoinHandle<T> {
         self.task_id
     }
 
+    /// Slot holding the waker of whoever awaits this handle. The spawned task
+    /// wakes it after sending its result.
+    pub(crate) fn waiter(&self) -> Arc<Mutex<Option<Waker>>> {
+        self.waker.clone()
+    }
+
     /// Check if the task has completed
     pub fn is_finished(&self) -> bool {
         matches!(
@@ -138,7 +144,12 @@ impl<T: Send> Future for JoinHandle<T> {
             Err(TryRecvError::Empty) => {
                 // Store the waker so the sender can wake us when result is ready
                 *self.waker.lock().unwrap() = Some(cx.waker().clone());
-                Poll::Pending
+                // The result may have been sent before the waker was stored
+                match self.receiver.try_recv() {
+                    Ok(result) => Poll::Ready(result),
+                    Err(TryRecvError::Empty) => Poll::Pending,
+                    Err(TryRecvError::Disconnected) => Poll::Ready(Err(TaskError::Cancelled)),
+                }
             }
             Err(TryRecvError::Disconnected) => Poll::Ready(Err(TaskError::Cancelled)),
         }
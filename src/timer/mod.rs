@@ -1,4 +1,5 @@
-use std::collections::VecDeque;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
 use std::task::Waker;
 use std::time::{Duration, Instant};
 
@@ -50,6 +51,71 @@ where
     Timeout::new(future, duration).await
 }
 
+/// Sleeps pending on one thread, ordered by deadline.
+#[derive(Default)]
+struct LocalTimers {
+    next_key: u64,
+    pending: BTreeMap<(Instant, u64), Waker>,
+}
+
+thread_local! {
+    /// The run loop of this thread wakes expired sleeps and bounds its reactor
+    /// wait by the earliest deadline, so sleeping tasks cost nothing while idle.
+    static LOCAL_TIMERS: RefCell<LocalTimers> = RefCell::new(LocalTimers::default());
+}
+
+/// Registers (or re-registers with a new waker) a sleep on the current thread.
+/// `key` identifies the registration and is assigned on first use.
+pub(crate) fn register_local(at: Instant, key: &mut Option<u64>, waker: &Waker) {
+    LOCAL_TIMERS.with(|timers| {
+        let mut timers = timers.borrow_mut();
+        let id = *key.get_or_insert_with(|| {
+            timers.next_key += 1;
+            timers.next_key
+        });
+        match timers.pending.get_mut(&(at, id)) {
+            Some(existing) if existing.will_wake(waker) => {}
+            Some(existing) => existing.clone_from(waker),
+            None => {
+                timers.pending.insert((at, id), waker.clone());
+            }
+        }
+    });
+}
+
+/// Removes a registration made by [`register_local`] on this thread.
+pub(crate) fn cancel_local(at: Instant, key: u64) {
+    // Ignore drops during thread teardown, after the map is gone.
+    let _ = LOCAL_TIMERS.try_with(|timers| timers.borrow_mut().pending.remove(&(at, key)));
+}
+
+/// Wakes every sleep on the current thread whose deadline is at or before
+/// `now`. Returns true if any were woken.
+pub(crate) fn wake_expired(now: Instant) -> bool {
+    let expired = LOCAL_TIMERS.with(|timers| {
+        let mut timers = timers.borrow_mut();
+        let later = timers.pending.split_off(&(now, u64::MAX));
+        std::mem::replace(&mut timers.pending, later)
+    });
+    let woke = !expired.is_empty();
+    for waker in expired.into_values() {
+        waker.wake();
+    }
+    woke
+}
+
+/// How long a run loop may block before the next sleep on this thread is due:
+/// `None` if there is none, zero if one is already due.
+pub(crate) fn time_until_next_deadline() -> Option<Duration> {
+    LOCAL_TIMERS.with(|timers| {
+        timers
+            .borrow()
+            .pending
+            .first_key_value()
+            .map(|(&(at, _), _)| at.saturating_duration_since(Instant::now()))
+    })
+}
+
 /// Add timeout combinator to Future trait
 pub trait FutureExt: std::future::Future + Sized {
     fn with_timeout(self, duration: Duration) -> Timeout<Self> {
@@ -1,4 +1,3 @@
-use super::TimerWheel;
 use std::future::Future;
 use std::pin::Pin;
 use std::task::{Context, Poll};
@@ -6,30 +5,35 @@ use std::time::{Duration, Instant};
 
 use std::marker::PhantomPinned;
 
+/// Completes once `duration` has passed.
+///
+/// While pending, the deadline is registered with the polling thread's run
+/// loop, which wakes the task when it is due.
 pub struct SleepFuture {
-    wheel: Option<TimerWheel>,
     end_time: Instant,
+    /// Key of the registration with the thread's timers, once polled.
+    key: Option<u64>,
     _pin: PhantomPinned,
 }
 
 impl SleepFuture {
     pub fn new(duration: Duration) -> Self {
-        let wheel = TimerWheel::default();
         let end_time = Instant::now() + duration;
         Self {
-            wheel: Some(wheel),
             end_time,
+            key: None,
             _pin: PhantomPinned,
         }
     }
 
     fn poll_inner(&mut self, cx: &mut Context<'_>) -> Poll<()> {
         if Instant::now() >= self.end_time {
+            if let Some(key) = self.key.take() {
+                super::cancel_local(self.end_time, key);
+            }
             Poll::Ready(())
         } else {
-            if let Some(ref mut wheel) = self.wheel {
-                wheel.schedule(self.end_time, cx.waker().clone());
-            }
+            super::register_local(self.end_time, &mut self.key, cx.waker());
             Poll::Pending
         }
     }
@@ -43,3 +47,11 @@ impl Future for SleepFuture {
         this.poll_inner(cx)
     }
 }
+
+impl Drop for SleepFuture {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            super::cancel_local(self.end_time, key);
+        }
+    }
+}
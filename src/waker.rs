@@ -3,6 +3,7 @@
 //! This module provides a custom Waker that integrates with our executor
 //! to schedule tasks when they become ready.
 
+use crate::io::Notifier;
 use crossbeam_queue::SegQueue;
 use std::sync::Arc;
 use std::task::{RawWaker, RawWakerVTable, Waker};
@@ -21,12 +22,27 @@ impl TaskId {
 pub struct MinissWaker {
     task_id: TaskId,
     queue: Arc<SegQueue<TaskId>>,
+    notifier: Option<Arc<Notifier>>,
 }
 
 impl MinissWaker {
     /// Create a new waker for the given task
     pub fn create_waker(task_id: TaskId, queue: Arc<SegQueue<TaskId>>) -> Waker {
-        let waker = Arc::new(MinissWaker { task_id, queue });
+        Self::create_waker_with_notifier(task_id, queue, None)
+    }
+
+    /// Create a waker that also signals `notifier`, so a run loop blocked in
+    /// its I/O backend wakes up to poll the task.
+    pub fn create_waker_with_notifier(
+        task_id: TaskId,
+        queue: Arc<SegQueue<TaskId>>,
+        notifier: Option<Arc<Notifier>>,
+    ) -> Waker {
+        let waker = Arc::new(MinissWaker {
+            task_id,
+            queue,
+            notifier,
+        });
         let raw_waker = RawWaker::new(Arc::into_raw(waker) as *const (), &VTABLE);
         unsafe { Waker::from_raw(raw_waker) }
     }
@@ -34,6 +50,9 @@ impl MinissWaker {
     /// Wake the task by adding it to the run queue
     fn wake_impl(&self) {
         self.queue.push(self.task_id);
+        if let Some(notifier) = &self.notifier {
+            notifier.notify();
+        }
     }
 }
 
@@ -138,4 +157,19 @@ mod tests {
         waker.wake();
         assert_eq!(queue.pop(), Some(task_id));
     }
+
+    #[test]
+    fn test_waker_signals_notifier() {
+        let queue = Arc::new(SegQueue::new());
+        let notifier = Arc::new(Notifier::new().unwrap());
+        let task_id = TaskId(7);
+
+        let waker =
+            MinissWaker::create_waker_with_notifier(task_id, queue.clone(), Some(notifier.clone()));
+        assert!(!notifier.is_notified());
+
+        waker.wake();
+        assert_eq!(queue.pop(), Some(task_id));
+        assert!(notifier.is_notified());
+    }
 }
@@ -103,3 +103,675 @@ fn test_async_file_create_write() {
         assert_eq!(contents, test_data);
     });
 }
+
+#[test]
+fn test_async_file_owned_buffers() {
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let temp_path = temp_dir.path().join("owned.txt");
+        let async_file = AsyncFile::create(&temp_path).expect("Failed to create file");
+
+        // Bytes and Vec<u8> are handed over without copying
+        let written = async_file
+            .write_at_buf(0, bytes::Bytes::from_static(b"owned "))
+            .await
+            .expect("Failed to write Bytes");
+        assert_eq!(written, 6);
+        let written = async_file
+            .write_at_buf(6, b"buffers".to_vec())
+            .await
+            .expect("Failed to write Vec");
+        assert_eq!(written, 7);
+
+        let reader = AsyncFile::open(&temp_path).expect("Failed to open file");
+        let buf = rust_miniss::Buffer::with_capacity(64);
+        let (bytes_read, buf) = reader
+            .read_at_into(0, buf)
+            .await
+            .expect("Failed to read into buffer");
+        assert_eq!(bytes_read, 13);
+        assert_eq!(&buf[..], b"owned buffers");
+
+        // The same buffer can be reused for the next read
+        let ptr = buf.as_ptr();
+        let (bytes_read, buf) = reader.read_at_into(6, buf).await.unwrap();
+        assert_eq!(&buf[..bytes_read], b"buffers");
+        assert_eq!(buf.as_ptr(), ptr);
+    });
+}
+
+#[cfg(io_backend = "io_uring")]
+#[test]
+fn test_async_file_fixed_buffers() {
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let temp_path = temp_dir.path().join("fixed.txt");
+        let async_file = AsyncFile::create(&temp_path).expect("Failed to create file");
+
+        // On a runtime thread the pool hands out buffers registered with the ring
+        let mut buf = rust_miniss::BufferPool::get(11);
+        assert!(buf.is_registered());
+        assert_eq!(&buf[..], &[0; 11]);
+        buf.copy_from_slice(b"fixed write");
+        let written = async_file
+            .write_at_buf(0, buf)
+            .await
+            .expect("Failed to write fixed buffer");
+        assert_eq!(written, 11);
+
+        let reader = AsyncFile::open(&temp_path).expect("Failed to open file");
+        let (bytes_read, data) = reader.read_at(0, 64).await.expect("Failed to read");
+        assert_eq!(bytes_read, 11);
+        assert_eq!(&data[..], b"fixed write");
+        assert!(data.is_registered());
+
+        let buf = rust_miniss::BufferPool::get(64);
+        let (bytes_read, buf) = reader
+            .read_at_into(6, buf)
+            .await
+            .expect("Failed to read into fixed buffer");
+        assert_eq!(&buf[..bytes_read], b"write");
+    });
+}
+
+#[test]
+fn test_async_file_write_at_synced() {
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let temp_path = temp_dir.path().join("synced.txt");
+        let async_file = AsyncFile::create(&temp_path).expect("Failed to create file");
+
+        let written = async_file
+            .write_at_synced(0, b"durable".to_vec())
+            .await
+            .expect("Failed to write and sync");
+        assert_eq!(written, 7);
+        assert_eq!(std::fs::read(&temp_path).unwrap(), b"durable");
+
+        // A failing write cancels the linked sync and reports the write error
+        let read_only = AsyncFile::open(&temp_path).expect("Failed to open file");
+        let err = read_only
+            .write_at_synced(0, b"nope".to_vec())
+            .await
+            .expect_err("Write to a read-only file succeeded");
+        assert_eq!(err.raw_os_error(), Some(libc::EBADF));
+    });
+}
+
+#[test]
+fn test_async_file_submit_batch() {
+    use rust_miniss::io::{future::IoFuture, CompletionKind, Op};
+    use std::os::unix::io::AsRawFd;
+
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let temp_path = temp_dir.path().join("batch.txt");
+        let async_file = AsyncFile::create(&temp_path).expect("Failed to create file");
+        let fd = async_file.as_raw_fd();
+
+        let ops = [b"aaaa", b"bbbb", b"cccc"]
+            .iter()
+            .enumerate()
+            .map(|(i, chunk)| Op::WriteFile {
+                fd,
+                offset: i as u64 * 4,
+                data: chunk.to_vec().into(),
+            })
+            .collect();
+        let tokens = rust_miniss::cpu::io_state().io_backend.submit_batch(ops);
+        assert_eq!(tokens.len(), 3);
+
+        // Every future exists before the first is awaited
+        let futures: Vec<IoFuture> = tokens.into_iter().map(IoFuture::new).collect();
+        for future in futures {
+            match future.await {
+                Ok(CompletionKind::WriteFile { bytes_written }) => assert_eq!(bytes_written, 4),
+                other => panic!("Unexpected completion: {:?}", other),
+            }
+        }
+        assert_eq!(std::fs::read(&temp_path).unwrap(), b"aaaabbbbcccc");
+    });
+}
+
+#[cfg(io_backend = "io_uring")]
+#[test]
+fn test_submissions_that_do_not_fit_complete_with_ebusy() {
+    use rust_miniss::io::uring::UringBackend;
+    use rust_miniss::io::{CompletionKind, IoError, IoProvider, Op};
+    use std::collections::HashMap;
+    use std::os::unix::io::AsRawFd;
+    use std::task::{Context, Poll};
+
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let file = std::fs::File::create(temp_dir.path().join("ebusy.txt")).unwrap();
+    let writes = |count: u64| -> Vec<Op> {
+        (0..count)
+            .map(|i| Op::WriteFile {
+                fd: file.as_raw_fd(),
+                offset: i,
+                data: b"x".to_vec().into(),
+            })
+            .collect()
+    };
+    let backend = UringBackend::new(4).unwrap();
+    let complete = |count: usize| {
+        let waker = futures::task::noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        let mut results = HashMap::new();
+        while results.len() < count {
+            match backend.poll_complete(&mut cx) {
+                Poll::Ready(completions) => {
+                    results.extend(completions.into_iter().map(|(token, _, res)| (token, res)))
+                }
+                Poll::Pending => backend.wait(Some(std::time::Duration::from_millis(10))),
+            }
+        }
+        results
+    };
+
+    // A chain longer than the queue is rejected whole rather than split
+    let tokens = backend.submit_linked(writes(6));
+    let mut results = complete(6);
+    for token in tokens {
+        match results.remove(&token).unwrap() {
+            Err(IoError::Io(e)) => assert_eq!(e.raw_os_error(), Some(libc::EBUSY)),
+            other => panic!("Unexpected completion: {:?}", other),
+        }
+    }
+    assert_eq!(file.metadata().unwrap().len(), 0);
+
+    // Independent operations flush the queue as it fills
+    let tokens = backend.submit_batch(writes(6));
+    let mut results = complete(6);
+    for token in tokens {
+        match results.remove(&token).unwrap() {
+            Ok(CompletionKind::WriteFile { bytes_written }) => assert_eq!(bytes_written, 1),
+            other => panic!("Unexpected completion: {:?}", other),
+        }
+    }
+    assert_eq!(file.metadata().unwrap().len(), 6);
+}
+
+#[cfg(all(target_os = "linux", io_backend = "epoll"))]
+#[test]
+fn test_epoll_file_io_round_trips_through_a_full_pool() {
+    use rust_miniss::io::epoll::EpollBackend;
+    use rust_miniss::io::{CompletionKind, IoProvider, Op};
+    use rust_miniss::{BlockingPool, BlockingPoolOptions};
+    use std::collections::HashMap;
+    use std::io::Seek;
+    use std::os::unix::io::AsRawFd;
+    use std::sync::Arc;
+    use std::task::{Context, Poll};
+
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let mut file = std::fs::File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(temp_dir.path().join("epoll.txt"))
+        .unwrap();
+    let fd = file.as_raw_fd();
+    // One thread and one queue slot, so most operations wait for room
+    let pool = Arc::new(BlockingPool::new(BlockingPoolOptions {
+        max_threads: 1,
+        queue_capacity: 1,
+        ..BlockingPoolOptions::default()
+    }));
+    let backend = EpollBackend::new(pool).unwrap();
+    let complete = |count: usize| {
+        let waker = futures::task::noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        let mut results = HashMap::new();
+        while results.len() < count {
+            match backend.poll_complete(&mut cx) {
+                Poll::Ready(completions) => {
+                    results.extend(completions.into_iter().map(|(token, _, res)| (token, res)))
+                }
+                Poll::Pending => backend.wait(Some(std::time::Duration::from_millis(10))),
+            }
+        }
+        results
+    };
+
+    let tokens: Vec<_> = (0..8u8)
+        .map(|i| {
+            backend.submit(Op::WriteFile {
+                fd,
+                offset: i as u64 * 2,
+                data: vec![b'a' + i; 2].into(),
+            })
+        })
+        .collect();
+    let mut results = complete(8);
+    for token in tokens {
+        match results.remove(&token).unwrap() {
+            Ok(CompletionKind::WriteFile { bytes_written }) => assert_eq!(bytes_written, 2),
+            other => panic!("Unexpected completion: {:?}", other),
+        }
+    }
+
+    let read = backend.submit(Op::ReadFile {
+        fd,
+        offset: 4,
+        len: 64,
+    });
+    let sync = backend.submit(Op::Fsync { fd });
+    let mut results = complete(2);
+    match results.remove(&read).unwrap() {
+        Ok(CompletionKind::ReadFile { bytes_read, data }) => {
+            assert_eq!(bytes_read, 12);
+            assert_eq!(&data[..], b"ccddeeffgghh");
+        }
+        other => panic!("Unexpected completion: {:?}", other),
+    }
+    assert!(matches!(
+        results.remove(&sync).unwrap(),
+        Ok(CompletionKind::Fsync)
+    ));
+
+    // The operations borrowed the descriptor: it is still open, and its
+    // offset untouched
+    assert_eq!(file.metadata().unwrap().len(), 16);
+    assert_eq!(file.stream_position().unwrap(), 0);
+}
+
+#[test]
+fn test_async_file_vectored() {
+    use rust_miniss::Buffer;
+
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let temp_path = temp_dir.path().join("vectored.txt");
+        let async_file = AsyncFile::create(&temp_path).expect("Failed to create file");
+
+        let bufs = vec![
+            Buffer::from(b"gather ".to_vec()),
+            Buffer::from(bytes::Bytes::from_static(b"and ")),
+            Buffer::from(b"scatter".to_vec()),
+        ];
+        let written = async_file
+            .write_vectored_at(2, bufs)
+            .await
+            .expect("Failed to write");
+        assert_eq!(written, 18);
+        assert_eq!(
+            std::fs::read(&temp_path).unwrap(),
+            b"\0\0gather and scatter"
+        );
+
+        let reader = AsyncFile::open(&temp_path).expect("Failed to open file");
+        let bufs = vec![Buffer::with_capacity(6), Buffer::with_capacity(64)];
+        let (bytes_read, bufs) = reader
+            .read_vectored_at(2, bufs)
+            .await
+            .expect("Failed to read");
+        assert_eq!(bytes_read, 18);
+        assert_eq!(&bufs[0][..], b"gather");
+        assert_eq!(&bufs[1][..], b" and scatter");
+    });
+}
+
+#[test]
+fn test_open_options() {
+    use rust_miniss::fs::OpenOptions;
+
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let path = temp_dir.path().join("options.txt");
+        let create_new = OpenOptions {
+            write: true,
+            create_new: true,
+            ..Default::default()
+        };
+
+        let file = create_new.open(&path).await.expect("Failed to create file");
+        file.write_at(0, b"first").await.expect("Failed to write");
+        // Read access is the default, so the new file reads back too
+        let (_, data) = file.read_at(0, 16).await.expect("Failed to read");
+        assert_eq!(&data[..], b"first");
+        let err = create_new.open(&path).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+
+        let append = OpenOptions {
+            read: false,
+            append: true,
+            ..Default::default()
+        };
+        let file = append.open(&path).await.expect("Failed to open for append");
+        file.write_at(0, b", second")
+            .await
+            .expect("Failed to append");
+        assert_eq!(std::fs::read(&path).unwrap(), b"first, second");
+
+        let truncate = OpenOptions {
+            write: true,
+            truncate: true,
+            ..Default::default()
+        };
+        truncate.open(&path).await.expect("Failed to truncate");
+        assert_eq!(std::fs::read(&path).unwrap(), b"");
+
+        let missing = OpenOptions::default()
+            .open(temp_dir.path().join("missing"))
+            .await
+            .unwrap_err();
+        assert_eq!(missing.kind(), std::io::ErrorKind::NotFound);
+        let invalid = OpenOptions {
+            create: true,
+            ..Default::default()
+        };
+        let err = invalid.open(&path).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    });
+}
+
+#[test]
+fn test_async_file_metadata_and_size() {
+    use rust_miniss::fs::{self, OpenOptions};
+
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let path = temp_dir.path().join("sized.bin");
+        let options = OpenOptions {
+            write: true,
+            create: true,
+            ..Default::default()
+        };
+        let file = options.open(&path).await.expect("Failed to create file");
+        file.write_at(0, b"0123456789")
+            .await
+            .expect("Failed to write");
+        file.sync_data().await.expect("Failed to sync data");
+
+        let metadata = file.metadata().await.expect("Failed to query file");
+        assert!(metadata.is_file());
+        assert!(!metadata.is_dir());
+        assert_eq!(metadata.len, 10);
+        assert_eq!(metadata, fs::metadata(&path).await.unwrap());
+
+        file.set_len(4).await.expect("Failed to truncate");
+        assert_eq!(file.metadata().await.unwrap().len, 4);
+        file.set_len(8).await.expect("Failed to extend");
+        assert_eq!(std::fs::read(&path).unwrap(), b"0123\0\0\0\0");
+
+        file.fallocate(0, 1 << 20)
+            .await
+            .expect("Failed to allocate");
+        let metadata = file.metadata().await.unwrap();
+        assert_eq!(metadata.len, 1 << 20);
+        assert!(metadata.blocks * 512 >= 1 << 20);
+
+        file.close().await.expect("Failed to close");
+    });
+}
+
+#[test]
+fn test_directory_operations() {
+    use rust_miniss::fs;
+
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let dir = temp_dir.path().join("dir");
+        fs::create_dir(&dir)
+            .await
+            .expect("Failed to create directory");
+        assert!(fs::metadata(&dir).await.unwrap().is_dir());
+        let err = fs::create_dir(&dir).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+
+        std::fs::write(dir.join("a.txt"), b"a").unwrap();
+        fs::rename(dir.join("a.txt"), dir.join("b.txt"))
+            .await
+            .expect("Failed to rename");
+        std::fs::create_dir(dir.join("sub")).unwrap();
+        std::os::unix::fs::symlink("b.txt", dir.join("link")).unwrap();
+        assert!(fs::symlink_metadata(dir.join("link"))
+            .await
+            .unwrap()
+            .is_symlink());
+        assert!(fs::metadata(dir.join("link")).await.unwrap().is_file());
+
+        let mut entries = fs::read_dir(&dir).await.expect("Failed to read directory");
+        entries.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+        let names: Vec<_> = entries.iter().map(|e| e.file_name.clone()).collect();
+        assert_eq!(names, ["b.txt", "link", "sub"]);
+        assert_eq!(entries[0].path, dir.join("b.txt"));
+        assert!(entries[0].file_type.is_file());
+        assert!(entries[1].file_type.is_symlink());
+        assert!(entries[2].file_type.is_dir());
+
+        // A directory with entries cannot be removed
+        let err = fs::remove_dir(&dir).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::DirectoryNotEmpty);
+        for name in ["b.txt", "link"] {
+            fs::remove_file(dir.join(name))
+                .await
+                .expect("Failed to remove file");
+        }
+        fs::remove_dir(dir.join("sub"))
+            .await
+            .expect("Failed to remove directory");
+        fs::remove_dir(&dir)
+            .await
+            .expect("Failed to remove directory");
+        let err = fs::metadata(&dir).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+        let err = fs::read_dir(&dir).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    });
+}
+
+#[test]
+fn test_direct_io() {
+    use rust_miniss::Buffer;
+    use std::io::ErrorKind;
+
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let path = temp_dir.path().join("direct.bin");
+        std::fs::write(&path, b"").expect("Failed to create file");
+        let file = match AsyncFile::open_direct(&path).await {
+            Ok(file) => file,
+            // Not every filesystem supports O_DIRECT
+            Err(e) if e.kind() == ErrorKind::InvalidInput => return,
+            Err(e) => panic!("Failed to open file for direct I/O: {e}"),
+        };
+        let align = file.direct_io_alignment().expect("Opened for direct I/O");
+        let metadata = file.metadata().await.expect("Failed to query file");
+        assert!(
+            metadata.dio_offset_align == 0 || metadata.dio_offset_align as usize == align.offset
+        );
+        let block = align.offset.max(align.memory);
+
+        let mut buf = file.alloc_dma_buffer(2 * block);
+        assert_eq!(buf.as_ptr() as usize % align.memory, 0);
+        buf.set_len(2 * block);
+        buf.fill(0xab);
+        let written = file.write_at_buf(0, buf).await.expect("Failed to write");
+        assert_eq!(written, 2 * block);
+        // Unaligned slices are copied into an aligned buffer
+        let written = file
+            .write_at(2 * block as u64, &vec![0xcd; block])
+            .await
+            .expect("Failed to write");
+        assert_eq!(written, block);
+
+        let (bytes_read, data) = file
+            .read_at(block as u64, 2 * block)
+            .await
+            .expect("Failed to read");
+        assert_eq!(bytes_read, 2 * block);
+        assert!(data.is_aligned_to(align.memory));
+        assert!(data[..block].iter().all(|&b| b == 0xab));
+        assert!(data[block..].iter().all(|&b| b == 0xcd));
+
+        let buf = Buffer::from(file.alloc_dma_buffer(block));
+        let (bytes_read, buf) = file.read_at_into(0, buf).await.expect("Failed to read");
+        assert_eq!(bytes_read, block);
+        assert!(buf.iter().all(|&b| b == 0xab));
+
+        // Misaligned transfers are rejected before reaching the kernel
+        let err = file.read_at(1, block).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let err = file.write_at(0, b"short").await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let bytes = bytes::Bytes::from(vec![0u8; 2 * block]);
+        let skip = if (bytes.as_ptr() as usize + 1).is_multiple_of(align.memory) {
+            2
+        } else {
+            1
+        };
+        let misaligned = Buffer::from(bytes.slice(skip..skip + block));
+        let err = file.write_at_buf(0, misaligned).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let err = file
+            .read_at_into(0, Buffer::with_capacity(block + 1))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+        // Files opened without O_DIRECT accept any transfer
+        let buffered = AsyncFile::open(&path).expect("Failed to open file");
+        assert!(buffered.direct_io_alignment().is_none());
+        let (bytes_read, _) = buffered.read_at(1, 5).await.expect("Failed to read");
+        assert_eq!(bytes_read, 5);
+    });
+}
+
+#[test]
+fn test_whole_file_helpers() {
+    use rust_miniss::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let source = temp_dir.path().join("source.bin");
+        let destination = temp_dir.path().join("destination.bin");
+        // Several chunks of the default size, and a partial one
+        let contents: Vec<u8> = (0..300 * 1024 + 123).map(|i| (i % 251) as u8).collect();
+
+        fs::write(&source, &contents)
+            .await
+            .expect("Failed to write file");
+        assert_eq!(std::fs::read(&source).unwrap(), contents);
+        assert_eq!(
+            fs::read(&source).await.expect("Failed to read file"),
+            contents
+        );
+
+        std::fs::set_permissions(&source, std::fs::Permissions::from_mode(0o640)).unwrap();
+        let copied = fs::copy(&source, &destination)
+            .await
+            .expect("Failed to copy");
+        assert_eq!(copied, contents.len() as u64);
+        assert_eq!(std::fs::read(&destination).unwrap(), contents);
+        let mode = std::fs::metadata(&destination)
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o640);
+        let err = fs::copy(temp_dir.path(), &destination).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+        // Writing over the start keeps the rest; empty files read as empty
+        let file = AsyncFile::open(&destination).expect("Failed to open file");
+        let writable = fs::OpenOptions {
+            write: true,
+            ..Default::default()
+        }
+        .open(&destination)
+        .await
+        .expect("Failed to open file");
+        writable
+            .write_all_at(1, b"patched")
+            .await
+            .expect("Failed to write");
+        let read_back = file.read_to_end().await.expect("Failed to read");
+        assert_eq!(&read_back[1..8], b"patched");
+        assert_eq!(read_back[8..], contents[8..]);
+
+        fs::write(&source, b"").await.expect("Failed to truncate");
+        assert!(fs::read(&source).await.expect("Failed to read").is_empty());
+    });
+}
+
+#[test]
+fn test_file_reader_and_writer() {
+    use rust_miniss::fs::{BufferingOptions, FileReader, FileWriter, OpenOptions};
+
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let path = temp_dir.path().join("streamed.bin");
+        let file = OpenOptions {
+            write: true,
+            create: true,
+            ..Default::default()
+        }
+        .open(&path)
+        .await
+        .expect("Failed to create file");
+        let options = BufferingOptions {
+            chunk_size: 4096,
+            queue_depth: 3,
+        };
+
+        // Small writes are gathered into chunks; an owned buffer goes as is
+        let mut writer = FileWriter::new(&file, 0, options);
+        let mut expected = Vec::new();
+        for i in 0..2000u32 {
+            let line = format!("line {i}\n");
+            writer
+                .write(line.as_bytes())
+                .await
+                .expect("Failed to write");
+            expected.extend_from_slice(line.as_bytes());
+        }
+        assert_eq!(writer.position(), expected.len() as u64);
+        writer
+            .write_buf(vec![b'!'; 10_000])
+            .await
+            .expect("Failed to write");
+        expected.extend(std::iter::repeat_n(b'!', 10_000));
+        writer.flush().await.expect("Failed to flush");
+        assert_eq!(writer.position(), expected.len() as u64);
+        assert_eq!(std::fs::read(&path).unwrap(), expected);
+
+        let reader_options = BufferingOptions {
+            chunk_size: 1000,
+            queue_depth: 4,
+        };
+        let mut reader = FileReader::new(&file, 500, reader_options);
+        let mut read_back = Vec::new();
+        while let Some(chunk) = reader.next_chunk().await.expect("Failed to read") {
+            assert!(chunk.len() <= 1000);
+            read_back.extend_from_slice(&chunk);
+        }
+        assert_eq!(read_back, expected[500..]);
+        assert_eq!(reader.position(), expected.len() as u64);
+        assert!(reader.next_chunk().await.expect("Failed to read").is_none());
+    });
+}
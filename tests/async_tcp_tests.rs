@@ -0,0 +1,741 @@
+//! Tests for async TCP reads and writes with owned buffers.
+
+use futures::StreamExt;
+use rust_miniss::{net::AsyncTcpStream, AsyncTcpListener, Buffer, Runtime};
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+
+/// Returns an accepted `AsyncTcpStream` and the blocking client end.
+async fn connected_pair() -> (AsyncTcpStream, TcpStream) {
+    let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let listener = AsyncTcpListener::bind(addr).expect("Failed to bind listener");
+    // The connection completes in the backlog before it is accepted
+    let client = TcpStream::connect(listener.local_addr().unwrap()).expect("Failed to connect");
+    let (server, _) = listener.accept().await.expect("Failed to accept");
+    (server, client)
+}
+
+#[test]
+fn test_read_returns_backend_buffer_truncated() {
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let (server, mut client) = connected_pair().await;
+        client.write_all(b"hello").unwrap();
+
+        let (bytes_read, data) = server.read().await.expect("Failed to read");
+        assert_eq!(bytes_read, 5);
+        assert_eq!(data.len(), 5);
+        assert_eq!(&data[..], b"hello");
+    });
+}
+
+#[test]
+fn test_read_into_reuses_caller_buffer() {
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let (server, mut client) = connected_pair().await;
+
+        let buf = Buffer::with_capacity(16);
+        let ptr = buf.as_ptr();
+
+        client.write_all(b"first").unwrap();
+        let (n, buf) = server.read_into(buf).await.expect("Failed to read");
+        assert_eq!(&buf[..n], b"first");
+        assert_eq!(buf.as_ptr(), ptr);
+
+        client.write_all(b"second").unwrap();
+        let (n, buf) = server.read_into(buf).await.expect("Failed to read");
+        assert_eq!(n, 6);
+        assert_eq!(&buf[..], b"second");
+        assert_eq!(buf.as_ptr(), ptr);
+    });
+}
+
+#[test]
+fn test_write_owned_buffers() {
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let (server, mut client) = connected_pair().await;
+
+        let n = server
+            .write_buf(bytes::Bytes::from_static(b"bytes,"))
+            .await
+            .expect("Failed to write Bytes");
+        assert_eq!(n, 6);
+
+        // Large enough to need more than one write on most systems
+        let large = vec![b'x'; 4 * 1024 * 1024];
+        let reader = std::thread::spawn(move || {
+            let mut received = Vec::new();
+            client.read_to_end(&mut received).unwrap();
+            received
+        });
+        server
+            .write_all_buf(large.clone())
+            .await
+            .expect("Failed to write all");
+        drop(server);
+
+        let received = reader.join().unwrap();
+        assert_eq!(&received[..6], b"bytes,");
+        assert_eq!(received.len(), 6 + large.len());
+        assert!(received[6..].iter().all(|&b| b == b'x'));
+    });
+}
+
+#[cfg(io_backend = "io_uring")]
+#[test]
+fn test_reads_pick_recycled_ring_buffers() {
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let (server, mut client) = connected_pair().await;
+
+        // More reads than the ring holds: dropped buffers must go back to the ring
+        for i in 0..600u32 {
+            let byte = (i % 251) as u8;
+            client.write_all(&[byte]).unwrap();
+            let (bytes_read, data) = server.read().await.expect("Failed to read");
+            assert_eq!(bytes_read, 1);
+            assert_eq!(&data[..], &[byte]);
+            assert!(data.is_registered());
+        }
+    });
+}
+
+#[cfg(io_backend = "io_uring")]
+#[test]
+fn test_reads_fall_back_when_ring_is_exhausted() {
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let (server, mut client) = connected_pair().await;
+
+        // Holding on to every buffer drains the ring, then the fixed buffers the
+        // pool falls back to; later reads use ordinary buffers
+        let mut held = Vec::new();
+        for i in 0..400u32 {
+            let byte = (i % 251) as u8;
+            client.write_all(&[byte]).unwrap();
+            let (bytes_read, data) = server.read().await.expect("Failed to read");
+            assert_eq!(bytes_read, 1);
+            assert_eq!(&data[..], &[byte]);
+            held.push(data);
+        }
+        assert!(held.iter().any(|data| !data.is_registered()));
+
+        drop(held);
+        client.write_all(b"x").unwrap();
+        let (_, data) = server.read().await.expect("Failed to read");
+        assert_eq!(&data[..], b"x");
+        assert!(data.is_registered());
+    });
+}
+
+#[test]
+fn test_incoming_accepts_many_connections() {
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let listener = AsyncTcpListener::bind(addr).expect("Failed to bind listener");
+        let local = listener.local_addr().unwrap();
+        let mut clients: Vec<TcpStream> = (0..5)
+            .map(|_| TcpStream::connect(local).expect("Failed to connect"))
+            .collect();
+
+        // One multishot accept serves every connection
+        let mut incoming = listener.incoming();
+        let mut servers = Vec::new();
+        for _ in 0..clients.len() {
+            let (server, peer) = incoming
+                .next()
+                .await
+                .expect("Incoming ended")
+                .expect("Failed to accept");
+            assert!(peer.is_some());
+            servers.push(server);
+        }
+
+        for (i, client) in clients.iter_mut().enumerate() {
+            client.write_all(&[i as u8]).unwrap();
+        }
+        let mut received = Vec::new();
+        for server in &servers {
+            let (_, data) = server.read().await.expect("Failed to read");
+            received.push(data[0]);
+        }
+        received.sort();
+        assert_eq!(received, vec![0, 1, 2, 3, 4]);
+    });
+}
+
+#[test]
+fn test_dropping_incoming_stops_accepting() {
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let listener = AsyncTcpListener::bind(addr).expect("Failed to bind listener");
+        let local = listener.local_addr().unwrap();
+
+        let _first = TcpStream::connect(local).expect("Failed to connect");
+        let mut incoming = listener.incoming();
+        incoming.next().await.unwrap().expect("Failed to accept");
+        drop(incoming);
+        // Let the cancellation reach the backend
+        rust_miniss::sleep(std::time::Duration::from_millis(20)).await;
+
+        // The next connection is left to an ordinary accept
+        let _second = TcpStream::connect(local).expect("Failed to connect");
+        let (_, peer) = listener.accept().await.expect("Failed to accept");
+        assert!(peer.is_some());
+    });
+}
+
+#[test]
+fn test_recv_stream_yields_chunks_until_eof() {
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let (server, mut client) = connected_pair().await;
+        let writer = std::thread::spawn(move || {
+            for chunk in [&b"multi"[..], b"shot", b" recv"] {
+                client.write_all(chunk).unwrap();
+                std::thread::sleep(std::time::Duration::from_millis(5));
+            }
+        });
+
+        let mut received = Vec::new();
+        let mut chunks = server.recv_stream();
+        while let Some(chunk) = chunks.next().await {
+            let chunk = chunk.expect("Failed to receive");
+            assert!(!chunk.is_empty());
+            received.extend_from_slice(&chunk);
+        }
+        assert_eq!(received, b"multishot recv");
+        // The stream stays ended
+        assert!(chunks.next().await.is_none());
+        writer.join().unwrap();
+    });
+}
+
+#[cfg(io_backend = "io_uring")]
+#[test]
+fn test_recv_stream_survives_ring_exhaustion() {
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let (server, mut client) = connected_pair().await;
+        let mut chunks = server.recv_stream();
+
+        // Holding every chunk drains the provided ring; receiving continues
+        // with ordinary buffers
+        let mut held = Vec::new();
+        for i in 0..400u32 {
+            let byte = (i % 251) as u8;
+            client.write_all(&[byte]).unwrap();
+            let data = chunks.next().await.unwrap().expect("Failed to receive");
+            assert_eq!(&data[..], &[byte]);
+            held.push(data);
+        }
+        assert!(held.iter().any(|data| !data.is_registered()));
+
+        drop(held);
+        client.write_all(b"x").unwrap();
+        let data = chunks.next().await.unwrap().expect("Failed to receive");
+        assert_eq!(&data[..], b"x");
+    });
+}
+
+#[test]
+fn test_vectored_read_write() {
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let (server, mut client) = connected_pair().await;
+
+        let bufs = vec![
+            Buffer::from(b"head".to_vec()),
+            Buffer::from(bytes::Bytes::from_static(b"er, ")),
+            Buffer::from(b"body".to_vec()),
+        ];
+        let written = server.write_vectored(bufs).await.expect("Failed to write");
+        assert_eq!(written, 12);
+        let mut received = [0u8; 12];
+        client.read_exact(&mut received).unwrap();
+        assert_eq!(&received, b"header, body");
+
+        // Buffers are filled in order, each up to its capacity
+        client.write_all(b"scattered").unwrap();
+        let bufs = vec![Buffer::with_capacity(4), Buffer::with_capacity(64)];
+        let (bytes_read, bufs) = server.read_vectored(bufs).await.expect("Failed to read");
+        assert_eq!(bytes_read, 9);
+        assert_eq!(&bufs[0][..], b"scat");
+        assert_eq!(&bufs[1][..], b"tered");
+    });
+}
+
+#[test]
+fn test_write_all_vectored_resumes_after_short_writes() {
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let (server, mut client) = connected_pair().await;
+
+        // Larger than the socket buffers, so writes come back short
+        let head = vec![b'h'; 1000];
+        let body: Vec<u8> = (0..4 * 1024 * 1024u32).map(|i| (i % 251) as u8).collect();
+        let mut expected = head.clone();
+        expected.extend_from_slice(&body);
+
+        let reader = std::thread::spawn(move || {
+            let mut received = Vec::new();
+            client.read_to_end(&mut received).unwrap();
+            received
+        });
+        server
+            .write_all_vectored(vec![head.into(), Buffer::new_zeroed(0), body.into()])
+            .await
+            .expect("Failed to write");
+        drop(server);
+
+        assert_eq!(reader.join().unwrap(), expected);
+    });
+}
+
+#[test]
+fn test_http_response_sent_vectored() {
+    use rust_miniss::{HttpConnection, Response, StatusCode};
+
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let (server, mut client) = connected_pair().await;
+
+        let response = Response::new(StatusCode::OK).with_body("vectored body");
+        let expected = response.to_bytes();
+        HttpConnection::new(server)
+            .send_response(response)
+            .await
+            .expect("Failed to send response");
+
+        let mut received = Vec::new();
+        client.read_to_end(&mut received).unwrap();
+        assert_eq!(received, expected);
+    });
+}
+
+#[test]
+fn test_http_requests_parsed_across_reads() {
+    use rust_miniss::http::ParseError;
+    use rust_miniss::HttpConnection;
+
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let (server, mut client) = connected_pair().await;
+        let writer = std::thread::spawn(move || {
+            // A request split mid-header and mid-body, with the next one pipelined
+            let pieces: [&[u8]; 4] = [
+                b"POST /a HTTP/1.1\r\nContent-Le",
+                b"ngth: 4\r\n\r\nbo",
+                b"dyGET /b HTTP/1.1\r\n\r\n",
+                b"POST /c HTTP/1.1\r\nContent-Length: 100000000\r\n\r\n",
+            ];
+            for piece in pieces {
+                client.write_all(piece).unwrap();
+                std::thread::sleep(std::time::Duration::from_millis(20));
+            }
+            let mut response = Vec::new();
+            client.read_to_end(&mut response).unwrap();
+            response
+        });
+
+        let mut connection = HttpConnection::new(server);
+        let first = connection
+            .read_request()
+            .await
+            .expect("Failed to read request");
+        assert_eq!(first.path, "/a");
+        assert_eq!(first.body, b"body");
+        let second = connection
+            .read_request()
+            .await
+            .expect("Failed to read request");
+        assert_eq!(second.path, "/b");
+
+        let err = connection.read_request().await.unwrap_err();
+        let parse_error = err
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<ParseError>())
+            .expect("Expected a parse error");
+        assert_eq!(*parse_error, ParseError::PayloadTooLarge);
+        connection
+            .send_response(parse_error.to_response())
+            .await
+            .expect("Failed to send response");
+        drop(connection);
+
+        let response = writer.join().unwrap();
+        assert!(response.starts_with(b"HTTP/1.1 413 Payload Too Large\r\n"));
+    });
+}
+
+#[test]
+fn test_http_serve_keep_alive_and_pipelining() {
+    use rust_miniss::{ConnectionOptions, EchoHandler, HttpConnection};
+    use std::time::{Duration, Instant};
+
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        // Pipelined requests are answered in order on one connection, until
+        // the client asks to close it
+        let (server, mut client) = connected_pair().await;
+        client
+            .write_all(
+                b"GET /1 HTTP/1.1\r\n\r\nGET /2 HTTP/1.1\r\n\r\n\
+                  GET /3 HTTP/1.1\r\nConnection: close\r\n\r\n",
+            )
+            .unwrap();
+        let mut connection = HttpConnection::new(server);
+        connection.serve(&EchoHandler).await.expect("Failed to serve");
+        drop(connection);
+        let mut responses = String::new();
+        client.read_to_string(&mut responses).unwrap();
+        assert_eq!(responses.matches("HTTP/1.1 200 OK").count(), 3);
+        let first = responses.find("Path: /1").unwrap();
+        let second = responses.find("Path: /2").unwrap();
+        let third = responses.find("Path: /3").unwrap();
+        assert!(first < second && second < third);
+        assert_eq!(responses.matches("connection: close").count(), 1);
+
+        // HTTP/1.0 closes after one response unless keep-alive is asked for
+        let (server, mut client) = connected_pair().await;
+        client
+            .write_all(b"GET /a HTTP/1.0\r\nConnection: keep-alive\r\n\r\nGET /b HTTP/1.0\r\n\r\nGET /c HTTP/1.0\r\n\r\n")
+            .unwrap();
+        let mut connection = HttpConnection::new(server);
+        connection.serve(&EchoHandler).await.expect("Failed to serve");
+        drop(connection);
+        let mut responses = String::new();
+        client.read_to_string(&mut responses).unwrap();
+        assert_eq!(responses.matches("HTTP/1.1 200 OK").count(), 2);
+        assert!(responses.contains("connection: keep-alive"));
+        assert!(!responses.contains("Path: /c"));
+
+        // The request limit closes the connection after the last allowed one
+        let (server, mut client) = connected_pair().await;
+        client
+            .write_all(b"GET /1 HTTP/1.1\r\n\r\nGET /2 HTTP/1.1\r\n\r\nGET /3 HTTP/1.1\r\n\r\n")
+            .unwrap();
+        let mut connection = HttpConnection::new(server).with_options(ConnectionOptions {
+            max_requests: Some(2),
+            ..ConnectionOptions::default()
+        });
+        connection.serve(&EchoHandler).await.expect("Failed to serve");
+        drop(connection);
+        let mut responses = String::new();
+        client.read_to_string(&mut responses).unwrap();
+        assert_eq!(responses.matches("HTTP/1.1 200 OK").count(), 2);
+        assert!(responses.contains("connection: close"));
+
+        // An idle connection is closed after the timeout
+        let (server, mut client) = connected_pair().await;
+        client.write_all(b"GET /1 HTTP/1.1\r\n\r\n").unwrap();
+        let mut connection = HttpConnection::new(server).with_options(ConnectionOptions {
+            idle_timeout: Some(Duration::from_millis(100)),
+            ..ConnectionOptions::default()
+        });
+        let started = Instant::now();
+        connection.serve(&EchoHandler).await.expect("Failed to serve");
+        assert!(started.elapsed() >= Duration::from_millis(100));
+        drop(connection);
+        let mut responses = String::new();
+        client.read_to_string(&mut responses).unwrap();
+        assert_eq!(responses.matches("HTTP/1.1 200 OK").count(), 1);
+    });
+}
+
+#[test]
+fn test_http_serve_streams_bodies() {
+    use rust_miniss::http::{handler_fn, Body, Router};
+    use rust_miniss::{HttpConnection, Request, Response, StatusCode};
+
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let path = temp_dir.path().join("page.html");
+    std::fs::write(&path, b"<h1>from disk</h1>").unwrap();
+
+    let router = Router::new()
+        .post(
+            "/upload",
+            handler_fn(|mut request: Request| async move {
+                let (mut chunks, mut bytes) = (0, 0);
+                while let Some(chunk) = request.body.chunk().await.unwrap() {
+                    chunks += 1;
+                    bytes += chunk.len();
+                }
+                Response::new(StatusCode::OK).with_body(format!("chunks={chunks} bytes={bytes}"))
+            }),
+        )
+        .post(
+            "/ignore",
+            handler_fn(|_| async { Response::new(StatusCode::OK).with_body("ignored") }),
+        )
+        .get(
+            "/stream",
+            handler_fn(|_| async {
+                let parts = ["hello", ", ", "world"].map(|s| Ok(Buffer::from_slice(s.as_bytes())));
+                Response::new(StatusCode::OK)
+                    .with_body(Body::from_stream(futures::stream::iter(parts)))
+            }),
+        )
+        .get(
+            "/file",
+            handler_fn(move |_| {
+                let path = path.clone();
+                async move {
+                    let body = Body::from_path(path).await.unwrap();
+                    Response::new(StatusCode::OK).with_body(body)
+                }
+            }),
+        );
+
+    let runtime = Runtime::new();
+    runtime.block_on(async {
+        // A chunked upload is read piece by piece, and the requests pipelined
+        // after it and after an unread body are still answered
+        let (server, mut client) = connected_pair().await;
+        client
+            .write_all(
+                b"POST /upload HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n\
+                  4\r\nabcd\r\n3\r\nefg\r\n0\r\n\r\n\
+                  GET /stream HTTP/1.1\r\n\r\n\
+                  POST /ignore HTTP/1.1\r\nContent-Length: 5\r\n\r\nxxxxx\
+                  GET /file HTTP/1.1\r\nConnection: close\r\n\r\n",
+            )
+            .unwrap();
+        let mut connection = HttpConnection::new(server);
+        connection.serve(&router).await.expect("Failed to serve");
+        drop(connection);
+        let mut responses = String::new();
+        client.read_to_string(&mut responses).unwrap();
+        assert_eq!(responses.matches("HTTP/1.1 200 OK").count(), 4);
+        assert!(responses.contains("chunks=2 bytes=7"));
+        assert!(responses.contains(
+            "transfer-encoding: chunked\r\n\r\n5\r\nhello\r\n2\r\n, \r\n5\r\nworld\r\n0\r\n\r\n"
+        ));
+        assert!(responses.contains("ignored"));
+        assert!(responses.contains("content-length: 18"));
+        assert!(responses.ends_with("\r\n\r\n<h1>from disk</h1>"));
+
+        // HTTP/1.0 has no chunked encoding, so the close ends a streamed body
+        let (server, mut client) = connected_pair().await;
+        client
+            .write_all(b"GET /stream HTTP/1.0\r\nConnection: keep-alive\r\n\r\n")
+            .unwrap();
+        let mut connection = HttpConnection::new(server);
+        connection.serve(&router).await.expect("Failed to serve");
+        drop(connection);
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        assert!(!response.contains("transfer-encoding"));
+        assert!(response.contains("connection: close"));
+        assert!(response.ends_with("\r\n\r\nhello, world"));
+
+        // A body left unread before all of it arrived closes the connection
+        let (server, mut client) = connected_pair().await;
+        client
+            .write_all(b"POST /ignore HTTP/1.1\r\nContent-Length: 100\r\n\r\npartial")
+            .unwrap();
+        let mut connection = HttpConnection::new(server);
+        connection.serve(&router).await.expect("Failed to serve");
+        drop(connection);
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        assert_eq!(response.matches("HTTP/1.1 200 OK").count(), 1);
+        assert!(response.ends_with("ignored"));
+    });
+}
+
+/// Writes `len` patterned bytes to a temporary file, returning its contents.
+fn patterned_file(path: &std::path::Path, len: usize) -> Vec<u8> {
+    let contents: Vec<u8> = (0..len).map(|i| (i % 253) as u8).collect();
+    std::fs::write(path, &contents).unwrap();
+    contents
+}
+
+#[test]
+fn test_sendfile_sends_file_range() {
+    use rust_miniss::fs::AsyncFile;
+
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let path = temp_dir.path().join("large.bin");
+        // Several pipe fills and larger than the socket buffers
+        let contents = patterned_file(&path, 3 * 1024 * 1024 + 17);
+        let file = AsyncFile::open(&path).expect("Failed to open file");
+        let (server, mut client) = connected_pair().await;
+
+        let reader = std::thread::spawn(move || {
+            let mut received = Vec::new();
+            client.read_to_end(&mut received).unwrap();
+            received
+        });
+        let sent = server
+            .sendfile(&file, 100, contents.len() - 100)
+            .await
+            .expect("Failed to send file");
+        assert_eq!(sent, contents.len() - 100);
+        // Asking for more than the file holds stops at its end
+        let sent = server
+            .sendfile(&file, 0, contents.len() + 4096)
+            .await
+            .expect("Failed to send file");
+        assert_eq!(sent, contents.len());
+        drop(server);
+
+        let mut expected = contents[100..].to_vec();
+        expected.extend_from_slice(&contents);
+        assert!(reader.join().unwrap() == expected);
+    });
+}
+
+#[test]
+fn test_splice_file_through_pipe_to_socket() {
+    use std::os::unix::io::AsRawFd;
+
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let path = temp_dir.path().join("splice.bin");
+        let contents = patterned_file(&path, 10_000);
+        let file = std::fs::File::open(&path).unwrap();
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let (server, mut client) = connected_pair().await;
+
+        let moved = rust_miniss::net::splice(file.as_raw_fd(), Some(10), fds[1], None, 5000)
+            .await
+            .expect("Failed to splice into pipe");
+        assert_eq!(moved, 5000);
+        let moved = rust_miniss::net::splice(fds[0], None, server.as_raw_fd(), None, moved)
+            .await
+            .expect("Failed to splice to socket");
+        assert_eq!(moved, 5000);
+
+        let mut received = vec![0u8; 5000];
+        client.read_exact(&mut received).unwrap();
+        assert_eq!(received, contents[10..5010]);
+        unsafe {
+            libc::close(fds[0]);
+            libc::close(fds[1]);
+        }
+    });
+}
+
+#[test]
+fn test_send_zc_and_large_write_all_buf() {
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let (server, mut client) = connected_pair().await;
+
+        let written = server
+            .send_zc(b"zero-copy".to_vec())
+            .await
+            .expect("Failed to send");
+        assert_eq!(written, 9);
+        let mut received = [0u8; 9];
+        client.read_exact(&mut received).unwrap();
+        assert_eq!(&received, b"zero-copy");
+
+        // Goes out zero-copy, resubmitting after short sends
+        let body: Vec<u8> = (0..4 * 1024 * 1024u32).map(|i| (i % 249) as u8).collect();
+        let expected = body.clone();
+        let reader = std::thread::spawn(move || {
+            let mut received = Vec::new();
+            client.read_to_end(&mut received).unwrap();
+            received
+        });
+        server.write_all_buf(body).await.expect("Failed to write");
+        drop(server);
+
+        assert!(reader.join().unwrap() == expected);
+    });
+}
+
+#[cfg(io_backend = "io_uring")]
+#[test]
+fn test_reads_larger_than_ring_buffers_are_not_truncated() {
+    use rust_miniss::io::uring::UringBackend;
+    use rust_miniss::{CompletionKind, IoProvider, Op};
+    use std::os::fd::AsRawFd;
+    use std::task::{Context, Poll};
+
+    let mut file = tempfile::tempfile().unwrap();
+    let data: Vec<u8> = (0..3 * rust_miniss::buffer::BUFFER_SIZE)
+        .map(|i| (i % 251) as u8)
+        .collect();
+    file.write_all(&data).unwrap();
+
+    let backend = UringBackend::new(32).unwrap();
+    backend.submit(Op::Read {
+        fd: file.as_raw_fd(),
+        offset: 0,
+        len: data.len(),
+    });
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    let completion = loop {
+        backend.flush();
+        match backend.poll_complete(&mut cx) {
+            Poll::Ready(mut completions) if !completions.is_empty() => {
+                break completions.remove(0).2.unwrap();
+            }
+            _ => backend.wait(Some(std::time::Duration::from_millis(10))),
+        }
+    };
+    let CompletionKind::Read {
+        bytes_read,
+        data: read,
+    } = completion
+    else {
+        panic!("Unexpected completion kind");
+    };
+    assert_eq!(bytes_read, data.len());
+    assert_eq!(&read[..], &data[..]);
+}
+
+#[test]
+fn test_timed_out_reads_are_cancelled() {
+    use rust_miniss::timer;
+    use std::time::Duration;
+
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let (server, mut client) = connected_pair().await;
+
+        for _ in 0..50 {
+            let read = timer::timeout(Duration::from_millis(1), server.read()).await;
+            assert!(read.is_err());
+        }
+        // The cancelled reads neither linger in the I/O state nor take data
+        timer::sleep(Duration::from_millis(20)).await;
+        let state = rust_miniss::cpu::io_state();
+        assert!(state.completed_io.lock().unwrap().is_empty());
+        assert!(state.awaited_io.lock().unwrap().is_empty());
+
+        client.write_all(b"after").unwrap();
+        let (_, data) = server.read().await.expect("Failed to read");
+        assert_eq!(&data[..], b"after");
+    });
+}
@@ -1,6 +1,6 @@
 //! Tests for async UDP functionality.
 
-use rust_miniss::{net::AsyncUdpSocket, Runtime};
+use rust_miniss::{net::AsyncUdpSocket, Datagram, Runtime, UdpBindOptions};
 use std::net::SocketAddr;
 
 #[test]
@@ -108,3 +108,180 @@ fn test_async_udp_multiple_sends() {
         }
     });
 }
+
+#[test]
+fn test_async_udp_send_to_async_receiver() {
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let sender = AsyncUdpSocket::bind(addr).expect("Failed to bind sender");
+        let receiver = AsyncUdpSocket::bind(addr).expect("Failed to bind receiver");
+        let receiver_addr = receiver.local_addr().unwrap();
+
+        let sent = sender.send_to(b"ping", receiver_addr).await.unwrap();
+        assert_eq!(sent, 4);
+
+        let mut buf = [0u8; 64];
+        let (len, from) = receiver.recv_from(&mut buf).await.unwrap();
+        assert_eq!(&buf[..len], b"ping");
+        assert_eq!(from, sender.local_addr().unwrap());
+    });
+}
+
+#[test]
+fn test_async_udp_connected_send_recv() {
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let a = AsyncUdpSocket::bind(addr).unwrap();
+        let b = AsyncUdpSocket::bind(addr).unwrap();
+        a.connect(b.local_addr().unwrap()).unwrap();
+        b.connect(a.local_addr().unwrap()).unwrap();
+        assert_eq!(a.peer_addr().unwrap(), b.local_addr().unwrap());
+
+        assert_eq!(a.send(b"hello").await.unwrap(), 5);
+        let mut buf = [0u8; 64];
+        let len = b.recv(&mut buf).await.unwrap();
+        assert_eq!(&buf[..len], b"hello");
+
+        assert_eq!(b.send(b"world").await.unwrap(), 5);
+        let len = a.recv(&mut buf).await.unwrap();
+        assert_eq!(&buf[..len], b"world");
+    });
+}
+
+#[test]
+fn test_async_udp_peek_from_leaves_datagram_queued() {
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let sender = AsyncUdpSocket::bind(addr).unwrap();
+        let receiver = AsyncUdpSocket::bind(addr).unwrap();
+        sender
+            .send_to(b"peekaboo", receiver.local_addr().unwrap())
+            .await
+            .unwrap();
+
+        let mut buf = [0u8; 64];
+        let (peeked, peek_from) = receiver.peek_from(&mut buf).await.unwrap();
+        assert_eq!(&buf[..peeked], b"peekaboo");
+
+        let mut buf = [0u8; 64];
+        let (len, from) = receiver.recv_from(&mut buf).await.unwrap();
+        assert_eq!(&buf[..len], b"peekaboo");
+        assert_eq!(from, peek_from);
+    });
+}
+
+#[test]
+fn test_async_udp_socket_options() {
+    let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+
+    // Plain bind no longer enables broadcast behind the caller's back
+    let plain = AsyncUdpSocket::bind(addr).unwrap();
+    assert!(!plain.broadcast().unwrap());
+    plain.set_broadcast(true).unwrap();
+    assert!(plain.broadcast().unwrap());
+
+    let options = UdpBindOptions {
+        broadcast: true,
+        ..Default::default()
+    };
+    let configured = AsyncUdpSocket::bind_with(addr, &options).unwrap();
+    assert!(configured.broadcast().unwrap());
+
+    configured.set_ttl(42).unwrap();
+    assert_eq!(configured.ttl().unwrap(), 42);
+}
+
+async fn recv_at_least(socket: &AsyncUdpSocket, count: usize, buf_size: usize) -> Vec<Datagram> {
+    let mut received = Vec::new();
+    while received.len() < count {
+        received.extend(socket.recv_batch(16, buf_size).await.unwrap());
+    }
+    received
+}
+
+#[test]
+fn test_async_udp_batch_round_trip() {
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let sender = AsyncUdpSocket::bind(addr).unwrap();
+        let receiver = AsyncUdpSocket::bind(addr).unwrap();
+        let target = receiver.local_addr().unwrap();
+
+        let payloads: Vec<Vec<u8>> = (0..40u8).map(|i| vec![i; 10 + i as usize]).collect();
+        let batch: Vec<(&[u8], SocketAddr)> =
+            payloads.iter().map(|p| (p.as_slice(), target)).collect();
+        assert_eq!(sender.send_batch(&batch).await.unwrap(), batch.len());
+
+        // 40 datagrams need at least three batches of 16
+        let received = recv_at_least(&receiver, payloads.len(), 1500).await;
+        assert_eq!(received.len(), payloads.len());
+        for (datagram, payload) in received.iter().zip(&payloads) {
+            assert_eq!(&datagram.data[..], payload.as_slice());
+            assert_eq!(datagram.addr, sender.local_addr().unwrap());
+        }
+    });
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_async_udp_gso_send_batch() {
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let sender = AsyncUdpSocket::bind(addr).unwrap();
+        let receiver = AsyncUdpSocket::bind(addr).unwrap();
+        let target = receiver.local_addr().unwrap();
+        sender.set_gso(true);
+        assert!(sender.gso());
+
+        // Four full segments plus a short tail, coalesced into one send
+        let payloads: Vec<Vec<u8>> = (0..5u8)
+            .map(|i| vec![i; if i == 4 { 100 } else { 1000 }])
+            .collect();
+        let batch: Vec<(&[u8], SocketAddr)> =
+            payloads.iter().map(|p| (p.as_slice(), target)).collect();
+        assert_eq!(sender.send_batch(&batch).await.unwrap(), 5);
+
+        let received = recv_at_least(&receiver, payloads.len(), 1500).await;
+        let lengths: Vec<usize> = received.iter().map(|d| d.data.len()).collect();
+        assert_eq!(lengths, vec![1000, 1000, 1000, 1000, 100]);
+        for (datagram, payload) in received.iter().zip(&payloads) {
+            assert_eq!(&datagram.data[..], payload.as_slice());
+        }
+    });
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_async_udp_gro_recv_batch_splits_segments() {
+    let runtime = Runtime::new();
+
+    runtime.block_on(async {
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let sender = AsyncUdpSocket::bind(addr).unwrap();
+        let receiver = AsyncUdpSocket::bind(addr).unwrap();
+        let target = receiver.local_addr().unwrap();
+        sender.set_gso(true);
+        receiver.set_gro(true).unwrap();
+
+        let payloads: Vec<Vec<u8>> = (0..8u8).map(|i| vec![i; 500]).collect();
+        let batch: Vec<(&[u8], SocketAddr)> =
+            payloads.iter().map(|p| (p.as_slice(), target)).collect();
+        assert_eq!(sender.send_batch(&batch).await.unwrap(), 8);
+
+        let received = recv_at_least(&receiver, payloads.len(), 65535).await;
+        assert_eq!(received.len(), payloads.len());
+        for (datagram, payload) in received.iter().zip(&payloads) {
+            assert_eq!(&datagram.data[..], payload.as_slice());
+        }
+    });
+}
@@ -1,4 +1,11 @@
+// Each test crate that includes this module uses only part of it
+#![allow(dead_code)]
+
+use rust_miniss::http::{HttpHandler, Server};
+use rust_miniss::{timer, Runtime};
+use std::net::SocketAddr;
 use std::sync::Once;
+use std::time::Duration;
 
 static INIT: Once = Once::new();
 
@@ -8,3 +15,30 @@ pub fn setup_tracing() {
         tracing_subscriber::fmt::init();
     });
 }
+
+/// Runs `client` on its own thread while `server` runs on a single-threaded
+/// runtime, then shuts the server down with `deadline`. Returns whether all
+/// connections closed in time.
+///
+/// A panic in `client` stops the server and is passed on.
+pub fn with_local_server<H, C>(server: Server<H>, deadline: Duration, client: C) -> bool
+where
+    H: HttpHandler,
+    C: FnOnce(SocketAddr) + Send + 'static,
+{
+    let handle = server.handle();
+    let addr = handle.local_addr();
+    let client = std::thread::spawn(move || client(addr));
+
+    let runtime = Runtime::new();
+    let (_, drained) = runtime.block_on(futures::future::join(server.run(), async {
+        while !client.is_finished() {
+            timer::sleep(Duration::from_millis(5)).await;
+        }
+        handle.shutdown(deadline).await
+    }));
+    if let Err(panic) = client.join() {
+        std::panic::resume_unwind(panic);
+    }
+    drained
+}
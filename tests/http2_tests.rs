@@ -0,0 +1,480 @@
+//! Tests for HTTP/2 over cleartext on `http::Server` connections, driven by
+//! a hand-written client on a blocking socket.
+
+mod common;
+
+use common::with_local_server;
+use rust_miniss::http::{handler_fn, Http2Options, Router, Server};
+use rust_miniss::{timer, ConnectionOptions, Request, Response, StatusCode};
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+const PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+const DATA: u8 = 0x0;
+const HEADERS: u8 = 0x1;
+const RST_STREAM: u8 = 0x3;
+const SETTINGS: u8 = 0x4;
+const PING: u8 = 0x6;
+const GOAWAY: u8 = 0x7;
+const WINDOW_UPDATE: u8 = 0x8;
+
+const END_STREAM: u8 = 0x1;
+const ACK: u8 = 0x1;
+const END_HEADERS: u8 = 0x4;
+
+/// `:status 200` and `:status 404` as HPACK static table indexes
+const STATUS_200: u8 = 0x88;
+const STATUS_404: u8 = 0x8d;
+
+/// How long shutdown waits for connections to close
+const DEADLINE: Duration = Duration::from_secs(1);
+
+/// `/fast` answers at once, `/slow` after a while, `/hello` with 12 bytes,
+/// `POST /echo` streams the request body back, and `POST /slow` answers
+/// after a while without reading the body.
+fn router() -> Router {
+    Router::new()
+        .get(
+            "/fast",
+            handler_fn(|_| async { Response::new(StatusCode::OK).with_body("fast") }),
+        )
+        .get(
+            "/slow",
+            handler_fn(|_| async {
+                timer::sleep(Duration::from_millis(100)).await;
+                Response::new(StatusCode::OK).with_body("slow")
+            }),
+        )
+        .get(
+            "/hello",
+            handler_fn(|_| async { Response::new(StatusCode::OK).with_body("hello, world") }),
+        )
+        .post(
+            "/slow",
+            handler_fn(|request: Request| async move {
+                timer::sleep(Duration::from_millis(100)).await;
+                drop(request);
+                Response::new(StatusCode::OK).with_body("slow")
+            }),
+        )
+        .post(
+            "/echo",
+            handler_fn(|request: Request| async move {
+                Response::new(StatusCode::OK).with_body(request.body)
+            }),
+        )
+}
+
+fn server(options: ConnectionOptions) -> Server<Router> {
+    Server::builder(router())
+        .connection_options(options)
+        .bind("127.0.0.1:0".parse().unwrap())
+        .expect("Failed to bind")
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Frame {
+    kind: u8,
+    flags: u8,
+    stream_id: u32,
+    payload: Vec<u8>,
+}
+
+fn send_frame(stream: &mut TcpStream, kind: u8, flags: u8, stream_id: u32, payload: &[u8]) {
+    let mut frame = (payload.len() as u32).to_be_bytes()[1..].to_vec();
+    frame.extend_from_slice(&[kind, flags]);
+    frame.extend_from_slice(&stream_id.to_be_bytes());
+    frame.extend_from_slice(payload);
+    stream.write_all(&frame).unwrap();
+}
+
+fn read_frame(stream: &mut TcpStream) -> Frame {
+    let mut head = [0u8; 9];
+    stream.read_exact(&mut head).unwrap();
+    let len = u32::from_be_bytes([0, head[0], head[1], head[2]]) as usize;
+    let mut payload = vec![0u8; len];
+    stream.read_exact(&mut payload).unwrap();
+    Frame {
+        kind: head[3],
+        flags: head[4],
+        stream_id: u32::from_be_bytes([head[5], head[6], head[7], head[8]]),
+        payload,
+    }
+}
+
+/// The next frame that is not a settings acknowledgement or a window update
+fn read_stream_frame(stream: &mut TcpStream) -> Frame {
+    loop {
+        let frame = read_frame(stream);
+        if frame.kind != WINDOW_UPDATE && !(frame.kind == SETTINGS && frame.flags & ACK != 0) {
+            return frame;
+        }
+    }
+}
+
+fn settings_payload(settings: &[(u16, u32)]) -> Vec<u8> {
+    let mut payload = Vec::new();
+    for &(id, value) in settings {
+        payload.extend_from_slice(&id.to_be_bytes());
+        payload.extend_from_slice(&value.to_be_bytes());
+    }
+    payload
+}
+
+/// Send the preface with `settings` and exchange settings with the server,
+/// returning the ones it announced
+fn handshake(stream: &mut TcpStream, settings: &[(u16, u32)]) -> Vec<u8> {
+    stream.write_all(PREFACE).unwrap();
+    send_frame(stream, SETTINGS, 0, 0, &settings_payload(settings));
+    let server_settings = read_frame(stream);
+    assert_eq!((server_settings.kind, server_settings.flags), (SETTINGS, 0));
+    send_frame(stream, SETTINGS, ACK, 0, &[]);
+    server_settings.payload
+}
+
+fn connect(addr: SocketAddr, settings: &[(u16, u32)]) -> TcpStream {
+    let mut stream = TcpStream::connect(addr).expect("Failed to connect");
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    handshake(&mut stream, settings);
+    stream
+}
+
+/// A header block of literal fields, neither indexed nor Huffman-coded
+fn header_block(fields: &[(&str, &str)]) -> Vec<u8> {
+    let mut block = Vec::new();
+    for (name, value) in fields {
+        block.push(0x00);
+        block.push(name.len() as u8);
+        block.extend_from_slice(name.as_bytes());
+        block.push(value.len() as u8);
+        block.extend_from_slice(value.as_bytes());
+    }
+    block
+}
+
+fn send_request(stream: &mut TcpStream, id: u32, method: &str, path: &str, end_stream: bool) {
+    let block = header_block(&[
+        (":method", method),
+        (":scheme", "http"),
+        (":path", path),
+        (":authority", "localhost"),
+    ]);
+    let flags = END_HEADERS | if end_stream { END_STREAM } else { 0 };
+    send_frame(stream, HEADERS, flags, id, &block);
+}
+
+/// Read the response to stream `id`, made of frames for it alone: the first
+/// byte of its header block, and its body
+fn read_response(stream: &mut TcpStream, id: u32) -> (u8, Vec<u8>) {
+    let headers = read_stream_frame(stream);
+    assert_eq!((headers.kind, headers.stream_id), (HEADERS, id));
+    assert_ne!(headers.flags & END_HEADERS, 0);
+    let mut body = Vec::new();
+    let mut ended = headers.flags & END_STREAM != 0;
+    while !ended {
+        let data = read_stream_frame(stream);
+        assert_eq!((data.kind, data.stream_id), (DATA, id));
+        body.extend_from_slice(&data.payload);
+        ended = data.flags & END_STREAM != 0;
+    }
+    (headers.payload[0], body)
+}
+
+fn assert_closed(stream: &mut TcpStream) {
+    let mut rest = Vec::new();
+    stream.read_to_end(&mut rest).unwrap();
+    assert!(rest.is_empty(), "unexpected bytes after close: {rest:?}");
+}
+
+#[test]
+fn test_http2_streams_are_multiplexed() {
+    let drained = with_local_server(server(ConnectionOptions::default()), DEADLINE, |addr| {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let settings = handshake(&mut stream, &[]);
+        // SETTINGS_MAX_CONCURRENT_STREAMS comes first
+        assert_eq!(settings[..6], [0, 3, 0, 0, 0, 100]);
+
+        // The fast response overtakes the slow one
+        send_request(&mut stream, 1, "GET", "/slow", true);
+        send_request(&mut stream, 3, "GET", "/fast", true);
+        assert_eq!(
+            read_response(&mut stream, 3),
+            (STATUS_200, b"fast".to_vec())
+        );
+        assert_eq!(
+            read_response(&mut stream, 1),
+            (STATUS_200, b"slow".to_vec())
+        );
+
+        send_request(&mut stream, 5, "GET", "/missing", true);
+        assert_eq!(read_response(&mut stream, 5).0, STATUS_404);
+
+        send_frame(&mut stream, PING, 0, 0, b"12345678");
+        let pong = read_stream_frame(&mut stream);
+        assert_eq!((pong.kind, pong.flags), (PING, ACK));
+        assert_eq!(pong.payload, b"12345678");
+
+        // A request body streams back as it is sent
+        send_request(&mut stream, 7, "POST", "/echo", false);
+        send_frame(&mut stream, DATA, 0, 7, b"hello, ");
+        let echoed = read_stream_frame(&mut stream);
+        assert_eq!(echoed.kind, HEADERS);
+        let echoed = read_stream_frame(&mut stream);
+        assert_eq!(
+            (echoed.kind, echoed.payload.as_slice()),
+            (DATA, &b"hello, "[..])
+        );
+        send_frame(&mut stream, DATA, END_STREAM, 7, b"world");
+        let mut rest = Vec::new();
+        loop {
+            let data = read_stream_frame(&mut stream);
+            assert_eq!((data.kind, data.stream_id), (DATA, 7));
+            rest.extend_from_slice(&data.payload);
+            if data.flags & END_STREAM != 0 {
+                break;
+            }
+        }
+        assert_eq!(rest, b"world");
+    });
+    assert!(drained);
+}
+
+#[test]
+fn test_http2_flow_control() {
+    let drained = with_local_server(server(ConnectionOptions::default()), DEADLINE, |addr| {
+        // SETTINGS_INITIAL_WINDOW_SIZE of 5: the body stops after 5 bytes
+        let mut stream = connect(addr, &[(0x4, 5)]);
+        send_request(&mut stream, 1, "GET", "/hello", true);
+        let headers = read_stream_frame(&mut stream);
+        assert_eq!((headers.kind, headers.payload[0]), (HEADERS, STATUS_200));
+        let first = read_stream_frame(&mut stream);
+        assert_eq!((first.kind, first.flags), (DATA, 0));
+        assert_eq!(first.payload, b"hello");
+
+        send_frame(&mut stream, WINDOW_UPDATE, 0, 1, &100u32.to_be_bytes());
+        let rest = read_stream_frame(&mut stream);
+        assert_eq!((rest.kind, rest.flags), (DATA, END_STREAM));
+        assert_eq!(rest.payload, b", world");
+
+        // Request body bytes are granted back on the connection and, once
+        // the handler has read them, on the stream
+        send_request(&mut stream, 3, "POST", "/echo", false);
+        send_frame(&mut stream, DATA, 0, 3, b"abc");
+        let mut granted = Vec::new();
+        while granted.len() < 2 {
+            let frame = read_frame(&mut stream);
+            if frame.kind == WINDOW_UPDATE {
+                granted.push((frame.stream_id, frame.payload));
+            }
+        }
+        granted.sort();
+        assert_eq!(
+            granted,
+            [
+                (0, 3u32.to_be_bytes().to_vec()),
+                (3, 3u32.to_be_bytes().to_vec())
+            ]
+        );
+        send_frame(&mut stream, RST_STREAM, 0, 3, &8u32.to_be_bytes());
+
+        // Growing a window beyond 2^31-1 is a connection error
+        let increment = (1u32 << 31) - 1;
+        send_frame(&mut stream, WINDOW_UPDATE, 0, 0, &increment.to_be_bytes());
+        let goaway = read_stream_frame(&mut stream);
+        assert_eq!(goaway.kind, GOAWAY);
+        assert_eq!(goaway.payload[4..], 3u32.to_be_bytes()); // FLOW_CONTROL_ERROR
+        assert_closed(&mut stream);
+    });
+    assert!(drained);
+}
+
+#[test]
+fn test_http2_connection_window() {
+    let options = ConnectionOptions {
+        http2: Some(Http2Options {
+            connection_window_size: 65535,
+            ..Http2Options::default()
+        }),
+        ..ConnectionOptions::default()
+    };
+    let drained = with_local_server(server(options), DEADLINE, |addr| {
+        // Neither handler reads its body, so the two streams' windows
+        // together overrun the connection's
+        let mut stream = connect(addr, &[]);
+        let chunk = vec![b'x'; 16384];
+        for id in [1, 3] {
+            send_request(&mut stream, id, "POST", "/slow", false);
+            for _ in 0..3 {
+                send_frame(&mut stream, DATA, 0, id, &chunk);
+            }
+        }
+        let goaway = read_stream_frame(&mut stream);
+        assert_eq!(goaway.kind, GOAWAY);
+        assert_eq!(goaway.payload[4..], 3u32.to_be_bytes()); // FLOW_CONTROL_ERROR
+        assert_closed(&mut stream);
+    });
+    assert!(drained);
+}
+
+#[test]
+fn test_http2_limits_and_errors() {
+    let options = ConnectionOptions {
+        max_requests: Some(2),
+        http2: Some(Http2Options {
+            max_concurrent_streams: 1,
+            ..Http2Options::default()
+        }),
+        ..ConnectionOptions::default()
+    };
+    let drained = with_local_server(server(options), DEADLINE, |addr| {
+        // Beyond max_concurrent_streams a stream is refused
+        let mut stream = connect(addr, &[]);
+        send_request(&mut stream, 1, "GET", "/slow", true);
+        send_request(&mut stream, 3, "GET", "/fast", true);
+        let refused = read_stream_frame(&mut stream);
+        assert_eq!((refused.kind, refused.stream_id), (RST_STREAM, 3));
+        assert_eq!(refused.payload, 7u32.to_be_bytes()); // REFUSED_STREAM
+        assert_eq!(
+            read_response(&mut stream, 1),
+            (STATUS_200, b"slow".to_vec())
+        );
+
+        // The last of max_requests is announced with GOAWAY, then answered
+        send_request(&mut stream, 5, "GET", "/fast", true);
+        let goaway = read_stream_frame(&mut stream);
+        assert_eq!(goaway.kind, GOAWAY);
+        assert_eq!(goaway.payload, [0, 0, 0, 5, 0, 0, 0, 0]);
+        assert_eq!(
+            read_response(&mut stream, 5),
+            (STATUS_200, b"fast".to_vec())
+        );
+        assert_closed(&mut stream);
+
+        // Clients may not use even stream identifiers
+        let mut stream = connect(addr, &[]);
+        send_request(&mut stream, 2, "GET", "/fast", true);
+        let goaway = read_stream_frame(&mut stream);
+        assert_eq!(goaway.kind, GOAWAY);
+        assert_eq!(goaway.payload, [0, 0, 0, 0, 0, 0, 0, 1]); // PROTOCOL_ERROR
+        assert_closed(&mut stream);
+
+        // A small block expanding past the header list limit is refused on its
+        // stream alone, and the dynamic table stays in step
+        let mut stream = connect(addr, &[]);
+        let mut block = header_block(&[
+            (":method", "GET"),
+            (":scheme", "http"),
+            (":path", "/fast"),
+            (":authority", "localhost"),
+        ]);
+        block.extend_from_slice(b"\x40\x05x-big\x01y");
+        block.extend_from_slice(&[0xbe; 2000]);
+        send_frame(&mut stream, HEADERS, END_HEADERS | END_STREAM, 1, &block);
+        let (_, body) = read_response(&mut stream, 1);
+        assert_eq!(body, b"request header fields too large\n");
+        let mut block = header_block(&[
+            (":method", "GET"),
+            (":scheme", "http"),
+            (":path", "/fast"),
+            (":authority", "localhost"),
+        ]);
+        block.push(0xbe);
+        send_frame(&mut stream, HEADERS, END_HEADERS | END_STREAM, 3, &block);
+        assert_eq!(
+            read_response(&mut stream, 3),
+            (STATUS_200, b"fast".to_vec())
+        );
+
+        // A garbled preface is not taken for HTTP/2
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .write_all(b"PRI * HTTP/2.0\r\n\r\nXX\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+    });
+    assert!(drained);
+}
+
+#[test]
+fn test_h2c_upgrade() {
+    let drained = with_local_server(server(ConnectionOptions::default()), DEADLINE, |addr| {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        // HTTP2-Settings carries SETTINGS_INITIAL_WINDOW_SIZE of 3
+        stream
+            .write_all(
+                b"GET /hello HTTP/1.1\r\nhost: localhost\r\n\
+                  connection: Upgrade, HTTP2-Settings\r\nupgrade: h2c\r\n\
+                  http2-settings: AAQAAAAD\r\n\r\n",
+            )
+            .unwrap();
+        let mut head = Vec::new();
+        let mut byte = [0u8; 1];
+        while !head.ends_with(b"\r\n\r\n") {
+            stream.read_exact(&mut byte).unwrap();
+            head.push(byte[0]);
+        }
+        let head = String::from_utf8(head).unwrap();
+        assert!(head.starts_with("HTTP/1.1 101 Switching Protocols\r\n"));
+        assert!(head.contains("upgrade: h2c\r\n"));
+
+        // The request is answered on stream 1, within the window it set
+        handshake(&mut stream, &[]);
+        let headers = read_stream_frame(&mut stream);
+        assert_eq!((headers.kind, headers.stream_id), (HEADERS, 1));
+        let first = read_stream_frame(&mut stream);
+        assert_eq!((first.kind, first.payload.as_slice()), (DATA, &b"hel"[..]));
+        send_frame(&mut stream, WINDOW_UPDATE, 0, 1, &100u32.to_be_bytes());
+        let rest = read_stream_frame(&mut stream);
+        assert_eq!(
+            (rest.flags, rest.payload.as_slice()),
+            (END_STREAM, &b"lo, world"[..])
+        );
+
+        // Further requests open streams as usual, once the window allows
+        send_frame(
+            &mut stream,
+            SETTINGS,
+            0,
+            0,
+            &settings_payload(&[(0x4, 65_535)]),
+        );
+        send_request(&mut stream, 3, "GET", "/fast", true);
+        assert_eq!(
+            read_response(&mut stream, 3),
+            (STATUS_200, b"fast".to_vec())
+        );
+    });
+    assert!(drained);
+
+    // Without HTTP/2, the upgrade is ignored
+    let options = ConnectionOptions {
+        http2: None,
+        ..ConnectionOptions::default()
+    };
+    let drained = with_local_server(server(options), DEADLINE, |addr| {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .write_all(
+                b"GET /fast HTTP/1.1\r\nhost: localhost\r\nconnection: Upgrade, HTTP2-Settings, close\r\n\
+                  upgrade: h2c\r\nhttp2-settings: \r\n\r\n",
+            )
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with("fast"));
+    });
+    assert!(drained);
+}
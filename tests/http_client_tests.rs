@@ -0,0 +1,147 @@
+//! Tests for `http::Client` against scripted and miniss servers.
+
+use rust_miniss::http::{Client, Server, Version};
+use rust_miniss::{EchoHandler, Method, Runtime, StatusCode};
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Serves bodiless requests on a thread per connection, answering by path,
+/// and counts the connections accepted.
+fn scripted_server() -> (SocketAddr, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let accepted = Arc::new(AtomicUsize::new(0));
+    let counter = accepted.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            counter.fetch_add(1, Ordering::SeqCst);
+            std::thread::spawn(move || serve_scripted(stream.unwrap()));
+        }
+    });
+    (addr, accepted)
+}
+
+fn serve_scripted(stream: TcpStream) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut stream = stream;
+    loop {
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line).unwrap_or(0) == 0 {
+            return;
+        }
+        let mut line = String::new();
+        while reader.read_line(&mut line).unwrap_or(0) > 2 {
+            line.clear();
+        }
+        let path = request_line.split(' ').nth(1).unwrap_or("/").to_string();
+        let response: &[u8] = match path.as_str() {
+            "/length" => b"HTTP/1.1 200 OK\r\ncontent-length: 6\r\n\r\nlength",
+            "/chunked" => {
+                b"HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\n\r\n\
+                  5\r\nhello\r\n6;x=y\r\n world\r\n0\r\ntrailer: 1\r\n\r\n"
+            }
+            "/continue" => b"HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 204 No Content\r\n\r\n",
+            "/slow" => {
+                std::thread::sleep(Duration::from_millis(500));
+                b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n"
+            }
+            // Delimited by the close
+            "/close" => b"HTTP/1.0 200 OK\r\n\r\nuntil the end",
+            // Announces keep-alive but closes anyway
+            "/drop" => b"HTTP/1.1 200 OK\r\ncontent-length: 4\r\n\r\ndrop",
+            _ => b"HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\n\r\n",
+        };
+        stream.write_all(response).unwrap();
+        if path == "/close" || path == "/drop" {
+            return;
+        }
+    }
+}
+
+#[test]
+fn test_client_parses_framings_and_reuses_connections() {
+    let (addr, accepted) = scripted_server();
+    let url = |path: &str| format!("http://{addr}{path}");
+
+    let runtime = Runtime::new();
+    runtime.block_on(async {
+        let client = Client::new();
+        let length = client.get(&url("/length")).await.unwrap();
+        assert_eq!(length.status, StatusCode::OK);
+        assert_eq!(length.body, b"length");
+        let chunked = client.get(&url("/chunked")).await.unwrap();
+        assert_eq!(chunked.body, b"hello world");
+        let interim = client.get(&url("/continue")).await.unwrap();
+        assert_eq!(interim.status, StatusCode::NO_CONTENT);
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+
+        // A close-delimited body ends the connection's reuse
+        let closed = client.get(&url("/close")).await.unwrap();
+        assert_eq!(closed.version, Version::HTTP_10);
+        assert_eq!(closed.body, b"until the end");
+        let missing = client.get(&url("/missing")).await.unwrap();
+        assert_eq!(missing.status, StatusCode::NOT_FOUND);
+        assert_eq!(accepted.load(Ordering::SeqCst), 2);
+
+        // A pooled connection the server dropped is replaced transparently
+        client.get(&url("/drop")).await.unwrap();
+        std::thread::sleep(Duration::from_millis(50));
+        let retried = client.get(&url("/length")).await.unwrap();
+        assert_eq!(retried.body, b"length");
+        assert_eq!(accepted.load(Ordering::SeqCst), 3);
+    });
+}
+
+#[test]
+fn test_client_request_timeout() {
+    let (addr, _) = scripted_server();
+
+    let runtime = Runtime::new();
+    runtime.block_on(async {
+        let client = Client::new();
+        let err = client
+            .request(Method::GET, &format!("http://{addr}/slow"))
+            .with_timeout(Duration::from_millis(100))
+            .send()
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+
+        let refused = client.get("http://127.0.0.1:1/").await.unwrap_err();
+        assert_eq!(refused.kind(), ErrorKind::ConnectionRefused);
+    });
+}
+
+#[test]
+fn test_client_round_trip_to_server() {
+    let server = Server::builder(EchoHandler)
+        .bind("127.0.0.1:0".parse().unwrap())
+        .expect("Failed to bind");
+    let handle = server.handle();
+    let url = format!("http://{}/echo?x=1", handle.local_addr());
+
+    let runtime = Runtime::new();
+    let (_, ()) = runtime.block_on(futures::future::join(server.run(), async {
+        let client = Client::new();
+        for i in 0..3 {
+            let response = client
+                .request(Method::POST, &url)
+                .with_header("X-Attempt", &i.to_string())
+                .with_body(format!("ping {i}"))
+                .send()
+                .await
+                .unwrap();
+            let echoed =
+                String::from_utf8(response.body.collect().await.unwrap().to_vec()).unwrap();
+            assert!(echoed.contains("Method: POST"));
+            assert!(echoed.contains(&format!("Body: ping {i}")));
+            assert!(echoed.contains(&format!("\"x-attempt\": \"{i}\"")));
+        }
+        // Every request went over the one pooled connection
+        assert_eq!(handle.active_connections(), 1);
+        assert!(handle.shutdown(Duration::from_secs(1)).await);
+    }));
+}
@@ -0,0 +1,199 @@
+//! Tests for `http::FileServer` serving a temporary directory.
+
+mod common;
+
+use common::with_local_server;
+use rust_miniss::http::{header, Client, FileServer, FileServerOptions, Router, Server};
+use rust_miniss::{Method, Response, Runtime, StatusCode};
+use std::fs;
+use std::future::Future;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Serve `router` while `client` runs against its base URL, on a runtime
+/// of its own
+fn with_server<F, Fut>(router: Router, client: F)
+where
+    F: FnOnce(Client, String) -> Fut + Send + 'static,
+    Fut: Future<Output = ()>,
+{
+    let server = Server::builder(router)
+        .bind("127.0.0.1:0".parse().unwrap())
+        .expect("Failed to bind");
+    let drained = with_local_server(server, Duration::from_secs(1), |addr| {
+        Runtime::new().block_on(client(Client::new(), format!("http://{addr}")));
+    });
+    assert!(drained);
+}
+
+fn header_of<'a>(response: &'a Response, name: &str) -> &'a str {
+    response.headers[name].to_str().unwrap()
+}
+
+/// A directory with the served root, `public`, next to a file outside it
+fn site() -> (tempfile::TempDir, PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().join("public");
+    fs::create_dir_all(root.join("docs")).unwrap();
+    fs::create_dir(root.join("empty")).unwrap();
+    fs::write(root.join("hello.txt"), "hello, world").unwrap();
+    fs::write(root.join("docs/index.html"), "<h1>docs</h1>").unwrap();
+    fs::write(dir.path().join("secret.txt"), "secret").unwrap();
+    (dir, root)
+}
+
+#[test]
+fn test_file_server_serves_files_and_indexes() {
+    let (_dir, root) = site();
+    let options = FileServerOptions {
+        max_age: Some(Duration::from_secs(60)),
+        ..FileServerOptions::default()
+    };
+    let router = Router::new()
+        .get("/files/*", FileServer::with_options(&root, options))
+        .fallback(FileServer::new(&root));
+
+    with_server(router, |client, base| async move {
+        let hello = client
+            .get(&format!("{base}/files/hello.txt"))
+            .await
+            .unwrap();
+        assert_eq!(hello.status, StatusCode::OK);
+        assert_eq!(hello.body, "hello, world");
+        assert_eq!(
+            header_of(&hello, "content-type"),
+            "text/plain; charset=utf-8"
+        );
+        assert_eq!(header_of(&hello, "content-length"), "12");
+        assert_eq!(header_of(&hello, "accept-ranges"), "bytes");
+        assert_eq!(header_of(&hello, "cache-control"), "max-age=60");
+        assert!(header_of(&hello, "last-modified").ends_with(" GMT"));
+
+        // Served as a fallback, from the whole request path
+        let fallback = client.get(&format!("{base}/hello.txt")).await.unwrap();
+        assert_eq!(fallback.body, "hello, world");
+        assert!(!fallback.headers.contains_key(header::CACHE_CONTROL));
+
+        let head = client
+            .request(Method::HEAD, &format!("{base}/hello.txt"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(head.status, StatusCode::OK);
+        assert_eq!(header_of(&head, "content-length"), "12");
+        assert_eq!(head.body, "");
+
+        let redirect = client.get(&format!("{base}/files/docs?v=1")).await.unwrap();
+        assert_eq!(redirect.status, StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(header_of(&redirect, "location"), "/files/docs/?v=1");
+        let index = client.get(&format!("{base}/files/docs/")).await.unwrap();
+        assert_eq!(index.body, "<h1>docs</h1>");
+        assert_eq!(
+            header_of(&index, "content-type"),
+            "text/html; charset=utf-8"
+        );
+        let empty = client.get(&format!("{base}/empty/")).await.unwrap();
+        assert_eq!(empty.status, StatusCode::NOT_FOUND);
+
+        for path in [
+            "/missing.txt",
+            "/%2e%2e/secret.txt",
+            "/files/docs/%2E%2E/%2e%2e/secret.txt",
+        ] {
+            let response = client.get(&format!("{base}{path}")).await.unwrap();
+            assert_eq!(response.status, StatusCode::NOT_FOUND, "{path}");
+        }
+        let post = client
+            .request(Method::POST, &format!("{base}/hello.txt"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(post.status, StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(header_of(&post, "allow"), "GET, HEAD");
+    });
+}
+
+#[test]
+fn test_file_server_ranges_and_conditional_requests() {
+    let (_dir, root) = site();
+    let router = Router::new().fallback(FileServer::new(root));
+
+    with_server(router, |client, base| async move {
+        let url = format!("{base}/hello.txt");
+        let get = |range: &'static str| {
+            client
+                .request(Method::GET, &url)
+                .with_header("range", range)
+                .send()
+        };
+
+        let part = get("bytes=0-4").await.unwrap();
+        assert_eq!(part.status, StatusCode::PARTIAL_CONTENT);
+        assert_eq!(part.body, "hello");
+        assert_eq!(header_of(&part, "content-range"), "bytes 0-4/12");
+        let suffix = get("bytes=-5").await.unwrap();
+        assert_eq!(suffix.body, "world");
+        assert_eq!(header_of(&suffix, "content-range"), "bytes 7-11/12");
+        let unsatisfiable = get("bytes=50-").await.unwrap();
+        assert_eq!(unsatisfiable.status, StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(header_of(&unsatisfiable, "content-range"), "bytes */12");
+        let several = get("bytes=0-1,3-4").await.unwrap();
+        assert_eq!(several.status, StatusCode::OK);
+        assert_eq!(several.body, "hello, world");
+
+        let full = client.get(&url).await.unwrap();
+        let etag = header_of(&full, "etag").to_string();
+        let last_modified = header_of(&full, "last-modified").to_string();
+
+        let cached = client
+            .request(Method::GET, &url)
+            .with_header("if-none-match", &format!("\"other\", W/{etag}"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(cached.status, StatusCode::NOT_MODIFIED);
+        assert_eq!(cached.body, "");
+        assert_eq!(header_of(&cached, "etag"), etag);
+        let changed = client
+            .request(Method::GET, &url)
+            .with_header("if-none-match", "\"other\"")
+            .with_header("if-modified-since", &last_modified)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(changed.status, StatusCode::OK);
+        let unmodified = client
+            .request(Method::GET, &url)
+            .with_header("if-modified-since", &last_modified)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(unmodified.status, StatusCode::NOT_MODIFIED);
+        let stale = client
+            .request(Method::GET, &url)
+            .with_header("if-modified-since", "Thu, 01 Jan 1970 00:00:00 GMT")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(stale.status, StatusCode::OK);
+
+        // A range applies only while the validator still matches
+        let current = client
+            .request(Method::GET, &url)
+            .with_header("range", "bytes=7-")
+            .with_header("if-range", &etag)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(current.body, "world");
+        let outdated = client
+            .request(Method::GET, &url)
+            .with_header("range", "bytes=7-")
+            .with_header("if-range", "\"other\"")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(outdated.status, StatusCode::OK);
+        assert_eq!(outdated.body, "hello, world");
+    });
+}
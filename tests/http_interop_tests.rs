@@ -0,0 +1,97 @@
+//! Tests for `Request`/`Response` conversions to and from the `http` crate's
+//! types, and for headers that repeat.
+
+use rust_miniss::http::{handler_fn, header, Body, Client, Router, Server, Version};
+use rust_miniss::{Method, Request, Response, Runtime, StatusCode};
+use std::time::Duration;
+
+#[test]
+fn test_request_converts_losslessly() {
+    let mut request = Request::new(Method::PUT, "/items/7?tag=a+b&x=%41".to_string());
+    request.version = Version::HTTP_10;
+    request
+        .headers
+        .append(header::ACCEPT, "text/plain".parse().unwrap());
+    request
+        .headers
+        .append(header::ACCEPT, "text/html".parse().unwrap());
+    request.params.insert("id".to_string(), "7".to_string());
+    request.body = Body::from("payload");
+
+    let converted: http::Request<Body> = request.into();
+    assert_eq!(converted.method(), Method::PUT);
+    assert_eq!(converted.uri(), "/items/7?tag=a+b&x=%41");
+    assert_eq!(
+        converted.headers().get_all(header::ACCEPT).iter().count(),
+        2
+    );
+
+    let back = Request::from(converted);
+    assert_eq!(back.path, "/items/7");
+    assert_eq!(back.query_string.as_deref(), Some("tag=a+b&x=%41"));
+    assert_eq!(back.query["tag"], "a b");
+    assert_eq!(back.query["x"], "A");
+    assert_eq!(back.params["id"], "7");
+    assert_eq!(back.version, Version::HTTP_10);
+    let accepts: Vec<_> = back.headers.get_all(header::ACCEPT).iter().collect();
+    assert_eq!(accepts, ["text/plain", "text/html"]);
+    assert_eq!(back.body, "payload");
+}
+
+#[test]
+fn test_response_converts_losslessly() {
+    let response = Response::new(StatusCode::IM_A_TEAPOT)
+        .append_header("set-cookie", "a=1")
+        .append_header("set-cookie", "b=2")
+        .with_body("short and stout");
+    let converted: http::Response<Body> = response.into();
+    assert_eq!(converted.status(), StatusCode::IM_A_TEAPOT);
+
+    let back = Response::from(converted);
+    let head = String::from_utf8(back.head_bytes()).unwrap();
+    assert!(head.starts_with("HTTP/1.1 418 I'm a teapot\r\n"));
+    assert!(head.contains("set-cookie: a=1\r\nset-cookie: b=2\r\n"));
+    assert_eq!(back.body, "short and stout");
+
+    let plain = Response::from(http::Response::new(()));
+    assert_eq!(plain.status, StatusCode::OK);
+    assert_eq!(plain.body.content_length(), Some(0));
+}
+
+#[test]
+fn test_repeated_headers_cross_the_wire() {
+    let router = Router::new().get(
+        "/cookies",
+        handler_fn(|request: Request| async move {
+            let sent = request.headers.get_all(header::COOKIE).iter().count();
+            Response::new(StatusCode::SERVICE_UNAVAILABLE)
+                .append_header("set-cookie", "a=1")
+                .append_header("set-cookie", "b=2")
+                .with_body(sent.to_string())
+        }),
+    );
+    let server = Server::builder(router)
+        .bind("127.0.0.1:0".parse().unwrap())
+        .expect("Failed to bind");
+    let handle = server.handle();
+    let url = format!("http://{}/cookies", handle.local_addr());
+
+    let runtime = Runtime::new();
+    let (_, ()) = runtime.block_on(futures::future::join(server.run(), async {
+        let response = Client::new()
+            .request(Method::GET, &url)
+            .with_header("cookie", "x=1")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status, StatusCode::SERVICE_UNAVAILABLE);
+        let cookies: Vec<_> = response
+            .headers
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .collect();
+        assert_eq!(cookies, ["a=1", "b=2"]);
+        assert_eq!(response.body, "1");
+        assert!(handle.shutdown(Duration::from_secs(1)).await);
+    }));
+}
@@ -0,0 +1,178 @@
+//! Tests for `http::Server` on the single-threaded and multi-core runtimes.
+
+mod common;
+
+use common::with_local_server;
+use rust_miniss::http::Server;
+use rust_miniss::{
+    timer, EchoHandler, HttpHandler, MultiCoreRuntime, Request, Response, Runtime, StatusCode,
+};
+use std::io::{ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+/// Answers after sleeping for the number of milliseconds in the path.
+struct SlowHandler;
+
+impl HttpHandler for SlowHandler {
+    async fn handle(&self, request: Request) -> Response {
+        let millis = request.path.trim_start_matches('/').parse().unwrap_or(0);
+        timer::sleep(Duration::from_millis(millis)).await;
+        Response::new(StatusCode::OK).with_body("done")
+    }
+}
+
+fn connect(addr: SocketAddr) -> TcpStream {
+    let stream = TcpStream::connect(addr).expect("Failed to connect");
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    stream
+}
+
+/// Reads one response with a `content-length`, leaving the connection open.
+fn read_response(stream: &mut TcpStream) -> std::io::Result<String> {
+    let mut response = Vec::new();
+    let mut byte = [0u8; 1];
+    while !response.ends_with(b"\r\n\r\n") {
+        if stream.read(&mut byte)? == 0 {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+        response.push(byte[0]);
+    }
+    let head = String::from_utf8(response).unwrap();
+    let len: usize = head
+        .lines()
+        .find_map(|line| line.strip_prefix("content-length: "))
+        .and_then(|len| len.parse().ok())
+        .unwrap_or(0);
+    let mut body = vec![0u8; len];
+    stream.read_exact(&mut body)?;
+    Ok(head + &String::from_utf8_lossy(&body))
+}
+
+#[test]
+fn test_server_limits_concurrent_connections() {
+    let server = Server::builder(EchoHandler)
+        .max_connections(1)
+        .bind("127.0.0.1:0".parse().unwrap())
+        .expect("Failed to bind");
+
+    let drained = with_local_server(server, Duration::from_secs(1), |addr| {
+        let mut first = connect(addr);
+        first.write_all(b"GET /first HTTP/1.1\r\n\r\n").unwrap();
+        assert!(read_response(&mut first).unwrap().contains("Path: /first"));
+
+        // The second connection waits while the first holds the only slot
+        let mut second = connect(addr);
+        second.write_all(b"GET /second HTTP/1.1\r\n\r\n").unwrap();
+        second
+            .set_read_timeout(Some(Duration::from_millis(200)))
+            .unwrap();
+        let err = read_response(&mut second).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ErrorKind::WouldBlock | ErrorKind::TimedOut
+        ));
+
+        drop(first);
+        second
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        assert!(read_response(&mut second)
+            .unwrap()
+            .contains("Path: /second"));
+    });
+    assert!(drained);
+}
+
+#[test]
+fn test_server_shutdown_drains_in_flight_requests() {
+    let server = Server::builder(SlowHandler)
+        .bind("127.0.0.1:0".parse().unwrap())
+        .expect("Failed to bind");
+    let handle = server.handle();
+    let addr = handle.local_addr();
+
+    let runtime = Runtime::new();
+    let client = std::thread::spawn(move || {
+        let mut stream = connect(addr);
+        stream.write_all(b"GET /200 HTTP/1.1\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    });
+    let (_, drained) = runtime.block_on(futures::future::join(server.run(), async {
+        // Let the request arrive, then shut down while it is being handled
+        while handle.active_connections() == 0 {
+            timer::sleep(Duration::from_millis(5)).await;
+        }
+        timer::sleep(Duration::from_millis(50)).await;
+        handle.shutdown(Duration::from_secs(2)).await
+    }));
+    assert!(drained);
+
+    // The request completed, and the connection closed after it
+    let response = client.join().unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.contains("connection: close"));
+    assert!(response.ends_with("done"));
+    assert!(TcpStream::connect(addr).is_err());
+}
+
+#[test]
+fn test_server_shutdown_cuts_off_after_deadline() {
+    let server = Server::builder(SlowHandler)
+        .bind("127.0.0.1:0".parse().unwrap())
+        .expect("Failed to bind");
+    let handle = server.handle();
+    let addr = handle.local_addr();
+
+    let runtime = Runtime::new();
+    let client = std::thread::spawn(move || {
+        let mut stream = connect(addr);
+        stream.write_all(b"GET /10000 HTTP/1.1\r\n\r\n").unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+        response
+    });
+    let (_, drained) = runtime.block_on(futures::future::join(server.run(), async {
+        while handle.active_connections() == 0 {
+            timer::sleep(Duration::from_millis(5)).await;
+        }
+        handle.shutdown(Duration::from_millis(100)).await
+    }));
+    assert!(!drained);
+
+    // The connection was closed without an answer
+    assert!(client.join().unwrap().is_empty());
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_server_on_every_core() {
+    let runtime = MultiCoreRuntime::new(Some(2)).unwrap();
+    let handle = Server::builder(EchoHandler)
+        .bind("127.0.0.1:0".parse().unwrap())
+        .expect("Failed to bind")
+        .start(&runtime)
+        .expect("Failed to start");
+
+    for i in 0..8 {
+        let mut stream = connect(handle.local_addr());
+        write!(stream, "GET /{i} HTTP/1.1\r\n\r\n").unwrap();
+        let response = read_response(&mut stream).unwrap();
+        assert!(response.contains(&format!("Path: /{i}")));
+    }
+
+    // Open keep-alive connections are closed by the shutdown
+    let mut idle = connect(handle.local_addr());
+    idle.write_all(b"GET /idle HTTP/1.1\r\n\r\n").unwrap();
+    read_response(&mut idle).unwrap();
+    assert!(runtime.block_on(handle.shutdown(Duration::from_secs(2))));
+    assert_eq!(handle.active_connections(), 0);
+    let mut rest = Vec::new();
+    assert_eq!(idle.read_to_end(&mut rest).unwrap(), 0);
+
+    runtime.shutdown().unwrap();
+}
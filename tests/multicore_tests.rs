@@ -391,3 +391,206 @@ fn test_multicore_graceful_shutdown() {
     // All tasks should have completed
     assert_eq!(running_tasks.load(Ordering::SeqCst), 0);
 }
+
+#[test]
+fn test_idle_core_wakes_for_spawned_task() {
+    init_tracing();
+    let runtime = MultiCoreRuntime::new(Some(2)).unwrap();
+
+    // Let both cores run out of work and block in their IO backends
+    std::thread::sleep(Duration::from_millis(50));
+
+    let (tx, rx) = mpsc::channel();
+    for core_id in 0..2 {
+        let tx = tx.clone();
+        runtime
+            .spawn_on(core_id, async move {
+                tx.send(core_id).unwrap();
+            })
+            .unwrap();
+    }
+
+    let mut cores = vec![
+        rx.recv_timeout(Duration::from_secs(1)).unwrap(),
+        rx.recv_timeout(Duration::from_secs(1)).unwrap(),
+    ];
+    cores.sort();
+    assert_eq!(cores, vec![0, 1]);
+
+    runtime.shutdown().unwrap();
+}
+
+#[test]
+fn test_sleeping_task_wakes_on_idle_core() {
+    init_tracing();
+    let runtime = MultiCoreRuntime::new(Some(1)).unwrap();
+
+    let (tx, rx) = mpsc::channel();
+    let start = std::time::Instant::now();
+    runtime
+        .spawn(async move {
+            sleep(Duration::from_millis(50)).await;
+            tx.send(()).unwrap();
+        })
+        .unwrap();
+
+    rx.recv_timeout(Duration::from_secs(1)).unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(50));
+
+    runtime.shutdown().unwrap();
+}
+
+#[test]
+fn test_cross_core_waker_wakes_blocked_core() {
+    init_tracing();
+    let runtime = MultiCoreRuntime::new(Some(2)).unwrap();
+
+    let (wake_tx, wake_rx) = futures::channel::oneshot::channel::<u32>();
+    let (tx, rx) = mpsc::channel();
+    runtime
+        .spawn_on(0, async move {
+            let value = wake_rx.await.unwrap();
+            tx.send(value).unwrap();
+        })
+        .unwrap();
+
+    // Core 0 parks the task and blocks; a task on core 1 wakes it
+    std::thread::sleep(Duration::from_millis(50));
+    runtime
+        .spawn_on(1, async move {
+            wake_tx.send(7).unwrap();
+        })
+        .unwrap();
+
+    assert_eq!(rx.recv_timeout(Duration::from_secs(1)).unwrap(), 7);
+
+    runtime.shutdown().unwrap();
+}
+
+#[test]
+fn test_block_on_wakes_from_other_thread() {
+    init_tracing();
+    let runtime = Runtime::new();
+
+    let (wake_tx, wake_rx) = futures::channel::oneshot::channel::<u32>();
+    let sender = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(30));
+        wake_tx.send(11).unwrap();
+    });
+
+    let value = runtime.block_on(async move {
+        sleep(Duration::from_millis(10)).await;
+        wake_rx.await.unwrap()
+    });
+    assert_eq!(value, 11);
+    sender.join().unwrap();
+}
+
+#[cfg(io_backend = "io_uring")]
+#[test]
+fn test_ring_options_fall_back_and_report_setup() {
+    use rust_miniss::{fs::AsyncFile, RingOptions, SqPollOptions};
+
+    init_tracing();
+    let options = RingOptions {
+        entries: 256,
+        cq_entries: Some(1024),
+        sqpoll: Some(SqPollOptions::default()),
+        single_issuer: true,
+        coop_taskrun: true,
+        defer_taskrun: true,
+    };
+    let runtime = MultiCoreRuntime::with_ring_options(Some(2), options).unwrap();
+
+    let stats = runtime.stats();
+    assert_eq!(stats.ring_setups.len(), 2);
+    for setup in stats.ring_setups.iter().map(|setup| setup.unwrap()) {
+        assert_eq!(setup.sq_entries, 256);
+        assert!(setup.cq_entries >= 512);
+        // DEFER_TASKRUN needs SINGLE_ISSUER and can't be combined with SQPOLL
+        if setup.defer_taskrun {
+            assert!(setup.single_issuer);
+            assert!(!setup.sqpoll);
+        }
+    }
+
+    // IO still completes with whichever flags were accepted
+    let temp_dir = tempfile::tempdir().unwrap();
+    let path = temp_dir.path().join("ring_options.txt");
+    let (tx, rx) = mpsc::channel();
+    for core_id in 0..2 {
+        let tx = tx.clone();
+        let path = path.with_extension(core_id.to_string());
+        runtime
+            .spawn_on(core_id, async move {
+                let file = AsyncFile::create(&path).unwrap();
+                let written = file.write_at(0, b"ring").await.unwrap();
+                tx.send(written).unwrap();
+            })
+            .unwrap();
+    }
+    for _ in 0..2 {
+        assert_eq!(rx.recv_timeout(Duration::from_secs(2)).unwrap(), 4);
+    }
+
+    runtime.shutdown().unwrap();
+}
+
+#[test]
+fn test_spawn_blocking_shares_bounded_pool() {
+    use std::sync::atomic::AtomicUsize;
+
+    init_tracing();
+    let blocking_options = BlockingPoolOptions {
+        max_threads: 2,
+        queue_capacity: 1,
+        ..Default::default()
+    };
+    let runtime =
+        MultiCoreRuntime::with_options(Some(2), RingOptions::default(), blocking_options).unwrap();
+
+    // More jobs than threads and queue slots: the surplus waits for room
+    let running = Arc::new(AtomicUsize::new(0));
+    let most_running = Arc::new(AtomicUsize::new(0));
+    let (tx, rx) = mpsc::channel();
+    for i in 0..8 {
+        let tx = tx.clone();
+        let running = running.clone();
+        let most_running = most_running.clone();
+        runtime
+            .spawn(async move {
+                let name = spawn_blocking(move || {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    most_running.fetch_max(now, Ordering::SeqCst);
+                    std::thread::sleep(Duration::from_millis(20));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    std::thread::current().name().map(str::to_string)
+                })
+                .await
+                .unwrap();
+                tx.send((i, name)).unwrap();
+            })
+            .unwrap();
+    }
+    let mut done: Vec<_> = (0..8)
+        .map(|_| rx.recv_timeout(Duration::from_secs(5)).unwrap())
+        .collect();
+    done.sort();
+    for (i, (job, name)) in done.into_iter().enumerate() {
+        assert_eq!(job, i);
+        assert_eq!(name.as_deref(), Some("miniss-blocking"));
+    }
+    assert!(most_running.load(Ordering::SeqCst) <= 2);
+
+    runtime.shutdown().unwrap();
+}
+
+#[test]
+fn test_spawn_blocking_reports_panics() {
+    let runtime = Runtime::new();
+    runtime.block_on(async {
+        assert_eq!(spawn_blocking(|| 6 * 7).await.unwrap(), 42);
+        let result = spawn_blocking(|| panic!("blocking job failed")).await;
+        assert!(matches!(result, Err(TaskError::Panic(_))));
+    });
+}
@@ -0,0 +1,71 @@
+//! Tests for `SO_REUSEPORT` listeners and `MultiCoreRuntime::serve_tcp`.
+
+use rust_miniss::{AsyncTcpListener, MultiCoreRuntime, TcpServeOptions};
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+#[test]
+fn test_bind_reuseport_shares_address() {
+    let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let first = AsyncTcpListener::bind_reuseport(addr).expect("Failed to bind first listener");
+    let local_addr = first.local_addr().unwrap();
+
+    // A second listener on the same port only succeeds with SO_REUSEPORT
+    let second =
+        AsyncTcpListener::bind_reuseport(local_addr).expect("Failed to bind second listener");
+    assert_eq!(second.local_addr().unwrap(), local_addr);
+
+    // A plain listener must not be able to join the group
+    assert!(AsyncTcpListener::bind(local_addr).is_err());
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_attach_reuseport_cbpf() {
+    let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let listener = AsyncTcpListener::bind_reuseport(addr).unwrap();
+    listener
+        .attach_reuseport_cbpf(2)
+        .expect("Failed to attach CPU steering program");
+    assert!(listener.attach_reuseport_cbpf(0).is_err());
+}
+
+fn request(addr: SocketAddr) -> String {
+    let mut stream = TcpStream::connect(addr).expect("Failed to connect");
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    stream.write_all(b"ping").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn test_serve_tcp_on_every_core() {
+    let runtime = MultiCoreRuntime::new(Some(2)).unwrap();
+    let addr = runtime
+        .serve_tcp_with(
+            "127.0.0.1:0".parse().unwrap(),
+            TcpServeOptions { cpu_steering: true },
+            |stream, _peer| async move {
+                let core = rust_miniss::multicore::current_core().unwrap();
+                let _ = stream.read().await;
+                let _ = stream.write_all(format!("core {core}").as_bytes()).await;
+            },
+        )
+        .expect("Failed to serve");
+    assert_ne!(addr.port(), 0);
+
+    for _ in 0..8 {
+        let response = request(addr);
+        assert!(
+            response == "core 0" || response == "core 1",
+            "unexpected response: {response:?}"
+        );
+    }
+
+    runtime.shutdown().unwrap();
+}
@@ -0,0 +1,260 @@
+//! Tests for WebSocket upgrades of `http::Server` connections, driven by a
+//! hand-written client on a blocking socket.
+
+mod common;
+
+use common::with_local_server;
+use rust_miniss::http::{handler_fn, Message, Router, Server, WebSocketOptions, WebSocketUpgrade};
+use rust_miniss::Request;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+const TEXT: u8 = 0x1;
+const BINARY: u8 = 0x2;
+const CONTINUATION: u8 = 0x0;
+const CLOSE: u8 = 0x8;
+const PING: u8 = 0x9;
+const PONG: u8 = 0xA;
+
+/// Echoes messages in frames of at most 4 bytes on `/echo`; on `/push`
+/// greets, pings and closes without waiting for the client to speak.
+fn router() -> Router {
+    Router::new()
+        .get(
+            "/echo",
+            handler_fn(|mut request: Request| async move {
+                let upgrade = match WebSocketUpgrade::from_request(&mut request) {
+                    Ok(upgrade) => upgrade,
+                    Err(rejection) => return rejection,
+                };
+                let options = WebSocketOptions {
+                    max_frame_size: Some(4),
+                    max_message_size: 64,
+                };
+                upgrade
+                    .with_protocols(&["chat", "superchat"])
+                    .with_options(options)
+                    .on_upgrade(|socket| async move {
+                        while let Ok(Some(message)) = socket.recv().await {
+                            if socket.send(message).await.is_err() {
+                                break;
+                            }
+                        }
+                    })
+            }),
+        )
+        .get(
+            "/push",
+            handler_fn(|mut request: Request| async move {
+                match WebSocketUpgrade::from_request(&mut request) {
+                    Ok(upgrade) => upgrade.on_upgrade(|socket| async move {
+                        socket.send("welcome").await.unwrap();
+                        socket.ping(&b"are you there"[..]).await.unwrap();
+                        socket.close(1001, "going away").await.unwrap();
+                        let err = socket.send("too late").await.unwrap_err();
+                        assert_eq!(err.kind(), ErrorKind::NotConnected);
+                    }),
+                    Err(rejection) => rejection,
+                }
+            }),
+        )
+}
+
+fn server() -> Server<Router> {
+    Server::builder(router())
+        .bind("127.0.0.1:0".parse().unwrap())
+        .expect("Failed to bind")
+}
+
+/// Sends a handshake for `path` and returns the response head.
+fn handshake(addr: SocketAddr, path: &str, headers: &str) -> (TcpStream, String) {
+    let mut stream = TcpStream::connect(addr).expect("Failed to connect");
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    write!(
+        stream,
+        "GET {path} HTTP/1.1\r\nhost: localhost\r\nupgrade: websocket\r\n\
+         connection: keep-alive, Upgrade\r\n{headers}\r\n"
+    )
+    .unwrap();
+    let mut head = Vec::new();
+    let mut byte = [0u8; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        stream.read_exact(&mut byte).unwrap();
+        head.push(byte[0]);
+    }
+    (stream, String::from_utf8(head).unwrap())
+}
+
+const KEY: &str = "sec-websocket-version: 13\r\nsec-websocket-key: dGhlIHNhbXBsZSBub25jZQ==\r\n";
+
+/// Writes a frame masked as a client must.
+fn send_frame(stream: &mut TcpStream, fin: bool, opcode: u8, payload: &[u8]) {
+    let mask = [0x37, 0xFA, 0x21, 0x3D];
+    let mut frame = vec![if fin { 0x80 } else { 0 } | opcode];
+    match payload.len() {
+        len @ 0..=125 => frame.push(0x80 | len as u8),
+        len => {
+            frame.push(0x80 | 126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(&mask);
+    frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+    stream.write_all(&frame).unwrap();
+}
+
+/// Reads one unmasked frame: its fin bit, opcode and payload.
+fn read_frame(stream: &mut TcpStream) -> (bool, u8, Vec<u8>) {
+    let mut head = [0u8; 2];
+    stream.read_exact(&mut head).unwrap();
+    assert_eq!(head[1] & 0x80, 0, "server frames are not masked");
+    let len = match head[1] & 0x7F {
+        126 => {
+            let mut len = [0u8; 2];
+            stream.read_exact(&mut len).unwrap();
+            u16::from_be_bytes(len) as usize
+        }
+        len => len as usize,
+    };
+    let mut payload = vec![0u8; len];
+    stream.read_exact(&mut payload).unwrap();
+    (head[0] & 0x80 != 0, head[0] & 0x0F, payload)
+}
+
+fn close_payload(code: u16, reason: &str) -> Vec<u8> {
+    [&code.to_be_bytes()[..], reason.as_bytes()].concat()
+}
+
+fn assert_closed(stream: &mut TcpStream) {
+    let mut rest = Vec::new();
+    stream.read_to_end(&mut rest).unwrap();
+    assert!(rest.is_empty(), "unexpected bytes after close: {rest:?}");
+}
+
+#[test]
+fn test_websocket_echoes_fragmented_messages() {
+    let drained = with_local_server(server(), Duration::from_secs(1), |addr| {
+        let protocols = format!("{KEY}sec-websocket-protocol: superchat, chat\r\n");
+        let (mut stream, head) = handshake(addr, "/echo", &protocols);
+        assert!(head.starts_with("HTTP/1.1 101 Switching Protocols\r\n"));
+        assert!(head.contains("sec-websocket-accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n"));
+        assert!(head.contains("sec-websocket-protocol: chat\r\n"));
+        assert!(!head.contains("content-length"));
+
+        // A ping in the middle of a fragmented message is answered at once
+        send_frame(&mut stream, false, TEXT, b"Hel");
+        send_frame(&mut stream, true, PING, b"ping");
+        send_frame(&mut stream, true, CONTINUATION, "lo 👋".as_bytes());
+        assert_eq!(read_frame(&mut stream), (true, PONG, b"ping".to_vec()));
+
+        // Echoed in frames of at most 4 bytes
+        let mut echoed = Vec::new();
+        let (mut fin, opcode, mut payload) = read_frame(&mut stream);
+        assert_eq!(opcode, TEXT);
+        echoed.append(&mut payload);
+        while !fin {
+            let (last, opcode, mut payload) = read_frame(&mut stream);
+            assert_eq!(opcode, CONTINUATION);
+            assert!(payload.len() <= 4);
+            echoed.append(&mut payload);
+            fin = last;
+        }
+        assert_eq!(String::from_utf8(echoed).unwrap(), "Hello 👋");
+
+        send_frame(&mut stream, true, BINARY, &[1, 2, 3]);
+        assert_eq!(read_frame(&mut stream), (true, BINARY, vec![1, 2, 3]));
+
+        // The close is answered with the same code, then the server hangs up
+        send_frame(&mut stream, true, CLOSE, &close_payload(1000, "bye"));
+        assert_eq!(
+            read_frame(&mut stream),
+            (true, CLOSE, 1000u16.to_be_bytes().to_vec())
+        );
+        assert_closed(&mut stream);
+    });
+    assert!(drained);
+}
+
+#[test]
+fn test_websocket_server_pushes_and_closes() {
+    let drained = with_local_server(server(), Duration::from_secs(1), |addr| {
+        let (mut stream, head) = handshake(addr, "/push", KEY);
+        assert!(head.starts_with("HTTP/1.1 101 Switching Protocols\r\n"));
+        assert!(!head.contains("sec-websocket-protocol"));
+
+        assert_eq!(read_frame(&mut stream), (true, TEXT, b"welcome".to_vec()));
+        assert_eq!(
+            read_frame(&mut stream),
+            (true, PING, b"are you there".to_vec())
+        );
+        send_frame(&mut stream, true, PONG, b"are you there");
+        assert_eq!(
+            read_frame(&mut stream),
+            (true, CLOSE, close_payload(1001, "going away"))
+        );
+        // Messages sent before the client saw the close are dropped
+        send_frame(&mut stream, true, TEXT, b"ignored");
+        send_frame(&mut stream, true, CLOSE, &close_payload(1001, ""));
+        assert_closed(&mut stream);
+    });
+    assert!(drained);
+}
+
+#[test]
+fn test_websocket_protocol_violations_close_the_connection() {
+    let drained = with_local_server(server(), Duration::from_secs(1), |addr| {
+        // Unmasked frames are refused
+        let (mut stream, _) = handshake(addr, "/echo", KEY);
+        stream.write_all(&[0x81, 0x02, b'h', b'i']).unwrap();
+        let (_, opcode, payload) = read_frame(&mut stream);
+        assert_eq!(opcode, CLOSE);
+        assert_eq!(payload[..2], 1002u16.to_be_bytes());
+        assert_closed(&mut stream);
+
+        // Text must be UTF-8
+        let (mut stream, _) = handshake(addr, "/echo", KEY);
+        send_frame(&mut stream, true, TEXT, &[0xFF, 0xFE]);
+        let (_, opcode, payload) = read_frame(&mut stream);
+        assert_eq!(opcode, CLOSE);
+        assert_eq!(payload[..2], 1007u16.to_be_bytes());
+        assert_closed(&mut stream);
+
+        // Messages beyond max_message_size are refused
+        let (mut stream, _) = handshake(addr, "/echo", KEY);
+        send_frame(&mut stream, false, BINARY, &[0; 40]);
+        send_frame(&mut stream, true, CONTINUATION, &[0; 40]);
+        let (_, opcode, payload) = read_frame(&mut stream);
+        assert_eq!(opcode, CLOSE);
+        assert_eq!(payload[..2], 1009u16.to_be_bytes());
+        assert_closed(&mut stream);
+    });
+    assert!(drained);
+}
+
+#[test]
+fn test_websocket_handshake_rejections() {
+    let drained = with_local_server(server(), Duration::from_secs(1), |addr| {
+        let (_, head) = handshake(addr, "/echo", "sec-websocket-version: 13\r\n");
+        assert!(head.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+        let (_, head) = handshake(
+            addr,
+            "/echo",
+            "sec-websocket-version: 8\r\nsec-websocket-key: dGhlIHNhbXBsZSBub25jZQ==\r\n",
+        );
+        assert!(head.starts_with("HTTP/1.1 426 Upgrade Required\r\n"));
+        assert!(head.contains("sec-websocket-version: 13\r\n"));
+    });
+    assert!(drained);
+}
+
+#[test]
+fn test_message_conversions() {
+    assert_eq!(Message::from("hi"), Message::Text("hi".to_string()));
+    assert_eq!(
+        Message::from(vec![1, 2]),
+        Message::Binary(vec![1u8, 2].into())
+    );
+}